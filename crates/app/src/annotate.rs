@@ -0,0 +1,146 @@
+//! `fireshot annotate`: shapes drawn onto an existing image from the command
+//! line, rendered the way the editor exports them but without a window, so
+//! scripts can annotate screenshots outside a graphical session.
+
+use std::path::Path;
+
+use fireshot_core::config::Config;
+use fireshot_core::shapes::{
+    ArrowShape, CircleShape, Color32, EffectKind, EffectShape, LineShape, Pos2, RectShape, Shape,
+    TextShape,
+};
+use fireshot_core::CaptureError;
+
+/// Text is never drawn smaller than this, as in the editor.
+const MIN_TEXT_SIZE: f32 = 8.0;
+
+/// `X,Y:X,Y`, from one point to another.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Segment {
+    start: Pos2,
+    end: Pos2,
+}
+
+/// `X,Y:TEXT`, text with its top-left corner at a point.
+#[derive(Debug, Clone)]
+pub(crate) struct Label {
+    pos: Pos2,
+    text: String,
+}
+
+/// What to draw, in the order it is drawn: effects first so nothing drawn
+/// over them gets blurred, then outlines, lines and arrows, then text.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Annotations {
+    pub(crate) blur: Vec<Segment>,
+    pub(crate) pixelate: Vec<Segment>,
+    pub(crate) rect: Vec<Segment>,
+    pub(crate) circle: Vec<Segment>,
+    pub(crate) line: Vec<Segment>,
+    pub(crate) arrow: Vec<Segment>,
+    pub(crate) text: Vec<Label>,
+}
+
+impl Annotations {
+    fn shapes(self, color: Color32, size: f32) -> Vec<Shape> {
+        let effect = |kind| {
+            move |Segment { start, end }| {
+                let mut effect = EffectShape::new(start, size, kind);
+                effect.end = end;
+                Shape::Effect(effect)
+            }
+        };
+        let blur = self.blur.into_iter().map(effect(EffectKind::Blur));
+        let pixelate = self.pixelate.into_iter().map(effect(EffectKind::Pixelate));
+        let rect = self.rect.into_iter().map(|Segment { start, end }| {
+            Shape::Rect(RectShape { start, end, color, size })
+        });
+        let circle = self.circle.into_iter().map(|Segment { start, end }| {
+            Shape::Circle(CircleShape { start, end, color, size })
+        });
+        let line = self.line.into_iter().map(|Segment { start, end }| {
+            Shape::Line(LineShape { start, end, color, size })
+        });
+        let arrow = self.arrow.into_iter().map(|Segment { start, end }| {
+            Shape::Arrow(ArrowShape { start, end, color, size })
+        });
+        let text = self.text.into_iter().map(|Label { pos, text }| {
+            Shape::Text(TextShape {
+                pos,
+                text,
+                color,
+                size: size.max(MIN_TEXT_SIZE),
+            })
+        });
+        blur.chain(pixelate)
+            .chain(rect)
+            .chain(circle)
+            .chain(line)
+            .chain(arrow)
+            .chain(text)
+            .collect()
+    }
+}
+
+/// Draws `annotations` onto `input` in `color` at tool size `size` and
+/// writes the result to `output`, whose extension picks the format.
+pub(crate) fn run(
+    config: &Config,
+    input: &Path,
+    output: &Path,
+    annotations: Annotations,
+    color: Color32,
+    size: f32,
+) -> Result<(), CaptureError> {
+    let base = image::open(input)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", input.display(), e)))?
+        .to_rgba8();
+    let shapes = annotations.shapes(color, size);
+    let annotated = fireshot_gui::render_annotations(&base, &shapes);
+    fireshot_gui::save_image(&annotated, output, None, config.save.matte, None, None).map_err(|e| {
+        CaptureError::EncodeError {
+            format: output.display().to_string(),
+            reason: e.to_string(),
+        }
+    })
+}
+
+fn parse_point(value: &str) -> Result<Pos2, String> {
+    let (x, y) = value.split_once(',').ok_or("expected X,Y")?;
+    let coord = |c: &str| c.trim().parse::<f32>().map_err(|err| err.to_string());
+    Ok(Pos2::new(coord(x)?, coord(y)?))
+}
+
+/// X,Y:X,Y.
+pub(crate) fn parse_segment(value: &str) -> Result<Segment, String> {
+    let (start, end) = value.split_once(':').ok_or("expected X,Y:X,Y")?;
+    Ok(Segment {
+        start: parse_point(start)?,
+        end: parse_point(end)?,
+    })
+}
+
+/// X,Y:TEXT; the text may contain colons.
+pub(crate) fn parse_label(value: &str) -> Result<Label, String> {
+    let (pos, text) = value.split_once(':').ok_or("expected X,Y:TEXT")?;
+    if text.is_empty() {
+        return Err("expected X,Y:TEXT with some text".to_string());
+    }
+    Ok(Label {
+        pos: parse_point(pos)?,
+        text: text.to_string(),
+    })
+}
+
+/// #rrggbb, the # optional.
+pub(crate) fn parse_color(value: &str) -> Result<Color32, String> {
+    let hex = value.trim().trim_start_matches('#');
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok(Color32::from_rgb(r, g, b)),
+        _ => Err("expected #rrggbb".to_string()),
+    }
+}
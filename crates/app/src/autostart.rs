@@ -0,0 +1,219 @@
+use std::path::PathBuf;
+
+use fireshot_core::{paths, CaptureError};
+
+const DESKTOP_FILE: &str = "org.fireshot.Fireshot.desktop";
+const UNIT_FILE: &str = "fireshot.service";
+const DBUS_SERVICE_FILE: &str = "org.fireshot.Fireshot.service";
+/// Handles `fireshot://` links; see `uri.rs`.
+const URI_HANDLER_FILE: &str = "org.fireshot.Fireshot.UriHandler.desktop";
+
+fn desktop_path() -> PathBuf {
+    paths::autostart_dir().join(DESKTOP_FILE)
+}
+
+fn unit_path() -> PathBuf {
+    paths::systemd_user_dir().join(UNIT_FILE)
+}
+
+fn dbus_service_path() -> PathBuf {
+    paths::dbus_services_dir().join(DBUS_SERVICE_FILE)
+}
+
+fn uri_handler_path() -> PathBuf {
+    paths::applications_dir().join(URI_HANDLER_FILE)
+}
+
+fn daemon_exe() -> Result<PathBuf, CaptureError> {
+    std::env::current_exe().map_err(|e| CaptureError::Io(e.to_string()))
+}
+
+/// `exe` as one argument of a desktop entry's `Exec` key: quoted, with the
+/// spec's escapes inside the quotes, then escaped again as a string value.
+fn desktop_exec(exe: &std::path::Path) -> String {
+    let mut quoted = String::from("\"");
+    for ch in exe.display().to_string().chars() {
+        match ch {
+            '"' | '`' | '$' => quoted.extend(['\\', '\\', ch]),
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '%' => quoted.push_str("%%"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// `exe` as one argument of a D-Bus service file's `Exec`, which the bus
+/// splits like a shell.
+fn dbus_exec(exe: &std::path::Path) -> String {
+    let mut quoted = String::from("\"");
+    for ch in exe.display().to_string().chars() {
+        if matches!(ch, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(ch);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// `exe` as the command of a systemd `ExecStart`, where `%` starts a
+/// specifier and `$` a variable.
+fn systemd_exec(exe: &std::path::Path) -> String {
+    let mut quoted = String::from("\"");
+    for ch in exe.display().to_string().chars() {
+        match ch {
+            '"' | '\\' => quoted.extend(['\\', ch]),
+            '%' => quoted.push_str("%%"),
+            '$' => quoted.push_str("$$"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn write_file(path: &std::path::Path, contents: &str) -> Result<(), CaptureError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CaptureError::Io(format!("{}: {}", dir.display(), e)))?;
+    }
+    std::fs::write(path, contents)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))
+}
+
+/// Makes `org.fireshot.Fireshot` bus-activatable and, unless `on_demand` is
+/// set, starts `fireshot daemon` at login. With `systemd` the daemon runs as a
+/// `Type=notify` user unit that activation goes through, which is enabled
+/// here; otherwise the bus and an XDG autostart entry start it directly. Also registers the handler
+/// for `fireshot://` links, which go through activation. Returns the files
+/// written.
+pub fn install(systemd: bool, on_demand: bool) -> Result<Vec<PathBuf>, CaptureError> {
+    let exe = daemon_exe()?;
+    let mut written = Vec::new();
+
+    let mut dbus_service = format!(
+        "[D-BUS Service]\n\
+         Name=org.fireshot.Fireshot\n\
+         Exec={} daemon\n",
+        dbus_exec(&exe)
+    );
+    if systemd {
+        dbus_service.push_str(&format!("SystemdService={}\n", UNIT_FILE));
+    }
+    write_file(&dbus_service_path(), &dbus_service)?;
+    written.push(dbus_service_path());
+
+    write_file(
+        &uri_handler_path(),
+        &format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Fireshot\n\
+             Comment=Open fireshot:// links\n\
+             Exec={} open-uri %u\n\
+             Icon=camera-photo\n\
+             Terminal=false\n\
+             NoDisplay=true\n\
+             MimeType=x-scheme-handler/{};\n",
+            desktop_exec(&exe),
+            crate::uri::SCHEME
+        ),
+    )?;
+    written.push(uri_handler_path());
+    // Makes it the default handler; without xdg-mime the MimeType line
+    // still lets most desktops find it.
+    let _ = std::process::Command::new("xdg-mime")
+        .args(["default", URI_HANDLER_FILE])
+        .arg(format!("x-scheme-handler/{}", crate::uri::SCHEME))
+        .status();
+
+    if systemd {
+        write_file(
+            &unit_path(),
+            &format!(
+                "[Unit]\n\
+                 Description=Fireshot screenshot daemon\n\
+                 PartOf=graphical-session.target\n\
+                 After=graphical-session.target\n\
+                 \n\
+                 [Service]\n\
+                 Type=notify\n\
+                 BusName=org.fireshot.Fireshot\n\
+                 ExecStart={} daemon\n\
+                 Restart=on-failure\n\
+                 \n\
+                 [Install]\n\
+                 WantedBy=graphical-session.target\n",
+                systemd_exec(&exe)
+            ),
+        )?;
+        written.push(unit_path());
+        // Like `remove`, a missing systemctl is not fatal: the unit is
+        // written, and activation still finds it after the next login.
+        let _ = std::process::Command::new("systemctl")
+            .args(["--user", "daemon-reload"])
+            .status();
+        if !on_demand {
+            let _ = std::process::Command::new("systemctl")
+                .args(["--user", "enable", UNIT_FILE])
+                .status();
+        }
+    } else if !on_demand {
+        write_file(
+            &desktop_path(),
+            &format!(
+                "[Desktop Entry]\n\
+                 Type=Application\n\
+                 Name=Fireshot\n\
+                 Comment=Screenshot daemon with tray icon and hotkeys\n\
+                 Exec={} daemon\n\
+                 Icon=camera-photo\n\
+                 Terminal=false\n\
+                 NoDisplay=true\n\
+                 X-GNOME-Autostart-enabled=true\n",
+                desktop_exec(&exe)
+            ),
+        )?;
+        written.push(desktop_path());
+    }
+    Ok(written)
+}
+
+/// Removes whichever autostart and activation files exist and returns their
+/// paths.
+pub fn remove() -> Result<Vec<PathBuf>, CaptureError> {
+    if unit_path().exists() {
+        // Drop the `WantedBy` symlink too; a missing systemctl is not fatal.
+        let _ = std::process::Command::new("systemctl")
+            .args(["--user", "disable", UNIT_FILE])
+            .status();
+    }
+    let mut removed = Vec::new();
+    let files = [desktop_path(), unit_path(), dbus_service_path(), uri_handler_path()];
+    for path in files {
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(CaptureError::Io(format!("{}: {}", path.display(), err)));
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn quotes_exec_paths() {
+        let exe = Path::new("/opt/My Apps/fire$hot%\\\"x");
+        assert_eq!(desktop_exec(exe), r#""/opt/My Apps/fire\\$hot%%\\\\\\"x""#);
+        assert_eq!(dbus_exec(exe), r#""/opt/My Apps/fire\$hot%\\\"x""#);
+        assert_eq!(systemd_exec(exe), r#""/opt/My Apps/fire$$hot%%\\\"x""#);
+    }
+}
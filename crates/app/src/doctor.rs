@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use fireshot_core::config::Config;
+use fireshot_core::sandbox::host_command;
+use fireshot_core::tr;
+
+/// Number of attempts to read the clipboard back; wl-copy serves it from a
+/// background process that may take a moment to claim the selection.
+const CLIPBOARD_READ_ATTEMPTS: u32 = 5;
+const CLIPBOARD_READ_DELAY_MS: u64 = 100;
+
+struct Check {
+    ok: bool,
+    detail: String,
+    fix: Option<&'static str>,
+}
+
+impl Check {
+    fn print(&self, name: &str) {
+        let mark = if self.ok { tr!("doctor-ok") } else { tr!("doctor-fail") };
+        println!("  {}: {} ({})", name, mark, self.detail);
+        if let (false, Some(fix)) = (self.ok, self.fix) {
+            println!("    {}", tr!("doctor-fix", fix = fix));
+        }
+    }
+}
+
+/// First line of `tool <args>` output, from stdout or stderr (xclip prints
+/// its version to stderr).
+fn tool_version(tool: &str, args: &[&str]) -> Option<String> {
+    let output = host_command(tool).args(args).output().ok()?;
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    let text = String::from_utf8_lossy(&text);
+    Some(text.lines().next().unwrap_or("").trim().to_string())
+}
+
+fn tool_check(tool: &str, args: &[&str], fix: &'static str) -> Check {
+    match tool_version(tool, args) {
+        Some(version) => Check {
+            ok: true,
+            detail: version,
+            fix: None,
+        },
+        None => Check {
+            ok: false,
+            detail: tr!("doctor-not-found").to_string(),
+            fix: Some(fix),
+        },
+    }
+}
+
+fn read_clipboard() -> Option<String> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let output = if wayland {
+        host_command("wl-paste").arg("--no-newline").output()
+    } else {
+        host_command("xclip")
+            .args(["-selection", "clipboard", "-o"])
+            .output()
+    };
+    output
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+}
+
+fn clipboard_check() -> Check {
+    let previous = read_clipboard();
+    let token = format!("fireshot-doctor-{}", std::process::id());
+    if let Err(err) = fireshot_gui::copy_text_to_clipboard(&token) {
+        return Check {
+            ok: false,
+            detail: err.to_string(),
+            fix: Some(tr!("doctor-install-clipboard")),
+        };
+    }
+    let mut read_back = None;
+    for _ in 0..CLIPBOARD_READ_ATTEMPTS {
+        std::thread::sleep(Duration::from_millis(CLIPBOARD_READ_DELAY_MS));
+        read_back = read_clipboard();
+        if read_back.as_deref() == Some(token.as_str()) {
+            break;
+        }
+    }
+    // Put back whatever text the user had copied.
+    if let Some(previous) = previous.filter(|p| !p.is_empty()) {
+        let _ = fireshot_gui::copy_text_to_clipboard(&previous);
+    }
+    match read_back {
+        Some(text) if text == token => Check {
+            ok: true,
+            detail: tr!("doctor-round-tripped").to_string(),
+            fix: None,
+        },
+        Some(_) => Check {
+            ok: false,
+            detail: tr!("doctor-read-different").to_string(),
+            fix: Some(tr!("doctor-fix-clipboard-manager")),
+        },
+        None => Check {
+            ok: false,
+            detail: tr!("doctor-read-failed").to_string(),
+            fix: Some(tr!("doctor-fix-clipboard-reach")),
+        },
+    }
+}
+
+fn save_dir_check(config: &Config) -> Check {
+    let dir = config.save.directory();
+    let probe = dir.join(".fireshot-doctor");
+    let result = std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&probe, b"fireshot"))
+        .and_then(|()| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => Check {
+            ok: true,
+            detail: tr!("doctor-writable", dir = dir.display()),
+            fix: None,
+        },
+        Err(err) => Check {
+            ok: false,
+            detail: format!("{}: {}", dir.display(), err),
+            fix: Some(tr!("doctor-fix-save-dir")),
+        },
+    }
+}
+
+/// Checks the external tools and locations fireshot relies on and prints a
+/// suggested fix for each failure. Returns whether every check passed.
+pub fn run(config: &Config) -> bool {
+    println!();
+    println!("{}", tr!("doctor-tools"));
+    let wl_clipboard = tr!("doctor-install-wl-clipboard");
+    let mut checks = vec![
+        ("wl-copy", tool_check("wl-copy", &["--version"], wl_clipboard)),
+        ("wl-paste", tool_check("wl-paste", &["--version"], wl_clipboard)),
+        ("xclip", tool_check("xclip", &["-version"], tr!("doctor-install-xclip"))),
+    ];
+    if config.record.format.is_video() {
+        checks.push((
+            "ffmpeg",
+            tool_check("ffmpeg", &["-version"], tr!("doctor-install-ffmpeg")),
+        ));
+    }
+    let mut all_ok = true;
+    for (name, check) in &checks {
+        check.print(name);
+        all_ok &= check.ok;
+    }
+
+    println!();
+    println!("{}", tr!("doctor-clipboard"));
+    let check = clipboard_check();
+    check.print(tr!("doctor-round-trip"));
+    all_ok &= check.ok;
+
+    println!();
+    println!("{}", tr!("doctor-save-dir"));
+    let check = save_dir_check(config);
+    check.print(tr!("doctor-write-access"));
+    all_ok &= check.ok;
+
+    if all_ok {
+        println!();
+        println!("{}", tr!("doctor-all-passed"));
+    }
+    all_ok
+}
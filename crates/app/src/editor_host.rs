@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc};
+
+use fireshot_core::config::{Config, SingleInstance};
+use fireshot_core::window::WindowInfo;
+use fireshot_core::{CaptureError, RecordTarget};
+use fireshot_gui::EditorControl;
+use log::{debug, error, warn};
+use tokio::sync::mpsc;
+
+use crate::hooks::Hooks;
+use crate::notify;
+use crate::tray::TrayState;
+use crate::{instance, DaemonCommand};
+
+/// Runs editor sessions on a long-lived thread inside the daemon. The event
+/// loop and portal connection survive between captures, so a hotkey press
+/// skips process start-up and goes straight to the editor.
+pub(crate) struct EditorHost {
+    jobs: std_mpsc::Sender<(Config, u64)>,
+    /// Jobs queued or running; the editor is busy while this is non-zero.
+    pending: Arc<AtomicUsize>,
+    control: EditorControl,
+    /// Finished only when a job panicked and took the thread down with it.
+    thread: std::thread::JoinHandle<()>,
+    /// The daemon's config, for when the file no longer parses.
+    config: Config,
+}
+
+/// Counts a job as done when dropped, even while a panic unwinds the thread.
+struct Done(Arc<AtomicUsize>);
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl EditorHost {
+    pub(crate) fn spawn(
+        config: &Config,
+        cmd_tx: mpsc::UnboundedSender<DaemonCommand>,
+    ) -> std::io::Result<Self> {
+        let (jobs, rx) = std_mpsc::channel::<(Config, u64)>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let control = EditorControl::default();
+        let handle = tokio::runtime::Handle::current();
+        let thread_pending = pending.clone();
+        let thread_control = control.clone();
+        let thread = std::thread::Builder::new()
+            .name("fireshot-editor".to_string())
+            .spawn(move || {
+                for (config, delay_ms) in rx {
+                    let _done = Done(thread_pending.clone());
+                    let mut next = Some(delay_ms);
+                    while let Some(delay_ms) = next {
+                        next = run_job(&handle, &config, &thread_control, &cmd_tx, delay_ms);
+                        crate::report_profile(false);
+                    }
+                }
+            })?;
+        Ok(Self {
+            jobs,
+            pending,
+            control,
+            thread,
+            config: config.clone(),
+        })
+    }
+
+    /// Hands a capture to the hosted editor. Returns `false` when the caller
+    /// should spawn a capture process instead.
+    pub(crate) fn submit(&self, delay_ms: u64) -> bool {
+        if self.thread.is_finished() {
+            error!("hosted editor thread is gone");
+            return false;
+        }
+        // Read for every capture, as a spawned capture process would.
+        let mut config = match Config::load() {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("hosted editor: {}", err.user_message());
+                self.config.clone()
+            }
+        };
+        if crate::HIGH_CONTRAST.load(Ordering::Relaxed) {
+            config.theme.high_contrast = true;
+        }
+        if !config.editor.daemon_hosted {
+            return false;
+        }
+        if self.pending.load(Ordering::SeqCst) > 0 {
+            match config.editor.single_instance {
+                SingleInstance::Focus => {
+                    debug!("hosted editor busy, focusing it");
+                    self.control.focus();
+                    return true;
+                }
+                SingleInstance::Queue => {}
+                SingleInstance::Off => return false,
+            }
+        }
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        if self.jobs.send((config, delay_ms)).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            error!("hosted editor thread is gone");
+            return false;
+        }
+        true
+    }
+}
+
+/// Captures and edits once. Returns the delay to do it again after when the
+/// editor was closed with Recapture.
+fn run_job(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+    control: &EditorControl,
+    cmd_tx: &mpsc::UnboundedSender<DaemonCommand>,
+    delay_ms: u64,
+) -> Option<u64> {
+    let set_state = |state| {
+        let _ = cmd_tx.send(DaemonCommand::SetState(state));
+    };
+    fireshot_core::history::set_enabled(config.history.enabled);
+    let claim = match handle.block_on(instance::claim_editor(
+        config.editor.single_instance,
+        control,
+        &[],
+    )) {
+        Ok(Some(claim)) => claim,
+        Ok(None) => return None,
+        Err(err) => {
+            error!("hosted editor: {}", err);
+            set_state(TrayState::Failed);
+            return None;
+        }
+    };
+    let hooks = Hooks::load(config, "gui");
+    if !hooks.pre_capture() {
+        return None;
+    }
+    if delay_ms > 0 {
+        set_state(TrayState::Countdown);
+        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+        set_state(TrayState::Idle);
+    }
+
+    let mut options = crate::editor_options(handle, config, &hooks);
+    options.control = control.clone();
+    options.window = WindowInfo::focused();
+    options.from_screen = true;
+    let (record, recapture) = (options.record.clone(), options.recapture.clone());
+    let result = handle
+        .block_on(crate::take_capture(config, fireshot_portal::capture_fullscreen()))
+        .and_then(|captured| {
+            options.icc_profile = crate::export_profile(config, &captured);
+            fireshot_gui::run_viewer(captured.image, options)
+        });
+    drop(claim);
+    let result = result.and_then(|mut exports| {
+        if let Some(region) = record.and_then(|record| record.take()) {
+            set_state(TrayState::Recording);
+            let target = RecordTarget::Region(region);
+            match handle.block_on(crate::record::record_screen(config, target, false)) {
+                Ok(export) => exports.push(export),
+                Err(err) if exports.is_empty() => return Err(err),
+                // What the editor already copied or saved still gets finished.
+                Err(err) => error!("hosted editor: recording: {}", err.user_message()),
+            }
+        }
+        Ok(exports)
+    });
+    let exports = match result {
+        Ok(exports) => exports,
+        Err(CaptureError::PortalCancelled) => {
+            set_state(TrayState::Idle);
+            let notifications = config.notifications.clone();
+            handle.spawn(async move {
+                if let Err(err) = notify::notify_cancelled(&notifications).await {
+                    warn!("notification: {}", err);
+                }
+            });
+            return None;
+        }
+        Err(err) => {
+            error!("hosted editor: {}", err.user_message());
+            set_state(TrayState::Failed);
+            return None;
+        }
+    };
+
+    crate::finish_exports_with(config, &hooks, exports, set_state, |notices| {
+        // The daemon outlives the job, so the notices can wait for clicks
+        // without holding up the next capture.
+        let notifications = config.notifications.clone();
+        handle.spawn(async move { notify::notify_exports(&notifications, notices, true).await });
+    });
+    recapture.and_then(|recapture| recapture.take())
+}
@@ -0,0 +1,158 @@
+//! Compatibility shim for `org.flameshot.Flameshot`, so integrations written
+//! for Flameshot's DBus API drive fireshot instead. Each request runs in a
+//! capture process that tells the daemon under the request's id how it went,
+//! and the daemon passes that on as Flameshot's `captureTaken` or
+//! `captureFailed` signal, which those integrations wait for.
+
+use std::sync::Mutex;
+
+use fireshot_core::ExportResult;
+use log::{debug, warn};
+use zbus::{dbus_interface, SignalContext};
+
+use crate::{encode_png, open_dir_in_desktop, spawn_capture_for, CaptureKind};
+
+/// Where Flameshot serves its interface.
+const PATH: &str = "/";
+
+/// The request a capture process answers, from `--flameshot-id`; taken once
+/// it has been answered.
+static REQUEST_ID: Mutex<Option<u32>> = Mutex::new(None);
+
+pub(crate) struct FlameshotCompat;
+
+#[dbus_interface(name = "org.flameshot.Flameshot")]
+impl FlameshotCompat {
+    #[dbus_interface(name = "graphicCapture")]
+    fn graphic_capture(&self, path: String, delay: i32, id: u32) {
+        let path = if path.is_empty() { None } else { Some(path) };
+        let kind = CaptureKind::Gui {
+            delay_ms: delay.max(0) as u64,
+            path,
+        };
+        spawn_capture_for(kind, Some(id));
+    }
+
+    #[dbus_interface(name = "fullScreen")]
+    fn full_screen(&self, path: String, to_clipboard: bool, delay: i32, id: u32) {
+        let path = if path.is_empty() { None } else { Some(path) };
+        let kind = CaptureKind::Full {
+            delay_ms: delay.max(0) as u64,
+            path,
+            edit: false,
+            clipboard: to_clipboard,
+        };
+        spawn_capture_for(kind, Some(id));
+    }
+
+    /// The portal cannot target a specific output, so `number` is ignored and
+    /// the whole screen is captured.
+    #[dbus_interface(name = "captureScreen")]
+    fn capture_screen(&self, _number: i32, path: String, to_clipboard: bool, delay: i32, id: u32) {
+        self.full_screen(path, to_clipboard, delay, id);
+    }
+
+    #[dbus_interface(name = "openLauncher")]
+    fn open_launcher(&self) {
+        spawn_capture_for(CaptureKind::Launcher, None);
+    }
+
+    #[dbus_interface(name = "openConfig")]
+    fn open_config(&self) {
+        open_dir_in_desktop(&fireshot_core::paths::config_dir());
+    }
+
+    /// The capture as PNG, and where it is as Qt's `QRect`.
+    #[dbus_interface(signal, name = "captureTaken")]
+    async fn capture_taken(
+        ctxt: &SignalContext<'_>,
+        id: u32,
+        raw_image: Vec<u8>,
+        selection: (i32, i32, i32, i32),
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal, name = "captureFailed")]
+    async fn capture_failed(ctxt: &SignalContext<'_>, id: u32) -> zbus::Result<()>;
+}
+
+/// Emits `captureTaken`, or `captureFailed` without an image, for a capture
+/// process's report.
+pub(crate) async fn emit(
+    conn: &zbus::Connection,
+    id: u32,
+    png: Vec<u8>,
+    selection: (i32, i32, i32, i32),
+) -> zbus::Result<()> {
+    let ctxt = SignalContext::new(conn, PATH)?;
+    if png.is_empty() {
+        FlameshotCompat::capture_failed(&ctxt, id).await
+    } else {
+        FlameshotCompat::capture_taken(&ctxt, id, png, selection).await
+    }
+}
+
+pub(crate) fn set_request_id(id: u32) {
+    if let Ok(mut slot) = REQUEST_ID.lock() {
+        *slot = Some(id);
+    }
+}
+
+/// Answers the request with the first of `exports` that is an image, or as
+/// failed when none is. Does nothing outside a Flameshot request.
+pub(crate) fn report(rt: &tokio::runtime::Runtime, exports: &[ExportResult]) {
+    let Some(id) = REQUEST_ID.lock().ok().and_then(|mut slot| slot.take()) else {
+        return;
+    };
+    let image = exports.iter().find_map(exported_image);
+    let (png, selection) = match image.map(|image| (encode_png(&image), image)) {
+        Some((Ok(png), image)) => (png, (0, 0, image.width() as i32, image.height() as i32)),
+        Some((Err(err), _)) => {
+            warn!("flameshot compat: {}", err);
+            (Vec::new(), (0, 0, 0, 0))
+        }
+        None => (Vec::new(), (0, 0, 0, 0)),
+    };
+    send(rt, id, png, selection);
+}
+
+/// Answers a request the capture never got to report on, such as one that
+/// was cancelled or failed.
+pub(crate) fn report_failed(rt: &tokio::runtime::Runtime) {
+    if let Some(id) = REQUEST_ID.lock().ok().and_then(|mut slot| slot.take()) {
+        send(rt, id, Vec::new(), (0, 0, 0, 0));
+    }
+}
+
+/// The image behind an export, read back from where it went. The editor
+/// hands back where the capture went rather than the image itself.
+fn exported_image(export: &ExportResult) -> Option<image::DynamicImage> {
+    match export {
+        ExportResult::Saved { path }
+        | ExportResult::Emailed { path }
+        | ExportResult::Copied {
+            history_path: Some(path),
+        } => image::open(path).ok(),
+        ExportResult::Copied { history_path: None } => fireshot_gui::paste_image_from_clipboard()
+            .ok()
+            .map(image::DynamicImage::ImageRgba8),
+        ExportResult::Recorded { .. } | ExportResult::Shared { .. } => None,
+    }
+}
+
+fn send(rt: &tokio::runtime::Runtime, id: u32, png: Vec<u8>, selection: (i32, i32, i32, i32)) {
+    let result = rt.block_on(async {
+        let conn = zbus::Connection::session().await?;
+        conn.call_method(
+            Some("org.fireshot.Fireshot"),
+            "/org/fireshot/Fireshot",
+            Some("org.fireshot.Fireshot"),
+            "ReportFlameshot",
+            &(id, png, selection),
+        )
+        .await?;
+        Ok::<(), zbus::Error>(())
+    });
+    if let Err(err) = result {
+        debug!("flameshot compat: report {}: {}", id, err);
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use fireshot_core::config::Config;
+use fireshot_core::ExportResult;
+use fireshot_gui::PostRender;
+use image::{DynamicImage, RgbaImage};
+use log::{debug, warn};
+
+/// The user's hook script (`[hooks] script`), set up for one command such as
+/// `gui` or `full`. Without a script, or in builds without the `scripting`
+/// feature, every hook does nothing.
+#[derive(Clone)]
+pub(crate) struct Hooks {
+    script: Option<Arc<script::Script>>,
+    command: &'static str,
+}
+
+impl Hooks {
+    pub(crate) fn load(config: &Config, command: &'static str) -> Self {
+        let path = config.hooks.script_path();
+        let script = if path.exists() {
+            script::Script::load(&path).map(Arc::new)
+        } else {
+            None
+        };
+        Self { script, command }
+    }
+
+    /// Whether the capture goes ahead; `pre_capture` returning `false` skips
+    /// it.
+    pub(crate) fn pre_capture(&self) -> bool {
+        let go = self
+            .script
+            .as_ref()
+            .is_none_or(|script| script.pre_capture(self.command));
+        if !go {
+            debug!("hooks: pre_capture skipped {}", self.command);
+        }
+        go
+    }
+
+    /// Lets `post_render` change an image before it is copied or saved.
+    pub(crate) fn post_render(&self, image: &mut RgbaImage) {
+        if let Some(script) = &self.script {
+            script.post_render(image, self.command);
+        }
+    }
+
+    /// `post_render` for the editor's exports, if the script has one.
+    pub(crate) fn post_render_hook(&self) -> Option<PostRender> {
+        if !self.script.as_ref()?.has("post_render") {
+            return None;
+        }
+        let hooks = self.clone();
+        Some(Arc::new(move |image: &mut RgbaImage| hooks.post_render(image)))
+    }
+
+    /// A capture exported without the editor, after `post_render`; `None`
+    /// when the script has no `post_render`.
+    pub(crate) fn post_render_capture(&self, image: &DynamicImage) -> Option<DynamicImage> {
+        let hook = self.post_render_hook()?;
+        let mut rgba = image.to_rgba8();
+        hook(&mut rgba);
+        Some(DynamicImage::ImageRgba8(rgba))
+    }
+
+    /// Runs `post_save` on every saved file. A string it returns renames the
+    /// file; names without a directory stay next to the original.
+    pub(crate) fn post_save(&self, exports: Vec<ExportResult>) -> Vec<ExportResult> {
+        let Some(script) = &self.script else {
+            return exports;
+        };
+        exports
+            .into_iter()
+            .map(|export| match export {
+                ExportResult::Saved { path } => ExportResult::Saved {
+                    path: rename(script.post_save(&path, self.command), path),
+                },
+                ExportResult::Recorded { path } => ExportResult::Recorded {
+                    path: rename(script.post_save(&path, self.command), path),
+                },
+                export => export,
+            })
+            .collect()
+    }
+}
+
+fn rename(name: Option<String>, path: std::path::PathBuf) -> std::path::PathBuf {
+    let Some(name) = name.filter(|name| !name.trim().is_empty()) else {
+        return path;
+    };
+    let target = match path.parent() {
+        Some(dir) => dir.join(name.trim()),
+        None => name.trim().into(),
+    };
+    match std::fs::rename(&path, &target) {
+        Ok(()) => target,
+        Err(err) => {
+            warn!("hooks: rename {} to {}: {}", path.display(), target.display(), err);
+            path
+        }
+    }
+}
+
+/// Parses `#rrggbb` (the `#` is optional).
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(feature = "scripting")]
+mod script {
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    use image::{Rgba, RgbaImage};
+    use log::warn;
+    use rhai::{Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST};
+
+    use super::parse_color;
+
+    pub(super) struct Script {
+        engine: Engine,
+        ast: AST,
+    }
+
+    /// The image a `post_render` call works on; clones share it.
+    #[derive(Clone)]
+    struct ScriptImage(Arc<Mutex<RgbaImage>>);
+
+    impl ScriptImage {
+        fn with<T>(&mut self, f: impl FnOnce(&mut RgbaImage) -> T) -> T {
+            let mut image = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut image)
+        }
+
+        fn fill(&mut self, x: i64, y: i64, width: i64, height: i64, color: &str) -> Result<(), Box<EvalAltResult>> {
+            let [r, g, b] = parse_color(color).ok_or_else(|| format!("expected #rrggbb, got {:?}", color))?;
+            self.with(|image| {
+                let clamp = |value: i64, max: u32| value.clamp(0, i64::from(max)) as u32;
+                let (x0, y0) = (clamp(x, image.width()), clamp(y, image.height()));
+                let x1 = clamp(x.saturating_add(width), image.width());
+                let y1 = clamp(y.saturating_add(height), image.height());
+                for py in y0..y1 {
+                    for px in x0..x1 {
+                        image.put_pixel(px, py, Rgba([r, g, b, 255]));
+                    }
+                }
+            });
+            Ok(())
+        }
+    }
+
+    fn register_image(engine: &mut Engine) {
+        engine
+            .register_type_with_name::<ScriptImage>("Image")
+            .register_get("width", |image: &mut ScriptImage| image.with(|image| i64::from(image.width())))
+            .register_get("height", |image: &mut ScriptImage| image.with(|image| i64::from(image.height())))
+            .register_fn("fill", ScriptImage::fill)
+            .register_fn("redact", |image: &mut ScriptImage, x: i64, y: i64, width: i64, height: i64| {
+                image.fill(x, y, width, height, "#000000")
+            })
+            .register_fn("get_pixel", |image: &mut ScriptImage, x: i64, y: i64| {
+                image.with(|image| {
+                    let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+                        return String::new();
+                    };
+                    image
+                        .get_pixel_checked(x, y)
+                        .map(|px| format!("#{:02x}{:02x}{:02x}", px[0], px[1], px[2]))
+                        .unwrap_or_default()
+                })
+            })
+            .register_fn("set_pixel", |image: &mut ScriptImage, x: i64, y: i64, color: &str| {
+                image.fill(x, y, 1, 1, color)
+            });
+    }
+
+    /// What every hook gets to know about the capture.
+    fn info(command: &str) -> Map {
+        let mut info = Map::new();
+        info.insert("command".into(), command.to_string().into());
+        let time = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        info.insert("time".into(), time.into());
+        info
+    }
+
+    impl Script {
+        pub(super) fn load(path: &Path) -> Option<Self> {
+            let mut engine = Engine::new();
+            register_image(&mut engine);
+            match engine.compile_file(path.to_path_buf()) {
+                Ok(ast) => Some(Self { engine, ast }),
+                Err(err) => {
+                    warn!("hooks: {}: {}", path.display(), err);
+                    None
+                }
+            }
+        }
+
+        pub(super) fn has(&self, name: &str) -> bool {
+            self.ast.iter_functions().any(|f| f.name == name)
+        }
+
+        /// Calls `name` if the script defines it. Errors are logged and
+        /// treated like a hook that returned nothing.
+        fn call(&self, name: &str, args: impl FuncArgs) -> Option<Dynamic> {
+            if !self.has(name) {
+                return None;
+            }
+            match self.engine.call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    warn!("hooks: {}: {}", name, err);
+                    None
+                }
+            }
+        }
+
+        pub(super) fn pre_capture(&self, command: &str) -> bool {
+            self.call("pre_capture", (info(command),))
+                .and_then(|value| value.as_bool().ok())
+                .unwrap_or(true)
+        }
+
+        pub(super) fn post_render(&self, image: &mut RgbaImage, command: &str) {
+            if !self.has("post_render") {
+                return;
+            }
+            let mut shared = ScriptImage(Arc::new(Mutex::new(std::mem::take(image))));
+            self.call("post_render", (shared.clone(), info(command)));
+            *image = shared.with(std::mem::take);
+        }
+
+        pub(super) fn post_save(&self, path: &Path, command: &str) -> Option<String> {
+            let mut info = info(command);
+            info.insert("path".into(), path.display().to_string().into());
+            self.call("post_save", (path.display().to_string(), info))?
+                .into_string()
+                .ok()
+        }
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+mod script {
+    use std::path::Path;
+
+    use image::RgbaImage;
+    use log::warn;
+
+    pub(super) struct Script;
+
+    impl Script {
+        pub(super) fn load(path: &Path) -> Option<Self> {
+            warn!(
+                "hooks: ignoring {}; this build has no scripting (enable the `scripting` feature)",
+                path.display()
+            );
+            None
+        }
+
+        pub(super) fn has(&self, _name: &str) -> bool {
+            false
+        }
+
+        pub(super) fn pre_capture(&self, _command: &str) -> bool {
+            true
+        }
+
+        pub(super) fn post_render(&self, _image: &mut RgbaImage, _command: &str) {}
+
+        pub(super) fn post_save(&self, _path: &Path, _command: &str) -> Option<String> {
+            None
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+
+use fireshot_core::config::SingleInstance;
+use fireshot_core::CaptureError;
+use fireshot_gui::EditorControl;
+use log::{debug, warn};
+use zbus::fdo::{RequestNameFlags, RequestNameReply};
+use zbus::dbus_interface;
+
+const EDITOR_BUS_NAME: &str = "org.fireshot.Fireshot.Editor";
+const EDITOR_OBJECT_PATH: &str = "/org/fireshot/Editor";
+/// Every open editor also owns a name under this prefix, whatever
+/// `single_instance` is, for captures to hide it.
+const WINDOW_BUS_PREFIX: &str = "org.fireshot.Fireshot.Window.";
+/// How long hidden editors get to leave the screen before a capture.
+const HIDE_SETTLE_MS: u64 = 250;
+/// Poll interval while queued behind another editor.
+const QUEUE_POLL_MS: u64 = 300;
+
+struct EditorInstance {
+    control: EditorControl,
+}
+
+#[dbus_interface(name = "org.fireshot.Editor")]
+impl EditorInstance {
+    fn focus(&self) {
+        self.control.focus();
+    }
+
+    /// Gets the editor out of the way of a capture; `false` when none is
+    /// open.
+    fn hide(&self) -> bool {
+        self.control.hide()
+    }
+
+    fn show(&self) {
+        self.control.show();
+    }
+
+    /// Opens the image at `path` in a new tab. Returns `false` when it
+    /// can't be read.
+    fn open(&self, path: String) -> bool {
+        match image::open(&path) {
+            Ok(image) => self.control.open_tab(tab_title(Path::new(&path)), image.to_rgba8()),
+            Err(err) => {
+                warn!("{}: {}", path, err);
+                false
+            }
+        }
+    }
+}
+
+/// The tab name for an image opened from `path`: its file name.
+pub fn tab_title(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// Keeps the editor bus name owned for as long as it is alive.
+pub struct EditorClaim {
+    _conn: Option<zbus::Connection>,
+}
+
+/// Claims the single editor slot before capturing. Returns `None` when the
+/// request was forwarded to an editor that is already open, which opens the
+/// images at `open` in new tabs or else is raised.
+pub async fn claim_editor(
+    mode: SingleInstance,
+    control: &EditorControl,
+    open: &[PathBuf],
+) -> Result<Option<EditorClaim>, CaptureError> {
+    let conn = match serve_editor(control).await {
+        Ok(conn) => conn,
+        Err(err) if mode == SingleInstance::Off => {
+            debug!("editor not on the bus: {}", err);
+            return Ok(Some(EditorClaim { _conn: None }));
+        }
+        Err(err) => return Err(err),
+    };
+    if mode == SingleInstance::Off {
+        return Ok(Some(claimed(conn).await));
+    }
+
+    loop {
+        let reply = conn
+            .request_name_with_flags(EDITOR_BUS_NAME, RequestNameFlags::DoNotQueue.into())
+            .await
+            .map_err(|e| CaptureError::Io(e.to_string()))?;
+        match reply {
+            RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner => {
+                return Ok(Some(claimed(conn).await));
+            }
+            RequestNameReply::Exists | RequestNameReply::InQueue => {}
+        }
+        match mode {
+            SingleInstance::Focus if !open.is_empty() => {
+                debug!("editor already open, adding tabs to it");
+                for path in open {
+                    conn.call_method(
+                        Some(EDITOR_BUS_NAME),
+                        EDITOR_OBJECT_PATH,
+                        Some("org.fireshot.Editor"),
+                        "Open",
+                        &(path.to_string_lossy().as_ref(),),
+                    )
+                    .await
+                    .map_err(|e| CaptureError::Io(e.to_string()))?;
+                }
+                return Ok(None);
+            }
+            SingleInstance::Focus => {
+                debug!("editor already open, focusing it");
+                conn.call_method(
+                    Some(EDITOR_BUS_NAME),
+                    EDITOR_OBJECT_PATH,
+                    Some("org.fireshot.Editor"),
+                    "Focus",
+                    &(),
+                )
+                .await
+                .map_err(|e| CaptureError::Io(e.to_string()))?;
+                return Ok(None);
+            }
+            SingleInstance::Queue => {
+                tokio::time::sleep(std::time::Duration::from_millis(QUEUE_POLL_MS)).await;
+            }
+            SingleInstance::Off => unreachable!(),
+        }
+    }
+}
+
+async fn serve_editor(control: &EditorControl) -> Result<zbus::Connection, CaptureError> {
+    let conn = zbus::Connection::session()
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    conn.object_server()
+        .at(
+            EDITOR_OBJECT_PATH,
+            EditorInstance {
+                control: control.clone(),
+            },
+        )
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    Ok(conn)
+}
+
+/// The claim on `conn`, which also takes this process's window name so
+/// `hide_editors` finds the editor.
+async fn claimed(conn: zbus::Connection) -> EditorClaim {
+    let name = format!("{}p{}", WINDOW_BUS_PREFIX, std::process::id());
+    if let Err(err) = conn
+        .request_name_with_flags(name.as_str(), RequestNameFlags::DoNotQueue.into())
+        .await
+    {
+        debug!("{}: {}", name, err);
+    }
+    EditorClaim { _conn: Some(conn) }
+}
+
+/// Hides every open fireshot editor, in this process or others, so a
+/// capture doesn't show them, and waits for them to leave the screen.
+/// Returns the names to hand `show_editors` afterwards.
+pub async fn hide_editors() -> Vec<String> {
+    let Ok(conn) = zbus::Connection::session().await else {
+        return Vec::new();
+    };
+    let names = match zbus::fdo::DBusProxy::new(&conn).await {
+        Ok(proxy) => proxy.list_names().await.unwrap_or_default(),
+        Err(_) => return Vec::new(),
+    };
+    let mut hidden = Vec::new();
+    for name in names {
+        if !name.starts_with(WINDOW_BUS_PREFIX) {
+            continue;
+        }
+        let reply = conn
+            .call_method(
+                Some(name.as_str()),
+                EDITOR_OBJECT_PATH,
+                Some("org.fireshot.Editor"),
+                "Hide",
+                &(),
+            )
+            .await;
+        if reply.is_ok_and(|reply| reply.body::<bool>().unwrap_or(false)) {
+            hidden.push(name.to_string());
+        }
+    }
+    if !hidden.is_empty() {
+        debug!("hid {} editor(s) for the capture", hidden.len());
+        tokio::time::sleep(std::time::Duration::from_millis(HIDE_SETTLE_MS)).await;
+    }
+    hidden
+}
+
+/// Brings back the editors `hide_editors` hid.
+pub async fn show_editors(names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    let Ok(conn) = zbus::Connection::session().await else {
+        return;
+    };
+    for name in names {
+        let shown = conn
+            .call_method(
+                Some(name.as_str()),
+                EDITOR_OBJECT_PATH,
+                Some("org.fireshot.Editor"),
+                "Show",
+                &(),
+            )
+            .await;
+        if let Err(err) = shown {
+            debug!("{}: {}", name, err);
+        }
+    }
+}
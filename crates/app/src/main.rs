@@ -1,24 +1,64 @@
-use clap::{CommandFactory, Parser, Subcommand};
-use fireshot_core::{CaptureError, CaptureRequest};
-use ksni::menu::{MenuItem, StandardItem};
-use ksni::{Tray, TrayService};
-use log::{debug, error};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use fireshot_core::config::{Config, HotkeyConfig, Matte, ThemeMode};
+use fireshot_core::config::RecordFormat;
+use fireshot_core::snippet::SnippetFormat;
+use fireshot_core::window::WindowInfo;
+use fireshot_core::{tr, CaptureError, CaptureMode, ExportResult, RecordRegion, RecordTarget};
+use fireshot_portal::GlobalShortcut;
+use ksni::TrayService;
+use log::{debug, error, warn};
 use tokio::sync::{mpsc, oneshot};
 use zbus::dbus_interface;
 
+mod annotate;
+mod autostart;
+mod doctor;
+mod editor_host;
+mod flameshot;
+mod hooks;
+mod instance;
+mod notify;
+mod record;
+mod sound;
+mod systemd;
+mod tray;
+mod uri;
+
+use editor_host::EditorHost;
+use flameshot::FlameshotCompat;
+use hooks::Hooks;
+use tray::{recent_captures, FireshotTray, TrayState, TRAY_REFRESH_SECS, TRAY_TRANSIENT_SECS};
+
 #[derive(Parser)]
 #[command(
     name = "fireshot",
     version,
     about = "Wayland-first screenshot app",
-    after_help = "Examples:\n  fireshot gui\n  fireshot gui -d 2000 -p /tmp/cap.png\n  fireshot full -p /tmp/cap.png\n  fireshot full --edit\n\nPortal notes:\n  Requires xdg-desktop-portal and a backend (wlr/gnome/kde).",
     arg_required_else_help = true
 )]
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+    /// Print how long each stage took when the command finishes.
+    #[arg(long, global = true, default_value_t = false)]
+    profile: bool,
+    /// Use the high-contrast editor theme.
+    #[arg(long, global = true, default_value_t = false)]
+    high_contrast: bool,
+    /// Keep captures out of the history and crash recovery, for sensitive
+    /// ones.
+    #[arg(long, global = true, default_value_t = false)]
+    incognito: bool,
+    /// Answer this Flameshot DBus request once done; set by the daemon.
+    #[arg(long, global = true, hide = true)]
+    flameshot_id: Option<u32>,
 }
 
+/// Set by `--high-contrast` and passed on to captures the daemon spawns.
+static HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+
 #[derive(Subcommand)]
 enum Command {
     /// Capture fullscreen for editor (selection happens in the editor).
@@ -29,6 +69,22 @@ enum Command {
         /// Save the capture to a path.
         #[arg(short, long)]
         path: Option<String>,
+        /// Start with this `[[preset]]` region selected, or save only it
+        /// with --path.
+        #[arg(long, value_name = "NAME", conflicts_with = "region")]
+        region_preset: Option<String>,
+        /// Start with this region selected, as X,Y,WIDTHxHEIGHT, or save
+        /// only it with --path.
+        #[arg(long, value_parser = parse_region)]
+        region: Option<RecordRegion>,
+        /// Export right away without waiting for a click: `copy`, or
+        /// `save:PATH`.
+        #[arg(long, value_parser = parse_accept, conflicts_with = "path")]
+        accept: Option<fireshot_gui::Accept>,
+        /// Show only the selection overlay and copy the area as soon as it
+        /// is picked, without the annotation tools.
+        #[arg(long, conflicts_with_all = ["path", "accept"])]
+        copy_region: bool,
     },
     /// Capture and save without opening the editor.
     Full {
@@ -41,105 +97,1337 @@ enum Command {
         /// Open the editor after capture.
         #[arg(long, default_value_t = false)]
         edit: bool,
+        /// Copy the capture to the clipboard.
+        #[arg(short, long, default_value_t = false)]
+        clipboard: bool,
+        /// Capture only this `[[preset]]` region.
+        #[arg(long, value_name = "NAME")]
+        region_preset: Option<String>,
+    },
+    /// Open image files, or the image on the clipboard, in the editor.
+    Edit {
+        /// The images to annotate, each in a tab of its own.
+        #[arg(required_unless_present = "from_clipboard", conflicts_with = "from_clipboard")]
+        file: Vec<String>,
+        /// Edit the image on the clipboard, e.g. a screenshot from another
+        /// tool.
+        #[arg(long)]
+        from_clipboard: bool,
+    },
+    /// Open a small window with capture mode buttons.
+    Launcher,
+    /// Compare two captures side by side or as a diff heatmap.
+    Compare {
+        /// The first capture: an image file, or @N for the Nth newest capture
+        /// in the history.
+        before: String,
+        /// The second capture, like BEFORE; a new full-screen capture when
+        /// left out.
+        after: Option<String>,
+        /// Start with (or write) the diff heatmap instead of the side-by-side
+        /// view.
+        #[arg(long)]
+        diff: bool,
+        /// Ignore channel differences up to this much (0-255), such as
+        /// compression noise.
+        #[arg(long, default_value_t = 0)]
+        threshold: u8,
+        /// Write the comparison to a path instead of opening the window.
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Delay in milliseconds before capturing AFTER.
+        #[arg(short, long, default_value_t = 0)]
+        delay: u64,
+    },
+    /// Draw arrows, text, boxes or blur onto an image without opening the
+    /// editor.
+    Annotate {
+        /// The image: a file, or @N for the Nth newest capture in the
+        /// history.
+        input: String,
+        /// Where to write the annotated image.
+        #[arg(short, long)]
+        output: std::path::PathBuf,
+        /// An arrow from the first point to the second.
+        #[arg(long, value_name = "X,Y:X,Y", value_parser = annotate::parse_segment)]
+        arrow: Vec<annotate::Segment>,
+        /// A line between two points.
+        #[arg(long, value_name = "X,Y:X,Y", value_parser = annotate::parse_segment)]
+        line: Vec<annotate::Segment>,
+        /// A box between two corners.
+        #[arg(long, value_name = "X,Y:X,Y", value_parser = annotate::parse_segment)]
+        rect: Vec<annotate::Segment>,
+        /// An ellipse inside the box between two corners.
+        #[arg(long, value_name = "X,Y:X,Y", value_parser = annotate::parse_segment)]
+        circle: Vec<annotate::Segment>,
+        /// Text with its top-left corner at the point.
+        #[arg(long, value_name = "X,Y:TEXT", value_parser = annotate::parse_label)]
+        text: Vec<annotate::Label>,
+        /// Blur the box between two corners.
+        #[arg(long, value_name = "X,Y:X,Y", value_parser = annotate::parse_segment)]
+        blur: Vec<annotate::Segment>,
+        /// Pixelate the box between two corners.
+        #[arg(long, value_name = "X,Y:X,Y", value_parser = annotate::parse_segment)]
+        pixelate: Vec<annotate::Segment>,
+        /// Colour of everything drawn, as #rrggbb.
+        #[arg(long, default_value = "#ff0000", value_parser = annotate::parse_color)]
+        color: fireshot_core::shapes::Color32,
+        /// Line width and text and effect strength, as the editor's tool
+        /// size.
+        #[arg(long, default_value_t = 3.0)]
+        size: f32,
+    },
+    /// Record an output, window or region until stopped from the tray or
+    /// with Ctrl+C.
+    Record {
+        /// Delay in milliseconds before starting the recording.
+        #[arg(short, long, default_value_t = 0)]
+        delay: u64,
+        /// Record a window instead of a whole output.
+        #[arg(long, conflicts_with_all = ["region", "region_preset"])]
+        window: bool,
+        /// Record only this region of the output, as X,Y,WIDTH,HEIGHT.
+        #[arg(long, value_parser = parse_region, conflicts_with = "region_preset")]
+        region: Option<RecordRegion>,
+        /// Record only this `[[preset]]` region.
+        #[arg(long, value_name = "NAME")]
+        region_preset: Option<String>,
+        /// Write gif, mp4 or webm instead of `[record] format`.
+        #[arg(long, value_parser = parse_format)]
+        format: Option<RecordFormat>,
+        /// Record the microphone too (video formats only).
+        #[arg(long)]
+        audio: bool,
+    },
+    /// Copy a Markdown, HTML or reStructuredText snippet showing an image.
+    Snippet {
+        /// A file, a URL, or @N for the Nth newest capture in the history.
+        target: String,
+        /// markdown, html or rst instead of `[snippet] format`.
+        #[arg(long, value_parser = parse_snippet_format)]
+        format: Option<SnippetFormat>,
+        /// Alt text instead of the `[snippet] alt` template.
+        #[arg(long)]
+        alt: Option<String>,
+    },
+    /// Put the newest capture in the history back on the clipboard, e.g.
+    /// after a clipboard manager lost it.
+    Recopy,
+    /// Start the capture a fireshot:// link asks for, through the daemon.
+    OpenUri {
+        /// e.g. fireshot://capture?mode=gui&delay=2000
+        uri: String,
+    },
+    /// Search or delete the captures kept in the history.
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    /// Number every capture into a folder until stopped, e.g. for a how-to
+    /// guide.
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
     },
     /// Run DBus daemon to handle capture requests.
-    Daemon,
+    Daemon {
+        /// Start the daemon at login instead of running it now.
+        #[arg(long, conflicts_with = "remove_autostart")]
+        install_autostart: bool,
+        /// Remove the files written by --install-autostart.
+        #[arg(long)]
+        remove_autostart: bool,
+        /// With --install-autostart, write a systemd user unit instead of an
+        /// XDG autostart entry.
+        #[arg(long, requires = "install_autostart")]
+        systemd: bool,
+        /// With --install-autostart, skip the login entry and let the first
+        /// DBus call start the daemon.
+        #[arg(long, requires = "install_autostart")]
+        on_demand: bool,
+    },
     /// Print portal and environment diagnostics.
     Diagnose,
+    /// Run the diagnostics plus clipboard, tool and save directory checks,
+    /// with suggested fixes.
+    Doctor,
 }
 
-fn main() -> Result<(), CaptureError> {
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// Delete every capture in the history.
+    Clear,
+    /// List the captures whose text, read with `[history] ocr`, contains
+    /// QUERY.
+    Search { query: String },
+}
+
+#[derive(Subcommand)]
+enum SessionAction {
+    /// Start a session; every capture until `session stop` is also saved
+    /// into the folder as step-01.png, step-02.png, ...
+    Start {
+        /// The folder for the steps; a new one in the save directory when
+        /// left out.
+        dir: Option<std::path::PathBuf>,
+    },
+    /// End the session.
+    Stop {
+        /// Compile the steps into one numbered contact-sheet.png.
+        #[arg(long)]
+        contact_sheet: bool,
+        /// Write steps.md, a markdown snippet showing every step.
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// Print the running session's folder and step count.
+    Status,
+}
+
+/// The derived command with help texts from the locale catalog.
+fn localized_command() -> clap::Command {
+    Cli::command()
+        .about(tr!("cli-about"))
+        .after_help(tr!("cli-after-help"))
+        .mut_arg("profile", |arg| arg.help(tr!("cli-arg-profile")))
+        .mut_arg("high_contrast", |arg| arg.help(tr!("cli-arg-high-contrast")))
+        .mut_arg("incognito", |arg| arg.help(tr!("cli-arg-incognito")))
+        .mut_subcommand("gui", |cmd| {
+            cmd.about(tr!("cli-gui"))
+                .mut_arg("delay", |arg| arg.help(tr!("cli-arg-delay")))
+                .mut_arg("path", |arg| arg.help(tr!("cli-arg-path")))
+                .mut_arg("region_preset", |arg| arg.help(tr!("cli-arg-gui-region-preset")))
+                .mut_arg("region", |arg| arg.help(tr!("cli-arg-gui-region")))
+                .mut_arg("accept", |arg| arg.help(tr!("cli-arg-accept")))
+                .mut_arg("copy_region", |arg| arg.help(tr!("cli-arg-copy-region")))
+        })
+        .mut_subcommand("full", |cmd| {
+            cmd.about(tr!("cli-full"))
+                .mut_arg("delay", |arg| arg.help(tr!("cli-arg-delay")))
+                .mut_arg("path", |arg| arg.help(tr!("cli-arg-path")))
+                .mut_arg("edit", |arg| arg.help(tr!("cli-arg-edit")))
+                .mut_arg("clipboard", |arg| arg.help(tr!("cli-arg-clipboard")))
+                .mut_arg("region_preset", |arg| arg.help(tr!("cli-arg-region-preset")))
+        })
+        .mut_subcommand("edit", |cmd| {
+            cmd.about(tr!("cli-edit"))
+                .mut_arg("file", |arg| arg.help(tr!("cli-arg-file")))
+                .mut_arg("from_clipboard", |arg| arg.help(tr!("cli-arg-from-clipboard")))
+        })
+        .mut_subcommand("launcher", |cmd| cmd.about(tr!("cli-launcher")))
+        .mut_subcommand("compare", |cmd| {
+            cmd.about(tr!("cli-compare"))
+                .mut_arg("before", |arg| arg.help(tr!("cli-arg-before")))
+                .mut_arg("after", |arg| arg.help(tr!("cli-arg-after")))
+                .mut_arg("diff", |arg| arg.help(tr!("cli-arg-diff")))
+                .mut_arg("threshold", |arg| arg.help(tr!("cli-arg-threshold")))
+                .mut_arg("path", |arg| arg.help(tr!("cli-arg-compare-path")))
+                .mut_arg("delay", |arg| arg.help(tr!("cli-arg-delay")))
+        })
+        .mut_subcommand("annotate", |cmd| {
+            cmd.about(tr!("cli-annotate"))
+                .mut_arg("input", |arg| arg.help(tr!("cli-arg-annotate-input")))
+                .mut_arg("output", |arg| arg.help(tr!("cli-arg-annotate-output")))
+                .mut_arg("arrow", |arg| arg.help(tr!("cli-arg-annotate-arrow")))
+                .mut_arg("line", |arg| arg.help(tr!("cli-arg-annotate-line")))
+                .mut_arg("rect", |arg| arg.help(tr!("cli-arg-annotate-rect")))
+                .mut_arg("circle", |arg| arg.help(tr!("cli-arg-annotate-circle")))
+                .mut_arg("text", |arg| arg.help(tr!("cli-arg-annotate-text")))
+                .mut_arg("blur", |arg| arg.help(tr!("cli-arg-annotate-blur")))
+                .mut_arg("pixelate", |arg| arg.help(tr!("cli-arg-annotate-pixelate")))
+                .mut_arg("color", |arg| arg.help(tr!("cli-arg-annotate-color")))
+                .mut_arg("size", |arg| arg.help(tr!("cli-arg-annotate-size")))
+        })
+        .mut_subcommand("record", |cmd| {
+            cmd.about(tr!("cli-record"))
+                .mut_arg("delay", |arg| arg.help(tr!("cli-arg-record-delay")))
+                .mut_arg("window", |arg| arg.help(tr!("cli-arg-window")))
+                .mut_arg("region", |arg| arg.help(tr!("cli-arg-region")))
+                .mut_arg("region_preset", |arg| arg.help(tr!("cli-arg-record-region-preset")))
+                .mut_arg("format", |arg| arg.help(tr!("cli-arg-format")))
+                .mut_arg("audio", |arg| arg.help(tr!("cli-arg-audio")))
+        })
+        .mut_subcommand("snippet", |cmd| {
+            cmd.about(tr!("cli-snippet"))
+                .mut_arg("target", |arg| arg.help(tr!("cli-arg-snippet-target")))
+                .mut_arg("format", |arg| arg.help(tr!("cli-arg-snippet-format")))
+                .mut_arg("alt", |arg| arg.help(tr!("cli-arg-alt")))
+        })
+        .mut_subcommand("recopy", |cmd| cmd.about(tr!("cli-recopy")))
+        .mut_subcommand("open-uri", |cmd| {
+            cmd.about(tr!("cli-open-uri"))
+                .mut_arg("uri", |arg| arg.help(tr!("cli-arg-uri")))
+        })
+        .mut_subcommand("history", |cmd| {
+            cmd.about(tr!("cli-history"))
+                .mut_subcommand("clear", |cmd| cmd.about(tr!("cli-history-clear")))
+                .mut_subcommand("search", |cmd| {
+                    cmd.about(tr!("cli-history-search"))
+                        .mut_arg("query", |arg| arg.help(tr!("cli-arg-history-query")))
+                })
+        })
+        .mut_subcommand("session", |cmd| {
+            cmd.about(tr!("cli-session"))
+                .mut_subcommand("start", |cmd| {
+                    cmd.about(tr!("cli-session-start"))
+                        .mut_arg("dir", |arg| arg.help(tr!("cli-arg-session-dir")))
+                })
+                .mut_subcommand("stop", |cmd| {
+                    cmd.about(tr!("cli-session-stop"))
+                        .mut_arg("contact_sheet", |arg| arg.help(tr!("cli-arg-contact-sheet")))
+                        .mut_arg("markdown", |arg| arg.help(tr!("cli-arg-markdown")))
+                })
+                .mut_subcommand("status", |cmd| cmd.about(tr!("cli-session-status")))
+        })
+        .mut_subcommand("daemon", |cmd| {
+            cmd.about(tr!("cli-daemon"))
+                .mut_arg("install_autostart", |arg| {
+                    arg.help(tr!("cli-arg-install-autostart"))
+                })
+                .mut_arg("remove_autostart", |arg| {
+                    arg.help(tr!("cli-arg-remove-autostart"))
+                })
+                .mut_arg("systemd", |arg| arg.help(tr!("cli-arg-systemd")))
+                .mut_arg("on_demand", |arg| arg.help(tr!("cli-arg-on-demand")))
+        })
+        .mut_subcommand("diagnose", |cmd| cmd.about(tr!("cli-diagnose")))
+        .mut_subcommand("doctor", |cmd| cmd.about(tr!("cli-doctor")))
+}
+
+/// X,Y,WIDTH,HEIGHT, or X,Y,WIDTHxHEIGHT.
+fn parse_region(value: &str) -> Result<RecordRegion, String> {
+    let parts: Vec<u32> = value
+        .split([',', 'x'])
+        .map(|part| part.trim().parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|err| err.to_string())?;
+    match parts[..] {
+        [x, y, width, height] if width > 0 && height > 0 => Ok(RecordRegion {
+            x,
+            y,
+            width,
+            height,
+        }),
+        _ => Err("expected X,Y,WIDTH,HEIGHT with a non-zero size".to_string()),
+    }
+}
+
+fn parse_accept(value: &str) -> Result<fireshot_gui::Accept, String> {
+    match value.split_once(':') {
+        None if value == "copy" => Ok(fireshot_gui::Accept::Copy),
+        Some(("save", path)) if !path.is_empty() => Ok(fireshot_gui::Accept::Save(path.into())),
+        _ => Err("expected copy or save:PATH".to_string()),
+    }
+}
+
+/// The region of the `[[preset]]` called `name`.
+fn region_preset(config: &Config, name: Option<&str>) -> Result<Option<RecordRegion>, CaptureError> {
+    name.map(|name| {
+        config
+            .region_preset(name)
+            .map(|preset| preset.region)
+            .ok_or_else(|| CaptureError::Io(format!("{}: no such region preset", name)))
+    })
+    .transpose()
+}
+
+fn parse_format(value: &str) -> Result<RecordFormat, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "gif" => Ok(RecordFormat::Gif),
+        "mp4" => Ok(RecordFormat::Mp4),
+        "webm" => Ok(RecordFormat::Webm),
+        _ => Err("expected gif, mp4 or webm".to_string()),
+    }
+}
+
+fn parse_snippet_format(value: &str) -> Result<SnippetFormat, String> {
+    SnippetFormat::parse(value).ok_or_else(|| "expected markdown, html or rst".to_string())
+}
+
+fn main() -> std::process::ExitCode {
+    match run() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", tr!("cli-error", error = err.user_message()));
+            std::process::ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<(), CaptureError> {
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .map_err(|e| CaptureError::Io(e.to_string()))?;
 
-    let cli = Cli::parse();
+    let started = std::time::Instant::now();
+    let cli = Cli::from_arg_matches(&localized_command().get_matches())
+        .unwrap_or_else(|err| err.exit());
+    let print_profile = cli.profile;
     let Some(command) = cli.command else {
-        Cli::command().print_help().ok();
+        localized_command().print_help().ok();
         println!();
         return Ok(());
     };
+    let mut config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{}", tr!("cli-config-error", error = err.user_message()));
+        Config::default()
+    });
+    if cli.high_contrast {
+        config.theme.high_contrast = true;
+        HIGH_CONTRAST.store(true, Ordering::Relaxed);
+    }
+    fireshot_core::history::set_enabled(config.history.enabled);
+    if cli.incognito {
+        fireshot_core::history::go_incognito();
+    }
+    if let Some(id) = cli.flameshot_id {
+        flameshot::set_request_id(id);
+    }
 
-    match command {
+    let result = match command {
         Command::Diagnose => {
             diagnose(&rt);
+            Ok(())
+        }
+        Command::Doctor => {
+            diagnose(&rt);
+            if doctor::run(&config) {
+                Ok(())
+            } else {
+                Err(CaptureError::ChecksFailed)
+            }
+        }
+        Command::Gui {
+            delay,
+            path,
+            region_preset: preset,
+            region,
+            accept,
+            copy_region,
+        } => {
+            let region = match region {
+                Some(region) => Some(region),
+                None => region_preset(&config, preset.as_deref())?,
+            };
+            run_gui(&rt, &config, delay, path, region, accept, copy_region)
+        }
+        Command::Full {
+            delay,
+            path,
+            edit,
+            clipboard,
+            region_preset: preset,
+        } => region_preset(&config, preset.as_deref())
+            .and_then(|region| run_full(&rt, &config, delay, path, edit, clipboard, region)),
+        Command::Edit { file, .. } => run_edit(&rt, &config, file),
+        Command::Launcher => run_launcher(&rt, &config),
+        Command::Compare {
+            before,
+            after,
+            diff,
+            threshold,
+            path,
+            delay,
+        } => {
+            let mode = if diff {
+                fireshot_gui::CompareMode::Diff
+            } else {
+                fireshot_gui::CompareMode::SideBySide
+            };
+            run_compare(&rt, &config, &before, after.as_deref(), mode, threshold, path, delay)
+        }
+        Command::Annotate {
+            input,
+            output,
+            arrow,
+            line,
+            rect,
+            circle,
+            text,
+            blur,
+            pixelate,
+            color,
+            size,
+        } => {
+            let input = if input.starts_with('@') {
+                history_entry(&input)?
+            } else {
+                input.into()
+            };
+            let annotations = annotate::Annotations {
+                blur,
+                pixelate,
+                rect,
+                circle,
+                line,
+                arrow,
+                text,
+            };
+            annotate::run(&config, &input, &output, annotations, color, size).map(|()| {
+                println!("{}", tr!("cli-wrote", path = output.display()));
+            })
         }
-        Command::Gui { delay, path } => {
-            let req = CaptureRequest {
-                delay_ms: delay,
-                ..Default::default()
+        Command::Record {
+            delay,
+            window,
+            region,
+            region_preset: preset,
+            format,
+            audio,
+        } => {
+            if let Some(format) = format {
+                config.record.format = format;
+            }
+            config.record.audio |= audio;
+            let region = match region {
+                Some(region) => Some(region),
+                None => region_preset(&config, preset.as_deref())?,
+            };
+            let target = match (region, window) {
+                (Some(region), _) => RecordTarget::Region(region),
+                (None, true) => RecordTarget::Window,
+                (None, false) => RecordTarget::Output,
             };
-            if req.delay_ms > 0 {
-                std::thread::sleep(std::time::Duration::from_millis(req.delay_ms));
+            run_record(&rt, &config, delay, target)
+        }
+        Command::Snippet { target, format, alt } => {
+            if let Some(format) = format {
+                config.snippet.format = format;
+            }
+            if let Some(alt) = alt {
+                config.snippet.alt = alt;
             }
+            run_snippet(&config, &target)
+        }
+        Command::Recopy => recopy_last().map(|path| {
+            println!("{}", tr!("cli-recopied", path = path.display()));
+        }),
+        Command::OpenUri { uri } => {
+            uri::parse(&uri).and_then(|request| run_async(&rt, uri::send(request)))
+        }
+        Command::History { action } => run_history(&config, action),
+        Command::Session { action } => run_session(&config, action),
+        Command::Daemon {
+            install_autostart: true,
+            systemd,
+            on_demand,
+            ..
+        } => install_autostart(systemd, on_demand),
+        Command::Daemon {
+            remove_autostart: true,
+            ..
+        } => remove_autostart(),
+        Command::Daemon { .. } => run_daemon(&rt, config.clone()),
+    };
+    match &result {
+        Err(CaptureError::PortalCancelled) => {
+            report_tray_state(&rt, TrayState::Idle);
+            if std::env::var_os(DAEMON_CHILD_ENV).is_some() {
+                if let Err(err) = rt.block_on(notify::notify_cancelled(&config.notifications)) {
+                    warn!("notification: {}", err);
+                }
+            }
+        }
+        Err(_) => report_tray_state(&rt, TrayState::Failed),
+        Ok(()) => {}
+    }
+    flameshot::report_failed(&rt);
+    fireshot_core::profile::record("total", started.elapsed());
+    report_profile(print_profile);
+    result
+}
+
+/// Logs the recorded stage timings, and prints them for `--profile`.
+pub(crate) fn report_profile(print: bool) {
+    let samples = fireshot_core::profile::take();
+    if print && !samples.is_empty() {
+        eprintln!("profile:");
+    }
+    for (stage, elapsed) in samples {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        debug!("profile: {}: {:.1} ms", stage, ms);
+        if print {
+            eprintln!("  {:<18} {:>9.1} ms", stage, ms);
+        }
+    }
+}
 
-            let captured = run_async(&rt, fireshot_portal::capture_fullscreen())?;
+/// Set on capture processes spawned by the daemon so they report their
+/// progress back to its tray icon.
+const DAEMON_CHILD_ENV: &str = "FIRESHOT_DAEMON_CHILD";
 
-            if let Some(save_path) = path.as_ref() {
-                captured
-                    .image
-                    .save(save_path)
-                    .map_err(|e| CaptureError::Io(e.to_string()))?;
+fn report_tray_state(rt: &tokio::runtime::Runtime, state: TrayState) {
+    if std::env::var_os(DAEMON_CHILD_ENV).is_none() {
+        return;
+    }
+    let result = rt.block_on(async {
+        let conn = zbus::Connection::session().await?;
+        conn.call_method(
+            Some("org.fireshot.Fireshot"),
+            "/org/fireshot/Fireshot",
+            Some("org.fireshot.Fireshot"),
+            "ReportState",
+            &(state.as_str(),),
+        )
+        .await?;
+        Ok::<(), zbus::Error>(())
+    });
+    if let Err(err) = result {
+        debug!("report tray state: {}", err);
+    }
+}
+
+/// Finishes a capture in its own process. Only a capture the daemon started
+/// offers notification actions and waits for a click; a CLI run, maybe from
+/// a script, exits as soon as the notices are up.
+fn finish_exports(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    hooks: &Hooks,
+    exports: Vec<ExportResult>,
+) {
+    flameshot::report(rt, &exports);
+    let report_state = |state| report_tray_state(rt, state);
+    finish_exports_with(config, hooks, exports, report_state, |notices| {
+        let actions = std::env::var_os(DAEMON_CHILD_ENV).is_some();
+        rt.block_on(notify::notify_exports(&config.notifications, notices, actions));
+    });
+}
+
+/// What every capture does with its exports, in its own process or the
+/// daemon's hosted editor: runs the post-save hook, numbers them into a
+/// running session and prunes the history, then reports the tray state and
+/// hands the notices to `notify`.
+pub(crate) fn finish_exports_with(
+    config: &Config,
+    hooks: &Hooks,
+    exports: Vec<ExportResult>,
+    report_state: impl Fn(TrayState),
+    notify: impl FnOnce(Vec<notify::ExportNotice>),
+) {
+    let exports = hooks.post_save(exports);
+    record_session_steps(&exports);
+    prune_history(config);
+    report_state(if exports.is_empty() {
+        TrayState::Idle
+    } else {
+        TrayState::Done
+    });
+    notify(export_notices(config, &exports));
+}
+
+/// Deletes the oldest captures past the `[history]` limits, then starts
+/// OCR on the new ones when `[history] ocr` is on.
+pub(crate) fn prune_history(config: &Config) {
+    match fireshot_core::history::prune(&config.history) {
+        Ok(0) => {}
+        Ok(count) => debug!("history: deleted {} old captures", count),
+        Err(err) => warn!("history: {}", err),
+    }
+    if !config.history.ocr {
+        return;
+    }
+    match fireshot_core::history::index_text() {
+        Ok(0) => {}
+        Ok(count) => debug!("history: reading the text in {} captures", count),
+        Err(err) => warn!("history: {}", err),
+    }
+}
+
+/// Adds what the capture was saved or copied as to the running session.
+fn record_session_steps(exports: &[ExportResult]) {
+    let Some(session) = fireshot_core::session::current() else {
+        return;
+    };
+    for export in exports {
+        let source = match export {
+            ExportResult::Saved { path } => path,
+            ExportResult::Copied { history_path: Some(path) } => path,
+            _ => continue,
+        };
+        if session.contains(source) {
+            continue;
+        }
+        let is_png = source.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        let added = if is_png {
+            session.add_step(source)
+        } else {
+            let path = session.next_step_path();
+            image::open(source)
+                .and_then(|image| image.save(&path))
+                .map(|()| path.clone())
+                .map_err(|e| save_error(&path, e))
+        };
+        match added {
+            Ok(path) => debug!("session: saved {}", path.display()),
+            Err(err) => warn!("session: {}", err),
+        }
+    }
+}
+
+/// Time for the compositor to unmap the launcher before the capture fires.
+const LAUNCHER_SETTLE_MS: u64 = 250;
+
+fn sleep_delay(rt: &tokio::runtime::Runtime, delay_ms: u64) {
+    if delay_ms > 0 {
+        report_tray_state(rt, TrayState::Countdown);
+        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+        report_tray_state(rt, TrayState::Idle);
+    }
+}
+
+fn encode_png(image: &image::DynamicImage) -> Result<Vec<u8>, CaptureError> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|e| CaptureError::EncodeError {
+            format: "PNG".to_string(),
+            reason: e.to_string(),
+        })?;
+    Ok(bytes)
+}
+
+fn save_error(path: &std::path::Path, err: image::ImageError) -> CaptureError {
+    match err {
+        image::ImageError::IoError(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+            CaptureError::PermissionDenied(format!("{}: {}", path.display(), err))
+        }
+        image::ImageError::IoError(err) => {
+            CaptureError::Io(format!("{}: {}", path.display(), err))
+        }
+        err => CaptureError::EncodeError {
+            format: path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_uppercase())
+                .unwrap_or_else(|| "image".to_string()),
+            reason: err.to_string(),
+        },
+    }
+}
+
+/// `image` cut down to `region`, clipped to its bounds.
+fn crop_region(image: &image::DynamicImage, region: RecordRegion) -> image::DynamicImage {
+    image.crop_imm(region.x, region.y, region.width, region.height)
+}
+
+fn save_captured(
+    image: &image::DynamicImage,
+    path: &std::path::Path,
+    icc_profile: Option<&[u8]>,
+    matte: Matte,
+    window: Option<&WindowInfo>,
+) -> Result<ExportResult, CaptureError> {
+    fireshot_gui::save_image(&image.to_rgba8(), path, icc_profile, matte, None, window)
+        .map_err(|e| save_error(path, e))?;
+    let recorded = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")) {
+        fireshot_core::history::record_file(path)
+    } else {
+        encode_png(image).and_then(|bytes| fireshot_core::history::record_png(&bytes))
+    };
+    if let Err(err) = recorded {
+        warn!("history: failed to record capture: {}", err);
+    }
+    Ok(ExportResult::Saved {
+        path: path.to_path_buf(),
+    })
+}
+
+/// The capture's output profile, when exports should be tagged with it.
+pub(crate) fn export_profile(
+    config: &Config,
+    captured: &fireshot_portal::CapturedImage,
+) -> Option<std::sync::Arc<[u8]>> {
+    let icc = captured.icc_profile.as_deref()?;
+    config.save.embed_icc_profile.then(|| icc.into())
+}
+
+/// The notifications for `exports`, with a snippet for each saved file.
+fn export_notices(
+    config: &Config,
+    exports: &[ExportResult],
+) -> Vec<notify::ExportNotice> {
+    exports
+        .iter()
+        .map(|export| {
+            let mut notice = notify::ExportNotice::from(export);
+            if let ExportResult::Saved { path } = export {
+                notice.snippet = Some(config.snippet.render(&path.display().to_string()));
             }
+            notice
+        })
+        .collect()
+}
+
+/// The desktop's file chooser for the editor's Save, when `[save] dialog`
+/// picks it over the editor's own.
+pub(crate) fn save_dialog(
+    config: &Config,
+    handle: &tokio::runtime::Handle,
+) -> Option<fireshot_gui::SaveDialog> {
+    if !config.save.dialog.uses_portal() {
+        return None;
+    }
+    let handle = handle.clone();
+    Some(std::sync::Arc::new(move |file_name: &str| {
+        handle.block_on(fireshot_portal::save_file_dialog(file_name))
+    }))
+}
+
+/// The configured chrome theme, asking the desktop for its light/dark
+/// preference when set to follow the system.
+pub(crate) async fn editor_theme(config: &Config) -> fireshot_gui::Theme {
+    let system = if config.theme.mode == ThemeMode::System {
+        fireshot_portal::prefers_dark().await
+    } else {
+        None
+    };
+    let theme = fireshot_gui::Theme::from_config(&config.theme, system);
+    if let (Some(accent), None) = (&config.theme.accent, theme.accent) {
+        warn!("theme: ignoring accent {:?}, expected #rrggbb", accent);
+    }
+    theme
+}
+
+/// Claims the editor slot per `[editor] single_instance`. Returns `None` when
+/// the request was handed to an editor that is already open, which opens the
+/// images at `open` in new tabs.
+fn claim_editor(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    hooks: &Hooks,
+    open: &[std::path::PathBuf],
+) -> Result<Option<(instance::EditorClaim, fireshot_gui::ViewerOptions)>, CaptureError> {
+    let options = editor_options(rt.handle(), config, hooks);
+    let claim = run_async(
+        rt,
+        instance::claim_editor(config.editor.single_instance, &options.control, open),
+    )?;
+    Ok(claim.map(|claim| (claim, options)))
+}
+
+/// The editor's options from the config, for every editor whether it runs
+/// in its own process or in the daemon; callers add the capture's own.
+pub(crate) fn editor_options(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+    hooks: &Hooks,
+) -> fireshot_gui::ViewerOptions {
+    fireshot_gui::ViewerOptions {
+        theme: handle.block_on(editor_theme(config)),
+        layer_shell: config.editor.layer_shell,
+        toolbar: config.toolbar.clone(),
+        #[cfg(feature = "recording")]
+        record: Some(fireshot_gui::RecordRequest::default()),
+        recapture: Some(fireshot_gui::RecaptureRequest::new(config.editor.recapture_delay_secs)),
+        post_render: hooks.post_render_hook(),
+        save_dialog: save_dialog(config, handle),
+        share: config.share.clone(),
+        timestamp: config.timestamp.clone(),
+        tool_defaults: fireshot_gui::ToolDefaults::from_config(&config.editor),
+        undo_limit: fireshot_gui::UndoLimit::from_config(&config.editor),
+        save: config.save.clone(),
+        presets: config.presets.clone(),
+        after_export: config.behavior.after_export,
+        hide_ui_while_drawing: config.editor.hide_ui_while_drawing,
+        window_caption: config.editor.window_caption,
+        flash: config.behavior.flash,
+        ..Default::default()
+    }
+}
+
+/// Runs the editor and, when it was closed with Record, the recording after
+/// it. The editor slot is released before recording starts.
+fn run_editor(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    claim: instance::EditorClaim,
+    mut image: image::DynamicImage,
+    mut options: fireshot_gui::ViewerOptions,
+) -> Result<Vec<ExportResult>, CaptureError> {
+    let record = options.record.clone();
+    let mut exports = Vec::new();
+    loop {
+        exports.extend(fireshot_gui::run_viewer(image, options.clone())?);
+        let Some(delay_ms) = options.recapture.as_ref().and_then(|r| r.take()) else {
+            break;
+        };
+        options.title = None;
+        options.tabs.clear();
+        sleep_delay(rt, delay_ms);
+        options.window = WindowInfo::focused();
+        let captured = run_async(rt, take_capture(config, fireshot_portal::capture_fullscreen()))?;
+        options.icc_profile = export_profile(config, &captured);
+        options.from_screen = true;
+        options.selection = None;
+        image = captured.image;
+    }
+    drop(claim);
+    if let Some(region) = record.and_then(|record| record.take()) {
+        report_tray_state(rt, TrayState::Recording);
+        let target = RecordTarget::Region(region);
+        match rt.block_on(record::record_screen(config, target, true)) {
+            Ok(export) => exports.push(export),
+            Err(err) if exports.is_empty() => return Err(err),
+            // What the editor already copied or saved still gets finished.
+            Err(err) => eprintln!("{}", tr!("cli-error", error = err.user_message())),
+        }
+    }
+    Ok(exports)
+}
+
+/// The history entry `@N` names: the Nth newest capture.
+fn history_entry(source: &str) -> Result<std::path::PathBuf, CaptureError> {
+    let index: usize = source
+        .strip_prefix('@')
+        .and_then(|index| index.parse().ok())
+        .filter(|&index| index > 0)
+        .ok_or_else(|| CaptureError::Io(format!("{}: expected @1, @2, ...", source)))?;
+    fireshot_core::history::entries()
+        .into_iter()
+        .nth(index - 1)
+        .map(|entry| entry.path)
+        .ok_or_else(|| CaptureError::Io(format!("{}: no such capture in the history", source)))
+}
 
-            if path.is_none() {
-                fireshot_gui::run_viewer(captured.image)?;
+/// Opens `source`: an image file, or `@N` for the Nth newest history entry.
+fn load_compare_source(source: &str) -> Result<image::RgbaImage, CaptureError> {
+    let path = if source.starts_with('@') {
+        history_entry(source)?
+    } else {
+        std::path::PathBuf::from(source)
+    };
+    image::open(&path)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_compare(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    before: &str,
+    after: Option<&str>,
+    mode: fireshot_gui::CompareMode,
+    threshold: u8,
+    path: Option<String>,
+    delay_ms: u64,
+) -> Result<(), CaptureError> {
+    let before = load_compare_source(before)?;
+    let after = match after {
+        Some(after) => load_compare_source(after)?,
+        None => {
+            sleep_delay(rt, delay_ms);
+            let captured =
+                run_async(rt, take_capture(config, fireshot_portal::capture_fullscreen()))?;
+            captured.image.to_rgba8()
+        }
+    };
+
+    if let Some(path) = path {
+        let (diff, stats) = fireshot_gui::diff_heatmap(&before, &after, threshold);
+        println!(
+            "{}",
+            tr!(
+                "compare-stats",
+                changed = stats.changed,
+                total = stats.total,
+                percent = format!("{:.2}", stats.percent())
+            )
+        );
+        let image = match mode {
+            fireshot_gui::CompareMode::Diff => diff,
+            fireshot_gui::CompareMode::SideBySide => fireshot_gui::side_by_side(&before, &after),
+        };
+        let path = std::path::Path::new(&path);
+        image.save(path).map_err(|e| save_error(path, e))?;
+        return Ok(());
+    }
+
+    let theme = rt.block_on(editor_theme(config));
+    let exports = match fireshot_gui::run_compare(before, after, mode, threshold, theme)? {
+        Some(fireshot_gui::CompareExport::Save(image)) => {
+            let save_path = run_async(rt, fireshot_portal::save_file_dialog("comparison.png"))?;
+            match save_path {
+                Some(save_path) => {
+                    image.save(&save_path).map_err(|e| save_error(&save_path, e))?;
+                    vec![ExportResult::Saved { path: save_path }]
+                }
+                None => Vec::new(),
             }
         }
-        Command::Full { delay, path, edit } => {
-            let req = CaptureRequest {
-                delay_ms: delay,
-                ..Default::default()
-            };
-            if req.delay_ms > 0 {
-                std::thread::sleep(std::time::Duration::from_millis(req.delay_ms));
+        Some(fireshot_gui::CompareExport::Copy(image)) => {
+            fireshot_gui::copy_image_to_clipboard(&image::DynamicImage::ImageRgba8(image))?;
+            vec![ExportResult::Copied { history_path: None }]
+        }
+        None => Vec::new(),
+    };
+    finish_exports(rt, config, &Hooks::load(config, "compare"), exports);
+    Ok(())
+}
+
+fn run_record(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    delay_ms: u64,
+    target: RecordTarget,
+) -> Result<(), CaptureError> {
+    let hooks = Hooks::load(config, "record");
+    if !hooks.pre_capture() {
+        return Ok(());
+    }
+    sleep_delay(rt, delay_ms);
+    report_tray_state(rt, TrayState::Recording);
+    let export = rt.block_on(record::record_screen(config, target, true))?;
+    finish_exports(rt, config, &hooks, vec![export]);
+    Ok(())
+}
+
+fn run_gui(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    delay_ms: u64,
+    path: Option<String>,
+    region: Option<RecordRegion>,
+    accept: Option<fireshot_gui::Accept>,
+    copy_region: bool,
+) -> Result<(), CaptureError> {
+    let hooks = Hooks::load(config, "gui");
+    let editor = if path.is_none() {
+        match claim_editor(rt, config, &hooks, &[])? {
+            Some(editor) => Some(editor),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+    if !hooks.pre_capture() {
+        return Ok(());
+    }
+    sleep_delay(rt, delay_ms);
+
+    let window = WindowInfo::focused();
+    let mut captured = run_async(rt, take_capture(config, fireshot_portal::capture_fullscreen()))?;
+
+    let exports = match (path.as_ref(), editor) {
+        (Some(save_path), _) => {
+            if let Some(region) = region {
+                captured.image = crop_region(&captured.image, region);
             }
+            let icc_profile = export_profile(config, &captured);
+            let path = std::path::Path::new(save_path);
+            let exported = hooks.post_render_capture(&captured.image);
+            let image = exported.as_ref().unwrap_or(&captured.image);
+            let matte = config.save.matte;
+            vec![save_captured(image, path, icc_profile.as_deref(), matte, window.as_ref())?]
+        }
+        (None, Some((claim, mut options))) => {
+            options.icc_profile = export_profile(config, &captured);
+            options.from_screen = true;
+            options.window = window;
+            options.selection = region;
+            options.accept = accept;
+            options.copy_region = copy_region;
+            run_editor(rt, config, claim, captured.image, options)?
+        }
+        (None, None) => unreachable!("editor is claimed whenever no path is given"),
+    };
+    finish_exports(rt, config, &hooks, exports);
+    Ok(())
+}
+
+fn run_full(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    delay_ms: u64,
+    path: Option<String>,
+    edit: bool,
+    clipboard: bool,
+    region: Option<RecordRegion>,
+) -> Result<(), CaptureError> {
+    let hooks = Hooks::load(config, "full");
+    let editor = if edit {
+        match claim_editor(rt, config, &hooks, &[])? {
+            Some(editor) => Some(editor),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+    if !hooks.pre_capture() {
+        return Ok(());
+    }
+    sleep_delay(rt, delay_ms);
+
+    let window = WindowInfo::focused();
+    let mut captured = run_async(rt, take_capture(config, fireshot_portal::capture_fullscreen()))?;
+    if let Some(region) = region {
+        captured.image = crop_region(&captured.image, region);
+    }
+    let icc_profile = export_profile(config, &captured);
+    let exported = hooks.post_render_capture(&captured.image);
+    let image = exported.as_ref().unwrap_or(&captured.image);
+    let (matte, window_ref) = (config.save.matte, window.as_ref());
+    let mut exports = Vec::new();
+    if let Some(save_path) = path.as_ref() {
+        let path = std::path::Path::new(save_path);
+        exports.push(save_captured(image, path, icc_profile.as_deref(), matte, window_ref)?);
+    }
+    if clipboard {
+        fireshot_gui::copy_image_to_clipboard(image)?;
+        let history_path = encode_png(image)
+            .and_then(|bytes| fireshot_core::history::record_png(&bytes))
+            .ok()
+            .flatten();
+        exports.push(ExportResult::Copied { history_path });
+    }
+    if let Some((claim, mut options)) = editor {
+        options.icc_profile = icc_profile;
+        options.from_screen = true;
+        options.window = window.clone();
+        exports.extend(run_editor(rt, config, claim, captured.image, options)?);
+    } else if let (None, false, Some(session)) = (&path, clipboard, fireshot_core::session::current()) {
+        // A session numbers the capture instead of asking where it goes.
+        let step = session.next_step_path();
+        exports.push(save_captured(image, &step, icc_profile.as_deref(), matte, window_ref)?);
+    } else if path.is_none() && !clipboard {
+        let default_name = format!("{}.png", config.save.file_stem_for(window_ref));
+        let save_path = run_async(rt, fireshot_portal::save_file_dialog(&default_name))?;
+        if let Some(save_path) = save_path {
+            let icc_profile = icc_profile.as_deref();
+            exports.push(save_captured(image, &save_path, icc_profile, matte, window_ref)?);
+        }
+    }
+    finish_exports(rt, config, &hooks, exports);
+    Ok(())
+}
+
+/// Opens `file`, or the clipboard's image when it is `None`, in the editor.
+fn run_edit(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    files: Vec<String>,
+) -> Result<(), CaptureError> {
+    // An editor that is already open gets them by path.
+    let paths: Vec<std::path::PathBuf> = files
+        .iter()
+        .map(|file| std::fs::canonicalize(file).unwrap_or_else(|_| file.into()))
+        .collect();
+    let hooks = Hooks::load(config, "edit");
+    let Some((claim, mut options)) = claim_editor(rt, config, &hooks, &paths)? else {
+        return Ok(());
+    };
+    let mut images = Vec::new();
+    for path in &paths {
+        let image = image::open(path)
+            .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+        images.push((instance::tab_title(path), image));
+    }
+    let image = if images.is_empty() {
+        image::DynamicImage::ImageRgba8(fireshot_gui::paste_image_from_clipboard()?)
+    } else {
+        let (title, image) = images.remove(0);
+        options.title = Some(title);
+        options.tabs = images
+            .into_iter()
+            .map(|(title, image)| (title, image.to_rgba8()))
+            .collect();
+        image
+    };
+    let exports = run_editor(rt, config, claim, image, options)?;
+    finish_exports(rt, config, &hooks, exports);
+    Ok(())
+}
+
+fn run_window(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    delay_ms: u64,
+) -> Result<(), CaptureError> {
+    let hooks = Hooks::load(config, "window");
+    let Some((claim, mut options)) = claim_editor(rt, config, &hooks, &[])? else {
+        return Ok(());
+    };
+    if !hooks.pre_capture() {
+        return Ok(());
+    }
+    sleep_delay(rt, delay_ms);
+
+    options.window = WindowInfo::focused();
+    let captured = run_async(rt, take_capture(config, fireshot_portal::capture_interactive()))?;
+    options.icc_profile = export_profile(config, &captured);
+    options.from_screen = true;
+    let exports = run_editor(rt, config, claim, captured.image, options)?;
+    finish_exports(rt, config, &hooks, exports);
+    Ok(())
+}
+
+fn run_launcher(rt: &tokio::runtime::Runtime, config: &Config) -> Result<(), CaptureError> {
+    let theme = rt.block_on(editor_theme(config));
+    let screens = launcher_screens(rt);
+    let Some(req) = fireshot_gui::run_launcher(theme, screens, config.history.ocr)? else {
+        return Ok(());
+    };
+    std::thread::sleep(std::time::Duration::from_millis(LAUNCHER_SETTLE_MS));
+    match req.mode {
+        CaptureMode::Graphical => {
+            run_gui(rt, config, req.delay_ms, req.save_path, None, None, false)
+        }
+        CaptureMode::Fullscreen => {
+            run_full(rt, config, req.delay_ms, req.save_path, false, false, None)
+        }
+        CaptureMode::Screen if req.region.is_some() => {
+            run_full(rt, config, req.delay_ms, req.save_path, false, false, req.region)
+        }
+        CaptureMode::Screen | CaptureMode::Window => run_window(rt, config, req.delay_ms),
+    }
+}
+
+/// Each screen as it is now, for the launcher's thumbnails; none with a
+/// single screen, or when the compositor doesn't say where its screens are.
+fn launcher_screens(rt: &tokio::runtime::Runtime) -> Vec<fireshot_gui::LauncherScreen> {
+    let outputs = fireshot_core::output::outputs();
+    if outputs.len() < 2 {
+        return Vec::new();
+    }
+    let captured = match run_async(rt, fireshot_portal::capture_fullscreen()) {
+        Ok(captured) => captured,
+        Err(err) => {
+            warn!("launcher: no screen thumbnails: {}", err);
+            return Vec::new();
+        }
+    };
+    let (width, height) = (captured.image.width(), captured.image.height());
+    let regions = fireshot_core::output::capture_regions(&outputs, width, height);
+    outputs
+        .into_iter()
+        .zip(regions)
+        .map(|(output, region)| fireshot_gui::LauncherScreen {
+            name: output.name,
+            region,
+            image: crop_region(&captured.image, region).to_rgba8(),
+        })
+        .collect()
+}
 
-            let captured = run_async(&rt, fireshot_portal::capture_fullscreen())?;
-            if let Some(save_path) = path.as_ref() {
-                captured
-                    .image
-                    .save(save_path)
-                    .map_err(|e| CaptureError::Io(e.to_string()))?;
+/// Prints the snippet for `target` and copies it to the clipboard.
+fn run_snippet(config: &Config, target: &str) -> Result<(), CaptureError> {
+    let target = if target.starts_with('@') {
+        history_entry(target)?.display().to_string()
+    } else {
+        target.to_string()
+    };
+    let snippet = config.snippet.render(&target);
+    println!("{}", snippet);
+    fireshot_gui::copy_text_to_clipboard(&snippet)
+}
+
+/// Copies the newest history entry to the clipboard again and returns its
+/// path.
+pub(crate) fn recopy_last() -> Result<std::path::PathBuf, CaptureError> {
+    let path = fireshot_core::history::entries()
+        .into_iter()
+        .next()
+        .map(|entry| entry.path)
+        .ok_or_else(|| CaptureError::Io("the history is empty".to_string()))?;
+    let image =
+        image::open(&path).map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+    fireshot_gui::copy_image_to_clipboard(&image)?;
+    Ok(path)
+}
+
+fn run_history(config: &Config, action: HistoryAction) -> Result<(), CaptureError> {
+    match action {
+        HistoryAction::Clear => {
+            let count = fireshot_core::history::clear()?;
+            println!("{}", tr!("history-cleared", count = count));
+        }
+        HistoryAction::Search { query } => {
+            let found = fireshot_core::history::search(&query);
+            for entry in &found {
+                println!("{}  {}", entry.label(), entry.path.display());
             }
-            if edit {
-                fireshot_gui::run_viewer(captured.image)?;
-            } else if path.is_none() {
-                let default_name = "screenshot.png";
-                let save_path = run_async(&rt, fireshot_portal::save_file_dialog(default_name))?;
-                let Some(save_path) = save_path else {
-                    return Ok(());
-                };
-                captured
-                    .image
-                    .save(&save_path)
-                    .map_err(|e| CaptureError::Io(e.to_string()))?;
+            if found.is_empty() {
+                eprintln!("{}", tr!("history-no-match", query = query));
+                if !config.history.ocr {
+                    eprintln!("{}", tr!("history-ocr-off"));
+                }
             }
         }
-        Command::Daemon => {
-            run_daemon(&rt)?;
-        }
     }
+    Ok(())
+}
+
+fn run_session(config: &Config, action: SessionAction) -> Result<(), CaptureError> {
+    use fireshot_core::session::{self, Session};
 
+    match action {
+        SessionAction::Start { dir } => {
+            let dir = dir.unwrap_or_else(|| config.save.session_dir());
+            let session = Session::start(dir)?;
+            println!("{}", tr!("session-started", dir = session.dir.display()));
+        }
+        SessionAction::Stop {
+            contact_sheet,
+            markdown,
+        } => {
+            let Some(session) = session::stop() else {
+                println!("{}", tr!("session-none"));
+                return Ok(());
+            };
+            let steps = session.steps();
+            if contact_sheet && !steps.is_empty() {
+                let images = steps
+                    .iter()
+                    .map(|path| {
+                        image::open(path)
+                            .map(|image| image.to_rgba8())
+                            .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let path = session.dir.join("contact-sheet.png");
+                fireshot_gui::contact_sheet(&images)
+                    .save(&path)
+                    .map_err(|e| save_error(&path, e))?;
+                println!("{}", tr!("cli-wrote", path = path.display()));
+            }
+            if markdown && !steps.is_empty() {
+                let path = session.dir.join("steps.md");
+                std::fs::write(&path, session.markdown())
+                    .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+                println!("{}", tr!("cli-wrote", path = path.display()));
+            }
+            println!(
+                "{}",
+                tr!("session-stopped", count = steps.len(), dir = session.dir.display())
+            );
+        }
+        SessionAction::Status => match session::current() {
+            Some(session) => println!(
+                "{}",
+                tr!("session-status", count = session.steps().len(), dir = session.dir.display())
+            ),
+            None => println!("{}", tr!("session-none")),
+        },
+    }
     Ok(())
 }
 
 fn diagnose(rt: &tokio::runtime::Runtime) {
-    println!("Fireshot Wayland diagnostics");
-    println!("env:");
+    println!("{}", tr!("diagnose-title"));
+    println!("{}", tr!("diagnose-env"));
     for key in [
         "XDG_SESSION_TYPE",
         "XDG_CURRENT_DESKTOP",
         "WAYLAND_DISPLAY",
         "DISPLAY",
     ] {
-        let val = std::env::var(key).unwrap_or_else(|_| "<unset>".to_string());
+        let val = std::env::var(key).unwrap_or_else(|_| tr!("diagnose-unset").to_string());
         println!("  {}={}", key, val);
     }
 
     println!();
-    println!("portal service:");
+    println!("{}", tr!("diagnose-portal-service"));
     let dbus_result = rt.block_on(async {
         let conn = zbus::Connection::session().await?;
         let proxy = zbus::fdo::DBusProxy::new(&conn).await?;
@@ -150,11 +1438,11 @@ fn diagnose(rt: &tokio::runtime::Runtime) {
     });
     match dbus_result {
         Ok(has_owner) => println!("  org.freedesktop.portal.Desktop: {}", has_owner),
-        Err(err) => println!("  session bus error: {}", err),
+        Err(err) => println!("  {}", tr!("diagnose-bus-error", error = err)),
     }
 
     println!();
-    println!("portal backends:");
+    println!("{}", tr!("diagnose-portal-backends"));
     let portals_dir = std::path::Path::new("/usr/share/xdg-desktop-portal/portals");
     if portals_dir.exists() {
         match std::fs::read_dir(portals_dir) {
@@ -165,13 +1453,49 @@ fn diagnose(rt: &tokio::runtime::Runtime) {
                     }
                 }
             }
-            Err(err) => println!("  error reading {}: {}", portals_dir.display(), err),
+            Err(err) => {
+                let path = portals_dir.display();
+                println!("  {}", tr!("diagnose-read-error", path = path, error = err));
+            }
         }
     } else {
-        println!("  {} not found", portals_dir.display());
+        println!("  {}", tr!("diagnose-not-found", path = portals_dir.display()));
     }
 }
 
+fn install_autostart(systemd: bool, on_demand: bool) -> Result<(), CaptureError> {
+    for path in autostart::install(systemd, on_demand)? {
+        println!("{}", tr!("cli-wrote", path = path.display()));
+    }
+    Ok(())
+}
+
+fn remove_autostart() -> Result<(), CaptureError> {
+    let removed = autostart::remove()?;
+    if removed.is_empty() {
+        println!("{}", tr!("cli-no-autostart"));
+    }
+    for path in removed {
+        println!("{}", tr!("cli-removed", path = path.display()));
+    }
+    Ok(())
+}
+
+/// Takes the capture `capture` makes with fireshot's own editors hidden,
+/// holding notification popups back meanwhile and playing the shutter sound
+/// after, as configured.
+pub(crate) async fn take_capture(
+    config: &Config,
+    capture: impl std::future::Future<Output = Result<fireshot_portal::CapturedImage, CaptureError>>,
+) -> Result<fireshot_portal::CapturedImage, CaptureError> {
+    let hidden = instance::hide_editors().await;
+    let captured = notify::quietly(&config.notifications, capture).await;
+    instance::show_editors(&hidden).await;
+    let captured = captured?;
+    sound::shutter(config);
+    Ok(captured)
+}
+
 fn run_async<T>(
     rt: &tokio::runtime::Runtime,
     future: impl std::future::Future<Output = Result<T, CaptureError>>,
@@ -181,13 +1505,22 @@ fn run_async<T>(
 
 struct FireshotService {
     shutdown: std::sync::Mutex<Option<oneshot::Sender<()>>>,
+    cmd_tx: mpsc::UnboundedSender<DaemonCommand>,
+    /// For captures taken in the daemon itself, such as `CaptureRegionBytes`.
+    config: Config,
 }
 
 #[dbus_interface(name = "org.fireshot.Fireshot")]
 impl FireshotService {
     fn gui(&self, delay_ms: u64, path: String) {
-        let path = if path.is_empty() { None } else { Some(path) };
-        spawn_capture(CaptureKind::Gui { delay_ms, path });
+        if path.is_empty() {
+            let _ = self.cmd_tx.send(DaemonCommand::Gui { delay_ms });
+        } else {
+            spawn_capture(CaptureKind::Gui {
+                delay_ms,
+                path: Some(path),
+            });
+        }
     }
 
     fn full(&self, delay_ms: u64, path: String) {
@@ -196,6 +1529,7 @@ impl FireshotService {
             delay_ms,
             path,
             edit: false,
+            clipboard: false,
         });
     }
 
@@ -205,9 +1539,64 @@ impl FireshotService {
             delay_ms,
             path,
             edit: true,
+            clipboard: false,
         });
     }
 
+    /// Opens an image file in the editor, or in a new tab of the one already
+    /// open, for file managers and other apps to send images to annotate.
+    /// The path must be absolute, as the daemon's working directory isn't
+    /// the caller's.
+    fn open_editor(&self, path: String) -> zbus::fdo::Result<()> {
+        let path = std::path::PathBuf::from(path);
+        if !path.is_absolute() {
+            let message = format!("{}: not an absolute path", path.display());
+            return Err(zbus::fdo::Error::InvalidArgs(message));
+        }
+        if !path.is_file() {
+            let message = format!("{}: no such file", path.display());
+            return Err(zbus::fdo::Error::FileNotFound(message));
+        }
+        spawn_capture(CaptureKind::Edit { path });
+        Ok(())
+    }
+
+    /// Runs an interactive portal capture and returns the PNG bytes directly,
+    /// so callers don't need a temp file. Like every capture, it hides open
+    /// editors, holds notifications back and plays the shutter as set up.
+    async fn capture_region_bytes(&self, delay_ms: u64) -> zbus::fdo::Result<Vec<u8>> {
+        if delay_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+        }
+        let captured = take_capture(&self.config, fireshot_portal::capture_interactive())
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        encode_png(&captured.image).map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    /// Lets spawned capture processes drive the tray icon
+    /// (`idle`, `countdown`, `recording`, `done`, `failed`).
+    fn report_state(&self, state: String) -> zbus::fdo::Result<()> {
+        let state = TrayState::parse(&state)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("unknown state {}", state)))?;
+        let _ = self.cmd_tx.send(DaemonCommand::SetState(state));
+        Ok(())
+    }
+
+    /// Lets capture processes spawned for a Flameshot request answer it: an
+    /// empty `png` is `captureFailed`, anything else `captureTaken`.
+    async fn report_flameshot(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        id: u32,
+        png: Vec<u8>,
+        selection: (i32, i32, i32, i32),
+    ) -> zbus::fdo::Result<()> {
+        flameshot::emit(conn, id, png, selection)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
     fn quit(&self) {
         if let Some(sender) = self.shutdown.lock().ok().and_then(|mut s| s.take()) {
             let _ = sender.send(());
@@ -228,70 +1617,58 @@ enum CaptureKind {
         delay_ms: u64,
         path: Option<String>,
         edit: bool,
+        clipboard: bool,
     },
+    /// Pick an area and copy it, skipping the annotation tools.
+    RegionCopy,
+    /// Open an image file in the editor.
+    Edit {
+        path: std::path::PathBuf,
+    },
+    Launcher,
+    #[cfg(feature = "recording")]
+    Record,
 }
 
-enum DaemonCommand {
-    Gui,
+pub(crate) enum DaemonCommand {
+    Gui { delay_ms: u64 },
     FullSave,
+    /// Pick an area and copy it without the editor's tools.
+    RegionCopy,
+    /// Open the launcher, e.g. to pick a delay not in the tray menu.
+    Launcher,
+    #[cfg(feature = "recording")]
+    Record,
+    StopRecording,
+    /// Copy the newest capture to the clipboard again.
+    Recopy,
+    SetState(TrayState),
+    /// Return to idle unless another state was set since `generation`.
+    ExpireState(u64),
     Quit,
 }
 
-struct FireshotTray {
-    cmd_tx: mpsc::UnboundedSender<DaemonCommand>,
-}
-
-impl Tray for FireshotTray {
-    fn activate(&mut self, _x: i32, _y: i32) {
-        let _ = self.cmd_tx.send(DaemonCommand::Gui);
-    }
-
-    fn id(&self) -> String {
-        "fireshot".to_string()
-    }
-
-    fn title(&self) -> String {
-        "Fireshot".to_string()
+pub(crate) fn open_in_desktop(path: &std::path::Path) {
+    if let Err(err) = fireshot_core::paths::open_with_default_app(path) {
+        error!("open {}: {}", path.display(), err);
     }
+}
 
-    fn icon_name(&self) -> String {
-        "camera-photo".to_string()
-    }
-
-    fn menu(&self) -> Vec<MenuItem<Self>> {
-        vec![
-            StandardItem {
-                label: "Capture (GUI)".into(),
-                icon_name: "camera-photo".into(),
-                activate: Box::new(|this: &mut FireshotTray| {
-                    let _ = this.cmd_tx.send(DaemonCommand::Gui);
-                }),
-                ..Default::default()
-            }
-            .into(),
-            StandardItem {
-                label: "Full Screen".into(),
-                icon_name: "display".into(),
-                activate: Box::new(|this: &mut FireshotTray| {
-                    let _ = this.cmd_tx.send(DaemonCommand::FullSave);
-                }),
-                ..Default::default()
-            }
-            .into(),
-            StandardItem {
-                label: "Quit".into(),
-                icon_name: "application-exit".into(),
-                activate: Box::new(|this: &mut FireshotTray| {
-                    let _ = this.cmd_tx.send(DaemonCommand::Quit);
-                }),
-                ..Default::default()
-            }
-            .into(),
-        ]
+pub(crate) fn open_dir_in_desktop(dir: &std::path::Path) {
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        error!("create {}: {}", dir.display(), err);
+        return;
     }
+    open_in_desktop(dir);
 }
 
 fn spawn_capture(kind: CaptureKind) {
+    spawn_capture_for(kind, None);
+}
+
+/// Spawns a capture process, which answers the Flameshot request `flameshot_id`
+/// when there is one.
+fn spawn_capture_for(kind: CaptureKind, flameshot_id: Option<u32>) {
     std::thread::spawn(move || {
         debug!("spawn_capture: start");
         let exe = match std::env::current_exe() {
@@ -303,6 +1680,16 @@ fn spawn_capture(kind: CaptureKind) {
         };
 
         let mut cmd = std::process::Command::new(exe);
+        cmd.env(DAEMON_CHILD_ENV, "1");
+        if HIGH_CONTRAST.load(Ordering::Relaxed) {
+            cmd.arg("--high-contrast");
+        }
+        if fireshot_core::history::is_incognito() {
+            cmd.arg("--incognito");
+        }
+        if let Some(id) = flameshot_id {
+            cmd.arg("--flameshot-id").arg(id.to_string());
+        }
         match kind {
             CaptureKind::Gui { delay_ms, path } => {
                 cmd.arg("gui");
@@ -317,6 +1704,7 @@ fn spawn_capture(kind: CaptureKind) {
                 delay_ms,
                 path,
                 edit,
+                clipboard,
             } => {
                 cmd.arg("full");
                 if delay_ms > 0 {
@@ -328,6 +1716,22 @@ fn spawn_capture(kind: CaptureKind) {
                 if edit {
                     cmd.arg("--edit");
                 }
+                if clipboard {
+                    cmd.arg("--clipboard");
+                }
+            }
+            CaptureKind::RegionCopy => {
+                cmd.arg("gui").arg("--copy-region");
+            }
+            CaptureKind::Edit { path } => {
+                cmd.arg("edit").arg("--").arg(path);
+            }
+            CaptureKind::Launcher => {
+                cmd.arg("launcher");
+            }
+            #[cfg(feature = "recording")]
+            CaptureKind::Record => {
+                cmd.arg("record");
             }
         }
 
@@ -338,17 +1742,72 @@ fn spawn_capture(kind: CaptureKind) {
     });
 }
 
-fn run_daemon(rt: &tokio::runtime::Runtime) -> Result<(), CaptureError> {
+fn spawn_hotkeys(hotkeys: &HotkeyConfig, cmd_tx: mpsc::UnboundedSender<DaemonCommand>) {
+    let mut shortcuts = Vec::new();
+    if !hotkeys.gui.is_empty() {
+        shortcuts.push(GlobalShortcut {
+            id: "gui".to_string(),
+            description: tr!("hotkey-gui").to_string(),
+            trigger: hotkeys.gui.clone(),
+        });
+    }
+    if !hotkeys.full.is_empty() {
+        shortcuts.push(GlobalShortcut {
+            id: "full".to_string(),
+            description: tr!("hotkey-full").to_string(),
+            trigger: hotkeys.full.clone(),
+        });
+    }
+    if shortcuts.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let result = fireshot_portal::listen_global_shortcuts(&shortcuts, |id| {
+            debug!("global shortcut activated: {}", id);
+            let cmd = match id {
+                "gui" => DaemonCommand::Gui { delay_ms: 0 },
+                "full" => DaemonCommand::FullSave,
+                _ => return,
+            };
+            let _ = cmd_tx.send(cmd);
+        })
+        .await;
+        if let Err(err) = result {
+            error!("global shortcuts unavailable: {}", err);
+        }
+    });
+}
+
+fn run_daemon(rt: &tokio::runtime::Runtime, config: Config) -> Result<(), CaptureError> {
     rt.block_on(async {
         env_logger::builder().is_test(false).try_init().ok();
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (cmd_tx, mut cmd_rx) = mpsc::unbounded_channel();
         let service = FireshotService {
             shutdown: std::sync::Mutex::new(Some(shutdown_tx)),
+            cmd_tx: cmd_tx.clone(),
+            config: config.clone(),
+        };
+        if config.hotkeys.enabled {
+            spawn_hotkeys(&config.hotkeys, cmd_tx.clone());
+        }
+        let tray_service = TrayService::new(FireshotTray {
+            cmd_tx: cmd_tx.clone(),
+            recent: recent_captures(),
+            save_dir: config.save.directory(),
+            state: TrayState::Idle,
+        });
+        let tray_handle = tray_service.handle();
+        let editor_host = if config.editor.daemon_hosted {
+            EditorHost::spawn(&config, cmd_tx.clone())
+                .map_err(|err| warn!("hosted editor unavailable: {}", err))
+                .ok()
+        } else {
+            None
         };
-        let (cmd_tx, mut cmd_rx) = mpsc::unbounded_channel();
-        let tray_service = TrayService::new(FireshotTray { cmd_tx });
 
-        let _conn = zbus::ConnectionBuilder::session()
+        let conn = zbus::ConnectionBuilder::session()
             .map_err(|e| CaptureError::Io(e.to_string()))?
             .name("org.fireshot.Fireshot")
             .map_err(|e| CaptureError::Io(e.to_string()))?
@@ -358,23 +1817,94 @@ fn run_daemon(rt: &tokio::runtime::Runtime) -> Result<(), CaptureError> {
             .await
             .map_err(|e| CaptureError::Io(e.to_string()))?;
 
+        match conn.request_name("org.flameshot.Flameshot").await {
+            Ok(()) => {
+                if let Err(err) = conn
+                    .object_server()
+                    .at("/", FlameshotCompat)
+                    .await
+                {
+                    warn!("flameshot compat: {}", err);
+                }
+            }
+            Err(err) => warn!("flameshot compat: name unavailable: {}", err),
+        }
+
         tray_service.spawn();
-        println!("fireshot daemon running (org.fireshot.Fireshot)");
+        println!("{}", tr!("cli-daemon-running"));
+        systemd::notify("READY=1");
         tokio::pin!(shutdown_rx);
+        let mut tray_refresh =
+            tokio::time::interval(std::time::Duration::from_secs(TRAY_REFRESH_SECS));
+        let mut state_generation = 0u64;
         loop {
             tokio::select! {
                 _ = &mut shutdown_rx => break,
+                _ = tray_refresh.tick() => {
+                    let recent = recent_captures();
+                    tray_handle.update(move |tray| {
+                        if tray.recent != recent {
+                            tray.recent = recent;
+                        }
+                    });
+                }
                 Some(cmd) = cmd_rx.recv() => match cmd {
-                    DaemonCommand::Gui => {
-                        spawn_capture(CaptureKind::Gui { delay_ms: 0, path: None });
+                    DaemonCommand::Gui { delay_ms } => {
+                        if !editor_host.as_ref().is_some_and(|host| host.submit(delay_ms)) {
+                            spawn_capture(CaptureKind::Gui { delay_ms, path: None });
+                        }
                     }
                     DaemonCommand::FullSave => {
-                        spawn_capture(CaptureKind::Full { delay_ms: 0, path: None, edit: false });
+                        spawn_capture(CaptureKind::Full {
+                            delay_ms: 0,
+                            path: None,
+                            edit: false,
+                            clipboard: false,
+                        });
+                    }
+                    DaemonCommand::RegionCopy => spawn_capture(CaptureKind::RegionCopy),
+                    DaemonCommand::Launcher => spawn_capture(CaptureKind::Launcher),
+                    #[cfg(feature = "recording")]
+                    DaemonCommand::Record => spawn_capture(CaptureKind::Record),
+                    DaemonCommand::StopRecording => {
+                        tokio::spawn(async {
+                            if let Err(err) = record::request_stop().await {
+                                warn!("stop recording: {}", err);
+                            }
+                        });
+                    }
+                    DaemonCommand::Recopy => {
+                        tokio::task::spawn_blocking(|| {
+                            if let Err(err) = recopy_last() {
+                                warn!("recopy: {}", err);
+                            }
+                        });
+                    }
+                    DaemonCommand::SetState(state) => {
+                        state_generation += 1;
+                        tray_handle.update(move |tray| tray.state = state);
+                        if state.is_transient() {
+                            let cmd_tx = cmd_tx.clone();
+                            let generation = state_generation;
+                            tokio::spawn(async move {
+                                tokio::time::sleep(std::time::Duration::from_secs(
+                                    TRAY_TRANSIENT_SECS,
+                                ))
+                                .await;
+                                let _ = cmd_tx.send(DaemonCommand::ExpireState(generation));
+                            });
+                        }
+                    }
+                    DaemonCommand::ExpireState(generation) => {
+                        if generation == state_generation {
+                            tray_handle.update(|tray| tray.state = TrayState::Idle);
+                        }
                     }
                     DaemonCommand::Quit => break,
                 },
             }
         }
+        systemd::notify("STOPPING=1");
         Ok(())
     })
 }
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use fireshot_core::config::NotificationConfig;
+use fireshot_core::{paths, tr, CaptureError, ExportResult};
+use futures_util::StreamExt;
+use log::{debug, warn};
+use zbus::dbus_proxy;
+use zbus::zvariant::Value;
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    fn get_capabilities(&self) -> zbus::Result<Vec<String>>;
+
+    /// Holds popups back until `un_inhibit`, or until the caller leaves the
+    /// bus; Plasma has it.
+    fn inhibit(
+        &self,
+        desktop_entry: &str,
+        reason: &str,
+        hints: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<u32>;
+
+    fn un_inhibit(&self, cookie: u32) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.dunstproject.cmd0",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Dunst {
+    #[dbus_proxy(property(emits_changed_signal = "false"), name = "paused")]
+    fn paused(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property(emits_changed_signal = "false"), name = "paused")]
+    fn set_paused(&self, paused: bool) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.erikreider.swaync.cc",
+    default_service = "org.erikreider.swaync.cc",
+    default_path = "/org/erikreider/swaync/cc"
+)]
+trait SwayNc {
+    fn get_dnd(&self) -> zbus::Result<bool>;
+
+    fn set_dnd(&self, state: bool) -> zbus::Result<()>;
+}
+
+/// A notification about a finished export and the file/URL its actions act on.
+pub struct ExportNotice {
+    pub summary: String,
+    pub body: String,
+    pub path: Option<PathBuf>,
+    pub url: Option<String>,
+    /// Markdown/HTML/reST showing the saved file or URL, for Copy snippet.
+    pub snippet: Option<String>,
+}
+
+impl From<&ExportResult> for ExportNotice {
+    fn from(export: &ExportResult) -> Self {
+        match export {
+            ExportResult::Copied { history_path } => Self {
+                summary: tr!("notify-copied").to_string(),
+                body: tr!("notify-copied-body").to_string(),
+                path: history_path.clone(),
+                url: None,
+                snippet: None,
+            },
+            ExportResult::Saved { path } => Self {
+                summary: tr!("notify-saved").to_string(),
+                body: path.display().to_string(),
+                path: Some(path.clone()),
+                url: None,
+                snippet: None,
+            },
+            ExportResult::Recorded { path } => Self {
+                summary: tr!("notify-recorded").to_string(),
+                body: path.display().to_string(),
+                path: Some(path.clone()),
+                url: None,
+                snippet: None,
+            },
+            ExportResult::Emailed { .. } => Self {
+                summary: tr!("notify-emailed").to_string(),
+                body: tr!("notify-emailed-body").to_string(),
+                path: None,
+                url: None,
+                snippet: None,
+            },
+            ExportResult::Shared { url } => Self {
+                summary: tr!("notify-shared").to_string(),
+                body: url.clone(),
+                path: None,
+                url: Some(url.clone()),
+                snippet: None,
+            },
+        }
+    }
+}
+
+/// Shows a notification per export, all at once. With `actions` each offers
+/// Open, Copy snippet and the rest and waits for a click; without, they are
+/// plain notices for a process about to exit.
+pub async fn notify_exports(
+    config: &NotificationConfig,
+    notices: Vec<ExportNotice>,
+    actions: bool,
+) {
+    let shown = notices
+        .into_iter()
+        .map(|notice| notify_export(config, notice, actions));
+    for result in futures_util::future::join_all(shown).await {
+        if let Err(err) = result {
+            warn!("notification: {}", err);
+        }
+    }
+}
+
+/// Shows the notification and, with `with_actions`, runs whichever action
+/// the user clicks, waiting at most `config.action_timeout_secs` for one.
+pub async fn notify_export(
+    config: &NotificationConfig,
+    notice: ExportNotice,
+    with_actions: bool,
+) -> Result<(), CaptureError> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let conn = zbus::Connection::session()
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let proxy = NotificationsProxy::new(&conn)
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+
+    let mut actions = Vec::new();
+    if with_actions && notice.path.is_some() {
+        actions.extend([
+            "open",
+            tr!("notify-action-open"),
+            "folder",
+            tr!("notify-action-folder"),
+        ]);
+    }
+    if with_actions && notice.url.is_some() {
+        actions.extend(["copy-url", tr!("notify-action-copy-url")]);
+    }
+    if with_actions && notice.snippet.is_some() {
+        actions.extend(["copy-snippet", tr!("notify-action-copy-snippet")]);
+    }
+    if with_actions && notice.path.is_some() {
+        actions.extend(["delete", tr!("notify-action-delete")]);
+    }
+
+    let mut hints = HashMap::new();
+    let image_path = notice.path.as_ref().map(|p| p.display().to_string());
+    if let Some(image_path) = image_path.as_deref() {
+        hints.insert("image-path", Value::from(image_path));
+    }
+
+    // Subscribe before sending so a fast click can't slip past us.
+    let mut invoked = proxy
+        .receive_action_invoked()
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let mut closed = proxy
+        .receive_notification_closed()
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+
+    let id = proxy
+        .notify(
+            "Fireshot",
+            0,
+            "camera-photo",
+            &notice.summary,
+            &notice.body,
+            &actions,
+            hints,
+            -1,
+        )
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    if actions.is_empty() {
+        return Ok(());
+    }
+
+    let timeout = tokio::time::sleep(std::time::Duration::from_secs(config.action_timeout_secs));
+    tokio::pin!(timeout);
+    loop {
+        tokio::select! {
+            _ = &mut timeout => return Ok(()),
+            Some(signal) = closed.next() => {
+                if signal.args().is_ok_and(|args| args.id == id) {
+                    return Ok(());
+                }
+            }
+            Some(signal) = invoked.next() => {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                if args.id != id {
+                    continue;
+                }
+                debug!("notification action: {}", args.action_key);
+                run_action(&args.action_key, &notice);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Briefly tells the user a daemon capture was dismissed: low urgency,
+/// transient and without actions, so it doesn't read as a failure.
+pub async fn notify_cancelled(config: &NotificationConfig) -> Result<(), CaptureError> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let conn = zbus::Connection::session()
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let proxy = NotificationsProxy::new(&conn)
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let mut hints = HashMap::new();
+    hints.insert("urgency", Value::from(0u8));
+    hints.insert("transient", Value::from(true));
+    proxy
+        .notify(
+            "Fireshot",
+            0,
+            "camera-photo",
+            tr!("notify-cancelled"),
+            "",
+            &[],
+            hints,
+            CANCELLED_TIMEOUT_MS,
+        )
+        .await
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    Ok(())
+}
+
+/// Runs `capture` with notification popups held back when
+/// `config.do_not_disturb` is set, so none land in the screenshot, and puts
+/// the daemon back as it was afterwards.
+pub async fn quietly<F: std::future::Future>(config: &NotificationConfig, capture: F) -> F::Output {
+    let quiet = if config.do_not_disturb {
+        Quiet::start().await
+    } else {
+        None
+    };
+    let output = capture.await;
+    if let Some(quiet) = quiet {
+        quiet.end().await;
+    }
+    output
+}
+
+/// Do-not-disturb turned on for a capture, and how to turn it off again.
+struct Quiet {
+    /// Kept open: Plasma drops an inhibition whose caller leaves the bus.
+    conn: zbus::Connection,
+    undo: Undo,
+}
+
+enum Undo {
+    UnInhibit(u32),
+    Unpause,
+    SwayNcDndOff,
+}
+
+impl Quiet {
+    /// Turns on do-not-disturb through whichever of the Notifications
+    /// inhibit call, dunst or swaync the running daemon has. Daemons where
+    /// it was already on are left alone.
+    async fn start() -> Option<Self> {
+        let conn = match zbus::Connection::session().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                warn!("do not disturb: {}", err);
+                return None;
+            }
+        };
+        let undo = match Self::turn_on(&conn).await {
+            Ok(undo) => undo?,
+            Err(err) => {
+                warn!("do not disturb: {}", err);
+                return None;
+            }
+        };
+        Some(Self { conn, undo })
+    }
+
+    async fn turn_on(conn: &zbus::Connection) -> zbus::Result<Option<Undo>> {
+        let notifications = NotificationsProxy::new(conn).await?;
+        let inhibits = notifications
+            .get_capabilities()
+            .await
+            .is_ok_and(|caps| caps.iter().any(|cap| cap == "inhibitions"));
+        if inhibits {
+            let cookie = notifications
+                .inhibit("fireshot", tr!("notify-inhibit-reason"), HashMap::new())
+                .await?;
+            return Ok(Some(Undo::UnInhibit(cookie)));
+        }
+        let dunst = DunstProxy::new(conn).await?;
+        if let Ok(paused) = dunst.paused().await {
+            if paused {
+                return Ok(None);
+            }
+            dunst.set_paused(true).await?;
+            return Ok(Some(Undo::Unpause));
+        }
+        let swaync = SwayNcProxy::new(conn).await?;
+        match swaync.get_dnd().await {
+            Ok(false) => {
+                swaync.set_dnd(true).await?;
+                Ok(Some(Undo::SwayNcDndOff))
+            }
+            Ok(true) => Ok(None),
+            Err(err) => {
+                debug!("do not disturb: no inhibit, dunst or swaync: {}", err);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn end(self) {
+        let undone = match self.undo {
+            Undo::UnInhibit(cookie) => match NotificationsProxy::new(&self.conn).await {
+                Ok(proxy) => proxy.un_inhibit(cookie).await,
+                Err(err) => Err(err),
+            },
+            Undo::Unpause => match DunstProxy::new(&self.conn).await {
+                Ok(proxy) => proxy.set_paused(false).await,
+                Err(err) => Err(err),
+            },
+            Undo::SwayNcDndOff => match SwayNcProxy::new(&self.conn).await {
+                Ok(proxy) => proxy.set_dnd(false).await,
+                Err(err) => Err(err),
+            },
+        };
+        if let Err(err) = undone {
+            warn!("do not disturb: restoring: {}", err);
+        }
+    }
+}
+
+/// How long the cancellation notice stays up.
+const CANCELLED_TIMEOUT_MS: i32 = 2000;
+
+fn run_action(action: &str, notice: &ExportNotice) {
+    let result = match (action, notice.path.as_deref(), notice.url.as_deref()) {
+        ("open", Some(path), _) => paths::open_with_default_app(path),
+        ("folder", Some(path), _) => {
+            paths::open_with_default_app(path.parent().unwrap_or(Path::new("/")))
+        }
+        ("copy-url", _, Some(url)) => fireshot_gui::copy_text_to_clipboard(url),
+        ("copy-snippet", _, _) => match &notice.snippet {
+            Some(snippet) => fireshot_gui::copy_text_to_clipboard(snippet),
+            None => Ok(()),
+        },
+        ("delete", Some(path), _) => {
+            std::fs::remove_file(path).map_err(|e| CaptureError::Io(e.to_string()))
+        }
+        _ => Ok(()),
+    };
+    if let Err(err) = result {
+        warn!("notification action {}: {}", action, err);
+    }
+}
@@ -0,0 +1,302 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::Duration;
+
+use fireshot_core::config::{Config, RecordFormat};
+use fireshot_core::{CaptureError, ExportResult, RecordTarget};
+use fireshot_portal::RecordedFrame;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::FilterType;
+use image::{Delay, Frame, RgbaImage};
+use log::{debug, warn};
+use tokio::sync::Notify;
+use zbus::dbus_interface;
+
+const RECORDER_BUS_NAME: &str = "org.fireshot.Fireshot.Recorder";
+const RECORDER_OBJECT_PATH: &str = "/org/fireshot/Recorder";
+/// Quantizer speed for GIF frames, 1 (best) to 30 (fastest).
+const GIF_SPEED: i32 = 10;
+/// Shortest frame delay; viewers slow anything faster right down.
+const MIN_FRAME_DELAY_MS: u64 = 20;
+
+struct Recorder {
+    stop: Arc<Notify>,
+}
+
+#[dbus_interface(name = "org.fireshot.Recorder")]
+impl Recorder {
+    fn stop(&self) {
+        self.stop.notify_one();
+    }
+}
+
+/// Records `target` in `[record] format` to the save directory until the
+/// tray's Stop recording (or anything calling `Stop` on the recorder) ends it
+/// or it has run for `[record] max_secs`. With `ctrl_c`, Ctrl+C ends it too;
+/// the daemon passes `false` so it keeps quitting on Ctrl+C afterwards.
+pub(crate) async fn record_screen(
+    config: &Config,
+    target: RecordTarget,
+    ctrl_c: bool,
+) -> Result<ExportResult, CaptureError> {
+    let stop = Arc::new(Notify::new());
+    let _service = serve_stop(stop.clone())
+        .await
+        .map_err(|err| warn!("recorder: stop control unavailable: {}", err))
+        .ok();
+
+    let format = config.record.format;
+    let audio = match (config.record.audio, format.is_video()) {
+        (true, true) => Some(config.record.audio_source.clone()),
+        (true, false) => {
+            warn!("recorder: GIFs have no audio, recording without it");
+            None
+        }
+        (false, _) => None,
+    };
+    let path = config.save.recording_path(format);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CaptureError::Io(format!("{}: {}", dir.display(), e)))?;
+    }
+    let (frames_tx, frames_rx) = std_mpsc::channel();
+    let encode_path = path.clone();
+    let encoder = std::thread::Builder::new()
+        .name("fireshot-encode".to_string())
+        .spawn(move || match format {
+            RecordFormat::Gif => encode_gif(&encode_path, frames_rx),
+            RecordFormat::Mp4 | RecordFormat::Webm => {
+                encode_video(&encode_path, format, audio.as_deref(), frames_rx)
+            }
+        })
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+
+    let max = match config.record.max_secs {
+        0 => Duration::MAX,
+        secs => Duration::from_secs(secs),
+    };
+    let interrupted = async {
+        if !ctrl_c || tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    let stopped = async {
+        tokio::select! {
+            _ = stop.notified() => debug!("recording stopped"),
+            _ = interrupted => debug!("recording interrupted"),
+            _ = tokio::time::sleep(max) => debug!("recording reached {}s", max.as_secs()),
+        }
+    };
+    let recorded = fireshot_portal::record_screen(target, config.record.fps, stopped, frames_tx).await;
+    let encoded = tokio::task::spawn_blocking(move || encoder.join())
+        .await
+        .ok()
+        .and_then(|joined| joined.ok())
+        .unwrap_or_else(|| Err(CaptureError::Io("recording encoder panicked".to_string())));
+
+    let result = recorded.and(encoded).and_then(|frames| match frames {
+        0 => Err(CaptureError::Portal("the screencast sent no frames".to_string())),
+        _ => Ok(frames),
+    });
+    match result {
+        Ok(frames) => {
+            debug!("recorded {} frames to {}", frames, path.display());
+            Ok(ExportResult::Recorded { path })
+        }
+        Err(err) => {
+            let _ = std::fs::remove_file(&path);
+            Err(err)
+        }
+    }
+}
+
+async fn serve_stop(stop: Arc<Notify>) -> zbus::Result<zbus::Connection> {
+    zbus::ConnectionBuilder::session()?
+        .name(RECORDER_BUS_NAME)?
+        .serve_at(RECORDER_OBJECT_PATH, Recorder { stop })?
+        .build()
+        .await
+}
+
+/// Asks the running recording, in this process or another, to stop.
+pub(crate) async fn request_stop() -> zbus::Result<()> {
+    let conn = zbus::Connection::session().await?;
+    conn.call_method(
+        Some(RECORDER_BUS_NAME),
+        RECORDER_OBJECT_PATH,
+        Some("org.fireshot.Recorder"),
+        "Stop",
+        &(),
+    )
+    .await?;
+    Ok(())
+}
+
+fn encode_error(err: image::ImageError) -> CaptureError {
+    CaptureError::EncodeError {
+        format: "GIF".to_string(),
+        reason: err.to_string(),
+    }
+}
+
+/// Writes frames to `path` as they arrive, each shown until the next one was
+/// captured; the last until the recording stopped. Returns the frame count.
+fn encode_gif(path: &Path, frames: std_mpsc::Receiver<RecordedFrame>) -> Result<usize, CaptureError> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), GIF_SPEED);
+    encoder.set_repeat(Repeat::Infinite).map_err(encode_error)?;
+    let mut write = |frame: RecordedFrame, shown: Duration| {
+        let shown = shown.max(Duration::from_millis(MIN_FRAME_DELAY_MS));
+        encoder
+            .encode_frame(Frame::from_parts(frame.image, 0, 0, Delay::from_saturating_duration(shown)))
+            .map_err(encode_error)
+    };
+
+    let mut count = 0;
+    let mut previous: Option<RecordedFrame> = None;
+    for frame in frames {
+        if let Some(previous) = previous.take() {
+            let shown = frame.captured_at - previous.captured_at;
+            write(previous, shown)?;
+            count += 1;
+        }
+        previous = Some(frame);
+    }
+    if let Some(last) = previous {
+        let shown = last.captured_at.elapsed();
+        write(last, shown)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Pipes frames into an ffmpeg child that encodes `path` as they arrive,
+/// timed by when each reached ffmpeg. The last frame is sent again at the end
+/// so a still screen keeps its length. Returns the frame count.
+fn encode_video(
+    path: &Path,
+    format: RecordFormat,
+    audio: Option<&str>,
+    frames: std_mpsc::Receiver<RecordedFrame>,
+) -> Result<usize, CaptureError> {
+    let mut frames = frames.into_iter();
+    let Some(first) = frames.next() else {
+        return Ok(0);
+    };
+    let (width, height) = first.image.dimensions();
+    let mut child = ffmpeg_command(path, format, width, height, audio)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => CaptureError::Unsupported(
+                "recording video needs ffmpeg; install it or set [record] format = \"gif\"".to_string(),
+            ),
+            _ => CaptureError::Io(format!("ffmpeg: {}", e)),
+        })?;
+    let mut stdin = child.stdin.take().expect("ffmpeg stdin is piped");
+
+    let mut count = 0;
+    let mut last: Option<RgbaImage> = None;
+    for frame in std::iter::once(first).chain(frames) {
+        // Windows can be resized mid-recording; the video keeps its first size.
+        let image = if frame.image.dimensions() == (width, height) {
+            frame.image
+        } else {
+            image::imageops::resize(&frame.image, width, height, FilterType::Triangle)
+        };
+        if stdin.write_all(image.as_raw()).is_err() {
+            // ffmpeg gave up; its exit status below says why.
+            break;
+        }
+        count += 1;
+        last = Some(image);
+    }
+    if let Some(last) = last {
+        let _ = stdin.write_all(last.as_raw());
+    }
+    drop(stdin);
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| CaptureError::Io(format!("ffmpeg: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CaptureError::EncodeError {
+            format: format.extension().to_uppercase(),
+            reason: stderr
+                .lines()
+                .last()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("ffmpeg exited with {}", output.status)),
+        });
+    }
+    Ok(count)
+}
+
+/// ffmpeg reading raw RGBA frames from stdin, plus a PulseAudio source when
+/// `audio` is set.
+fn ffmpeg_command(
+    path: &Path,
+    format: RecordFormat,
+    width: u32,
+    height: u32,
+    audio: Option<&str>,
+) -> Command {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-video_size"])
+        .arg(format!("{}x{}", width, height))
+        .args(["-use_wallclock_as_timestamps", "1", "-i", "-"]);
+    if let Some(source) = audio {
+        command.args(["-f", "pulse", "-i", source]);
+    }
+    // 4:2:0 chroma needs even sides.
+    command.args([
+        "-vf",
+        "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+        "-pix_fmt",
+        "yuv420p",
+        "-fps_mode",
+        "vfr",
+    ]);
+    match format {
+        RecordFormat::Mp4 => command.args([
+            "-c:v",
+            "libx264",
+            "-preset",
+            "veryfast",
+            "-crf",
+            "23",
+            "-movflags",
+            "+faststart",
+        ]),
+        _ => command.args([
+            "-c:v",
+            "libvpx-vp9",
+            "-deadline",
+            "realtime",
+            "-cpu-used",
+            "8",
+            "-row-mt",
+            "1",
+            "-b:v",
+            "0",
+            "-crf",
+            "32",
+        ]),
+    };
+    if audio.is_some() {
+        let codec = if format == RecordFormat::Mp4 { "aac" } else { "libopus" };
+        // The microphone never ends on its own; stop with the video.
+        command.args(["-c:a", codec, "-shortest"]);
+    }
+    command
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    command
+}
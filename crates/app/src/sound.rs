@@ -0,0 +1,109 @@
+#[cfg(not(feature = "sound"))]
+use std::process::{Command, Stdio};
+
+use fireshot_core::config::Config;
+use log::{debug, warn};
+
+/// The sound theme's name for the shutter.
+#[cfg(not(feature = "sound"))]
+const SHUTTER_EVENT: &str = "camera-shutter";
+/// Where the freedesktop sound theme keeps it, for players that take a file.
+const SHUTTER_FILE: &str = "/usr/share/sounds/freedesktop/stereo/camera-shutter.oga";
+
+/// Plays the shutter sound without waiting for it when `[behavior]
+/// shutter_sound` is on. Built with the `sound` feature it is played
+/// in-process with rodio; otherwise through `canberra-gtk-play` so the
+/// desktop's sound theme picks it, or `pw-play` / `paplay` on the
+/// freedesktop one.
+pub(crate) fn shutter(config: &Config) {
+    if !config.behavior.shutter_sound {
+        return;
+    }
+    play();
+}
+
+/// The freedesktop theme's shutter, or a short synthesized click where that
+/// isn't installed, so the sound never depends on what else is installed.
+#[cfg(feature = "sound")]
+fn play() {
+    use rodio::{Decoder, OutputStream, Sink};
+
+    // The stream has to outlive the sound; the daemon lives on.
+    std::thread::spawn(|| {
+        let (_stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(err) => {
+                warn!("shutter sound: {}", err);
+                return;
+            }
+        };
+        let sink = match Sink::try_new(&handle) {
+            Ok(sink) => sink,
+            Err(err) => {
+                warn!("shutter sound: {}", err);
+                return;
+            }
+        };
+        let themed = std::fs::File::open(SHUTTER_FILE)
+            .ok()
+            .and_then(|file| Decoder::new(std::io::BufReader::new(file)).ok());
+        match themed {
+            Some(source) => sink.append(source),
+            None => {
+                debug!("shutter sound: no {}, synthesizing one", SHUTTER_FILE);
+                sink.append(click());
+            }
+        }
+        sink.sleep_until_end();
+    });
+}
+
+/// A burst of noise fading out over `CLICK_MS`, roughly a shutter.
+#[cfg(feature = "sound")]
+fn click() -> rodio::buffer::SamplesBuffer<f32> {
+    const SAMPLE_RATE: u32 = 44_100;
+    const CLICK_MS: u32 = 60;
+    let len = SAMPLE_RATE * CLICK_MS / 1000;
+    let mut seed: u32 = 0x9e37_79b9;
+    let samples = (0..len)
+        .map(|i| {
+            // xorshift: noise without pulling in a random number crate.
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let noise = seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            let fade = 1.0 - i as f32 / len as f32;
+            noise * fade * fade * 0.5
+        })
+        .collect::<Vec<_>>();
+    rodio::buffer::SamplesBuffer::new(1, SAMPLE_RATE, samples)
+}
+
+#[cfg(not(feature = "sound"))]
+fn play() {
+    let players: [(&str, &[&str]); 3] = [
+        ("canberra-gtk-play", &["--id", SHUTTER_EVENT]),
+        ("pw-play", &[SHUTTER_FILE]),
+        ("paplay", &[SHUTTER_FILE]),
+    ];
+    for (player, args) in players {
+        let spawned = Command::new(player)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                // Reaped off the capture path; the daemon lives on.
+                std::thread::spawn(move || child.wait());
+                return;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                warn!("shutter sound: {}: {}", player, err);
+                return;
+            }
+        }
+    }
+    debug!("shutter sound: no canberra-gtk-play, pw-play or paplay");
+}
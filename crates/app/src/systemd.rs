@@ -0,0 +1,25 @@
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixDatagram;
+
+use log::debug;
+
+/// Sends `state` (e.g. `READY=1`) to the service manager when running as a
+/// `Type=notify` systemd unit; does nothing otherwise.
+pub fn notify(state: &str) {
+    let Some(socket_path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        let bytes = socket_path.as_bytes();
+        if let Some(name) = bytes.strip_prefix(b"@") {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)
+        } else {
+            socket.send_to(state.as_bytes(), &socket_path)
+        }
+    });
+    if let Err(err) = result {
+        debug!("sd_notify {}: {}", state, err);
+    }
+}
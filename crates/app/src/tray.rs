@@ -0,0 +1,285 @@
+use fireshot_core::history::HistoryEntry;
+use fireshot_core::tr;
+use ksni::menu::{MenuItem, StandardItem, SubMenu};
+use ksni::{ToolTip, Tray};
+use tokio::sync::mpsc;
+
+use crate::{open_dir_in_desktop, open_in_desktop, DaemonCommand};
+
+/// What the tray icon currently shows. Capture processes spawned by the
+/// daemon report these over DBus (`ReportState`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TrayState {
+    Idle,
+    Countdown,
+    Recording,
+    Done,
+    Failed,
+}
+
+impl TrayState {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            TrayState::Idle => "idle",
+            TrayState::Countdown => "countdown",
+            TrayState::Recording => "recording",
+            TrayState::Done => "done",
+            TrayState::Failed => "failed",
+        }
+    }
+
+    pub(crate) fn parse(state: &str) -> Option<Self> {
+        Some(match state {
+            "idle" => TrayState::Idle,
+            "countdown" => TrayState::Countdown,
+            "recording" => TrayState::Recording,
+            "done" => TrayState::Done,
+            "failed" => TrayState::Failed,
+            _ => return None,
+        })
+    }
+
+    /// Done/Failed only flash briefly before the icon returns to idle.
+    pub(crate) fn is_transient(self) -> bool {
+        matches!(self, TrayState::Done | TrayState::Failed)
+    }
+
+    fn icon_name(self) -> &'static str {
+        match self {
+            TrayState::Idle => "camera-photo",
+            TrayState::Countdown => "appointment-soon",
+            TrayState::Recording => "media-record",
+            TrayState::Done => "emblem-default",
+            TrayState::Failed => "dialog-error",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            TrayState::Idle => tr!("tray-state-idle"),
+            TrayState::Countdown => tr!("tray-state-countdown"),
+            TrayState::Recording => tr!("tray-state-recording"),
+            TrayState::Done => tr!("tray-state-done"),
+            TrayState::Failed => tr!("tray-state-failed"),
+        }
+    }
+}
+
+/// Delays offered by the tray's "Capture in" submenu, in seconds.
+const TRAY_DELAYS_SECS: [u64; 3] = [3, 5, 10];
+/// Number of history entries listed under "Recent captures".
+const TRAY_RECENT_COUNT: usize = 5;
+/// How often the daemon rereads the history for the tray menu.
+pub(crate) const TRAY_REFRESH_SECS: u64 = 5;
+/// How long the done/failed icon stays up before returning to idle.
+pub(crate) const TRAY_TRANSIENT_SECS: u64 = 3;
+
+pub(crate) struct FireshotTray {
+    pub(crate) cmd_tx: mpsc::UnboundedSender<DaemonCommand>,
+    pub(crate) recent: Vec<HistoryEntry>,
+    pub(crate) save_dir: std::path::PathBuf,
+    pub(crate) state: TrayState,
+}
+
+impl Tray for FireshotTray {
+    fn activate(&mut self, _x: i32, _y: i32) {
+        let cmd = if self.state == TrayState::Recording {
+            DaemonCommand::StopRecording
+        } else {
+            DaemonCommand::Gui { delay_ms: 0 }
+        };
+        let _ = self.cmd_tx.send(cmd);
+    }
+
+    fn id(&self) -> String {
+        "fireshot".to_string()
+    }
+
+    fn title(&self) -> String {
+        "Fireshot".to_string()
+    }
+
+    fn icon_name(&self) -> String {
+        self.state.icon_name().to_string()
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        ToolTip {
+            title: "Fireshot".to_string(),
+            description: self.state.description().to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut delayed: Vec<MenuItem<Self>> = TRAY_DELAYS_SECS
+            .iter()
+            .map(|&secs| {
+                StandardItem {
+                    label: tr!("tray-delay-seconds", secs = secs),
+                    activate: Box::new(move |this: &mut FireshotTray| {
+                        let _ = this.cmd_tx.send(DaemonCommand::Gui {
+                            delay_ms: secs * 1000,
+                        });
+                    }),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+        delayed.push(
+            StandardItem {
+                label: tr!("tray-delay-other").into(),
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::Launcher);
+                }),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        let mut recent: Vec<MenuItem<Self>> = self
+            .recent
+            .iter()
+            .map(|entry| {
+                let path = entry.path.clone();
+                StandardItem {
+                    label: entry.label(),
+                    icon_name: "image-x-generic".into(),
+                    activate: Box::new(move |_: &mut FireshotTray| open_in_desktop(&path)),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+        if recent.is_empty() {
+            recent.push(
+                StandardItem {
+                    label: tr!("tray-no-captures").into(),
+                    enabled: false,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
+        let save_dir = self.save_dir.clone();
+        let mut items: Vec<MenuItem<Self>> = Vec::new();
+        if self.state == TrayState::Recording {
+            items.push(
+                StandardItem {
+                    label: tr!("tray-stop-recording").into(),
+                    icon_name: "media-playback-stop".into(),
+                    activate: Box::new(|this: &mut FireshotTray| {
+                        let _ = this.cmd_tx.send(DaemonCommand::StopRecording);
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items.push(MenuItem::Separator);
+        }
+        items.extend([
+            StandardItem {
+                label: tr!("tray-capture").into(),
+                icon_name: "camera-photo".into(),
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::Gui { delay_ms: 0 });
+                }),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: tr!("tray-capture-in").into(),
+                icon_name: "chronometer".into(),
+                submenu: delayed,
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("tray-region-copy").into(),
+                icon_name: "edit-copy".into(),
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::RegionCopy);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("tray-full-screen").into(),
+                icon_name: "display".into(),
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::FullSave);
+                }),
+                ..Default::default()
+            }
+            .into(),
+        ]);
+        #[cfg(feature = "recording")]
+        items.push(
+            StandardItem {
+                label: tr!("tray-record").into(),
+                icon_name: "media-record".into(),
+                enabled: self.state != TrayState::Recording,
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::Record);
+                }),
+                ..Default::default()
+            }
+            .into(),
+        );
+        items.extend([
+            MenuItem::Separator,
+            SubMenu {
+                label: tr!("tray-recent").into(),
+                icon_name: "document-open-recent".into(),
+                submenu: recent,
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("tray-recopy").into(),
+                icon_name: "edit-copy".into(),
+                enabled: !self.recent.is_empty(),
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::Recopy);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("tray-open-folder").into(),
+                icon_name: "folder-pictures".into(),
+                activate: Box::new(move |_: &mut FireshotTray| open_dir_in_desktop(&save_dir)),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("tray-settings").into(),
+                icon_name: "preferences-system".into(),
+                activate: Box::new(|_: &mut FireshotTray| {
+                    open_dir_in_desktop(&fireshot_core::paths::config_dir())
+                }),
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: tr!("tray-quit").into(),
+                icon_name: "application-exit".into(),
+                activate: Box::new(|this: &mut FireshotTray| {
+                    let _ = this.cmd_tx.send(DaemonCommand::Quit);
+                }),
+                ..Default::default()
+            }
+            .into(),
+        ]);
+        items
+    }
+}
+
+pub(crate) fn recent_captures() -> Vec<HistoryEntry> {
+    let mut entries = fireshot_core::history::entries();
+    entries.truncate(TRAY_RECENT_COUNT);
+    entries
+}
@@ -0,0 +1,139 @@
+//! `fireshot://` links, so browsers and other apps can start a capture, e.g.
+//! `fireshot://capture?mode=gui&delay=2000`. The desktop hands the link to
+//! `fireshot open-uri`, which makes the matching call on the daemon's DBus
+//! interface. Links only open the editor, where nothing is kept until the
+//! user exports: a web page shouldn't take and save a capture on its own,
+//! or pick where files are written.
+
+use fireshot_core::CaptureError;
+
+pub(crate) const SCHEME: &str = "fireshot";
+
+/// The DBus method a link maps to, with its delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UriRequest {
+    pub(crate) method: &'static str,
+    pub(crate) delay_ms: u64,
+}
+
+fn invalid(uri: &str, reason: &str) -> CaptureError {
+    CaptureError::Unsupported(format!("{}: {}", uri, reason))
+}
+
+/// Reads `fireshot://capture?mode=gui|full-gui&delay=MS`; `mode` defaults
+/// to `gui` and `delay` to none.
+pub(crate) fn parse(uri: &str) -> Result<UriRequest, CaptureError> {
+    let rest = uri
+        .strip_prefix(SCHEME)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .ok_or_else(|| invalid(uri, "not a fireshot:// link"))?;
+    let rest = rest.trim_start_matches('/');
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if action.trim_end_matches('/') != "capture" {
+        return Err(invalid(uri, "unknown action"));
+    }
+    let mut request = UriRequest {
+        method: "Gui",
+        delay_ms: 0,
+    };
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "mode" => {
+                request.method = match value {
+                    "gui" => "Gui",
+                    "full-gui" | "full_gui" => "FullGui",
+                    "full" => return Err(invalid(uri, "links can't save a capture unasked")),
+                    _ => return Err(invalid(uri, "mode is gui or full-gui")),
+                }
+            }
+            "delay" => {
+                request.delay_ms = value
+                    .parse()
+                    .map_err(|_| invalid(uri, "delay is in milliseconds"))?;
+            }
+            _ => return Err(invalid(uri, "unknown parameter")),
+        }
+    }
+    Ok(request)
+}
+
+/// Makes the call on the daemon, which DBus activation starts if needed.
+pub(crate) async fn send(request: UriRequest) -> Result<(), CaptureError> {
+    let call = async {
+        let conn = zbus::Connection::session().await?;
+        conn.call_method(
+            Some("org.fireshot.Fireshot"),
+            "/org/fireshot/Fireshot",
+            Some("org.fireshot.Fireshot"),
+            request.method,
+            &(request.delay_ms, ""),
+        )
+        .await?;
+        Ok::<(), zbus::Error>(())
+    };
+    call.await
+        .map_err(|e| CaptureError::Portal(format!("daemon: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(uri: &str) -> bool {
+        matches!(parse(uri), Err(CaptureError::Unsupported(_)))
+    }
+
+    #[test]
+    fn defaults_to_gui_without_delay() {
+        let expected = UriRequest {
+            method: "Gui",
+            delay_ms: 0,
+        };
+        assert_eq!(parse("fireshot://capture").unwrap(), expected);
+        assert_eq!(parse("fireshot://capture/").unwrap(), expected);
+        assert_eq!(parse("fireshot:capture").unwrap(), expected);
+        assert_eq!(parse("fireshot://capture?").unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_other_prefixes_and_actions() {
+        assert!(rejected("https://capture?mode=gui"));
+        assert!(rejected("fireshot"));
+        assert!(rejected("fireshotx://capture"));
+        assert!(rejected("FIRESHOT://capture"));
+        assert!(rejected("fireshot://record"));
+        assert!(rejected("fireshot://"));
+    }
+
+    #[test]
+    fn reads_mode_and_delay() {
+        let request = parse("fireshot://capture?mode=full-gui&delay=2000").unwrap();
+        assert_eq!(request.method, "FullGui");
+        assert_eq!(request.delay_ms, 2000);
+        assert_eq!(parse("fireshot://capture?mode=full_gui").unwrap().method, "FullGui");
+        assert_eq!(parse("fireshot://capture?delay=5&&mode=gui").unwrap().delay_ms, 5);
+    }
+
+    #[test]
+    fn rejects_unknown_query() {
+        assert!(rejected("fireshot://capture?mode=window"));
+        assert!(rejected("fireshot://capture?mode"));
+        assert!(rejected("fireshot://capture?path=/tmp/x.png"));
+        assert!(rejected("fireshot://capture?Mode=gui"));
+    }
+
+    #[test]
+    fn never_saves_unasked() {
+        assert!(rejected("fireshot://capture?mode=full"));
+        assert!(rejected("fireshot://capture?mode=gui&mode=full"));
+    }
+
+    #[test]
+    fn rejects_bad_delays() {
+        assert!(rejected("fireshot://capture?delay="));
+        assert!(rejected("fireshot://capture?delay=-1"));
+        assert!(rejected("fireshot://capture?delay=2s"));
+        assert!(rejected("fireshot://capture?delay=99999999999999999999999"));
+    }
+}
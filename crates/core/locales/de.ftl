@@ -0,0 +1,367 @@
+# Deutsche Übersetzung. Fehlende Einträge fallen auf en.ftl zurück.
+
+## Editor
+
+help-title = Klicken und ziehen, um einen Bereich auszuwählen
+help-copy = Strg+C: kopieren
+help-save = Strg+S: speichern
+help-paste = Strg+V: stattdessen das Bild aus der Zwischenablage bearbeiten
+help-undo = Strg+Z / Strg+Umschalt+Z: rückgängig/wiederholen
+help-size = Mausrad: Werkzeuggröße ändern
+help-snap = Alt: ohne Einrasten an Kanten auswählen
+help-keyboard-select = Pfeiltasten: Auswahl verschieben, Strg+Pfeiltasten: Größe ändern, Strg+A: alles auswählen
+help-keyboard-tools = 1–0, [ ]: Werkzeug wählen, + −: Größe, Tab: Werkzeugleiste
+help-arrange = Anordnen: Rahmen ziehen wählt Formen, Umschalt ergänzt; Entf: löschen, Strg+G / Strg+Umschalt+G: gruppieren / Gruppe auflösen
+help-peek = H gedrückt halten: Bedienelemente ausblenden, um darunter zu sehen
+help-tabs = Strg+Bild↑ / Strg+Bild↓: Tab wechseln; Bilddateien hierher ziehen, um sie in Tabs zu öffnen
+help-stats = F12: Bildzeiten und Speicher anzeigen
+help-close = Esc: schließen
+
+tool-grip = Werkzeugleiste verschieben
+tool-select = Auswahl
+tool-pencil = Stift
+tool-line = Linie
+tool-arrow = Pfeil
+tool-rect = Rechteck
+tool-circle = Kreis
+tool-marker = Marker
+tool-marker-line = Markerlinie
+tool-circle-count = Nummerierung
+tool-text = Text
+tool-timestamp = Zeitstempel einfügen
+tool-pixelate = Verpixeln
+tool-blur = Weichzeichnen
+tool-arrange = Formen anordnen
+tool-record = GIF aufnehmen
+tool-undo = Rückgängig
+tool-copy = Kopieren
+tool-save = Speichern
+tool-clear = Leeren
+
+menu-redo = Wiederholen
+menu-reset-zoom = Zoom zurücksetzen
+menu-auto-redact = Automatisch schwärzen
+menu-auto-crop = Automatisch zuschneiden
+menu-email = Per E-Mail senden
+menu-share-lan = Im LAN teilen
+menu-export-all = Alle exportieren ({ $count })
+menu-export-layers = Als Ebenen exportieren
+menu-clear-regions = Andere Auswahlen verwerfen
+menu-group = Gruppieren
+menu-ungroup = Gruppierung aufheben
+menu-delete-picked = Gewählte Formen löschen
+menu-save-preset = Auswahl als Vorlage speichern…
+menu-recapture = Neu aufnehmen in
+menu-close = Schließen
+
+control-color = Farbe
+control-size = Größe
+control-effect-rect = Rechteck
+control-effect-ellipse = Ellipse
+control-effect-lasso = Lasso
+control-effect-lasso-hint = Den Umriss nachzeichnen, den der Effekt abdecken soll
+control-effect-brush = Pinsel
+control-effect-brush-hint = Den Effekt genau auf die überstrichenen Pixel statt auf ein Rechteck malen
+redact-proposals = { $count } zu schwärzen
+redact-accept-all = Alle verwischen
+redact-dismiss = Verwerfen
+
+tab-capture = Aufnahme
+tab-close = Tab schließen
+layers-title = Ebenen ({ $count })
+layers-visible = Im Editor und in Exporten zeigen
+layers-reorder = Ziehen, um die Stapelreihenfolge zu ändern
+layers-delete = Diese Form löschen
+adjust-title = Anpassungen
+adjust-brightness = Helligkeit
+adjust-contrast = Kontrast
+adjust-saturation = Sättigung
+adjust-gamma = Gamma
+adjust-reset = Zurücksetzen
+layer-stroke = Strich
+layer-line = Linie
+layer-arrow = Pfeil
+layer-rect = Rechteck
+layer-circle = Ellipse
+layer-counter = Zähler { $count }
+layer-pixelate = Verpixeln
+layer-blur = Weichzeichnen
+
+preset-title = Auswahl als Vorlage speichern
+preset-hint = Name, z. B. Browser-Ansicht
+preset-save = Speichern
+preset-cancel = Abbrechen
+
+status-copied = In die Zwischenablage kopiert ({ $method })
+status-saved = Gespeichert: { $path }
+status-saved-all = { $count } Aufnahmen gespeichert in { $path }
+status-saved-layers = Basis- und Anmerkungsebene gespeichert in { $path }
+status-save-failed = Speichern fehlgeschlagen: { $error }
+status-exporting = Exportiere…
+status-export-failed = Export fehlgeschlagen
+status-paste-annotated = Zum Einfügen eines Bildes die Anmerkungen rückgängig machen oder löschen
+status-undo-limit = Ältere Formen liegen jenseits der Rückgängig-Grenze
+status-redact-found = { $count } Bereiche zum Schwärzen gefunden; zum Verwischen anklicken, Enter verwischt alle
+status-redact-none = Kein Text und keine Gesichter zum Schwärzen gefunden
+status-auto-cropped = Den { $width }×{ $height } großen Inhalt innerhalb der Ränder ausgewählt
+status-auto-crop-none = Keinen einfarbigen Rand zum Abschneiden gefunden
+status-preset-saved = Vorlage { $name } gespeichert
+status-emailed = Im E-Mail-Programm geöffnet
+status-email-failed = E-Mail fehlgeschlagen: { $error }
+status-share-waiting = Warte darauf, dass ein Gerät die Aufnahme abruft…
+status-share-timeout = Teilen abgelaufen; niemand hat die Aufnahme abgerufen
+status-share-stopped = Teilen beendet
+status-share-failed = Teilen fehlgeschlagen
+status-open-failed = { $path } konnte nicht geöffnet werden: { $error }
+status-log-show = Frühere
+status-log-hide = Frühere ausblenden
+status-open-file = Klicken, um die Datei zu öffnen
+status-open-url = Klicken, um den Link zu öffnen
+status-copy-path = Pfad kopieren
+status-copy-url = Link kopieren
+status-copied-link = Kopiert
+
+share-title = Im LAN teilen
+share-hint = Mit einem Telefon im selben Netzwerk scannen. Das Teilen endet nach dem ersten Download.
+share-stop = Teilen beenden
+
+recovery-title = Vorherige Sitzung wiederherstellen?
+recovery-body = Ein Editor wurde unerwartet beendet. Aufnahme und Anmerkungen wiederherstellen?
+recovery-restore = Wiederherstellen
+recovery-discard = Verwerfen
+
+## Launcher
+
+launcher-region = Bereich
+launcher-region-tip = Bildschirm aufnehmen und im Editor einen Bereich wählen
+launcher-full = Vollbild
+launcher-full-tip = Den ganzen Bildschirm aufnehmen und speichern
+launcher-window = Fenster
+launcher-window-tip = Fenster oder Bildschirm im Portal-Dialog wählen
+launcher-screen-tip = { $name } aufnehmen und speichern
+launcher-delay = Verzögerung
+launcher-settings = Einstellungen
+launcher-history = Verlauf
+launcher-search-hint = Text in Aufnahmen suchen…
+launcher-search-none = Keine Aufnahme enthält diesen Text
+launcher-create-failed = { $path } kann nicht angelegt werden: { $error }
+launcher-open-failed = { $path } kann nicht geöffnet werden: { $error }
+
+## Tray
+
+tray-state-idle = Bereit
+tray-state-countdown = Countdown läuft
+tray-state-done = Aufnahme fertig
+tray-state-failed = Aufnahme fehlgeschlagen
+tray-state-recording = Bildschirmaufnahme läuft
+tray-stop-recording = Aufnahme beenden
+tray-capture = Aufnehmen (Editor)
+tray-capture-in = Aufnehmen in
+tray-delay-seconds = { $secs } Sekunden
+tray-delay-other = Andere Verzögerung…
+tray-region-copy = Bereich in die Zwischenablage
+tray-full-screen = Vollbild
+tray-record = Bildschirm aufnehmen
+tray-recent = Letzte Aufnahmen
+tray-no-captures = Noch keine Aufnahmen
+tray-recopy = Letzte Aufnahme erneut kopieren
+tray-open-folder = Speicherordner öffnen
+tray-settings = Einstellungen
+tray-quit = Beenden
+
+## Global shortcuts
+
+hotkey-gui = Aufnehmen und im Editor öffnen
+hotkey-full = Den ganzen Bildschirm aufnehmen und speichern
+
+## Compare
+
+compare-side-by-side = Nebeneinander
+compare-diff = Unterschiede
+compare-stats = { $changed } von { $total } Pixeln unterscheiden sich ({ $percent } %)
+compare-save = Speichern…
+compare-copy = Kopieren
+
+## Notifications
+
+notify-copied = Bildschirmfoto kopiert
+notify-copied-body = Die Aufnahme liegt in der Zwischenablage.
+notify-saved = Bildschirmfoto gespeichert
+notify-recorded = Bildschirmaufnahme gespeichert
+notify-emailed = Bildschirmfoto angehängt
+notify-emailed-body = Die Aufnahme hängt an einer neuen Nachricht.
+notify-shared = Bildschirmfoto geteilt
+notify-cancelled = Aufnahme abgebrochen
+notify-inhibit-reason = Bildschirmfoto wird aufgenommen
+notify-action-open = Öffnen
+notify-action-folder = Ordner öffnen
+notify-action-copy-url = URL kopieren
+notify-action-copy-snippet = Schnipsel kopieren
+notify-action-delete = Löschen
+
+## Errors
+
+error-portal = Portal-Fehler: { $detail }
+error-cancelled = Aufnahme abgebrochen
+error-timeout = Das { $portal }-Portal hat nicht innerhalb von { $secs } s geantwortet
+error-permission = Zugriff verweigert: { $detail }
+error-clipboard = Zwischenablage nicht verfügbar: { $detail }
+error-encode = { $format } konnte nicht kodiert werden: { $detail }
+error-upload = Hochladen zu { $service } fehlgeschlagen: { $detail }
+error-io = E/A-Fehler: { $detail }
+error-unsupported = Nicht unterstützt: { $detail }
+error-checks-failed = Einige Prüfungen sind fehlgeschlagen; siehe die Lösungsvorschläge oben
+hint-portal = mit `fireshot diagnose` xdg-desktop-portal und das Backend prüfen
+hint-permission = Bildschirmfotos für Fireshot in den Datenschutzeinstellungen erlauben
+hint-clipboard = wl-clipboard (wl-copy) oder xclip installieren
+hint-clipboard-flatpak = wl-clipboard oder xclip auf dem Host installieren und fireshot erlauben, es auszuführen: flatpak override --user --talk-name=org.freedesktop.Flatpak
+hint-upload = Netzwerkverbindung und Upload-Einstellungen prüfen
+
+## Command line
+
+cli-about = Bildschirmfoto-Werkzeug für Wayland
+cli-after-help =
+    Beispiele:
+      fireshot gui
+      fireshot gui -d 2000 -p /tmp/cap.png
+      fireshot full -p /tmp/cap.png
+      fireshot full --region-preset "Browser viewport" -p /tmp/cap.png
+      fireshot full --edit
+      fireshot edit --from-clipboard
+      fireshot launcher
+      fireshot record --format mp4 --audio
+      fireshot compare @2 @1 --diff
+      fireshot snippet @1 --format html
+      fireshot session start ~/guide
+      fireshot daemon --install-autostart
+      fireshot doctor
+
+    Hinweise zum Portal:
+      Benötigt xdg-desktop-portal und ein Backend (wlr/gnome/kde).
+cli-gui = Vollbild für den Editor aufnehmen (die Auswahl erfolgt im Editor).
+cli-full = Aufnehmen und speichern, ohne den Editor zu öffnen.
+cli-edit = Bilddateien oder das Bild aus der Zwischenablage im Editor öffnen.
+cli-launcher = Ein kleines Fenster mit Aufnahmemodi öffnen.
+cli-compare = Zwei Aufnahmen nebeneinander oder als Unterschieds-Heatmap vergleichen.
+cli-annotate = Pfeile, Text, Rahmen oder Unschärfe auf ein Bild zeichnen, ohne den Editor zu öffnen.
+cli-record = Einen Bildschirm, ein Fenster oder einen Bereich aufzeichnen, bis über das Tray oder mit Strg+C gestoppt wird.
+cli-snippet = Einen Markdown-, HTML- oder reStructuredText-Schnipsel kopieren, der ein Bild zeigt.
+cli-recopy = Die neueste Aufnahme aus dem Verlauf wieder in die Zwischenablage legen, z. B. wenn ein Zwischenablage-Manager sie verloren hat.
+cli-open-uri = Die Aufnahme starten, die ein fireshot://-Link verlangt, über den Dienst.
+cli-arg-uri = z. B. fireshot://capture?mode=gui&delay=2000
+cli-history = Die im Verlauf aufbewahrten Aufnahmen durchsuchen oder löschen.
+cli-history-clear = Alle Aufnahmen im Verlauf löschen.
+cli-history-search = Die Aufnahmen auflisten, deren mit [history] ocr gelesener Text QUERY enthält.
+cli-arg-history-query = Gesuchter Text, ohne Beachtung der Groß- und Kleinschreibung.
+cli-session = Bis zum Beenden jede Aufnahme nummeriert in einem Ordner ablegen, z. B. für eine Anleitung.
+cli-session-start = Eine Sitzung starten; bis `session stop` wird jede Aufnahme zusätzlich als step-01.png, step-02.png, … im Ordner gespeichert.
+cli-session-stop = Die Sitzung beenden.
+cli-session-status = Ordner und Schrittzahl der laufenden Sitzung ausgeben.
+cli-daemon = DBus-Dienst für Aufnahmeanfragen starten.
+cli-diagnose = Portal- und Umgebungsdiagnose ausgeben.
+cli-doctor = Diagnose samt Prüfung von Zwischenablage, Werkzeugen und Speicherordner, mit Lösungsvorschlägen.
+cli-arg-delay = Verzögerung in Millisekunden vor der Aufnahme.
+cli-arg-path = Die Aufnahme unter einem Pfad speichern.
+cli-arg-edit = Nach der Aufnahme den Editor öffnen.
+cli-arg-clipboard = Die Aufnahme in die Zwischenablage kopieren.
+cli-arg-gui-region-preset = Mit diesem [[preset]]-Bereich ausgewählt starten oder mit --path nur ihn speichern.
+cli-arg-gui-region = Mit diesem Bereich (X,Y,BREITExHÖHE) ausgewählt starten oder mit --path nur ihn speichern.
+cli-arg-accept = Sofort exportieren, ohne auf einen Klick zu warten: copy oder save:PFAD.
+cli-arg-copy-region = Nur die Auswahl zeigen und den Bereich kopieren, sobald er gewählt ist, ohne die Werkzeuge zum Anmerken.
+cli-arg-region-preset = Nur diesen [[preset]]-Bereich aufnehmen.
+cli-arg-record-region-preset = Nur diesen [[preset]]-Bereich aufzeichnen.
+cli-arg-record-delay = Verzögerung in Millisekunden vor dem Start der Aufzeichnung.
+cli-arg-window = Ein Fenster statt eines ganzen Bildschirms aufzeichnen.
+cli-arg-region = Nur diesen Bereich des Bildschirms aufzeichnen, als X,Y,BREITE,HÖHE.
+cli-arg-format = gif, mp4 oder webm statt des eingestellten Formats schreiben.
+cli-arg-audio = Auch das Mikrofon aufzeichnen (nur Videoformate).
+cli-arg-before = Die erste Aufnahme: eine Bilddatei oder @N für die N-neueste Aufnahme im Verlauf.
+cli-arg-after = Die zweite Aufnahme, wie BEFORE; ohne Angabe eine neue Vollbildaufnahme.
+cli-arg-diff = Mit der Unterschieds-Heatmap statt der Nebeneinander-Ansicht beginnen (oder sie schreiben).
+cli-arg-threshold = Kanalunterschiede bis zu diesem Wert (0-255) ignorieren, etwa Kompressionsrauschen.
+cli-arg-compare-path = Den Vergleich in eine Datei schreiben, statt das Fenster zu öffnen.
+cli-arg-annotate-input = Das Bild: eine Datei oder @N für die N-neueste Aufnahme im Verlauf.
+cli-arg-annotate-output = Wohin das beschriftete Bild geschrieben wird.
+cli-arg-annotate-arrow = Ein Pfeil vom ersten zum zweiten Punkt.
+cli-arg-annotate-line = Eine Linie zwischen zwei Punkten.
+cli-arg-annotate-rect = Ein Rahmen zwischen zwei Ecken.
+cli-arg-annotate-circle = Eine Ellipse im Rahmen zwischen zwei Ecken.
+cli-arg-annotate-text = Text mit seiner oberen linken Ecke am Punkt.
+cli-arg-annotate-blur = Den Bereich zwischen zwei Ecken weichzeichnen.
+cli-arg-annotate-pixelate = Den Bereich zwischen zwei Ecken verpixeln.
+cli-arg-annotate-color = Farbe alles Gezeichneten, als #rrggbb.
+cli-arg-annotate-size = Linienbreite sowie Text- und Effektstärke, wie die Werkzeuggröße im Editor.
+cli-arg-file = Die zu bearbeitenden Bilder, jedes in einem eigenen Tab.
+cli-arg-from-clipboard = Das Bild aus der Zwischenablage bearbeiten, z. B. einen Screenshot eines anderen Programms.
+cli-arg-snippet-target = Eine Datei, eine URL oder @N für die N-neueste Aufnahme im Verlauf.
+cli-arg-snippet-format = markdown, html oder rst statt des eingestellten Formats.
+cli-arg-alt = Alternativtext statt der eingestellten Vorlage.
+cli-arg-session-dir = Der Ordner für die Schritte; ohne Angabe ein neuer im Speicherverzeichnis.
+cli-arg-contact-sheet = Die Schritte zu einer nummerierten contact-sheet.png zusammenstellen.
+cli-arg-markdown = steps.md schreiben, einen Markdown-Ausschnitt mit allen Schritten.
+cli-arg-install-autostart = Den Dienst bei der Anmeldung starten, statt ihn jetzt auszuführen.
+cli-arg-remove-autostart = Die von --install-autostart geschriebenen Dateien entfernen.
+cli-arg-systemd = Mit --install-autostart eine systemd-Benutzereinheit statt eines XDG-Autostart-Eintrags schreiben.
+cli-arg-on-demand = Mit --install-autostart keinen Anmeldeeintrag anlegen; der erste DBus-Aufruf startet den Dienst.
+cli-arg-profile = Ausgeben, wie lange jeder Schritt (Aufnahme, Dekodieren, erstes Bild, Export, Zwischenablage) gedauert hat.
+cli-arg-high-contrast = Kontrastreichen Editor verwenden: kräftigere Umrisse, größere Schaltflächen und keine Abdunklung.
+cli-arg-incognito = Aufnahmen aus Verlauf und Absturzwiederherstellung heraushalten, für vertrauliche Aufnahmen.
+cli-config-error = Konfiguration: { $error }
+cli-error = fireshot: { $error }
+cli-wrote = { $path } geschrieben
+cli-recopied = { $path } kopiert
+cli-removed = { $path } entfernt
+cli-no-autostart = keine Autostart-Einträge gefunden
+cli-daemon-running = fireshot-Dienst läuft (org.fireshot.Fireshot)
+
+## Diagnose
+
+diagnose-title = Fireshot-Wayland-Diagnose
+diagnose-env = Umgebung:
+diagnose-unset = <nicht gesetzt>
+diagnose-portal-service = Portal-Dienst:
+diagnose-bus-error = Fehler am Sitzungsbus: { $error }
+diagnose-portal-backends = Portal-Backends:
+diagnose-read-error = { $path } konnte nicht gelesen werden: { $error }
+diagnose-not-found = { $path } nicht gefunden
+
+## Doctor
+
+doctor-ok = ok
+doctor-fail = FEHLER
+doctor-fix = Lösung: { $fix }
+doctor-tools = Programme:
+doctor-clipboard = Zwischenablage:
+doctor-round-trip = Hin und zurück
+doctor-save-dir = Speicherordner:
+doctor-write-access = Schreibzugriff
+doctor-not-found = nicht im PATH gefunden
+doctor-install-wl-clipboard = wl-clipboard installieren
+doctor-install-xclip = xclip installieren
+doctor-install-ffmpeg = ffmpeg installieren oder [record] format = "gif" setzen
+doctor-install-clipboard = wl-clipboard (Wayland) oder xclip (XWayland/X11) installieren
+doctor-round-tripped = Testinhalt kam unverändert zurück
+doctor-read-different = ein anderer Wert wurde zurückgelesen
+doctor-fix-clipboard-manager = ein Zwischenablage-Manager verändert womöglich die Auswahl; versuchen Sie, ihn abzuschalten
+doctor-read-failed = die Zwischenablage konnte nicht zurückgelesen werden
+doctor-fix-clipboard-reach = sicherstellen, dass wl-paste (wl-clipboard) oder xclip die Sitzung erreicht
+doctor-writable = { $dir } ist beschreibbar
+doctor-fix-save-dir = [save] directory in config.toml auf einen beschreibbaren Ordner setzen
+doctor-all-passed = alle Prüfungen bestanden
+
+## Session
+
+session-started = Sitzung gestartet: Aufnahmen werden nummeriert in { $dir } abgelegt
+session-stopped = Sitzung mit { $count } Schritten in { $dir } beendet
+session-status = Sitzung in { $dir }: { $count } Schritte
+session-none = Es läuft keine Sitzung
+session-step = Schritt { $number }
+history-cleared = { $count } Aufnahmen aus dem Verlauf gelöscht
+history-no-match = Keine Aufnahme im Verlauf enthält „{ $query }“
+history-ocr-off = Aufnahmen sind nur mit [history] ocr = true und installiertem tesseract durchsuchbar.
+
+stats-frame = Bild: { $ms } ms
+stats-render = Anmerkungen: { $ms } ms
+stats-first-frame = Erstes Bild: { $ms } ms
+stats-shapes = Formen: { $count }
+stats-textures = Texturen: { $mib } MiB
@@ -0,0 +1,368 @@
+# English (fallback) strings. Message ids are shared by every locale; a
+# missing id in another catalog falls back to this file.
+
+## Editor
+
+help-title = Click and drag to select area
+help-copy = Ctrl+C: copy
+help-save = Ctrl+S: save
+help-paste = Ctrl+V: edit the clipboard's image instead
+help-undo = Ctrl+Z / Ctrl+Shift+Z: undo/redo
+help-size = Mouse wheel: change tool size
+help-snap = Alt: select without snapping to edges
+help-keyboard-select = Arrows: move selection, Ctrl+arrows: resize, Ctrl+A: select all
+help-keyboard-tools = 1–0, [ ]: pick tool, + −: size, Tab: toolbar
+help-arrange = Arrange tool: drag a band to pick shapes, Shift adds; Del: delete, Ctrl+G / Ctrl+Shift+G: group / ungroup
+help-peek = Hold H: hide the panels to see what is under them
+help-tabs = Ctrl+PgUp / Ctrl+PgDn: switch tab; drop image files to open them in tabs
+help-stats = F12: show frame timings and memory
+help-close = Esc: close
+
+tool-grip = Move toolbar
+tool-select = Select
+tool-pencil = Pencil
+tool-line = Line
+tool-arrow = Arrow
+tool-rect = Rect
+tool-circle = Circle
+tool-marker = Marker
+tool-marker-line = Marker Line
+tool-circle-count = Circle Count
+tool-text = Text
+tool-timestamp = Insert timestamp
+tool-pixelate = Pixelate
+tool-blur = Blur
+tool-arrange = Arrange shapes
+tool-record = Record GIF
+tool-undo = Undo
+tool-copy = Copy
+tool-save = Save
+tool-clear = Clear
+
+menu-redo = Redo
+menu-reset-zoom = Reset zoom
+menu-auto-redact = Auto-redact
+menu-auto-crop = Auto-crop
+menu-email = Send by email
+menu-share-lan = Share on LAN
+menu-export-all = Export all ({ $count })
+menu-export-layers = Export as layers
+menu-clear-regions = Forget other selections
+menu-group = Group
+menu-ungroup = Ungroup
+menu-delete-picked = Delete picked shapes
+menu-save-preset = Save selection as preset…
+menu-recapture = Recapture in
+menu-close = Close
+
+control-color = Color
+control-size = Size
+control-effect-rect = Rectangle
+control-effect-ellipse = Ellipse
+control-effect-lasso = Lasso
+control-effect-lasso-hint = Trace the outline to cover with the effect
+control-effect-brush = Brush
+control-effect-brush-hint = Paint the effect over exactly the pixels you brush instead of a rectangle
+redact-proposals = { $count } to redact
+redact-accept-all = Blur all
+redact-dismiss = Dismiss
+
+tab-capture = Capture
+tab-close = Close tab
+layers-title = Layers ({ $count })
+layers-visible = Show in the editor and exports
+layers-reorder = Drag to change the stacking order
+layers-delete = Delete this shape
+adjust-title = Adjustments
+adjust-brightness = Brightness
+adjust-contrast = Contrast
+adjust-saturation = Saturation
+adjust-gamma = Gamma
+adjust-reset = Reset
+layer-stroke = Stroke
+layer-line = Line
+layer-arrow = Arrow
+layer-rect = Rectangle
+layer-circle = Ellipse
+layer-counter = Counter { $count }
+layer-pixelate = Pixelate
+layer-blur = Blur
+
+preset-title = Save selection as preset
+preset-hint = Name, e.g. Browser viewport
+preset-save = Save
+preset-cancel = Cancel
+
+status-copied = Copied to clipboard ({ $method })
+status-saved = Saved { $path }
+status-saved-all = Saved { $count } captures to { $path }
+status-saved-layers = Saved base and annotation layers to { $path }
+status-save-failed = Save failed: { $error }
+status-exporting = Exporting…
+status-export-failed = Export failed
+status-paste-annotated = Undo or clear the annotations to paste an image
+status-undo-limit = Older shapes are past the undo limit
+status-redact-found = Found { $count } areas to redact; click one to blur it, Enter blurs all
+status-redact-none = Found no text or faces to redact
+status-auto-cropped = Selected the { $width }×{ $height } content inside the borders
+status-auto-crop-none = Found no uniform border to trim
+status-preset-saved = Saved preset { $name }
+status-emailed = Opened in the mail client
+status-email-failed = Email failed: { $error }
+status-share-waiting = Waiting for a device to fetch the capture…
+status-share-timeout = Sharing timed out; nothing fetched the capture
+status-share-stopped = Stopped sharing
+status-share-failed = Sharing failed
+status-open-failed = Could not open { $path }: { $error }
+status-log-show = Earlier
+status-log-hide = Hide earlier
+status-open-file = Click to open the file
+status-open-url = Click to open the link
+status-copy-path = Copy path
+status-copy-url = Copy link
+status-copied-link = Copied
+
+share-title = Share on LAN
+share-hint = Scan with a phone on the same network. Sharing stops after the first download.
+share-stop = Stop sharing
+
+recovery-title = Restore previous session?
+recovery-body = An editor closed without finishing. Restore its capture and annotations?
+recovery-restore = Restore
+recovery-discard = Discard
+
+## Launcher
+
+launcher-region = Region
+launcher-region-tip = Capture the screen and select a region in the editor
+launcher-full = Full screen
+launcher-full-tip = Capture the whole screen and save it
+launcher-window = Window
+launcher-window-tip = Pick a window or screen in the portal dialog
+launcher-screen-tip = Capture { $name } and save it
+launcher-delay = Delay
+launcher-settings = Settings
+launcher-history = History
+launcher-search-hint = Search text in captures…
+launcher-search-none = No capture contains that text
+launcher-create-failed = Cannot create { $path }: { $error }
+launcher-open-failed = Cannot open { $path }: { $error }
+
+## Tray
+
+tray-state-idle = Ready
+tray-state-countdown = Capture countdown running
+tray-state-done = Capture finished
+tray-state-failed = Capture failed
+tray-state-recording = Recording
+tray-stop-recording = Stop recording
+tray-capture = Capture (GUI)
+tray-capture-in = Capture in
+tray-delay-seconds = { $secs } seconds
+tray-delay-other = Other delay…
+tray-region-copy = Region to Clipboard
+tray-full-screen = Full Screen
+tray-record = Record screen
+tray-recent = Recent captures
+tray-no-captures = No captures yet
+tray-recopy = Copy last capture again
+tray-open-folder = Open save folder
+tray-settings = Settings
+tray-quit = Quit
+
+## Global shortcuts
+
+hotkey-gui = Capture and open the editor
+hotkey-full = Capture the full screen and save it
+
+## Compare
+
+compare-side-by-side = Side by side
+compare-diff = Diff
+compare-stats = { $changed } of { $total } pixels differ ({ $percent }%)
+compare-save = Save…
+compare-copy = Copy
+
+## Notifications
+
+notify-copied = Screenshot copied
+notify-copied-body = The capture is on the clipboard.
+notify-saved = Screenshot saved
+notify-recorded = Recording saved
+notify-emailed = Screenshot attached
+notify-emailed-body = The capture is attached to a new message.
+notify-shared = Screenshot shared
+notify-cancelled = Capture cancelled
+notify-inhibit-reason = Taking a screenshot
+notify-action-open = Open
+notify-action-folder = Open folder
+notify-action-copy-url = Copy URL
+notify-action-copy-snippet = Copy snippet
+notify-action-delete = Delete
+
+## Errors
+
+error-portal = Portal error: { $detail }
+error-cancelled = Capture cancelled
+error-timeout = The { $portal } portal did not answer within { $secs }s
+error-permission = Permission denied: { $detail }
+error-clipboard = Clipboard unavailable: { $detail }
+error-encode = Could not encode { $format }: { $detail }
+error-upload = Upload to { $service } failed: { $detail }
+error-io = I/O error: { $detail }
+error-unsupported = Unsupported: { $detail }
+error-checks-failed = Some checks failed; see the suggested fixes above
+hint-portal = run `fireshot diagnose` to check xdg-desktop-portal and its backend
+hint-permission = allow screenshots for Fireshot in the desktop's privacy settings
+hint-clipboard = install wl-clipboard (wl-copy) or xclip
+hint-clipboard-flatpak = install wl-clipboard or xclip on the host and let fireshot run it: flatpak override --user --talk-name=org.freedesktop.Flatpak
+hint-upload = check the network connection and uploader settings
+
+## Command line
+
+cli-about = Wayland-first screenshot app
+cli-after-help =
+    Examples:
+      fireshot gui
+      fireshot gui -d 2000 -p /tmp/cap.png
+      fireshot full -p /tmp/cap.png
+      fireshot full --region-preset "Browser viewport" -p /tmp/cap.png
+      fireshot full --edit
+      fireshot edit --from-clipboard
+      fireshot launcher
+      fireshot record --format mp4 --audio
+      fireshot compare @2 @1 --diff
+      fireshot snippet @1 --format html
+      fireshot session start ~/guide
+      fireshot daemon --install-autostart
+      fireshot doctor
+
+    Portal notes:
+      Requires xdg-desktop-portal and a backend (wlr/gnome/kde).
+cli-gui = Capture fullscreen for editor (selection happens in the editor).
+cli-full = Capture and save without opening the editor.
+cli-edit = Open image files, or the image on the clipboard, in the editor.
+cli-launcher = Open a small window with capture mode buttons.
+cli-compare = Compare two captures side by side or as a diff heatmap.
+cli-annotate = Draw arrows, text, boxes or blur onto an image without opening the editor.
+cli-record = Record an output, window or region until stopped from the tray or with Ctrl+C.
+cli-snippet = Copy a Markdown, HTML or reStructuredText snippet showing an image.
+cli-recopy = Put the newest capture in the history back on the clipboard, e.g. after a clipboard manager lost it.
+cli-open-uri = Start the capture a fireshot:// link asks for, through the daemon.
+cli-arg-uri = e.g. fireshot://capture?mode=gui&delay=2000
+cli-history = Search or delete the captures kept in the history.
+cli-history-clear = Delete every capture in the history.
+cli-history-search = List the captures whose text, read with [history] ocr, contains QUERY.
+cli-arg-history-query = Text to look for, ignoring case.
+cli-session = Number every capture into a folder until stopped, e.g. for a how-to guide.
+cli-session-start = Start a session; every capture until `session stop` is also saved into the folder as step-01.png, step-02.png, ...
+cli-session-stop = End the session.
+cli-session-status = Print the running session's folder and step count.
+cli-daemon = Run DBus daemon to handle capture requests.
+cli-diagnose = Print portal and environment diagnostics.
+cli-doctor = Run the diagnostics plus clipboard, tool and save directory checks, with suggested fixes.
+cli-arg-delay = Delay in milliseconds before requesting capture.
+cli-arg-path = Save the capture to a path.
+cli-arg-edit = Open the editor after capture.
+cli-arg-clipboard = Copy the capture to the clipboard.
+cli-arg-gui-region-preset = Start with this [[preset]] region selected, or save only it with --path.
+cli-arg-gui-region = Start with this region selected, as X,Y,WIDTHxHEIGHT, or save only it with --path.
+cli-arg-accept = Export right away without waiting for a click: copy, or save:PATH.
+cli-arg-copy-region = Show only the selection overlay and copy the area as soon as it is picked, without the annotation tools.
+cli-arg-region-preset = Capture only this [[preset]] region.
+cli-arg-record-region-preset = Record only this [[preset]] region.
+cli-arg-record-delay = Delay in milliseconds before starting the recording.
+cli-arg-window = Record a window instead of a whole output.
+cli-arg-region = Record only this region of the output, as X,Y,WIDTH,HEIGHT.
+cli-arg-format = Write gif, mp4 or webm instead of the configured format.
+cli-arg-audio = Record the microphone too (video formats only).
+cli-arg-before = The first capture: an image file, or @N for the Nth newest capture in the history.
+cli-arg-after = The second capture, like BEFORE; a new full-screen capture when left out.
+cli-arg-diff = Start with (or write) the diff heatmap instead of the side-by-side view.
+cli-arg-threshold = Ignore channel differences up to this much (0-255), such as compression noise.
+cli-arg-compare-path = Write the comparison to a path instead of opening the window.
+cli-arg-annotate-input = The image: a file, or @N for the Nth newest capture in the history.
+cli-arg-annotate-output = Where to write the annotated image.
+cli-arg-annotate-arrow = An arrow from the first point to the second.
+cli-arg-annotate-line = A line between two points.
+cli-arg-annotate-rect = A box between two corners.
+cli-arg-annotate-circle = An ellipse inside the box between two corners.
+cli-arg-annotate-text = Text with its top-left corner at the point.
+cli-arg-annotate-blur = Blur the box between two corners.
+cli-arg-annotate-pixelate = Pixelate the box between two corners.
+cli-arg-annotate-color = Colour of everything drawn, as #rrggbb.
+cli-arg-annotate-size = Line width and text and effect strength, as the editor's tool size.
+cli-arg-file = The images to annotate, each in a tab of its own.
+cli-arg-from-clipboard = Edit the image on the clipboard, e.g. a screenshot from another tool.
+cli-arg-snippet-target = A file, a URL, or @N for the Nth newest capture in the history.
+cli-arg-snippet-format = markdown, html or rst instead of the configured format.
+cli-arg-alt = Alt text instead of the configured template.
+cli-arg-session-dir = The folder for the steps; a new one in the save directory when left out.
+cli-arg-contact-sheet = Compile the steps into one numbered contact-sheet.png.
+cli-arg-markdown = Write steps.md, a markdown snippet showing every step.
+cli-arg-install-autostart = Start the daemon at login instead of running it now.
+cli-arg-remove-autostart = Remove the files written by --install-autostart.
+cli-arg-systemd = With --install-autostart, write a systemd user unit instead of an XDG autostart entry.
+cli-arg-on-demand = With --install-autostart, skip the login entry and let the first DBus call start the daemon.
+cli-arg-profile = Print how long each stage (capture, decode, first frame, export, clipboard) took.
+cli-arg-high-contrast = Use the high-contrast editor: stronger outlines, larger buttons and no dimming.
+cli-arg-incognito = Keep captures out of the history and crash recovery, for sensitive ones.
+cli-config-error = config: { $error }
+cli-error = fireshot: { $error }
+cli-wrote = wrote { $path }
+cli-recopied = copied { $path }
+cli-removed = removed { $path }
+cli-no-autostart = no autostart entries found
+cli-daemon-running = fireshot daemon running (org.fireshot.Fireshot)
+
+## Diagnose
+
+diagnose-title = Fireshot Wayland diagnostics
+diagnose-env = env:
+diagnose-unset = <unset>
+diagnose-portal-service = portal service:
+diagnose-bus-error = session bus error: { $error }
+diagnose-portal-backends = portal backends:
+diagnose-read-error = error reading { $path }: { $error }
+diagnose-not-found = { $path } not found
+
+## Doctor
+
+doctor-ok = ok
+doctor-fail = FAIL
+doctor-fix = fix: { $fix }
+doctor-tools = tools:
+doctor-clipboard = clipboard:
+doctor-round-trip = round trip
+doctor-save-dir = save directory:
+doctor-write-access = write access
+doctor-not-found = not found in PATH
+doctor-install-wl-clipboard = install wl-clipboard
+doctor-install-xclip = install xclip
+doctor-install-ffmpeg = install ffmpeg or set [record] format = "gif"
+doctor-install-clipboard = install wl-clipboard (Wayland) or xclip (XWayland/X11)
+doctor-round-tripped = test payload round-tripped
+doctor-read-different = read back a different value
+doctor-fix-clipboard-manager = a clipboard manager may be rewriting the selection; try disabling it
+doctor-read-failed = could not read the clipboard back
+doctor-fix-clipboard-reach = make sure wl-paste (wl-clipboard) or xclip can reach the session
+doctor-writable = { $dir } is writable
+doctor-fix-save-dir = set [save] directory in config.toml to a writable folder
+doctor-all-passed = all checks passed
+
+## Session
+
+session-started = session started: captures are numbered into { $dir }
+session-stopped = session ended with { $count } steps in { $dir }
+session-status = session in { $dir }: { $count } steps
+session-none = no session is running
+session-step = Step { $number }
+history-cleared = Deleted { $count } captures from the history
+history-no-match = No capture in the history contains “{ $query }”
+history-ocr-off = Captures are only searchable with [history] ocr = true and tesseract installed.
+
+stats-frame = Frame: { $ms } ms
+stats-render = Annotations: { $ms } ms
+stats-first-frame = First frame: { $ms } ms
+stats-shapes = Shapes: { $count }
+stats-textures = Textures: { $mib } MiB
@@ -0,0 +1,753 @@
+use chrono::format::{Item, StrftimeItems};
+use serde::{Deserialize, Serialize};
+
+use crate::sandbox::Sandbox;
+use crate::snippet::SnippetFormat;
+use crate::window::WindowInfo;
+use crate::{paths, CaptureError, RecordRegion};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub hotkeys: HotkeyConfig,
+    pub notifications: NotificationConfig,
+    pub save: SaveConfig,
+    pub editor: EditorConfig,
+    pub behavior: BehaviorConfig,
+    pub theme: ThemeConfig,
+    pub toolbar: ToolbarConfig,
+    pub record: RecordConfig,
+    pub hooks: HooksConfig,
+    pub snippet: SnippetConfig,
+    pub share: ShareConfig,
+    pub timestamp: TimestampConfig,
+    pub history: HistoryConfig,
+    /// `[[preset]]` tables.
+    #[serde(rename = "preset")]
+    pub presets: Vec<RegionPreset>,
+}
+
+/// Global shortcuts registered by the daemon through the GlobalShortcuts
+/// portal. Triggers use the portal's shortcut syntax (e.g. `SHIFT+Print`);
+/// an empty trigger leaves that action unbound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeyConfig {
+    pub enabled: bool,
+    pub gui: String,
+    pub full: String,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gui: "Print".to_string(),
+            full: "SHIFT+Print".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// How long to keep listening for action clicks, in seconds.
+    pub action_timeout_secs: u64,
+    /// Hold notification popups back while a capture is taken.
+    pub do_not_disturb: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            action_timeout_secs: 30,
+            do_not_disturb: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveConfig {
+    /// Where captures are saved; defaults to the XDG pictures directory.
+    pub directory: Option<std::path::PathBuf>,
+    /// Save PNG/JPEG files in the captured output's colour profile, with the
+    /// profile embedded, instead of sRGB. Only applies when the portal
+    /// backend provides a profile.
+    pub embed_icc_profile: bool,
+    /// Name for new captures without the extension, with strftime fields
+    /// (e.g. `%Y-%m-%d`) filled in, and `{title}` and `{app_id}` with the
+    /// focused window's where the compositor reports it. Also the subject
+    /// of emailed captures.
+    pub file_name: String,
+    /// What transparency is laid over in JPEG and BMP files.
+    pub matte: Matte,
+    /// What exports with a selection keep of the rest of the capture.
+    pub outside_selection: OutsideSelection,
+    /// Width in pixels of a border drawn round images saved from the
+    /// editor; 0 draws none.
+    pub border_width: u32,
+    /// The border's colour, as `#rrggbb`.
+    pub border_color: String,
+    /// A 1px white or black line, whichever stands out from the border,
+    /// between the border and the image.
+    pub border_contrast_line: bool,
+    /// Radius in pixels to round the corners of images saved from the
+    /// editor to, after any border; 0 leaves them square.
+    pub corner_radius: u32,
+    /// What shows outside rounded corners, as `#rrggbb`; transparent when
+    /// unset.
+    pub corner_background: Option<String>,
+    /// Which file chooser the editor's Save opens.
+    pub dialog: SaveDialog,
+}
+
+impl Default for SaveConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            embed_icc_profile: false,
+            file_name: "screenshot-%Y-%m-%d_%H-%M-%S".to_string(),
+            matte: Matte::default(),
+            outside_selection: OutsideSelection::default(),
+            border_width: 0,
+            border_color: "#000000".to_string(),
+            border_contrast_line: false,
+            corner_radius: 0,
+            corner_background: None,
+            dialog: SaveDialog::default(),
+        }
+    }
+}
+
+/// The file chooser the editor saves through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveDialog {
+    /// The portal's when running in a Flatpak or Snap, the editor's own
+    /// otherwise.
+    #[default]
+    Auto,
+    /// The editor's own, drawn in its window.
+    Builtin,
+    /// The desktop's, through the file chooser portal, which can reach
+    /// folders a sandbox can't.
+    Portal,
+}
+
+impl SaveDialog {
+    pub fn uses_portal(self) -> bool {
+        match self {
+            Self::Auto => Sandbox::detect().is_some(),
+            Self::Builtin => false,
+            Self::Portal => true,
+        }
+    }
+}
+
+/// What becomes of the capture outside the selection on export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutsideSelection {
+    /// Export only the selection.
+    #[default]
+    Crop,
+    /// Export the whole capture with everything but the selection blurred.
+    Blur,
+    /// Export the whole capture with everything but the selection darkened.
+    Dim,
+}
+
+/// The background see-through pixels are flattened onto for formats
+/// without transparency. Written as `white`, `black`, `checkerboard` or
+/// `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Matte {
+    Color([u8; 3]),
+    /// Light and dark grey squares, as the editor shows transparency.
+    Checkerboard,
+}
+
+impl Default for Matte {
+    fn default() -> Self {
+        Self::Color([255, 255, 255])
+    }
+}
+
+impl TryFrom<String> for Matte {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        match text.to_ascii_lowercase().as_str() {
+            "white" => return Ok(Self::Color([255, 255, 255])),
+            "black" => return Ok(Self::Color([0, 0, 0])),
+            "checkerboard" => return Ok(Self::Checkerboard),
+            _ => {}
+        }
+        let hex = text.trim_start_matches('#');
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Self::Color([r, g, b])),
+            _ => Err(format!(
+                "unknown matte {:?}, expected white, black, checkerboard or #rrggbb",
+                text
+            )),
+        }
+    }
+}
+
+impl From<Matte> for String {
+    fn from(matte: Matte) -> Self {
+        match matte {
+            Matte::Color([255, 255, 255]) => "white".to_string(),
+            Matte::Color([0, 0, 0]) => "black".to_string(),
+            Matte::Color([r, g, b]) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            Matte::Checkerboard => "checkerboard".to_string(),
+        }
+    }
+}
+
+impl SaveConfig {
+    pub fn directory(&self) -> std::path::PathBuf {
+        self.directory.clone().unwrap_or_else(paths::pictures_dir)
+    }
+
+    /// A new file name in the save directory for a recording.
+    pub fn recording_path(&self, format: RecordFormat) -> std::path::PathBuf {
+        let stamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+        self.directory()
+            .join(format!("recording-{}.{}", stamp, format.extension()))
+    }
+
+    /// `file_name` filled in for now. A template chrono can't parse is used
+    /// as it is.
+    pub fn file_stem(&self) -> String {
+        self.file_stem_for(None)
+    }
+
+    /// `file_name` filled in for now, with `{title}` and `{app_id}` taken
+    /// from `window`; they are left empty without one.
+    pub fn file_stem_for(&self, window: Option<&WindowInfo>) -> String {
+        let items: Vec<Item> = StrftimeItems::new(&self.file_name).collect();
+        let stem = if items.contains(&Item::Error) {
+            self.file_name.clone()
+        } else {
+            chrono::Local::now().format_with_items(items.into_iter()).to_string()
+        };
+        let title = window.map(WindowInfo::file_name_title).unwrap_or_default();
+        let app_id = window.map(|window| window.app_id.as_str()).unwrap_or_default();
+        stem.replace("{title}", &title)
+            .replace("{app_id}", app_id)
+            .replace('/', "-")
+    }
+
+    /// A new folder in the save directory for a capture session's steps.
+    pub fn session_dir(&self) -> std::path::PathBuf {
+        let stamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+        self.directory().join(format!("session-{}", stamp))
+    }
+}
+
+/// Whether copied and saved captures are kept in a history, and how much of
+/// it; the oldest captures past any limit are deleted after each capture.
+/// 0 is no limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Keep a copy of each copied or saved capture, for recopy, the tray's
+    /// recent captures and `@N`. Off unless turned on, as captures may hold
+    /// passwords or tokens.
+    pub enabled: bool,
+    pub max_entries: usize,
+    pub max_age_days: u32,
+    pub max_mb: u32,
+    /// Read the text in new captures with tesseract, for `fireshot history
+    /// search`.
+    pub ocr: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 100,
+            max_age_days: 30,
+            max_mb: 0,
+            ocr: false,
+        }
+    }
+}
+
+/// A Rhai script whose `pre_capture`, `post_render` and `post_save`
+/// functions run at those points, in builds with the `scripting` feature.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Defaults to `hooks.rhai` in the config directory; relative paths are
+    /// taken from there too.
+    pub script: Option<std::path::PathBuf>,
+}
+
+impl HooksConfig {
+    pub fn script_path(&self) -> std::path::PathBuf {
+        let dir = paths::config_dir();
+        match &self.script {
+            Some(script) => dir.join(script),
+            None => dir.join("hooks.rhai"),
+        }
+    }
+}
+
+/// The snippet the Copy snippet notification action and `fireshot snippet`
+/// put on the clipboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetConfig {
+    pub format: SnippetFormat,
+    /// Alt text template; `{name}`, `{date}` and `{time}` are filled in.
+    pub alt: String,
+}
+
+impl Default for SnippetConfig {
+    fn default() -> Self {
+        Self {
+            format: SnippetFormat::default(),
+            alt: "Screenshot {date}".to_string(),
+        }
+    }
+}
+
+impl SnippetConfig {
+    /// The configured snippet for `target`, a path or URL.
+    pub fn render(&self, target: &str) -> String {
+        self.format.render(target, &crate::snippet::alt_text(&self.alt, target))
+    }
+}
+
+/// The editor's Share on LAN export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    /// Sharing stops if nothing fetched the capture within this many seconds.
+    pub timeout_secs: u64,
+    /// Port to serve on; 0 picks a free one.
+    pub port: u16,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 120,
+            port: 0,
+        }
+    }
+}
+
+/// The editor's Insert timestamp button.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampConfig {
+    /// strftime format of the capture time.
+    pub format: String,
+    /// Corner of the selection, or of the capture without one, the
+    /// timestamp goes in.
+    pub corner: Corner,
+}
+
+impl Default for TimestampConfig {
+    fn default() -> Self {
+        Self {
+            format: "%Y-%m-%d %H:%M:%S".to_string(),
+            corner: Corner::default(),
+        }
+    }
+}
+
+impl TimestampConfig {
+    /// `at` in `format`, local time. A format chrono can't parse falls back
+    /// to the default one.
+    pub fn text(&self, at: std::time::SystemTime) -> String {
+        let at = chrono::DateTime::<chrono::Local>::from(at);
+        let items: Vec<Item> = StrftimeItems::new(&self.format).collect();
+        if items.contains(&Item::Error) {
+            return at.format(&Self::default().format).to_string();
+        }
+        at.format_with_items(items.into_iter()).to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// A selection saved by name, recalled in the editor or with
+/// `--region-preset`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionPreset {
+    pub name: String,
+    #[serde(flatten)]
+    pub region: RecordRegion,
+}
+
+/// What `fireshot gui` does when another editor is already open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SingleInstance {
+    /// Raise the open editor and drop the new request.
+    #[default]
+    Focus,
+    /// Wait for the open editor to close, then capture.
+    Queue,
+    /// Allow editors to stack.
+    Off,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorConfig {
+    pub single_instance: SingleInstance,
+    /// Run the editor inside the daemon instead of spawning a process per
+    /// capture.
+    pub daemon_hosted: bool,
+    /// Draw the editor on the wlr-layer-shell overlay layer where the
+    /// compositor supports it, instead of a fullscreen window.
+    pub layer_shell: bool,
+    /// Delay the editor's Recapture starts with; it can be changed there
+    /// before each recapture.
+    pub recapture_delay_secs: u32,
+    /// Hide the toolbar, tool controls, layers and selection size while a
+    /// shape is being drawn.
+    pub hide_ui_while_drawing: bool,
+    /// Open captures with the focused window's title and app id written in
+    /// their top-left corner, where the compositor reports them.
+    pub window_caption: bool,
+    /// Tool active when the editor opens, by its toolbar button name.
+    /// Select when unset or when the button isn't a tool.
+    pub default_tool: Option<ToolbarButton>,
+    /// Colour the editor opens with, as `#rrggbb`.
+    pub default_color: String,
+    /// Tool size the editor opens with, 1 to 20.
+    pub default_size: f32,
+    /// Shapes Undo can take back; older ones stay drawn for good.
+    pub undo_depth: usize,
+    /// Rough memory, in MiB, the shapes Undo can still take back may hold
+    /// before the oldest drop out of reach. Long pencil strokes hold
+    /// thousands of points.
+    pub undo_memory_mb: u32,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            single_instance: SingleInstance::default(),
+            daemon_hosted: false,
+            layer_shell: false,
+            recapture_delay_secs: 5,
+            hide_ui_while_drawing: false,
+            window_caption: false,
+            default_tool: None,
+            default_color: "#ff0000".to_string(),
+            default_size: 3.0,
+            undo_depth: 200,
+            undo_memory_mb: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BehaviorConfig {
+    /// What the editor does once a copy, save or other export has finished.
+    pub after_export: AfterExport,
+    /// Play the camera shutter sound when a capture is taken.
+    pub shutter_sound: bool,
+    /// Flash the editor white as it opens on a capture.
+    pub flash: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AfterExport {
+    #[default]
+    Close,
+    /// Keep the editor open for more crops of the same capture.
+    Stay,
+    /// Keep it open but minimized. The layer-shell overlay can't be, so it
+    /// closes there.
+    Minimize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// Follow the desktop's light/dark preference.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub mode: ThemeMode,
+    /// Accent for selections, toolbar highlights and the selection outline,
+    /// as `#rrggbb`.
+    pub accent: Option<String>,
+    /// Stronger outlines and handles, larger toolbar buttons and no
+    /// translucent dimming or panels.
+    pub high_contrast: bool,
+    /// What is drawn behind the toolbar, tool controls and selection size
+    /// so they read over any capture.
+    pub backdrop: Backdrop,
+    /// Solid panels instead of translucent ones, whatever `backdrop` is.
+    pub reduce_transparency: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backdrop {
+    /// The capture blurred under a translucent panel colour.
+    #[default]
+    Frosted,
+    /// A translucent panel colour.
+    Dim,
+    /// Nothing behind the tool buttons; the other panels as they are.
+    None,
+}
+
+/// Which side the editor's tool buttons sit on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolbarSide {
+    /// Below the selection, wrapping round it where there is no room.
+    #[default]
+    Auto,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// What the toolbar's side is measured from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolbarAnchor {
+    /// Follow the selection.
+    #[default]
+    Selection,
+    /// Stay on the edge of the screen.
+    Screen,
+}
+
+/// A button on the editor's toolbar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolbarButton {
+    Select,
+    Pencil,
+    Line,
+    Arrow,
+    Rect,
+    Circle,
+    Marker,
+    MarkerLine,
+    CircleCount,
+    Text,
+    /// Inserts the capture time as text.
+    Timestamp,
+    Pixelate,
+    Blur,
+    Arrange,
+    Record,
+    Undo,
+    Copy,
+    Save,
+    Clear,
+}
+
+impl ToolbarButton {
+    /// Every button, in the toolbar's default order.
+    pub const ALL: [Self; 19] = [
+        Self::Select,
+        Self::Pencil,
+        Self::Line,
+        Self::Arrow,
+        Self::Rect,
+        Self::Circle,
+        Self::Marker,
+        Self::MarkerLine,
+        Self::CircleCount,
+        Self::Text,
+        Self::Timestamp,
+        Self::Pixelate,
+        Self::Blur,
+        Self::Arrange,
+        Self::Record,
+        Self::Undo,
+        Self::Copy,
+        Self::Save,
+        Self::Clear,
+    ];
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolbarConfig {
+    pub side: ToolbarSide,
+    pub anchor: ToolbarAnchor,
+    /// Buttons to show first, in this order; the rest follow in their
+    /// default order.
+    pub order: Vec<ToolbarButton>,
+    /// Buttons to leave off the toolbar.
+    pub hidden: Vec<ToolbarButton>,
+    /// Top-left corner of the toolbar, in points from the top-left of the
+    /// screen, instead of following the selection.
+    pub pin: Option<[f32; 2]>,
+}
+
+impl ToolbarConfig {
+    /// The buttons to show, in order.
+    pub fn buttons(&self) -> Vec<ToolbarButton> {
+        let mut buttons = Vec::with_capacity(ToolbarButton::ALL.len());
+        for button in self.order.iter().chain(ToolbarButton::ALL.iter()) {
+            if !buttons.contains(button) && !self.hidden.contains(button) {
+                buttons.push(*button);
+            }
+        }
+        buttons
+    }
+}
+
+/// What recordings are written as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    /// Animated GIF, encoded in-process.
+    #[default]
+    Gif,
+    /// H.264 in MP4, encoded by ffmpeg.
+    Mp4,
+    /// VP9 in WebM, encoded by ffmpeg.
+    Webm,
+}
+
+impl RecordFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "gif",
+            RecordFormat::Mp4 => "mp4",
+            RecordFormat::Webm => "webm",
+        }
+    }
+
+    /// Video formats go through ffmpeg and can carry audio.
+    pub fn is_video(self) -> bool {
+        self != RecordFormat::Gif
+    }
+}
+
+/// Recordings started from the editor's Record button, `fireshot record` or
+/// the tray.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordConfig {
+    pub format: RecordFormat,
+    /// Frames per second kept from the screencast.
+    pub fps: u32,
+    /// Recordings stop on their own after this many seconds; 0 records until
+    /// stopped.
+    pub max_secs: u64,
+    /// Record the microphone into videos.
+    pub audio: bool,
+    /// PulseAudio/PipeWire source for `audio`.
+    pub audio_source: String,
+}
+
+impl Default for RecordConfig {
+    fn default() -> Self {
+        Self {
+            format: RecordFormat::default(),
+            fps: 10,
+            max_secs: 60,
+            audio: false,
+            audio_source: "default".to_string(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> std::path::PathBuf {
+        paths::config_dir().join("config.toml")
+    }
+
+    /// Loads the config file, falling back to defaults when it does not exist.
+    pub fn load() -> Result<Self, CaptureError> {
+        let path = Self::path();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => return Err(CaptureError::Io(err.to_string())),
+        };
+        toml::from_str(&text)
+            .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The preset called `name`, ignoring case.
+    pub fn region_preset(&self, name: &str) -> Option<&RegionPreset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds `preset` to the config file, replacing one with the same name.
+    /// The rest of the file, comments included, is left as it is.
+    pub fn save_region_preset(preset: &RegionPreset) -> Result<(), CaptureError> {
+        let path = Self::path();
+        let io_error = |err: &dyn std::fmt::Display| {
+            CaptureError::Io(format!("{}: {}", path.display(), err))
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(io_error(&err)),
+        };
+        let mut doc: toml_edit::DocumentMut = text.parse().map_err(|e| io_error(&e))?;
+        let presets = doc
+            .entry("preset")
+            .or_insert_with(|| toml_edit::Item::ArrayOfTables(Default::default()))
+            .as_array_of_tables_mut()
+            .ok_or_else(|| io_error(&"`preset` is not an array of tables"))?;
+        let mut table = toml_edit::Table::new();
+        table["name"] = toml_edit::value(preset.name.as_str());
+        let RecordRegion { x, y, width, height } = preset.region;
+        for (key, value) in [("x", x), ("y", y), ("width", width), ("height", height)] {
+            table[key] = toml_edit::value(i64::from(value));
+        }
+        let existing = presets.iter_mut().find(|table| {
+            table
+                .get("name")
+                .and_then(|name| name.as_str())
+                .is_some_and(|name| name.eq_ignore_ascii_case(&preset.name))
+        });
+        match existing {
+            Some(existing) => *existing = table,
+            None => presets.push(table),
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| io_error(&e))?;
+        }
+        std::fs::write(&path, doc.to_string()).map_err(|e| io_error(&e))
+    }
+}
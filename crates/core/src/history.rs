@@ -0,0 +1,365 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::HistoryConfig;
+use crate::sandbox::host_command;
+use crate::{paths, CaptureError};
+
+/// Entries read with OCR per capture at most, so turning it on with a long
+/// history catches up gradually rather than starting a process per entry.
+const OCR_PER_CAPTURE: usize = 3;
+/// How long an OCR claim holds; one older was left by a run that died.
+const OCR_STALE_SECS: u64 = 600;
+/// Reads capture `$1` into `$3` through claim `$2`; see `index_text`.
+const OCR_SCRIPT: &str =
+    r#"tesseract "$1" "$2" && [ -e "$1" ] && mv -f "$2.txt" "$3"; rm -f "$2" "$2.txt""#;
+
+/// Set from `[history] enabled`; captures are only kept when it is on.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Set for `--incognito`: captures are kept out of the history.
+static INCOGNITO: AtomicBool = AtomicBool::new(false);
+
+/// Keeps this process's captures in the history, or stops keeping them, as
+/// `[history] enabled` says.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Keeps this process's captures out of the history, and anything else
+/// that would outlive it on disk.
+pub fn go_incognito() {
+    INCOGNITO.store(true, Ordering::Relaxed);
+}
+
+pub fn is_incognito() -> bool {
+    INCOGNITO.load(Ordering::Relaxed)
+}
+
+/// Whether captures go into the history: it is enabled and this process
+/// isn't incognito.
+fn keeps_captures() -> bool {
+    ENABLED.load(Ordering::Relaxed) && !is_incognito()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    pub captured_at: SystemTime,
+}
+
+impl HistoryEntry {
+    fn from_path(path: PathBuf) -> Self {
+        let from_name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix("capture-"))
+            // `capture-<millis>-<n>` when several were kept the same millisecond.
+            .and_then(|stem| stem.split('-').next())
+            .and_then(|millis| millis.parse::<u64>().ok())
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(millis));
+        let captured_at = from_name
+            .or_else(|| path.metadata().and_then(|m| m.modified()).ok())
+            .unwrap_or(UNIX_EPOCH);
+        Self { path, captured_at }
+    }
+
+    /// The text OCR found in the capture, once it has been read.
+    fn text_path(&self) -> PathBuf {
+        self.path.with_extension("txt")
+    }
+
+    /// Marks the capture as being read by OCR, and is where tesseract
+    /// writes before the text is moved into place.
+    fn ocr_path(&self) -> PathBuf {
+        self.path.with_extension("ocr")
+    }
+
+    /// Local capture time, for menus and lists.
+    pub fn label(&self) -> String {
+        let time: chrono::DateTime<chrono::Local> = self.captured_at.into();
+        time.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+/// Creates the file for a new entry. One kept the same millisecond as
+/// another, as a multi-selection export can, gets a counter appended
+/// rather than replacing it.
+fn create_entry() -> Result<(PathBuf, std::fs::File), CaptureError> {
+    let dir = paths::history_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| CaptureError::Io(e.to_string()))?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    for counter in 0.. {
+        let name = match counter {
+            0 => format!("capture-{}.png", millis),
+            n => format!("capture-{}-{}.png", millis, n),
+        };
+        let path = dir.join(name);
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(CaptureError::Io(format!("{}: {}", path.display(), err))),
+        }
+    }
+    unreachable!("the counter runs until a name is free")
+}
+
+/// Stores an encoded PNG as a new history entry; `None` when the history is
+/// off or this process is incognito.
+pub fn record_png(bytes: &[u8]) -> Result<Option<PathBuf>, CaptureError> {
+    if !keeps_captures() {
+        return Ok(None);
+    }
+    let (path, mut file) = create_entry()?;
+    std::io::Write::write_all(&mut file, bytes)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+    Ok(Some(path))
+}
+
+/// Copies an already saved PNG into the history; `None` when the history is
+/// off or this process is incognito.
+pub fn record_file(source: &Path) -> Result<Option<PathBuf>, CaptureError> {
+    if !keeps_captures() {
+        return Ok(None);
+    }
+    let mut source_file = std::fs::File::open(source)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", source.display(), e)))?;
+    let (path, mut file) = create_entry()?;
+    std::io::copy(&mut source_file, &mut file)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+    Ok(Some(path))
+}
+
+/// History entries, newest first.
+pub fn entries() -> Vec<HistoryEntry> {
+    let Ok(read_dir) = std::fs::read_dir(paths::history_dir()) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<HistoryEntry> = read_dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .map(HistoryEntry::from_path)
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.captured_at));
+    entries
+}
+
+/// Deletes the oldest entries past any of `config`'s limits. Returns how
+/// many were deleted.
+pub fn prune(config: &HistoryConfig) -> Result<usize, CaptureError> {
+    let size = |path: &Path| path.metadata().map_or(0, |m| m.len());
+    let expired = expired(entries(), config, SystemTime::now(), size);
+    for entry in &expired {
+        remove(&entry.path)?;
+    }
+    Ok(expired.len())
+}
+
+/// Which of `entries`, newest first, are past any of `config`'s limits at
+/// `now`. Only the entries kept count towards `max_mb`.
+fn expired(
+    entries: Vec<HistoryEntry>,
+    config: &HistoryConfig,
+    now: SystemTime,
+    size: impl Fn(&Path) -> u64,
+) -> Vec<HistoryEntry> {
+    let max_age = Duration::from_secs(u64::from(config.max_age_days) * 24 * 60 * 60);
+    let max_bytes = u64::from(config.max_mb) * 1024 * 1024;
+    let mut kept_bytes = 0;
+    let mut kept = 0;
+    let mut expired = Vec::new();
+    for entry in entries {
+        let bytes = size(&entry.path);
+        let too_many = config.max_entries > 0 && kept >= config.max_entries;
+        let too_old = config.max_age_days > 0
+            && now.duration_since(entry.captured_at).is_ok_and(|age| age > max_age);
+        let too_big = config.max_mb > 0 && kept_bytes + bytes > max_bytes;
+        if too_many || too_old || too_big {
+            expired.push(entry);
+        } else {
+            kept += 1;
+            kept_bytes += bytes;
+        }
+    }
+    expired
+}
+
+/// Deletes every entry. Returns how many there were.
+pub fn clear() -> Result<usize, CaptureError> {
+    let entries = entries();
+    for entry in &entries {
+        remove(&entry.path)?;
+    }
+    Ok(entries.len())
+}
+
+/// Starts tesseract on the newest entries whose text hasn't been read yet.
+/// Each entry's text is written next to it once tesseract succeeds, so one
+/// that fails is tried again after the next capture. Returns how many were
+/// started.
+pub fn index_text() -> Result<usize, CaptureError> {
+    let unread = entries()
+        .into_iter()
+        .filter(|entry| !entry.text_path().exists() && !being_read(&entry.ocr_path()))
+        .take(OCR_PER_CAPTURE);
+    let mut started = 0;
+    for entry in unread {
+        // Claims the entry, so the next capture doesn't start another
+        // tesseract on it while this one is still reading.
+        let claim = entry.ocr_path();
+        std::fs::write(&claim, "")
+            .map_err(|e| CaptureError::Io(format!("{}: {}", claim.display(), e)))?;
+        // tesseract writes `$2.txt`; it is renamed into place only when
+        // tesseract succeeded and the capture wasn't deleted meanwhile, and
+        // the claim goes either way. The shell finishes the job if fireshot
+        // exits first.
+        let spawned = host_command("sh")
+            .arg("-c")
+            .arg(OCR_SCRIPT)
+            .arg("sh")
+            .arg(&entry.path)
+            .arg(&claim)
+            .arg(entry.text_path())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                // Reaped off the capture path; the daemon lives on.
+                std::thread::spawn(move || child.wait());
+            }
+            Err(err) => {
+                let _ = std::fs::remove_file(&claim);
+                return Err(CaptureError::Io(format!("tesseract: {}", err)));
+            }
+        }
+        started += 1;
+    }
+    Ok(started)
+}
+
+/// Whether tesseract has `claim` on an entry, left less than
+/// `OCR_STALE_SECS` ago; older ones were left by a run that didn't finish.
+fn being_read(claim: &Path) -> bool {
+    claim
+        .metadata()
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| {
+            modified
+                .elapsed()
+                .is_ok_and(|age| age < Duration::from_secs(OCR_STALE_SECS))
+        })
+}
+
+/// Entries whose text contains `query`, ignoring case, newest first.
+pub fn search(query: &str) -> Vec<HistoryEntry> {
+    let query = query.to_lowercase();
+    entries()
+        .into_iter()
+        .filter(|entry| {
+            std::fs::read_to_string(entry.text_path())
+                .is_ok_and(|text| text.to_lowercase().contains(&query))
+        })
+        .collect()
+}
+
+fn remove(path: &Path) -> Result<(), CaptureError> {
+    std::fs::remove_file(path)
+        .map_err(|e| CaptureError::Io(format!("{}: {}", path.display(), e)))?;
+    let _ = std::fs::remove_file(path.with_extension("txt"));
+    let _ = std::fs::remove_file(path.with_extension("ocr"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    const MB: u64 = 1024 * 1024;
+
+    /// `count` entries a day apart, newest first.
+    fn history(now: SystemTime, count: u32) -> Vec<HistoryEntry> {
+        (0..count)
+            .map(|age| HistoryEntry {
+                path: PathBuf::from(format!("capture-{}.png", age)),
+                captured_at: now - DAY * age,
+            })
+            .collect()
+    }
+
+    fn names(entries: &[HistoryEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect()
+    }
+
+    fn limits(max_entries: usize, max_age_days: u32, max_mb: u32) -> HistoryConfig {
+        HistoryConfig {
+            max_entries,
+            max_age_days,
+            max_mb,
+            ..HistoryConfig::default()
+        }
+    }
+
+    #[test]
+    fn no_limits_keep_everything() {
+        let now = SystemTime::now();
+        let expired = expired(history(now, 5), &limits(0, 0, 0), now, |_| MB);
+        assert!(expired.is_empty());
+    }
+
+    #[test]
+    fn count_limit_drops_the_oldest() {
+        let now = SystemTime::now();
+        let expired = expired(history(now, 5), &limits(3, 0, 0), now, |_| MB);
+        assert_eq!(names(&expired), ["capture-3.png", "capture-4.png"]);
+    }
+
+    #[test]
+    fn age_limit_drops_older_entries() {
+        let now = SystemTime::now();
+        let expired = expired(history(now, 5), &limits(0, 2, 0), now, |_| MB);
+        assert_eq!(names(&expired), ["capture-3.png", "capture-4.png"]);
+    }
+
+    #[test]
+    fn size_limit_counts_only_kept_entries() {
+        let now = SystemTime::now();
+        let size = |path: &Path| {
+            if path == Path::new("capture-1.png") {
+                5 * MB
+            } else {
+                MB
+            }
+        };
+        // The 5 MiB entry doesn't fit next to the newest; the rest do.
+        let expired = expired(history(now, 5), &limits(0, 0, 4), now, size);
+        assert_eq!(names(&expired), ["capture-1.png"]);
+    }
+
+    #[test]
+    fn limits_combine() {
+        let now = SystemTime::now();
+        let expired = expired(history(now, 6), &limits(4, 3, 2), now, |_| MB);
+        assert_eq!(
+            names(&expired),
+            ["capture-2.png", "capture-3.png", "capture-4.png", "capture-5.png"]
+        );
+    }
+
+    #[test]
+    fn defaults_are_bounded() {
+        let now = SystemTime::now();
+        let config = HistoryConfig::default();
+        assert!(!config.enabled);
+        let expired = expired(history(now, 40), &config, now, |_| MB);
+        assert_eq!(expired.len(), 9);
+    }
+}
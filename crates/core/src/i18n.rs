@@ -0,0 +1,235 @@
+//! User-facing strings, looked up by message id from the catalogs in
+//! `crates/core/locales`. The files use the plain-message subset of Fluent
+//! (`id = value`, indented continuation lines, `{ $name }` placeables), so
+//! they can move to a full Fluent runtime without being rewritten.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const FALLBACK: &str = "en";
+
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+struct Catalog {
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Catalog {
+    fn get(&self, id: &str) -> Option<&str> {
+        self.messages
+            .get(id)
+            .or_else(|| self.fallback.get(id))
+            .map(String::as_str)
+    }
+}
+
+fn parse(source: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    let mut finish = |entry: Option<(String, Vec<&str>)>| {
+        let Some((id, lines)) = entry else {
+            return;
+        };
+        let indent = lines
+            .iter()
+            .skip(1)
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let mut value = lines[0].trim().to_string();
+        for line in &lines[1..] {
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(line.get(indent..).unwrap_or("").trim_end());
+        }
+        messages.insert(id, value.trim_end().to_string());
+    };
+
+    for line in source.lines() {
+        if line.starts_with(' ') {
+            if let Some((_, lines)) = current.as_mut() {
+                lines.push(line);
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            // Blank lines may sit inside a multiline value.
+            if let Some((_, lines)) = current.as_mut() {
+                lines.push("");
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            finish(current.take());
+            continue;
+        }
+        finish(current.take());
+        if let Some((id, value)) = line.split_once('=') {
+            current = Some((id.trim().to_string(), vec![value]));
+        }
+    }
+    finish(current.take());
+    messages
+}
+
+/// The user's language from `LANGUAGE`, `LC_ALL`, `LC_MESSAGES` or `LANG`,
+/// reduced to the bare language code (`de_AT.UTF-8` becomes `de`).
+pub fn detect_language() -> Option<String> {
+    language_from(|var| std::env::var(var).ok())
+}
+
+/// `detect_language` over the variables `var` looks up.
+fn language_from(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    for name in ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"] {
+        let Some(value) = var(name) else {
+            continue;
+        };
+        let first = value.split(':').next().unwrap_or("");
+        let lang = first
+            .split(['_', '.', '@'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if !lang.is_empty() && lang != "c" && lang != "posix" {
+            return Some(lang);
+        }
+    }
+    None
+}
+
+fn catalog() -> &'static Catalog {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    CATALOG.get_or_init(|| {
+        let source_for = |lang: &str| {
+            CATALOGS
+                .iter()
+                .find(|(code, _)| *code == lang)
+                .map(|(_, source)| *source)
+        };
+        let fallback = parse(source_for(FALLBACK).unwrap_or(""));
+        let messages = detect_language()
+            .and_then(|lang| source_for(&lang))
+            .map(parse)
+            .unwrap_or_default();
+        Catalog { messages, fallback }
+    })
+}
+
+/// Looks up a message, falling back to English and then to the id itself.
+pub fn tr(id: &str) -> &str {
+    catalog().get(id).unwrap_or(id)
+}
+
+/// Looks up a message and fills in its `{ $name }` placeables.
+pub fn tr_args(id: &str, args: &[(&str, String)]) -> String {
+    fill(tr(id), args)
+}
+
+fn fill(text: &str, args: &[(&str, String)]) -> String {
+    let mut text = text.to_string();
+    for (name, value) in args {
+        text = text
+            .replace(&format!("{{ ${} }}", name), value)
+            .replace(&format!("{{${}}}", name), value);
+    }
+    text
+}
+
+/// `tr!("id")` or `tr!("id", name = value, ...)`.
+#[macro_export]
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::tr($id)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr_args($id, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_and_multiline_values() {
+        let messages = parse(
+            "# a comment\n\
+             ## a section\n\
+             plain = Hello\n\
+             spaced   =   padded  \n\
+             multi =\n    first line\n      indented\n\n    after blank\n\
+             inline = starts here\n    and goes on\n\
+             # between = not a message\n\
+             last = end\n",
+        );
+        assert_eq!(messages["plain"], "Hello");
+        assert_eq!(messages["spaced"], "padded");
+        assert_eq!(messages["multi"], "first line\n  indented\n\nafter blank");
+        assert_eq!(messages["inline"], "starts here\nand goes on");
+        assert_eq!(messages["last"], "end");
+        assert!(!messages.contains_key("between"));
+        assert_eq!(messages.len(), 5);
+    }
+
+    #[test]
+    fn value_may_contain_equals() {
+        let messages = parse("fix = set [record] format = \"gif\"\n");
+        assert_eq!(messages["fix"], "set [record] format = \"gif\"");
+    }
+
+    #[test]
+    fn fills_placeables() {
+        let args = [("name", "DP-2".to_string()), ("count", "3".to_string())];
+        assert_eq!(fill("{ $name } has { $count }, {$count}", &args), "DP-2 has 3, 3");
+        assert_eq!(fill("{ $other } stays", &args), "{ $other } stays");
+    }
+
+    #[test]
+    fn falls_back_to_english_then_the_id() {
+        let catalog = Catalog {
+            messages: parse("both = Beide\n"),
+            fallback: parse("both = Both\nonly-en = English only\n"),
+        };
+        assert_eq!(catalog.get("both"), Some("Beide"));
+        assert_eq!(catalog.get("only-en"), Some("English only"));
+        assert_eq!(catalog.get("missing"), None);
+        assert_eq!(tr("no-such-message-id"), "no-such-message-id");
+    }
+
+    #[test]
+    fn every_translation_has_an_english_message() {
+        let english = parse(CATALOGS[0].1);
+        for (code, source) in &CATALOGS[1..] {
+            for id in parse(source).keys() {
+                assert!(english.contains_key(id), "{} has {} but en doesn't", code, id);
+            }
+        }
+    }
+
+    #[test]
+    fn detects_language_from_locale_variables() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(language_from(env(&[("LANG", "de_AT.UTF-8")])).as_deref(), Some("de"));
+        assert_eq!(language_from(env(&[("LANG", "sr@latin")])).as_deref(), Some("sr"));
+        assert_eq!(language_from(env(&[("LANG", "EN_US")])).as_deref(), Some("en"));
+        let both = env(&[("LANGUAGE", "fr:de"), ("LANG", "de_DE.UTF-8")]);
+        assert_eq!(language_from(both).as_deref(), Some("fr"));
+        let c_first = env(&[("LC_ALL", "C"), ("LANG", "de_DE.UTF-8")]);
+        assert_eq!(language_from(c_first).as_deref(), Some("de"));
+        let empty = env(&[("LANGUAGE", ""), ("LC_MESSAGES", "POSIX")]);
+        assert_eq!(language_from(empty), None);
+        assert_eq!(language_from(env(&[])), None);
+    }
+}
@@ -1,11 +1,27 @@
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
+use crate::sandbox::Sandbox;
+
+pub mod config;
+pub mod history;
+pub mod i18n;
+pub mod output;
+pub mod paths;
+pub mod profile;
+pub mod sandbox;
+pub mod session;
+pub mod shapes;
+pub mod snippet;
+pub mod tool_styles;
+pub mod window;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum CaptureMode {
     Graphical,
     Fullscreen,
     Screen,
+    Window,
 }
 
 bitflags! {
@@ -25,6 +41,8 @@ pub struct CaptureRequest {
     pub delay_ms: u64,
     pub tasks: ExportTask,
     pub save_path: Option<String>,
+    /// Part of a full-screen capture to keep, such as one screen.
+    pub region: Option<RecordRegion>,
 }
 
 impl Default for CaptureRequest {
@@ -34,16 +52,126 @@ impl Default for CaptureRequest {
             delay_ms: 0,
             tasks: ExportTask::NONE,
             save_path: None,
+            region: None,
         }
     }
 }
 
+/// A region of the screen to record, in capture pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RecordRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What a recording captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordTarget {
+    /// A region of the output picked in the portal dialog.
+    Region(RecordRegion),
+    /// A window picked in the portal dialog.
+    Window,
+    /// A whole output picked in the portal dialog.
+    Output,
+}
+
+/// What an editor or CLI flow did with a capture, reported back so the caller
+/// can follow up (e.g. with a notification).
+#[derive(Debug, Clone)]
+pub enum ExportResult {
+    Copied { history_path: Option<std::path::PathBuf> },
+    Saved { path: std::path::PathBuf },
+    /// A GIF or video recording.
+    Recorded { path: std::path::PathBuf },
+    /// Served on the local network until a device fetched it from `url`.
+    Shared { url: String },
+    /// Attached to a new message in the mail client.
+    Emailed { path: std::path::PathBuf },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum CaptureError {
     #[error("portal error: {0}")]
     Portal(String),
+    #[error("capture cancelled")]
+    PortalCancelled,
+    #[error("{portal} portal did not answer within {secs}s")]
+    PortalTimeout { portal: &'static str, secs: u64 },
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("clipboard unavailable: {0}")]
+    ClipboardUnavailable(String),
+    #[error("could not encode {format}: {reason}")]
+    EncodeError { format: String, reason: String },
+    #[error("upload to {service} failed: {reason}")]
+    UploadError { service: String, reason: String },
     #[error("io error: {0}")]
     Io(String),
     #[error("unsupported: {0}")]
     Unsupported(String),
+    /// `fireshot doctor` found problems, which it has printed.
+    #[error("some checks failed")]
+    ChecksFailed,
+}
+
+impl CaptureError {
+    /// Process exit code for this error; each kind gets its own so scripts
+    /// can tell a cancelled capture from a broken portal.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Portal(_) => 3,
+            Self::PortalCancelled => 4,
+            Self::PortalTimeout { .. } => 5,
+            Self::PermissionDenied(_) => 6,
+            Self::ClipboardUnavailable(_) => 7,
+            Self::EncodeError { .. } => 8,
+            Self::UploadError { .. } => 9,
+            Self::Io(_) => 10,
+            Self::Unsupported(_) => 11,
+            Self::ChecksFailed => 12,
+        }
+    }
+
+    /// What the user can do about it, when there is something to suggest.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Portal(_) | Self::PortalTimeout { .. } => Some(tr!("hint-portal")),
+            Self::PermissionDenied(_) => Some(tr!("hint-permission")),
+            Self::ClipboardUnavailable(_) if Sandbox::detect() == Some(Sandbox::Flatpak) => {
+                Some(tr!("hint-clipboard-flatpak"))
+            }
+            Self::ClipboardUnavailable(_) => Some(tr!("hint-clipboard")),
+            Self::UploadError { .. } => Some(tr!("hint-upload")),
+            _ => None,
+        }
+    }
+
+    /// The error in the user's language, followed by its hint, for status
+    /// lines and notifications. `Display` stays English for logs.
+    pub fn user_message(&self) -> String {
+        let message = match self {
+            Self::Portal(detail) => tr!("error-portal", detail = detail),
+            Self::PortalCancelled => tr!("error-cancelled").to_string(),
+            Self::PortalTimeout { portal, secs } => {
+                tr!("error-timeout", portal = portal, secs = secs)
+            }
+            Self::PermissionDenied(detail) => tr!("error-permission", detail = detail),
+            Self::ClipboardUnavailable(detail) => tr!("error-clipboard", detail = detail),
+            Self::EncodeError { format, reason } => {
+                tr!("error-encode", format = format, detail = reason)
+            }
+            Self::UploadError { service, reason } => {
+                tr!("error-upload", service = service, detail = reason)
+            }
+            Self::Io(detail) => tr!("error-io", detail = detail),
+            Self::Unsupported(detail) => tr!("error-unsupported", detail = detail),
+            Self::ChecksFailed => tr!("error-checks-failed").to_string(),
+        };
+        match self.hint() {
+            Some(hint) => format!("{} ({})", message, hint),
+            None => message,
+        }
+    }
 }
@@ -0,0 +1,123 @@
+//! The screens the desktop is laid out on, as the compositor reports them.
+//! Hyprland, sway and niri are asked through their IPC tools, as for the
+//! focused window; other desktops don't say, and have no screens to pick.
+
+use serde_json::Value;
+
+use crate::window::query;
+use crate::RecordRegion;
+
+/// A screen's place in the desktop layout, in logical pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputInfo {
+    /// The connector, such as `DP-2`.
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The enabled screens, left to right then top to bottom; none when the
+/// running compositor can't be asked.
+pub fn outputs() -> Vec<OutputInfo> {
+    let env = |var: &str| std::env::var_os(var).is_some();
+    let mut outputs: Vec<OutputInfo> = if env("HYPRLAND_INSTANCE_SIGNATURE") {
+        list(query("hyprctl", &["-j", "monitors"]))
+            .filter_map(hyprland_output)
+            .collect()
+    } else if env("SWAYSOCK") {
+        list(query("swaymsg", &["-t", "get_outputs", "-r"]))
+            .filter(|output| output["active"].as_bool() != Some(false))
+            .filter_map(|output| from_rect(&output["name"], &output["rect"]))
+            .collect()
+    } else if env("NIRI_SOCKET") {
+        query("niri", &["msg", "-j", "outputs"])
+            .and_then(|outputs| outputs.as_object().cloned())
+            .into_iter()
+            .flat_map(|outputs| outputs.into_iter())
+            .filter_map(|(name, output)| from_rect(&Value::String(name), &output["logical"]))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    outputs.sort_by_key(|output| (output.x, output.y));
+    outputs
+}
+
+/// Where each of `outputs` is in a capture of the whole desktop that is
+/// `width` by `height` pixels, in the same order. The layout is scaled to
+/// the capture, as backends capture every screen at one scale.
+pub fn capture_regions(outputs: &[OutputInfo], width: u32, height: u32) -> Vec<RecordRegion> {
+    let (Some(left), Some(top)) = (
+        outputs.iter().map(|output| output.x).min(),
+        outputs.iter().map(|output| output.y).min(),
+    ) else {
+        return Vec::new();
+    };
+    let right = outputs
+        .iter()
+        .map(|o| o.x + o.width as i32)
+        .max()
+        .unwrap_or(left);
+    let bottom = outputs
+        .iter()
+        .map(|o| o.y + o.height as i32)
+        .max()
+        .unwrap_or(top);
+    let scale_x = width as f32 / (right - left).max(1) as f32;
+    let scale_y = height as f32 / (bottom - top).max(1) as f32;
+    outputs
+        .iter()
+        .map(|output| RecordRegion {
+            x: ((output.x - left) as f32 * scale_x).round() as u32,
+            y: ((output.y - top) as f32 * scale_y).round() as u32,
+            width: (output.width as f32 * scale_x).round() as u32,
+            height: (output.height as f32 * scale_y).round() as u32,
+        })
+        .collect()
+}
+
+fn list(value: Option<Value>) -> impl Iterator<Item = Value> {
+    value
+        .and_then(|value| value.as_array().cloned())
+        .into_iter()
+        .flatten()
+}
+
+/// Hyprland gives the mode in physical pixels, before scale and rotation.
+fn hyprland_output(monitor: Value) -> Option<OutputInfo> {
+    if monitor["disabled"].as_bool() == Some(true) {
+        return None;
+    }
+    let scale = monitor["scale"]
+        .as_f64()
+        .filter(|scale| *scale > 0.0)
+        .unwrap_or(1.0);
+    let logical = |key: &str| Some((monitor[key].as_f64()? / scale).round() as u32);
+    let (mut width, mut height) = (logical("width")?, logical("height")?);
+    if monitor["transform"]
+        .as_u64()
+        .is_some_and(|transform| transform % 2 == 1)
+    {
+        std::mem::swap(&mut width, &mut height);
+    }
+    Some(OutputInfo {
+        name: monitor["name"].as_str()?.to_string(),
+        x: monitor["x"].as_i64()? as i32,
+        y: monitor["y"].as_i64()? as i32,
+        width,
+        height,
+    })
+}
+
+/// sway's `rect` and niri's `logical` are already in logical pixels.
+fn from_rect(name: &Value, rect: &Value) -> Option<OutputInfo> {
+    Some(OutputInfo {
+        name: name.as_str()?.to_string(),
+        x: rect["x"].as_i64()? as i32,
+        y: rect["y"].as_i64()? as i32,
+        width: rect["width"].as_u64().filter(|width| *width > 0)? as u32,
+        height: rect["height"].as_u64().filter(|height| *height > 0)? as u32,
+    })
+}
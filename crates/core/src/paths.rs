@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use crate::CaptureError;
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home_dir().join(fallback),
+    }
+}
+
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("fireshot")
+}
+
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("fireshot")
+}
+
+pub fn history_dir() -> PathBuf {
+    data_dir().join("history")
+}
+
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache").join("fireshot")
+}
+
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state").join("fireshot")
+}
+
+/// Open editor sessions, kept so they can be restored after a crash.
+pub fn recovery_dir() -> PathBuf {
+    state_dir().join("recovery")
+}
+
+/// XDG autostart entries, started by the desktop session at login.
+pub fn autostart_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("autostart")
+}
+
+pub fn systemd_user_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("systemd/user")
+}
+
+/// Session bus activation files, read by dbus-daemon/dbus-broker.
+pub fn dbus_services_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("dbus-1/services")
+}
+
+/// Desktop entries, where `x-scheme-handler/*` handlers are looked up.
+pub fn applications_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("applications")
+}
+
+/// `XDG_PICTURES_DIR` from the environment or `user-dirs.dirs`, else `~/Pictures`.
+pub fn pictures_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("XDG_PICTURES_DIR").filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    let user_dirs = xdg_dir("XDG_CONFIG_HOME", ".config").join("user-dirs.dirs");
+    if let Ok(text) = std::fs::read_to_string(user_dirs) {
+        for line in text.lines() {
+            let Some(value) = line.trim().strip_prefix("XDG_PICTURES_DIR=") else {
+                continue;
+            };
+            let value = value.trim_matches('"');
+            let home = home_dir();
+            return match value.strip_prefix("$HOME") {
+                Some(rest) => home.join(rest.trim_start_matches('/')),
+                None => PathBuf::from(value),
+            };
+        }
+    }
+    home_dir().join("Pictures")
+}
+
+/// Opens a file or directory with the desktop's default handler.
+pub fn open_with_default_app(path: &Path) -> Result<(), CaptureError> {
+    std::process::Command::new("xdg-open")
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| CaptureError::Io(e.to_string()))
+}
+
+/// Captures handed to the mail client. They are kept until the next email,
+/// since the client reads the attachment after we return.
+pub fn email_dir() -> PathBuf {
+    cache_dir().join("email")
+}
+
+/// Opens the default mail client on a new message with `attachment`, through
+/// `xdg-email`, or a `mailto:` link when that isn't installed.
+pub fn compose_email(subject: &str, attachment: &Path) -> Result<(), CaptureError> {
+    let spawned = std::process::Command::new("xdg-email")
+        .arg("--subject")
+        .arg(subject)
+        .arg("--attach")
+        .arg(attachment)
+        .spawn();
+    match spawned {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            // Thunderbird and Evolution take attachments in the link too.
+            let link = format!(
+                "mailto:?subject={}&attach={}",
+                percent_encode(subject),
+                percent_encode(&attachment.to_string_lossy())
+            );
+            std::process::Command::new("xdg-open")
+                .arg(link)
+                .spawn()
+                .map(|_| ())
+                .map_err(|e| CaptureError::Io(format!("xdg-email: {}", e)))
+        }
+        Err(err) => Err(CaptureError::Io(format!("xdg-email: {}", err))),
+    }
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
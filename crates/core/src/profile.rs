@@ -0,0 +1,42 @@
+//! Stage timings for `fireshot --profile`. Instrumented stages record into a
+//! process-wide list that the CLI reports once a command finishes.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static SAMPLES: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+pub fn record(stage: &'static str, elapsed: Duration) {
+    if let Ok(mut samples) = SAMPLES.lock() {
+        samples.push((stage, elapsed));
+    }
+}
+
+/// Drains everything recorded so far, in the order stages finished.
+pub fn take() -> Vec<(&'static str, Duration)> {
+    SAMPLES
+        .lock()
+        .map(|mut samples| std::mem::take(&mut *samples))
+        .unwrap_or_default()
+}
+
+/// Records the time from `start` until it is dropped.
+pub struct Timer {
+    stage: &'static str,
+    started: Instant,
+}
+
+impl Timer {
+    pub fn start(stage: &'static str) -> Self {
+        Self {
+            stage,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        record(self.stage, self.started.elapsed());
+    }
+}
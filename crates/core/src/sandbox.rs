@@ -0,0 +1,51 @@
+//! Whether fireshot runs confined in a Flatpak or Snap, where the host's
+//! files and programs are only within reach through portals, or through
+//! `flatpak-spawn --host` for a Flatpak allowed to use it.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+impl Sandbox {
+    /// The sandbox this process runs in, if any.
+    pub fn detect() -> Option<Self> {
+        static DETECTED: OnceLock<Option<Sandbox>> = OnceLock::new();
+        *DETECTED.get_or_init(|| {
+            if Path::new("/.flatpak-info").exists() || std::env::var_os("FLATPAK_ID").is_some() {
+                Some(Self::Flatpak)
+            } else if std::env::var_os("SNAP").is_some() {
+                Some(Self::Snap)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// `program` run on the host. Inside a Flatpak that goes through
+/// `flatpak-spawn --host`, as the runtime doesn't ship the clipboard tools
+/// or compositor clients; the app needs `--talk-name=org.freedesktop.Flatpak`
+/// for it. The display variables are passed on, since the host command
+/// doesn't inherit the sandbox's environment.
+pub fn host_command(program: &str) -> Command {
+    if Sandbox::detect() != Some(Sandbox::Flatpak) {
+        return Command::new(program);
+    }
+    let mut command = Command::new("flatpak-spawn");
+    command.arg("--host");
+    for var in ["WAYLAND_DISPLAY", "DISPLAY"] {
+        if let Some(value) = std::env::var_os(var) {
+            let mut env = std::ffi::OsString::from(format!("--env={}=", var));
+            env.push(value);
+            command.arg(env);
+        }
+    }
+    command.arg(program);
+    command
+}
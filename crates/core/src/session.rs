@@ -0,0 +1,107 @@
+//! Step-by-step capture sessions. While one is running, every capture is
+//! also saved into its folder as `step-01.png`, `step-02.png` and so on, for
+//! how-to guides that walk through a task one screenshot at a time.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{paths, tr, CaptureError};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Where the numbered steps are saved.
+    pub dir: PathBuf,
+}
+
+fn state_path() -> PathBuf {
+    paths::state_dir().join("session.toml")
+}
+
+fn io_error(path: &Path, err: impl std::fmt::Display) -> CaptureError {
+    CaptureError::Io(format!("{}: {}", path.display(), err))
+}
+
+/// The running session, if one was started and not stopped yet.
+pub fn current() -> Option<Session> {
+    let text = std::fs::read_to_string(state_path()).ok()?;
+    toml::from_str(&text).ok()
+}
+
+/// Ends the running session and returns it.
+pub fn stop() -> Option<Session> {
+    let session = current()?;
+    std::fs::remove_file(state_path()).ok()?;
+    Some(session)
+}
+
+/// The step number in a `step-NN.png` file name.
+fn step_number(path: &Path) -> Option<u32> {
+    let stem = path.file_stem()?.to_str()?.strip_prefix("step-")?;
+    let is_png = path.extension().is_some_and(|ext| ext == "png");
+    is_png.then(|| stem.parse().ok()).flatten()
+}
+
+impl Session {
+    /// Starts a session saving into `dir`, replacing any running one.
+    pub fn start(dir: PathBuf) -> Result<Self, CaptureError> {
+        std::fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        let dir = dir.canonicalize().unwrap_or(dir);
+        let session = Self { dir };
+        let state = state_path();
+        if let Some(parent) = state.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+        let text = toml::to_string(&session).map_err(|e| CaptureError::Io(e.to_string()))?;
+        std::fs::write(&state, text).map_err(|e| io_error(&state, e))?;
+        Ok(session)
+    }
+
+    /// The steps saved so far, in order.
+    pub fn steps(&self) -> Vec<PathBuf> {
+        let Ok(read_dir) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut steps: Vec<(u32, PathBuf)> = read_dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter_map(|path| Some((step_number(&path)?, path)))
+            .collect();
+        steps.sort();
+        steps.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Where the next capture goes, numbered after the last step.
+    pub fn next_step_path(&self) -> PathBuf {
+        let last = self
+            .steps()
+            .iter()
+            .filter_map(|path| step_number(path))
+            .max()
+            .unwrap_or(0);
+        self.dir.join(format!("step-{:02}.png", last + 1))
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        path.parent().is_some_and(|parent| parent == self.dir) && step_number(path).is_some()
+    }
+
+    /// Copies an already saved PNG in as the next step.
+    pub fn add_step(&self, source: &Path) -> Result<PathBuf, CaptureError> {
+        let path = self.next_step_path();
+        std::fs::copy(source, &path).map_err(|e| io_error(&path, e))?;
+        Ok(path)
+    }
+
+    /// A markdown snippet with a heading and the image for every step,
+    /// linked relative to the session folder.
+    pub fn markdown(&self) -> String {
+        let mut text = String::new();
+        for (i, path) in self.steps().iter().enumerate() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let title = tr!("session-step", number = i + 1);
+            text.push_str(&format!("## {}\n\n![{}]({})\n\n", title, title, name));
+        }
+        text
+    }
+}
@@ -0,0 +1,434 @@
+//! The annotation document: the shapes drawn on a capture, in image-space
+//! pixels, as the editor draws them, crash recovery saves them and exports
+//! render them. How a shape looks on screen stays with the GUI.
+
+use emath::{pos2, vec2};
+use serde::{Deserialize, Serialize};
+
+// egui's own geometry and colour types, so the GUI uses shapes as they are
+// and other crates needn't depend on egui's.
+pub use ecolor::Color32;
+pub use emath::{Pos2, Rect, Vec2};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrokeShape {
+    pub points: Vec<Pos2>,
+    pub color: Color32,
+    pub size: f32,
+    /// Bounding box of `points`, kept up to date by `push_point`.
+    pub bounds: Rect,
+    /// Width at each of `points`, for strokes drawn with pen pressure;
+    /// empty when the whole stroke is `size` wide.
+    #[serde(default)]
+    pub widths: Vec<f32>,
+}
+
+/// Pointer moves shorter than this (in image pixels) don't add a stroke point.
+pub const STROKE_MIN_DISTANCE: f32 = 1.5;
+
+/// The lightest pen touch still draws this fraction of the tool size.
+const MIN_PRESSURE: f32 = 0.1;
+
+impl StrokeShape {
+    /// Starts a stroke; with a pen `pressure` its width follows the pressure
+    /// of every point.
+    pub fn new(start: Pos2, color: Color32, size: f32, pressure: Option<f32>) -> Self {
+        let mut stroke = Self {
+            points: vec![start],
+            color,
+            size,
+            bounds: Rect::from_min_max(start, start),
+            widths: Vec::new(),
+        };
+        if pressure.is_some() {
+            stroke.widths.push(stroke.pressure_width(pressure));
+        }
+        stroke
+    }
+
+    /// Adds a point unless it is within `STROKE_MIN_DISTANCE` of the last
+    /// one; `force` keeps it anyway so a stroke ends exactly where the
+    /// pointer was released.
+    pub fn push_point(&mut self, pos: Pos2, pressure: Option<f32>, force: bool) {
+        if let Some(last) = self.points.last() {
+            let min = if force {
+                f32::EPSILON
+            } else {
+                STROKE_MIN_DISTANCE
+            };
+            if last.distance(pos) < min {
+                return;
+            }
+        }
+        self.points.push(pos);
+        self.bounds.extend_with(pos);
+        if !self.widths.is_empty() {
+            self.widths.push(self.pressure_width(pressure));
+        }
+    }
+
+    /// Consecutive point pairs with the width to draw between them.
+    pub fn segments(&self) -> impl Iterator<Item = (Pos2, Pos2, f32)> + '_ {
+        self.points.windows(2).enumerate().map(|(i, pair)| {
+            let width = match (self.widths.get(i), self.widths.get(i + 1)) {
+                (Some(a), Some(b)) => (a + b) / 2.0,
+                _ => self.size,
+            };
+            (pair[0], pair[1], width)
+        })
+    }
+
+    fn pressure_width(&self, pressure: Option<f32>) -> f32 {
+        self.size * pressure.unwrap_or(1.0).clamp(MIN_PRESSURE, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineShape {
+    pub start: Pos2,
+    pub end: Pos2,
+    pub color: Color32,
+    pub size: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RectShape {
+    pub start: Pos2,
+    pub end: Pos2,
+    pub color: Color32,
+    pub size: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircleShape {
+    pub start: Pos2,
+    pub end: Pos2,
+    pub color: Color32,
+    pub size: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrowShape {
+    pub start: Pos2,
+    pub end: Pos2,
+    pub color: Color32,
+    pub size: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircleCountShape {
+    pub center: Pos2,
+    pub pointer: Pos2,
+    pub color: Color32,
+    pub size: f32,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextShape {
+    pub pos: Pos2,
+    pub text: String,
+    pub color: Color32,
+    pub size: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectShape {
+    pub start: Pos2,
+    pub end: Pos2,
+    pub size: f32,
+    pub kind: EffectKind,
+    #[serde(default)]
+    pub outline: EffectOutline,
+    /// Path of an effect painted with the brush, which covers only the pixels
+    /// within `brush / 2` of it, or the corners of a lasso; `start` and `end`
+    /// then bound the path. Empty for an effect over its rect or ellipse.
+    #[serde(default)]
+    pub mask: Vec<Pos2>,
+    #[serde(default)]
+    pub brush: f32,
+}
+
+impl EffectShape {
+    pub fn new(start: Pos2, size: f32, kind: EffectKind) -> Self {
+        Self {
+            start,
+            end: start,
+            size,
+            kind,
+            outline: EffectOutline::Rect,
+            mask: Vec::new(),
+            brush: 0.0,
+        }
+    }
+
+    /// Starts an effect over the ellipse inside its rect.
+    pub fn ellipse(start: Pos2, size: f32, kind: EffectKind) -> Self {
+        Self {
+            outline: EffectOutline::Ellipse,
+            ..Self::new(start, size, kind)
+        }
+    }
+
+    /// Starts an effect over the polygon a lasso traces from `start`.
+    pub fn lasso(start: Pos2, size: f32, kind: EffectKind) -> Self {
+        Self {
+            outline: EffectOutline::Lasso,
+            mask: vec![start],
+            ..Self::new(start, size, kind)
+        }
+    }
+
+    /// Starts an effect painted with a brush `brush` pixels wide.
+    pub fn painted(start: Pos2, size: f32, kind: EffectKind, brush: f32) -> Self {
+        let reach = Vec2::splat(brush / 2.0);
+        Self {
+            start: start - reach,
+            end: start + reach,
+            size,
+            kind,
+            outline: EffectOutline::Rect,
+            mask: vec![start],
+            brush,
+        }
+    }
+
+    /// Whether the effect covers the whole of its rect, with no brush path,
+    /// ellipse or lasso cutting it down.
+    pub fn covers_rect(&self) -> bool {
+        self.outline == EffectOutline::Rect && self.mask.is_empty()
+    }
+
+    /// Whether `pos` is inside an ellipse or lasso effect's outline. Always
+    /// false for rect and brush effects.
+    pub fn outline_contains(&self, pos: Pos2) -> bool {
+        match self.outline {
+            EffectOutline::Rect => false,
+            EffectOutline::Ellipse => {
+                let rect = Rect::from_two_pos(self.start, self.end);
+                let radius = rect.size() / 2.0;
+                if radius.x <= 0.0 || radius.y <= 0.0 {
+                    return false;
+                }
+                ((pos - rect.center()) / radius).length_sq() <= 1.0
+            }
+            // Even-odd crossings of a ray to the right of `pos`.
+            EffectOutline::Lasso => {
+                let mut inside = false;
+                let mut prev = match self.mask.last() {
+                    Some(last) => *last,
+                    None => return false,
+                };
+                for &point in &self.mask {
+                    if (point.y > pos.y) != (prev.y > pos.y) {
+                        let t = (pos.y - point.y) / (prev.y - point.y);
+                        let x = point.x + t * (prev.x - point.x);
+                        if pos.x < x {
+                            inside = !inside;
+                        }
+                    }
+                    prev = point;
+                }
+                inside
+            }
+        }
+    }
+
+    /// Extends the painted path to `pos`, skipping moves shorter than
+    /// `STROKE_MIN_DISTANCE` unless `force` is set.
+    pub fn push_mask_point(&mut self, pos: Pos2, force: bool) {
+        if let Some(last) = self.mask.last() {
+            let min = if force {
+                f32::EPSILON
+            } else {
+                STROKE_MIN_DISTANCE
+            };
+            if last.distance(pos) < min {
+                return;
+            }
+        }
+        self.mask.push(pos);
+        let reach = Vec2::splat(self.brush / 2.0);
+        self.start = self.start.min(pos - reach);
+        self.end = self.end.max(pos + reach);
+    }
+
+    /// Which pixels of the `width` x `height` area at `origin` the effect
+    /// covers, row by row; `None` when it covers its whole rect.
+    pub fn coverage(&self, origin: (u32, u32), width: u32, height: u32) -> Option<Vec<bool>> {
+        if self.covers_rect() {
+            return None;
+        }
+        if self.outline != EffectOutline::Rect {
+            let (x0, y0) = (origin.0 as f32 + 0.5, origin.1 as f32 + 0.5);
+            let covered = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| self.outline_contains(pos2(x0 + x as f32, y0 + y as f32)))
+                .collect();
+            return Some(covered);
+        }
+        let (width, height) = (width as usize, height as usize);
+        let mut covered = vec![false; width * height];
+        let radius = self.brush / 2.0;
+        let origin = vec2(origin.0 as f32, origin.1 as f32);
+        let last = self.mask[self.mask.len() - 1];
+        let pairs = self
+            .mask
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .chain(std::iter::once((last, last)));
+        // Only the pixels around each segment need testing.
+        for (a, b) in pairs {
+            let (a, b) = (a - origin, b - origin);
+            let area = Rect::from_two_pos(a, b).expand(radius);
+            let x0 = area.min.x.floor().clamp(0.0, width as f32) as usize;
+            let x1 = area.max.x.ceil().clamp(0.0, width as f32) as usize;
+            let y0 = area.min.y.floor().clamp(0.0, height as f32) as usize;
+            let y1 = area.max.y.ceil().clamp(0.0, height as f32) as usize;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let center = pos2(x as f32 + 0.5, y as f32 + 0.5);
+                    if segment_distance(center, a, b) <= radius {
+                        covered[y * width + x] = true;
+                    }
+                }
+            }
+        }
+        Some(covered)
+    }
+}
+
+/// The part of its rect an effect covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectOutline {
+    /// The whole rect, or the brush path in `mask` when there is one.
+    #[default]
+    Rect,
+    /// The ellipse inside the rect.
+    Ellipse,
+    /// The polygon through the points in `mask`.
+    Lasso,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectKind {
+    Pixelate,
+    Blur,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Shape {
+    Stroke(StrokeShape),
+    Line(LineShape),
+    Arrow(ArrowShape),
+    Rect(RectShape),
+    Circle(CircleShape),
+    CircleCount(CircleCountShape),
+    Text(TextShape),
+    Effect(EffectShape),
+}
+
+impl Shape {
+    /// The same shape moved by `offset`, for rendering into a sub-image.
+    pub fn translated(&self, offset: Vec2) -> Shape {
+        let mut shape = self.clone();
+        shape.translate(offset);
+        shape
+    }
+
+    pub fn translate(&mut self, offset: Vec2) {
+        match self {
+            Shape::Stroke(stroke) => {
+                for point in &mut stroke.points {
+                    *point += offset;
+                }
+                stroke.bounds = stroke.bounds.translate(offset);
+            }
+            Shape::Line(line) => {
+                line.start += offset;
+                line.end += offset;
+            }
+            Shape::Arrow(arrow) => {
+                arrow.start += offset;
+                arrow.end += offset;
+            }
+            Shape::Rect(rect) => {
+                rect.start += offset;
+                rect.end += offset;
+            }
+            Shape::Circle(circle) => {
+                circle.start += offset;
+                circle.end += offset;
+            }
+            Shape::CircleCount(counter) => {
+                counter.center += offset;
+                counter.pointer += offset;
+            }
+            Shape::Text(text) => text.pos += offset,
+            Shape::Effect(effect) => {
+                effect.start += offset;
+                effect.end += offset;
+                for point in &mut effect.mask {
+                    *point += offset;
+                }
+            }
+        }
+    }
+
+    /// The colour it is drawn in; effects have none.
+    pub fn color(&self) -> Option<Color32> {
+        match self {
+            Shape::Stroke(stroke) => Some(stroke.color),
+            Shape::Line(line) => Some(line.color),
+            Shape::Arrow(arrow) => Some(arrow.color),
+            Shape::Rect(rect) => Some(rect.color),
+            Shape::Circle(circle) => Some(circle.color),
+            Shape::CircleCount(counter) => Some(counter.color),
+            Shape::Text(text) => Some(text.color),
+            Shape::Effect(_) => None,
+        }
+    }
+
+    /// Gives the shape `color`, keeping its own opacity so markers stay
+    /// see-through. Effects have no colour.
+    pub fn recolor(&mut self, color: Color32) {
+        let current = match self {
+            Shape::Stroke(stroke) => &mut stroke.color,
+            Shape::Line(line) => &mut line.color,
+            Shape::Arrow(arrow) => &mut arrow.color,
+            Shape::Rect(rect) => &mut rect.color,
+            Shape::Circle(circle) => &mut circle.color,
+            Shape::CircleCount(counter) => &mut counter.color,
+            Shape::Text(text) => &mut text.color,
+            Shape::Effect(_) => return,
+        };
+        *current = Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), current.a());
+    }
+
+    /// Image-space area the shape can paint to, or `None` when it isn't
+    /// cheap to know and the shape should always be drawn.
+    pub fn bounds(&self) -> Option<Rect> {
+        let (rect, size) = match self {
+            Shape::Stroke(stroke) => (stroke.bounds, stroke.size),
+            Shape::Line(line) => (Rect::from_two_pos(line.start, line.end), line.size),
+            Shape::Rect(rect) => (Rect::from_two_pos(rect.start, rect.end), rect.size),
+            Shape::Circle(circle) => (Rect::from_two_pos(circle.start, circle.end), circle.size),
+            // Arrow heads reach well past the shaft.
+            Shape::Arrow(arrow) => (Rect::from_two_pos(arrow.start, arrow.end), arrow.size * 4.0),
+            Shape::Effect(effect) => (Rect::from_two_pos(effect.start, effect.end), 0.0),
+            Shape::CircleCount(_) | Shape::Text(_) => return None,
+        };
+        Some(rect.expand(size))
+    }
+}
+
+/// Distance from `pos` to the segment from `a` to `b`.
+pub fn segment_distance(pos: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_sq();
+    if len_sq <= f32::EPSILON {
+        return pos.distance(a);
+    }
+    let t = ((pos - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+    pos.distance(a + ab * t)
+}
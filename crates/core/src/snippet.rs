@@ -0,0 +1,71 @@
+//! Ready-made Markdown, HTML and reStructuredText snippets that show a saved
+//! capture or an uploaded one's URL, for pasting into documentation.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetFormat {
+    /// `![alt](target)`
+    #[default]
+    Markdown,
+    /// `<img src="target" alt="alt">`
+    Html,
+    /// `.. image:: target` with an `:alt:` option.
+    Rst,
+}
+
+impl SnippetFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "rst" => Some(Self::Rst),
+            _ => None,
+        }
+    }
+
+    /// The snippet showing `target`, a path or URL, with `alt` as its
+    /// alternative text.
+    pub fn render(self, target: &str, alt: &str) -> String {
+        match self {
+            Self::Markdown => {
+                let alt = alt.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+                // Angle brackets keep spaces and parentheses in paths working.
+                if target.contains([' ', '(', ')']) {
+                    format!("![{}](<{}>)", alt, target)
+                } else {
+                    format!("![{}]({})", alt, target)
+                }
+            }
+            Self::Html => format!(
+                "<img src=\"{}\" alt=\"{}\">",
+                escape_html(target),
+                escape_html(alt)
+            ),
+            Self::Rst => {
+                let alt = alt.replace('\n', " ");
+                format!(".. image:: {}\n   :alt: {}", target, alt)
+            }
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Fills in an alt text template: `{name}` is the file name without its
+/// extension, `{date}` and `{time}` when the snippet is made.
+pub fn alt_text(template: &str, target: &str) -> String {
+    let file = target.rsplit(['/', '\\']).next().unwrap_or(target);
+    let name = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    let now = chrono::Local::now();
+    template
+        .replace("{name}", name)
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H:%M").to_string())
+}
@@ -0,0 +1,53 @@
+//! The colour and size each editor tool was last used with, kept in the
+//! state directory so a tool starts the next session as it was left.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{paths, CaptureError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ToolStyle {
+    /// Premultiplied RGBA, as the editor holds colours.
+    pub color: [u8; 4],
+    pub size: f32,
+}
+
+/// Styles by tool name, such as `arrow` or `marker`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolStyles(BTreeMap<String, ToolStyle>);
+
+fn state_path() -> PathBuf {
+    paths::state_dir().join("tools.toml")
+}
+
+impl ToolStyles {
+    /// The saved styles; none when there are none yet or they can't be read.
+    pub fn load() -> Self {
+        std::fs::read_to_string(state_path())
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), CaptureError> {
+        let path = state_path();
+        let io_error = |err: std::io::Error| CaptureError::Io(format!("{}: {}", path.display(), err));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let text = toml::to_string(self).map_err(|e| CaptureError::Io(e.to_string()))?;
+        std::fs::write(&path, text).map_err(io_error)
+    }
+
+    pub fn get(&self, tool: &str) -> Option<ToolStyle> {
+        self.0.get(tool).copied()
+    }
+
+    /// Sets `tool`'s style; returns whether it changed.
+    pub fn set(&mut self, tool: &str, style: ToolStyle) -> bool {
+        self.0.insert(tool.to_string(), style) != Some(style)
+    }
+}
@@ -0,0 +1,97 @@
+//! The window that had focus when a capture was taken, as the compositor
+//! reports it. Hyprland, sway and niri are asked through their IPC tools;
+//! other desktops don't say, and captures there have no window.
+
+use serde_json::Value;
+
+use crate::sandbox::host_command;
+
+/// Longest title put into a file name, in characters.
+const FILE_NAME_TITLE_MAX: usize = 80;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowInfo {
+    pub title: String,
+    /// The Wayland app id, or the X11 class under XWayland.
+    pub app_id: String,
+}
+
+impl WindowInfo {
+    /// The focused window, if the running compositor can be asked for it.
+    pub fn focused() -> Option<Self> {
+        let env = |var: &str| std::env::var_os(var).is_some();
+        let info = if env("HYPRLAND_INSTANCE_SIGNATURE") {
+            let window = query("hyprctl", &["-j", "activewindow"])?;
+            Self::from_fields(&window, "title", "class")
+        } else if env("SWAYSOCK") {
+            let tree = query("swaymsg", &["-t", "get_tree", "-r"])?;
+            let window = focused_node(&tree)?;
+            let mut info = Self::from_fields(window, "name", "app_id")?;
+            if info.app_id.is_empty() {
+                info.app_id = window["window_properties"]["class"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+            }
+            Some(info)
+        } else if env("NIRI_SOCKET") {
+            let window = query("niri", &["msg", "-j", "focused-window"])?;
+            Self::from_fields(&window, "title", "app_id")
+        } else {
+            None
+        };
+        info.filter(|info| !info.title.is_empty() || !info.app_id.is_empty())
+    }
+
+    fn from_fields(window: &Value, title: &str, app_id: &str) -> Option<Self> {
+        let field = |key: &str| window[key].as_str().unwrap_or_default().to_string();
+        window.is_object().then(|| Self {
+            title: field(title),
+            app_id: field(app_id),
+        })
+    }
+
+    /// Title and app id on one line, for a caption.
+    pub fn caption(&self) -> String {
+        match (self.title.is_empty(), self.app_id.is_empty()) {
+            (false, false) => format!("{} — {}", self.title, self.app_id),
+            (false, true) => self.title.clone(),
+            _ => self.app_id.clone(),
+        }
+    }
+
+    /// The title cut short and stripped of characters file names can't
+    /// hold or shells trip over.
+    pub(crate) fn file_name_title(&self) -> String {
+        self.title
+            .chars()
+            .filter(|c| !c.is_control())
+            .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"') { '-' } else { c })
+            .take(FILE_NAME_TITLE_MAX)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+}
+
+/// The JSON `program` prints, or `None` when it isn't installed or fails.
+pub(crate) fn query(program: &str, args: &[&str]) -> Option<Value> {
+    let output = host_command(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// The focused window in a sway tree, searching tiled and floating nodes.
+fn focused_node(node: &Value) -> Option<&Value> {
+    let window = matches!(node["type"].as_str(), Some("con" | "floating_con"));
+    if window && node["focused"].as_bool() == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(focused_node)
+}
@@ -0,0 +1,99 @@
+//! Raster and effect kernels on synthetic 1080p and 4K captures.
+//!
+//! `cargo bench -p fireshot_gui --features bench`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use eframe::egui;
+use fireshot_gui::bench::{self, Scene};
+use image::{Rgba, RgbaImage};
+
+const SIZES: [(&str, u32, u32); 2] = [("1080p", 1920, 1080), ("4k", 3840, 2160)];
+
+/// A gradient with some noise, so blur and pixelate have detail to average.
+fn capture(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        let noise = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)) >> 24;
+        Rgba([
+            (x * 255 / width) as u8,
+            (y * 255 / height) as u8,
+            noise as u8,
+            255,
+        ])
+    })
+}
+
+/// The middle half of the capture, about as much as a typical effect covers.
+fn middle(width: u32, height: u32) -> egui::Rect {
+    let (w, h) = (width as f32, height as f32);
+    egui::Rect::from_min_max(egui::pos2(w * 0.25, h * 0.25), egui::pos2(w * 0.75, h * 0.75))
+}
+
+fn raster(c: &mut Criterion) {
+    let color = egui::Color32::from_rgb(220, 50, 47);
+    let mut group = c.benchmark_group("raster");
+    for (name, width, height) in SIZES {
+        let mut img = capture(width, height);
+        let (w, h) = (width as f32, height as f32);
+        group.bench_function(BenchmarkId::new("draw_line", name), |b| {
+            b.iter(|| {
+                bench::draw_line(
+                    &mut img,
+                    egui::pos2(0.0, 0.0),
+                    egui::pos2(w, h),
+                    color,
+                    black_box(8.0),
+                )
+            })
+        });
+        group.bench_function(BenchmarkId::new("fill_triangle", name), |b| {
+            b.iter(|| {
+                bench::fill_triangle(
+                    &mut img,
+                    egui::pos2(w * 0.5, h * 0.1),
+                    egui::pos2(w * 0.1, h * 0.9),
+                    egui::pos2(w * 0.9, h * 0.9),
+                    black_box(Rgba([220, 50, 47, 255])),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+fn effects(c: &mut Criterion) {
+    let mut group = c.benchmark_group("effects");
+    group.sample_size(20);
+    for (name, width, height) in SIZES {
+        let mut img = capture(width, height);
+        let rect = middle(width, height);
+        group.throughput(Throughput::Elements(u64::from(width * height / 4)));
+        group.bench_function(BenchmarkId::new("apply_blur", name), |b| {
+            b.iter(|| bench::apply_blur(&mut img, rect, black_box(12)))
+        });
+        group.bench_function(BenchmarkId::new("apply_pixelate", name), |b| {
+            b.iter(|| bench::apply_pixelate(&mut img, rect, black_box(16)))
+        });
+    }
+    group.finish();
+}
+
+fn export(c: &mut Criterion) {
+    let mut group = c.benchmark_group("export");
+    group.sample_size(20);
+    for (name, width, height) in SIZES {
+        let img = capture(width, height);
+        let rect = middle(width, height);
+        let scene = Scene::synthetic(width, height);
+        group.throughput(Throughput::Elements(u64::from(width * height)));
+        group.bench_function(BenchmarkId::new("crop_image", name), |b| {
+            b.iter(|| bench::crop_image(black_box(&img), rect))
+        });
+        group.bench_function(BenchmarkId::new("render_full_image", name), |b| {
+            b.iter(|| bench::render_full_image(black_box(&img), &scene))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(kernels, raster, effects, export);
+criterion_main!(kernels);
@@ -0,0 +1,160 @@
+//! Brightness, contrast, saturation and gamma for the capture, with a
+//! histogram of the result. They are applied to a copy of the capture that
+//! the editor shows and exports render from; the capture itself is kept, so
+//! they can be changed or reset at any time.
+
+use eframe::egui;
+use fireshot_core::tr;
+use image::RgbaImage;
+
+use crate::parallel::for_each_row_band;
+
+/// Size of the histogram drawn in the panel, in points.
+const HISTOGRAM_SIZE: egui::Vec2 = egui::vec2(200.0, 56.0);
+/// Pixels sampled for the histogram at most; larger captures skip rows.
+const HISTOGRAM_MAX_SAMPLES: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Adjustments {
+    /// -1 (black) to 1 (white), 0 unchanged.
+    pub(crate) brightness: f32,
+    /// -1 (flat grey) to 1 (hard), 0 unchanged.
+    pub(crate) contrast: f32,
+    /// -1 (greyscale) to 1 (twice as saturated), 0 unchanged.
+    pub(crate) saturation: f32,
+    /// Above 1 lifts the shadows, below darkens them; 1 unchanged.
+    pub(crate) gamma: f32,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl Adjustments {
+    pub(crate) fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `base` with the adjustments applied; alpha is left alone.
+    pub(crate) fn apply(&self, base: &RgbaImage) -> RgbaImage {
+        let lut = self.tone_curve();
+        let saturation = 1.0 + self.saturation;
+        let mut adjusted = base.clone();
+        let row_bytes = adjusted.width() as usize * 4;
+        for_each_row_band(adjusted.as_mut(), row_bytes, 1, |_, band| {
+            for px in band.chunks_exact_mut(4) {
+                let [r, g, b] = [lut[px[0] as usize], lut[px[1] as usize], lut[px[2] as usize]];
+                if saturation == 1.0 {
+                    px[..3].copy_from_slice(&[r, g, b]);
+                    continue;
+                }
+                let luma = luma(r, g, b);
+                for (out, value) in px[..3].iter_mut().zip([r, g, b]) {
+                    let value = luma + (value as f32 - luma) * saturation;
+                    *out = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        });
+        adjusted
+    }
+
+    /// Brightness, contrast and gamma for one channel value, in that order.
+    fn tone_curve(&self) -> [u8; 256] {
+        let contrast = if self.contrast >= 0.0 {
+            1.0 / (1.0 - self.contrast.min(0.99))
+        } else {
+            1.0 + self.contrast
+        };
+        let gamma = self.gamma.max(0.01);
+        std::array::from_fn(|i| {
+            let value = i as f32 / 255.0 + self.brightness;
+            let value = ((value - 0.5) * contrast + 0.5).clamp(0.0, 1.0);
+            (value.powf(1.0 / gamma) * 255.0).round() as u8
+        })
+    }
+}
+
+fn luma(r: u8, g: u8, b: u8) -> f32 {
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32
+}
+
+/// How many pixels of each brightness there are.
+pub(crate) struct Histogram([u32; 256]);
+
+impl Histogram {
+    pub(crate) fn of(image: &RgbaImage) -> Self {
+        let mut bins = [0u32; 256];
+        let pixels = image.width() as usize * image.height() as usize;
+        let row_step = pixels.div_ceil(HISTOGRAM_MAX_SAMPLES).max(1);
+        for y in (0..image.height()).step_by(row_step) {
+            for x in 0..image.width() {
+                let [r, g, b, _] = image.get_pixel(x, y).0;
+                bins[luma(r, g, b).round().min(255.0) as usize] += 1;
+            }
+        }
+        Self(bins)
+    }
+
+    fn paint(&self, ui: &mut egui::Ui) {
+        let (rect, _) = ui.allocate_exact_size(HISTOGRAM_SIZE, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        // Square roots keep a few huge bins, such as a flat background,
+        // from flattening the rest.
+        let peak = self.0.iter().map(|&count| (count as f32).sqrt()).fold(1.0, f32::max);
+        let width = rect.width() / 256.0;
+        let color = ui.visuals().text_color();
+        for (i, &count) in self.0.iter().enumerate() {
+            let height = (count as f32).sqrt() / peak * rect.height();
+            let x = rect.left() + i as f32 * width;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(x, rect.bottom() - height),
+                egui::pos2(x + width, rect.bottom()),
+            );
+            painter.rect_filled(bar, 0.0, color);
+        }
+    }
+}
+
+/// The panel's sliders under the histogram, in a header that collapses
+/// them. Returns whether the adjustments changed.
+pub(crate) fn show(ui: &mut egui::Ui, adjustments: &mut Adjustments, histogram: &Histogram) -> bool {
+    let before = *adjustments;
+    egui::CollapsingHeader::new(tr!("adjust-title"))
+        .id_source("adjustments")
+        .default_open(false)
+        .show(ui, |ui| {
+            histogram.paint(ui);
+            ui.add(
+                egui::Slider::new(&mut adjustments.brightness, -1.0..=1.0)
+                    .text(tr!("adjust-brightness")),
+            );
+            ui.add(
+                egui::Slider::new(&mut adjustments.contrast, -1.0..=1.0)
+                    .text(tr!("adjust-contrast")),
+            );
+            ui.add(
+                egui::Slider::new(&mut adjustments.saturation, -1.0..=1.0)
+                    .text(tr!("adjust-saturation")),
+            );
+            ui.add(
+                egui::Slider::new(&mut adjustments.gamma, 0.2..=5.0)
+                    .logarithmic(true)
+                    .text(tr!("adjust-gamma")),
+            );
+            if ui
+                .add_enabled(!adjustments.is_neutral(), egui::Button::new(tr!("adjust-reset")))
+                .clicked()
+            {
+                *adjustments = Adjustments::default();
+            }
+        });
+    *adjustments != before
+}
@@ -1,82 +1,285 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
 use eframe::egui;
 use egui_file_dialog::{DialogState, FileDialog};
-use fireshot_core::CaptureError;
+use fireshot_core::{profile, tr, CaptureError, ExportResult, RecordRegion};
+use fireshot_core::shapes::{
+    ArrowShape, CircleCountShape, CircleShape, EffectKind, EffectOutline, EffectShape, LineShape,
+    RectShape, Shape, StrokeShape, TextShape,
+};
+use fireshot_core::config::{
+    AfterExport, Config, Corner, RegionPreset, SaveConfig, ShareConfig, TimestampConfig, ToolbarButton,
+    ToolbarConfig,
+};
+use fireshot_core::tool_styles::{ToolStyle, ToolStyles};
+use fireshot_core::window::WindowInfo;
 use image::{DynamicImage, RgbaImage};
 
-use crate::clipboard::{encode_bmp, encode_png, is_wayland, try_wl_copy_png, try_xclip};
+use crate::control::{
+    Accept, EditorControl, PostRender, RecaptureRequest, RecordRequest, SaveDialog, ToolDefaults,
+    UndoLimit, ViewerOptions,
+};
 use crate::draw::{
     arrow_head_points, circlecount_bubble_size, circlecount_contrast_colors, draw_arrow_head,
     draw_arrow_head_image, draw_circle_count_image, draw_circle_count_preview, draw_ellipse,
-    draw_handles, draw_line, draw_selection_hud, ellipse_points, paint_tool_icon, with_alpha,
-    CIRCLECOUNT_PADDING,
+    draw_handles, draw_line, draw_selection_hud, ellipse_points, paint_checkerboard,
+    paint_flash, paint_tool_icon, with_alpha, Backdrop, CIRCLECOUNT_PADDING, FLASH_SECS,
+};
+use crate::effects::{effect_strength, render_effect_region};
+use crate::export::{
+    ExportJob, ExportKind, ExportSource, Frame, Prerender, RenderKey, PRERENDER_IDLE_SECS,
+};
+use crate::geometry::{display_rect, dock_for_drop, dock_tool_buttons, hit_corner, normalize_rect, ViewTransform};
+use crate::layer_shell;
+use crate::adjust::{self, Adjustments, Histogram};
+use crate::arrange::{ArrangeDrag, Arrangement};
+use crate::layers::{self, LayerAction};
+use crate::recovery::{Orphan, Recovery};
+use crate::redact::{RedactKind, RedactProposal, REDACT_BLUR_DIVISOR};
+use crate::image_ops::{
+    backdrop_image, checker_image, display_image, has_transparency, rect_to_u32,
+    DISPLAY_MAX_SIDE,
 };
-use crate::effects::{apply_blur, apply_blur_full, apply_pixelate, apply_pixelate_full};
-use crate::geometry::{hit_corner, normalize_rect, selection_screen_rect, layout_tool_buttons};
-use crate::image_ops::{crop_image, crop_image_exact, rect_to_u32};
 use crate::shapes::{
-    EffectKind, EffectPreview, EffectShape, SelectionCorner, SelectionDrag, SelectionRect, Shape,
-    TextInput, TextShape, Tool, ToolAction, ToolIcon, CircleCountShape, FILE_DIALOG_SIZE,
+    AnnotationCache, EffectArea, EffectPreview, PenState, SelectionCorner, SelectionDrag,
+    SelectionRect,
+    PreviewKey, TextInput, Tool, ToolAction, ToolIcon,
+    CAPTION_MARGIN, CAPTION_SIZE, EFFECT_BRUSH_PER_SIZE, EFFECT_PREVIEW_DEBOUNCE_SECS, ERASER_REACH, FILE_DIALOG_SIZE, KEYBOARD_STEP, MAX_ZOOM, PREVIEW_TILE,
+    button_action, shape_area, shape_bytes, shape_hit, shape_is_degenerate, MIN_DRAG_DISTANCE,
 };
-use crate::text::draw_text_bitmap;
+use crate::share::{LanShare, ShareEnd, SHARE_QR_SIZE};
+use crate::snap::{detect_window, snap_edge, Axis, SNAP_DISTANCE};
+use crate::stats::FrameStats;
+use crate::status::{StatusLink, StatusLog};
+use crate::tabs::{Tab, TabState};
+use crate::text::{draw_text_bitmap, text_bitmap_size};
+use crate::theme::{Chrome, Theme};
 
 pub(crate) struct EditorApp {
-    base_image: RgbaImage,
-    texture_image: egui::ColorImage,
+    base_image: Arc<RgbaImage>,
     texture: Option<egui::TextureHandle>,
+    /// The capture blurred, behind the chrome when the backdrop is frosted.
+    backdrop_texture: Option<egui::TextureHandle>,
+    /// Some of the capture is see-through and shown over a checkerboard.
+    transparent: bool,
+    checker_texture: Option<egui::TextureHandle>,
     tool: Tool,
     last_draw_tool: Tool,
     color: egui::Color32,
     size: f32,
+    /// What Pixelate and Blur cover: a rect, an ellipse, a lasso or a brush.
+    effect_area: EffectArea,
+    /// Each drawing tool's colour and size, kept across sessions.
+    tool_styles: ToolStyles,
+    /// The tool `color` and `size` belong to, until they are stored in
+    /// `tool_styles` on a switch.
+    styled_tool: Tool,
     shapes: Vec<Shape>,
+    /// Areas auto-redact found that haven't been accepted or dismissed yet.
+    redact_proposals: Vec<RedactProposal>,
     active_shape: Option<Shape>,
     redo_stack: Vec<Shape>,
+    undo_limit: UndoLimit,
+    /// Shapes below this index are past `undo_limit` and stay drawn.
+    undo_floor: usize,
+    /// Shapes picked with the arrange tool, and groups of them.
+    arrange: Arrangement,
     selection: Option<SelectionRect>,
+    /// Earlier selections kept with Ctrl+drag, which Export all saves along
+    /// with the current one.
+    extra_selections: Vec<egui::Rect>,
     selection_drag: Option<SelectionDrag>,
-    status: Option<String>,
+    status: StatusLog,
     last_image_rect: Option<egui::Rect>,
-    last_pixels_per_point: f32,
+    last_view: ViewTransform,
+    /// Pinch zoom over the fitted capture; 1 shows it whole.
+    zoom: f32,
+    /// Offset of the zoomed capture's center from where it fits.
+    pan: egui::Vec2,
+    context_menu_rect: Option<egui::Rect>,
+    /// Fingers on the screen, for the center of a pinch.
+    touches: Vec<(egui::TouchId, egui::Pos2)>,
     tool_button_rects: Vec<egui::Rect>,
+    toolbar: ToolbarConfig,
+    record: Option<RecordRequest>,
+    recapture: Option<RecaptureRequest>,
+    /// Delay picked for Recapture, in seconds.
+    recapture_secs: u32,
+    post_render: Option<PostRender>,
     tool_controls_rect: Option<egui::Rect>,
     text_input: Option<TextInput>,
     text_editor_rect: Option<egui::Rect>,
     shapes_version: u64,
     effect_previews: Vec<EffectPreview>,
+    /// Key of the dragged effect's preview and when it last changed.
+    live_effect_change: Option<(PreviewKey, f64)>,
+    annotation_cache: Option<AnnotationCache>,
     file_dialog: FileDialog,
     file_dialog_open: bool,
+    /// The desktop's file chooser, used instead of `file_dialog` when set.
+    save_dialog: Option<SaveDialog>,
+    /// Where the desktop's file chooser, while open, will say to save.
+    portal_save: Option<Receiver<Result<Option<PathBuf>, CaptureError>>>,
+    exports: Rc<RefCell<Vec<ExportResult>>>,
+    pending_export: Option<ExportJob>,
+    prerender: Option<Prerender>,
+    /// When the current render key was first seen, for `prerender_when_idle`.
+    idle_since: Option<(RenderKey, f64)>,
+    theme: Theme,
+    icc_profile: Option<Arc<[u8]>>,
+    /// The shown image is a capture of the output the editor covers; its
+    /// PNGs are tagged with that output's scale.
+    from_screen: bool,
+    /// The window the capture was taken from, named in file names and
+    /// saved PNGs.
+    window: Option<WindowInfo>,
+    /// When the capture was taken, or the image opened, for timestamps.
+    captured_at: SystemTime,
+    /// The Insert timestamp button's format and corner.
+    timestamp: TimestampConfig,
+    recovery: Option<Recovery>,
+    /// A crashed session offered for restoring.
+    orphan: Option<Orphan>,
+    recovery_prompt_rect: Option<egui::Rect>,
+    presets: Vec<RegionPreset>,
+    /// Name typed so far while saving the selection as a preset.
+    preset_name: Option<String>,
+    preset_prompt_rect: Option<egui::Rect>,
+    share: ShareConfig,
+    save: SaveConfig,
+    after_export: AfterExport,
+    hide_ui_while_drawing: bool,
+    /// Export to start on the first frame.
+    accept: Option<Accept>,
+    /// Nothing but the selection is shown, and picking an area copies it.
+    copy_region: bool,
+    /// The capture flash is still fading out.
+    flash: bool,
+    /// When the flash started, once it has.
+    flash_started: Option<f64>,
+    /// The panels and selection size are out of the way this frame.
+    chrome_hidden: bool,
+    /// The capture being served by Share on LAN, while its QR code is shown.
+    lan_share: Option<LanShare>,
+    share_rect: Option<egui::Rect>,
+    /// Every open capture in strip order; the shown one's state is in the
+    /// fields above.
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    tab_strip_rect: Option<egui::Rect>,
+    layers_rect: Option<egui::Rect>,
+    /// Brightness and the like for the shown capture; see `adjusted_base`.
+    adjustments: Adjustments,
+    adjusted_base: Option<Arc<RgbaImage>>,
+    histogram: Option<Histogram>,
+    /// Hands over images opened in new tabs from outside the editor.
+    control: EditorControl,
+    pen: PenState,
+    /// When `run_viewer` was called, until the first frame is recorded.
+    opened_at: Option<Instant>,
+    /// Timings for the F12 overlay.
+    stats: FrameStats,
 }
 
 impl EditorApp {
-    fn new(image: DynamicImage) -> Self {
-        let rgba = image.to_rgba8();
-        let size = [rgba.width() as usize, rgba.height() as usize];
-        let pixels = rgba.clone().into_raw();
-        let image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
+    fn new(
+        image: DynamicImage,
+        exports: Rc<RefCell<Vec<ExportResult>>>,
+        theme: Theme,
+        defaults: ToolDefaults,
+    ) -> Self {
         Self {
-            base_image: rgba,
-            texture_image: image,
+            base_image: Arc::new(image.to_rgba8()),
             texture: None,
-            tool: Tool::Select,
+            backdrop_texture: None,
+            transparent: false,
+            checker_texture: None,
+            tool: defaults.tool,
             last_draw_tool: Tool::Pencil,
-            color: egui::Color32::from_rgb(255, 0, 0),
-            size: 3.0,
+            color: defaults.color,
+            size: defaults.size,
+            effect_area: EffectArea::default(),
+            tool_styles: ToolStyles::default(),
+            styled_tool: Tool::Select,
             shapes: Vec::new(),
+            redact_proposals: Vec::new(),
             active_shape: None,
             redo_stack: Vec::new(),
+            undo_limit: UndoLimit::default(),
+            undo_floor: 0,
             selection: None,
+            extra_selections: Vec::new(),
+            arrange: Arrangement::default(),
             selection_drag: None,
-            status: None,
+            status: StatusLog::default(),
             last_image_rect: None,
-            last_pixels_per_point: 1.0,
+            last_view: ViewTransform::default(),
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            context_menu_rect: None,
+            touches: Vec::new(),
             tool_button_rects: Vec::new(),
+            toolbar: ToolbarConfig::default(),
+            record: None,
+            recapture: None,
+            recapture_secs: 0,
+            post_render: None,
             tool_controls_rect: None,
             text_input: None,
             text_editor_rect: None,
             shapes_version: 0,
             effect_previews: Vec::new(),
+            live_effect_change: None,
+            annotation_cache: None,
             file_dialog: FileDialog::new()
                 .default_file_name("screenshot.png")
                 .default_size(FILE_DIALOG_SIZE),
             file_dialog_open: false,
+            save_dialog: None,
+            portal_save: None,
+            exports,
+            pending_export: None,
+            prerender: None,
+            idle_since: None,
+            theme,
+            icc_profile: None,
+            from_screen: false,
+            window: None,
+            captured_at: SystemTime::now(),
+            timestamp: TimestampConfig::default(),
+            recovery: None,
+            orphan: None,
+            recovery_prompt_rect: None,
+            presets: Vec::new(),
+            preset_name: None,
+            preset_prompt_rect: None,
+            share: ShareConfig::default(),
+            save: SaveConfig::default(),
+            after_export: AfterExport::default(),
+            hide_ui_while_drawing: false,
+            accept: None,
+            copy_region: false,
+            flash: false,
+            flash_started: None,
+            chrome_hidden: false,
+            lan_share: None,
+            share_rect: None,
+            tabs: vec![Tab::shown(tr!("tab-capture").to_string())],
+            active_tab: 0,
+            tab_strip_rect: None,
+            layers_rect: None,
+            adjustments: Adjustments::default(),
+            adjusted_base: None,
+            histogram: None,
+            control: EditorControl::default(),
+            pen: PenState::default(),
+            opened_at: None,
+            stats: FrameStats::default(),
         }
     }
 
@@ -84,11 +287,31 @@ impl EditorApp {
         egui::vec2(self.base_image.width() as f32, self.base_image.height() as f32)
     }
 
+    /// The blur to paint behind chrome, when the theme asks for one.
+    fn backdrop(&self) -> Option<Backdrop> {
+        let texture = self.backdrop_texture.as_ref()?;
+        if !self.theme.chrome().frosted {
+            return None;
+        }
+        let image = egui::Rect::from_min_size(egui::Pos2::ZERO, self.image_size());
+        Some(Backdrop {
+            texture: texture.id(),
+            image_rect: self.last_view.rect_to_screen(image),
+        })
+    }
+
     fn handle_input(&mut self, response: &egui::Response) {
         if self.file_dialog_open {
             return;
         }
-        let scale = response.ctx.pixels_per_point();
+        // Two fingers pan and zoom, and a long press opens the context
+        // menu; neither should leave a shape behind.
+        if response.ctx.multi_touch().is_some() || response.long_touched() {
+            self.cancel_drag();
+            return;
+        }
+        let view = ViewTransform::new(response.rect, self.image_size());
+        let scale = view.scale.x;
         let pointer = response.ctx.input(|i| i.pointer.clone());
         let Some(pointer_pos) = pointer.hover_pos() else {
             return;
@@ -106,17 +329,47 @@ impl EditorApp {
             return;
         }
 
-        let img_pos_vec = (pointer_pos - response.rect.min) * scale;
-        let img_pos = egui::pos2(img_pos_vec.x, img_pos_vec.y);
+        let img_pos = view.to_image(pointer_pos);
         let img_pos = egui::pos2(
             img_pos.x.clamp(0.0, self.image_size().x),
             img_pos.y.clamp(0.0, self.image_size().y),
         );
 
+        if let Some(index) = self.redact_proposals.iter().position(|p| p.rect.contains(img_pos)) {
+            response.ctx.output_mut(|o| o.cursor_icon = egui::CursorIcon::PointingHand);
+            if pointer.primary_pressed() {
+                self.accept_proposal(index);
+                return;
+            }
+        }
+
+        if self.pen.eraser {
+            // The stylus' eraser end removes shapes whatever the tool.
+            if pointer.primary_down() {
+                self.erase_at(img_pos, ERASER_REACH * scale);
+            }
+            return;
+        }
+
         if self.tool == Tool::Select {
             let icon = self.cursor_icon_for_selection(&pointer, img_pos, scale);
             response.ctx.output_mut(|o| o.cursor_icon = icon);
-            self.handle_selection_input(&pointer, img_pos, scale);
+            // Alt drags the selection freely, without snapping to edges, and
+            // Ctrl keeps the current one and adds another.
+            let (snap, add) = response.ctx.input(|i| (!i.modifiers.alt, i.modifiers.command));
+            self.handle_selection_input(&pointer, img_pos, scale, snap, add);
+            let picked = self.selection.is_some() && self.selection_drag.is_none();
+            if self.copy_region && picked && pointer.primary_released() {
+                self.accept = Some(Accept::Copy);
+                response.ctx.request_repaint();
+            }
+            return;
+        }
+
+        if self.tool == Tool::Arrange {
+            // Shift adds to what is picked.
+            let add = response.ctx.input(|i| i.modifiers.shift);
+            self.handle_arrange_input(response, &pointer, img_pos, scale, add);
             return;
         }
 
@@ -130,18 +383,20 @@ impl EditorApp {
 
         if pointer.primary_pressed() {
             self.active_shape = Some(match self.tool {
-                Tool::Select => return,
-                Tool::Pencil => Shape::Stroke(crate::shapes::StrokeShape {
-                    points: vec![img_pos],
-                    color: self.color,
-                    size: self.size,
-                }),
-                Tool::Marker => Shape::Stroke(crate::shapes::StrokeShape {
-                    points: vec![img_pos],
-                    color: with_alpha(self.color, 120),
-                    size: self.size.max(6.0),
-                }),
-                Tool::MarkerLine => Shape::Line(crate::shapes::LineShape {
+                Tool::Select | Tool::Arrange => return,
+                Tool::Pencil => Shape::Stroke(StrokeShape::new(
+                    img_pos,
+                    self.color,
+                    self.size,
+                    self.pen.pressure,
+                )),
+                Tool::Marker => Shape::Stroke(StrokeShape::new(
+                    img_pos,
+                    with_alpha(self.color, 120),
+                    self.size.max(6.0),
+                    self.pen.pressure,
+                )),
+                Tool::MarkerLine => Shape::Line(LineShape {
                     start: img_pos,
                     end: img_pos,
                     color: with_alpha(self.color, 120),
@@ -154,42 +409,46 @@ impl EditorApp {
                     size: self.size,
                     count: self.next_circle_count(),
                 }),
-                Tool::Line => Shape::Line(crate::shapes::LineShape {
+                Tool::Line => Shape::Line(LineShape {
                     start: img_pos,
                     end: img_pos,
                     color: self.color,
                     size: self.size,
                 }),
-                Tool::Arrow => Shape::Arrow(crate::shapes::ArrowShape {
+                Tool::Arrow => Shape::Arrow(ArrowShape {
                     start: img_pos,
                     end: img_pos,
                     color: self.color,
                     size: self.size,
                 }),
-                Tool::Rect => Shape::Rect(crate::shapes::RectShape {
+                Tool::Rect => Shape::Rect(RectShape {
                     start: img_pos,
                     end: img_pos,
                     color: self.color,
                     size: self.size,
                 }),
-                Tool::Circle => Shape::Circle(crate::shapes::CircleShape {
+                Tool::Circle => Shape::Circle(CircleShape {
                     start: img_pos,
                     end: img_pos,
                     color: self.color,
                     size: self.size,
                 }),
-                Tool::Pixelate => Shape::Effect(EffectShape {
-                    start: img_pos,
-                    end: img_pos,
-                    size: self.size,
-                    kind: EffectKind::Pixelate,
-                }),
-                Tool::Blur => Shape::Effect(EffectShape {
-                    start: img_pos,
-                    end: img_pos,
-                    size: self.size,
-                    kind: EffectKind::Blur,
-                }),
+                tool @ (Tool::Pixelate | Tool::Blur) => {
+                    let kind = if tool == Tool::Blur {
+                        EffectKind::Blur
+                    } else {
+                        EffectKind::Pixelate
+                    };
+                    Shape::Effect(match self.effect_area {
+                        EffectArea::Rect => EffectShape::new(img_pos, self.size, kind),
+                        EffectArea::Ellipse => EffectShape::ellipse(img_pos, self.size, kind),
+                        EffectArea::Lasso => EffectShape::lasso(img_pos, self.size, kind),
+                        EffectArea::Brush => {
+                            let brush = self.size * EFFECT_BRUSH_PER_SIZE;
+                            EffectShape::painted(img_pos, self.size, kind, brush)
+                        }
+                    })
+                }
                 Tool::Text => {
                     self.text_input = Some(TextInput {
                         pos: img_pos,
@@ -202,7 +461,7 @@ impl EditorApp {
             if let Some(active) = &mut self.active_shape {
                 match active {
                     Shape::Stroke(stroke) => {
-                        stroke.points.push(img_pos);
+                        stroke.push_point(img_pos, self.pen.pressure, false);
                     }
                     Shape::Line(line) => {
                         line.end = img_pos;
@@ -219,6 +478,9 @@ impl EditorApp {
                     Shape::CircleCount(counter) => {
                         counter.pointer = img_pos;
                     }
+                    Shape::Effect(effect) if !effect.mask.is_empty() => {
+                        effect.push_mask_point(img_pos, false);
+                    }
                     Shape::Effect(effect) => {
                         effect.end = img_pos;
                     }
@@ -226,8 +488,17 @@ impl EditorApp {
                 }
             }
         } else if pointer.primary_released() {
-            if let Some(shape) = self.active_shape.take() {
-                self.push_shape(shape);
+            if let Some(mut shape) = self.active_shape.take() {
+                match &mut shape {
+                    Shape::Stroke(stroke) => stroke.push_point(img_pos, self.pen.pressure, true),
+                    Shape::Effect(effect) if !effect.mask.is_empty() => {
+                        effect.push_mask_point(img_pos, true);
+                    }
+                    _ => {}
+                }
+                if !shape_is_degenerate(&shape, MIN_DRAG_DISTANCE * scale) {
+                    self.push_shape(shape);
+                }
             }
         }
     }
@@ -237,12 +508,23 @@ impl EditorApp {
         pointer: &egui::PointerState,
         img_pos: egui::Pos2,
         scale: f32,
+        snap: bool,
+        add: bool,
     ) {
+        // Selection edges sit on whole capture pixels, so what is outlined
+        // on screen is exactly what gets cropped.
+        let img_pos = img_pos.round();
         let handle_radius = 6.0 * scale;
         let image_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, self.image_size());
 
         if pointer.primary_pressed() {
-            if let Some(sel) = self.selection {
+            if let Some(sel) = self.selection.filter(|_| add) {
+                self.extra_selections.push(sel.rect);
+                self.selection_drag = Some(SelectionDrag::Adding { start: img_pos });
+                self.selection = Some(SelectionRect {
+                    rect: egui::Rect::from_two_pos(img_pos, img_pos),
+                });
+            } else if let Some(sel) = self.selection {
                 if let Some(corner) = hit_corner(sel.rect, img_pos, handle_radius) {
                     self.selection_drag = Some(SelectionDrag::Resizing { corner });
                 } else if sel.rect.contains(img_pos) {
@@ -263,8 +545,12 @@ impl EditorApp {
         } else if pointer.primary_down() {
             if let Some(drag) = self.selection_drag {
                 match drag {
-                    SelectionDrag::Creating { start } => {
-                        let rect = egui::Rect::from_two_pos(start, img_pos);
+                    SelectionDrag::Creating { start } | SelectionDrag::Adding { start } => {
+                        let mut rect = egui::Rect::from_two_pos(start, img_pos);
+                        if snap {
+                            let corner = self.snap_corner(img_pos, rect, scale);
+                            rect = egui::Rect::from_two_pos(start, corner);
+                        }
                         self.selection = Some(SelectionRect { rect: rect.intersect(image_rect) });
                     }
                     SelectionDrag::Moving { offset } => {
@@ -282,6 +568,11 @@ impl EditorApp {
                     SelectionDrag::Resizing { corner } => {
                         if let Some(sel) = self.selection {
                             let mut rect = sel.rect;
+                            let img_pos = if snap {
+                                self.snap_corner(img_pos, rect, scale)
+                            } else {
+                                img_pos
+                            };
                             match corner {
                                 SelectionCorner::TopLeft => {
                                     rect.min = img_pos;
@@ -307,15 +598,102 @@ impl EditorApp {
                 }
             }
         } else if pointer.primary_released() {
-            self.selection_drag = None;
-            if let Some(sel) = self.selection {
+            let drag = self.selection_drag.take();
+            let clicked_at = match drag {
+                Some(SelectionDrag::Creating { start } | SelectionDrag::Adding { start })
+                    if pointer.primary_clicked() =>
+                {
+                    Some(start)
+                }
+                _ => None,
+            };
+            if let Some(start) = clicked_at {
+                // A click without a drag selects the window under it.
+                self.selection =
+                    detect_window(&self.base_image, start).map(|rect| SelectionRect { rect });
+            } else if let Some(sel) = self.selection {
                 if sel.rect.width() < 1.0 || sel.rect.height() < 1.0 {
                     self.selection = None;
                 }
             }
+            // Adding nothing gives the kept selection back.
+            if matches!(drag, Some(SelectionDrag::Adding { .. })) && self.selection.is_none() {
+                self.selection = self.extra_selections.pop().map(|rect| SelectionRect { rect });
+            }
+        }
+    }
+
+    /// Picks shapes with a click or a band dragged round them, and drags
+    /// the picked ones along.
+    fn handle_arrange_input(
+        &mut self,
+        response: &egui::Response,
+        pointer: &egui::PointerState,
+        img_pos: egui::Pos2,
+        scale: f32,
+        add: bool,
+    ) {
+        let hit = self.topmost_at(img_pos, ERASER_REACH * scale);
+        if hit.is_some() || matches!(self.arrange.drag, Some(ArrangeDrag::Moving { .. })) {
+            response.ctx.output_mut(|o| o.cursor_icon = egui::CursorIcon::Move);
+        }
+
+        if pointer.primary_pressed() {
+            self.arrange.drag = Some(match hit {
+                Some(index) => {
+                    if !self.arrange.is_picked(index) {
+                        self.arrange.pick([index], add);
+                    }
+                    ArrangeDrag::Moving { last: img_pos }
+                }
+                None => {
+                    if !add {
+                        self.arrange.picked.clear();
+                    }
+                    ArrangeDrag::Band {
+                        start: img_pos,
+                        end: img_pos,
+                    }
+                }
+            });
+        } else if pointer.primary_down() {
+            match self.arrange.drag {
+                Some(ArrangeDrag::Band { start, .. }) => {
+                    self.arrange.drag = Some(ArrangeDrag::Band { start, end: img_pos });
+                }
+                Some(ArrangeDrag::Moving { last }) => {
+                    self.arrange.drag = Some(ArrangeDrag::Moving { last: img_pos });
+                    self.move_picked(img_pos - last);
+                }
+                None => {}
+            }
+        } else if pointer.primary_released() {
+            if let Some(ArrangeDrag::Band { start, end }) = self.arrange.drag.take() {
+                let band = egui::Rect::from_two_pos(start, end);
+                let touched: Vec<usize> = self
+                    .shapes
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, shape)| {
+                        !self.arrange.is_hidden(*index) && shape_area(shape).intersects(band)
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+                self.arrange.pick(touched, true);
+            }
         }
     }
 
+    /// Pulls `pos`, the dragged corner of `rect`, onto edges in the capture
+    /// that run along the sides meeting at it.
+    fn snap_corner(&self, pos: egui::Pos2, rect: egui::Rect, scale: f32) -> egui::Pos2 {
+        let radius = SNAP_DISTANCE * scale;
+        egui::pos2(
+            snap_edge(&self.base_image, Axis::X, pos.x, (rect.min.y, rect.max.y), radius),
+            snap_edge(&self.base_image, Axis::Y, pos.y, (rect.min.x, rect.max.x), radius),
+        )
+    }
+
     fn cursor_icon_for_selection(
         &self,
         pointer: &egui::PointerState,
@@ -333,7 +711,9 @@ impl EditorApp {
                         egui::CursorIcon::ResizeNeSw
                     }
                 },
-                SelectionDrag::Creating { .. } => egui::CursorIcon::Crosshair,
+                SelectionDrag::Creating { .. } | SelectionDrag::Adding { .. } => {
+                    egui::CursorIcon::Crosshair
+                }
             };
         }
 
@@ -374,27 +754,84 @@ impl EditorApp {
                 return true;
             }
         }
+        if let Some(rect) = self.recovery_prompt_rect {
+            if rect.contains(pos) {
+                return true;
+            }
+        }
+        if let Some(rect) = self.preset_prompt_rect {
+            if rect.contains(pos) {
+                return true;
+            }
+        }
+        if let Some(rect) = self.context_menu_rect {
+            if rect.contains(pos) {
+                return true;
+            }
+        }
+        if let Some(rect) = self.share_rect {
+            if rect.contains(pos) {
+                return true;
+            }
+        }
+        if let Some(rect) = self.tab_strip_rect {
+            if rect.contains(pos) {
+                return true;
+            }
+        }
+        if let Some(rect) = self.layers_rect {
+            if rect.contains(pos) {
+                return true;
+            }
+        }
         false
     }
 
+    /// Outlines the selections kept for Export all and numbers every region
+    /// in the order it is saved.
+    fn draw_extra_selections(&self, painter: &egui::Painter, view: &ViewTransform, chrome: &Chrome) {
+        if self.extra_selections.is_empty() {
+            return;
+        }
+        let current = self.selection.map(|sel| sel.rect);
+        let font_id = egui::FontId::proportional(12.0);
+        for (i, rect) in self.extra_selections.iter().chain(current.as_ref()).enumerate() {
+            let kept = i < self.extra_selections.len();
+            let rect = view.rect_to_screen(*rect);
+            if kept {
+                if let Some(halo) = chrome.outline_halo {
+                    painter.rect_stroke(rect, 0.0, egui::Stroke::new(chrome.outline_width + 2.0, halo));
+                }
+                painter.rect_stroke(rect, 0.0, egui::Stroke::new(chrome.outline_width, chrome.outline));
+            }
+            let label = painter.layout_no_wrap((i + 1).to_string(), font_id.clone(), chrome.hud_text);
+            let badge = egui::Rect::from_min_size(
+                rect.right_top() + egui::vec2(-label.size().x - 18.0, 6.0),
+                label.size() + egui::vec2(12.0, 6.0),
+            );
+            painter.rect_filled(badge, 3.0, chrome.hud_fill);
+            painter.galley(badge.min + egui::vec2(6.0, 3.0), label, chrome.hud_text);
+        }
+    }
+
     fn draw_overlay(&mut self, response: &egui::Response, painter: &egui::Painter) {
-        let scale = response.ctx.pixels_per_point();
-        let to_screen = |p: egui::Pos2| {
-            response.rect.min + egui::vec2(p.x / scale, p.y / scale)
-        };
+        let view = ViewTransform::new(response.rect, self.image_size());
+        let scale = view.scale.x;
+        let to_screen = |p: egui::Pos2| view.to_screen(p);
         let has_effects = self
             .shapes
             .iter()
             .any(|s| matches!(s, Shape::Effect(_)))
             || matches!(self.active_shape, Some(Shape::Effect(_)));
         let base_preview = if has_effects {
-            Some(self.render_full_image_without_effects())
+            Some(self.annotated_base())
         } else {
             None
         };
         let mut effect_index = 0usize;
-        let idle_dim = egui::Color32::from_rgba_premultiplied(0, 0, 0, 70);
-        let selection_dim = egui::Color32::from_rgba_premultiplied(0, 0, 0, 110);
+        let chrome = self.theme.chrome();
+        let idle_dim = chrome.idle_dim;
+        let selection_dim = chrome.selection_dim;
 
         if let Some(sel) = self.selection {
             let img_rect = response.rect;
@@ -417,37 +854,97 @@ impl EditorApp {
             painter.rect_filled(left, 0.0, selection_dim);
             painter.rect_filled(right, 0.0, selection_dim);
 
-            painter.rect_stroke(sel_rect, 0.0, egui::Stroke::new(1.5, egui::Color32::WHITE));
-            draw_handles(painter, sel_rect, 4.0, egui::Color32::WHITE);
-            draw_selection_hud(painter, sel_rect, sel.rect, response.rect);
+            if let Some(halo) = chrome.outline_halo {
+                let width = chrome.outline_width + 2.0;
+                painter.rect_stroke(sel_rect, 0.0, egui::Stroke::new(width, halo));
+                draw_handles(painter, sel_rect, chrome.handle_radius + 1.5, halo);
+            }
+            painter.rect_stroke(sel_rect, 0.0, egui::Stroke::new(chrome.outline_width, chrome.outline));
+            draw_handles(painter, sel_rect, chrome.handle_radius, chrome.outline);
+            if !self.chrome_hidden {
+                let backdrop = self.backdrop();
+                draw_selection_hud(painter, sel_rect, sel.rect, response.rect, &chrome, backdrop.as_ref());
+            }
         } else if !self.file_dialog_open {
             painter.rect_filled(response.rect, 0.0, idle_dim);
             self.draw_help_overlay(&response.ctx, painter, response.rect);
         }
+        self.draw_extra_selections(painter, &view, &chrome);
 
         self.draw_cursor_brush_preview(response, scale, painter);
-        let shapes = self.shapes.clone();
-        for shape in &shapes {
+        let shapes = std::mem::take(&mut self.shapes);
+        for (index, shape) in shapes.iter().enumerate() {
+            if self.arrange.is_hidden(index) {
+                // Later effects keep their own previews.
+                if matches!(shape, Shape::Effect(_)) {
+                    effect_index += 1;
+                }
+                continue;
+            }
             self.draw_shape_preview(
                 shape,
                 painter,
                 &to_screen,
                 scale,
-                base_preview.as_ref(),
+                base_preview.as_deref(),
                 &mut effect_index,
                 &response.ctx,
+                false,
             );
         }
-        if let Some(active) = self.active_shape.clone() {
+        self.shapes = shapes;
+        if let Some(active) = self.active_shape.take() {
             self.draw_shape_preview(
                 &active,
                 painter,
                 &to_screen,
                 scale,
-                base_preview.as_ref(),
+                base_preview.as_deref(),
                 &mut effect_index,
                 &response.ctx,
+                true,
             );
+            self.active_shape = Some(active);
+        }
+        self.draw_arrangement(painter, &view, &chrome);
+        self.draw_redact_proposals(painter, &to_screen);
+    }
+
+    /// Outlines the shapes the arrange tool picked, and the band while one
+    /// is dragged.
+    fn draw_arrangement(&self, painter: &egui::Painter, view: &ViewTransform, chrome: &Chrome) {
+        if self.tool != Tool::Arrange {
+            return;
+        }
+        let stroke = egui::Stroke::new(chrome.outline_width, chrome.outline);
+        for shape in self.arrange.picked.iter().filter_map(|&index| self.shapes.get(index)) {
+            let rect = view.rect_to_screen(shape_area(shape)).expand(3.0);
+            let outline = [
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+                rect.left_top(),
+            ];
+            painter.extend(egui::Shape::dashed_line(&outline, stroke, 4.0, 3.0));
+        }
+        if let Some(ArrangeDrag::Band { start, end }) = self.arrange.drag {
+            let band = view.rect_to_screen(egui::Rect::from_two_pos(start, end));
+            painter.rect(band, 0.0, chrome.outline.gamma_multiply(0.15), stroke);
+        }
+    }
+
+    /// Outlines the proposals auto-redact left to accept, faces in a
+    /// different colour from text.
+    fn draw_redact_proposals<F: Fn(egui::Pos2) -> egui::Pos2>(&self, painter: &egui::Painter, to_screen: &F) {
+        for proposal in &self.redact_proposals {
+            let rect = egui::Rect::from_two_pos(to_screen(proposal.rect.min), to_screen(proposal.rect.max));
+            let color = match proposal.kind {
+                RedactKind::Text => egui::Color32::from_rgb(255, 170, 0),
+                RedactKind::Face => egui::Color32::from_rgb(255, 64, 160),
+            };
+            painter.rect_filled(rect, 2.0, color.gamma_multiply(0.2));
+            painter.rect_stroke(rect, 2.0, egui::Stroke::new(1.5, color));
         }
     }
 
@@ -457,17 +954,26 @@ impl EditorApp {
         painter: &egui::Painter,
         rect: egui::Rect,
     ) {
-        let title = "Click and drag to select area";
+        let title = tr!("help-title");
         let hints = [
-            "Ctrl+C: copy",
-            "Ctrl+S: save",
-            "Ctrl+Z / Ctrl+Shift+Z: undo/redo",
-            "Mouse wheel: change tool size",
-            "Esc: close",
+            tr!("help-copy"),
+            tr!("help-save"),
+            tr!("help-paste"),
+            tr!("help-undo"),
+            tr!("help-size"),
+            tr!("help-snap"),
+            tr!("help-keyboard-select"),
+            tr!("help-keyboard-tools"),
+            tr!("help-arrange"),
+            tr!("help-peek"),
+            tr!("help-tabs"),
+            tr!("help-stats"),
+            tr!("help-close"),
         ];
         let font = egui::FontId::proportional(18.0);
-        let title_color = egui::Color32::from_rgb(245, 245, 245);
-        let hint_color = egui::Color32::from_rgb(220, 220, 220);
+        let chrome = self.theme.chrome();
+        let title_color = chrome.title;
+        let hint_color = chrome.text;
 
         let title_galley =
             ctx.fonts(|f| f.layout_no_wrap(title.into(), font.clone(), title_color));
@@ -487,12 +993,8 @@ impl EditorApp {
         let padding = egui::vec2(18.0, 14.0);
         let box_size = egui::vec2(width + padding.x * 2.0, height + padding.y * 2.0);
         let box_rect = egui::Rect::from_center_size(rect.center(), box_size);
-        painter.rect_filled(box_rect, 10.0, egui::Color32::from_rgb(12, 12, 12));
-        painter.rect_stroke(
-            box_rect,
-            10.0,
-            egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 255, 255, 30)),
-        );
+        painter.rect_filled(box_rect, 10.0, chrome.panel_fill);
+        painter.rect_stroke(box_rect, 10.0, egui::Stroke::new(1.0, chrome.panel_stroke));
 
         let x = box_rect.min.x + padding.x;
         let mut y = box_rect.min.y + padding.y;
@@ -522,7 +1024,7 @@ impl EditorApp {
         scale: f32,
         painter: &egui::Painter,
     ) {
-        if matches!(self.tool, Tool::Select) || self.text_input.is_some() {
+        if matches!(self.tool, Tool::Select | Tool::Arrange) || self.text_input.is_some() {
             return;
         }
         let Some(pointer_pos) = response.ctx.input(|i| i.pointer.hover_pos()) else {
@@ -534,8 +1036,7 @@ impl EditorApp {
         if !response.rect.contains(pointer_pos) {
             return;
         }
-        let img_pos_vec = (pointer_pos - response.rect.min) * scale;
-        let img_pos = egui::pos2(img_pos_vec.x, img_pos_vec.y);
+        let img_pos = ViewTransform::new(response.rect, self.image_size()).to_image(pointer_pos);
         if let Some(sel) = self.selection {
             if !sel.rect.contains(img_pos) {
                 return;
@@ -574,6 +1075,10 @@ impl EditorApp {
         if self.file_dialog_open {
             return;
         }
+        if self.chrome_hidden {
+            self.tool_button_rects.clear();
+            return;
+        }
         let Some(sel) = self.selection else {
             return;
         };
@@ -581,47 +1086,30 @@ impl EditorApp {
             return;
         };
         self.tool_button_rects.clear();
-        let scale = self.last_pixels_per_point;
-        let sel_rect_screen = selection_screen_rect(sel.rect, image_rect, scale);
+        let sel_rect_screen = self.last_view.rect_to_screen(sel.rect);
 
-        let button_size = egui::vec2(28.0, 28.0);
+        let button_size = egui::Vec2::splat(self.theme.chrome().button_size);
         let spacing = 6.0;
         let current_tool = self.tool;
-        let buttons = [
-            ("Select", ToolAction::Tool(Tool::Select), ToolIcon::Select, current_tool == Tool::Select),
-            ("Pencil", ToolAction::Tool(Tool::Pencil), ToolIcon::Pencil, current_tool == Tool::Pencil),
-            ("Line", ToolAction::Tool(Tool::Line), ToolIcon::Line, current_tool == Tool::Line),
-            ("Arrow", ToolAction::Tool(Tool::Arrow), ToolIcon::Arrow, current_tool == Tool::Arrow),
-            ("Rect", ToolAction::Tool(Tool::Rect), ToolIcon::Rect, current_tool == Tool::Rect),
-            ("Circle", ToolAction::Tool(Tool::Circle), ToolIcon::Circle, current_tool == Tool::Circle),
-            ("Marker", ToolAction::Tool(Tool::Marker), ToolIcon::Marker, current_tool == Tool::Marker),
-            (
-                "Marker Line",
-                ToolAction::Tool(Tool::MarkerLine),
-                ToolIcon::MarkerLine,
-                current_tool == Tool::MarkerLine,
-            ),
-            (
-                "Circle Count",
-                ToolAction::Tool(Tool::CircleCount),
-                ToolIcon::CircleCount,
-                current_tool == Tool::CircleCount,
-            ),
-            ("Text", ToolAction::Tool(Tool::Text), ToolIcon::Text, current_tool == Tool::Text),
-            ("Pixelate", ToolAction::Tool(Tool::Pixelate), ToolIcon::Pixelate, current_tool == Tool::Pixelate),
-            ("Blur", ToolAction::Tool(Tool::Blur), ToolIcon::Blur, current_tool == Tool::Blur),
-            ("Undo", ToolAction::Undo, ToolIcon::Undo, false),
-            ("Copy", ToolAction::Copy, ToolIcon::Copy, false),
-            ("Save", ToolAction::Save, ToolIcon::Save, false),
-            ("Clear", ToolAction::Clear, ToolIcon::Clear, false),
-        ];
-        let positions = layout_tool_buttons(
+        let mut buttons = vec![(tr!("tool-grip"), ToolAction::Grip, ToolIcon::Grip, false)];
+        for button in self.toolbar.buttons() {
+            if button == ToolbarButton::Record && self.record.is_none() {
+                continue;
+            }
+            let (action, icon) = button_action(button);
+            let selected = matches!(action, ToolAction::Tool(tool) if tool == current_tool);
+            buttons.push((button_tooltip(button), action, icon, selected));
+        }
+        let positions = dock_tool_buttons(
+            &self.toolbar,
             sel_rect_screen,
             image_rect,
             button_size,
             spacing,
             buttons.len(),
         );
+        let chrome = self.theme.chrome();
+        let backdrop = self.backdrop();
         let mut index = 0;
         let mut add_tool =
             |tooltip: &str, action: ToolAction, icon: ToolIcon, selected: bool| {
@@ -637,8 +1125,24 @@ impl EditorApp {
                     .order(egui::Order::Foreground)
                     .fixed_pos(pos)
                     .show(ctx, |ui| {
-                        let response = ui.add_sized(button_size, egui::Button::new(""));
+                        // Grown by half the gap so neighbouring buttons'
+                        // backdrops meet without overlapping.
+                        let behind = egui::Rect::from_min_size(pos, button_size).expand(spacing * 0.5);
+                        if let Some(backdrop) = &backdrop {
+                            ui.painter().add(backdrop.shape(behind, 0.0));
+                        }
+                        ui.painter().rect_filled(behind, 0.0, chrome.backdrop_fill);
+                        let sense = if matches!(action, ToolAction::Grip) {
+                            egui::Sense::drag()
+                        } else {
+                            egui::Sense::click()
+                        };
+                        let response =
+                            ui.add_sized(button_size, egui::Button::new("").sense(sense));
                         let response = response.on_hover_text(tooltip);
+                        response.widget_info(|| {
+                            egui::WidgetInfo::selected(egui::WidgetType::Button, selected, tooltip)
+                        });
                         let visuals = ui.visuals();
                         let fg = if selected {
                             visuals.selection.stroke.color
@@ -654,6 +1158,22 @@ impl EditorApp {
                             );
                         }
                         paint_tool_icon(&painter, response.rect, icon, fg);
+                        if response.dragged() {
+                            response.ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+                        } else if response.hovered() && matches!(action, ToolAction::Grip) {
+                            response.ctx.set_cursor_icon(egui::CursorIcon::Grab);
+                        }
+                        if response.drag_stopped() {
+                            if let Some(pos) = response.ctx.input(|i| i.pointer.latest_pos()) {
+                                if self.toolbar.pin.is_some() {
+                                    let corner = pos - button_size * 0.5 - image_rect.min;
+                                    self.toolbar.pin = Some([corner.x.max(0.0), corner.y.max(0.0)]);
+                                } else {
+                                    (self.toolbar.side, self.toolbar.anchor) =
+                                        dock_for_drop(pos, sel_rect_screen, image_rect);
+                                }
+                            }
+                        }
                         if response.clicked() {
                             match action {
                                 ToolAction::Tool(tool) => self.tool = tool,
@@ -661,8 +1181,11 @@ impl EditorApp {
                                     self.pop_shape();
                                 }
                                 ToolAction::Copy => self.copy_and_close(ctx),
-                                ToolAction::Save => self.save_image(),
+                                ToolAction::Save => self.save_image(ctx),
                                 ToolAction::Clear => self.clear_shapes(),
+                                ToolAction::Timestamp => self.insert_timestamp(),
+                                ToolAction::Record => self.record_and_close(ctx),
+                                ToolAction::Grip => {}
                             }
                         }
                     });
@@ -677,6 +1200,10 @@ impl EditorApp {
         if self.file_dialog_open {
             return;
         }
+        if self.chrome_hidden {
+            self.tool_controls_rect = None;
+            return;
+        }
         let Some(sel) = self.selection else {
             return;
         };
@@ -684,8 +1211,7 @@ impl EditorApp {
             return;
         };
         self.tool_controls_rect = None;
-        let scale = self.last_pixels_per_point;
-        let sel_rect_screen = selection_screen_rect(sel.rect, image_rect, scale);
+        let sel_rect_screen = self.last_view.rect_to_screen(sel.rect);
 
         let panel_size = egui::vec2(240.0, 36.0);
         let spacing = 6.0;
@@ -736,91 +1262,953 @@ impl EditorApp {
         });
         self.tool_controls_rect = Some(egui::Rect::from_min_size(pos, panel_size));
 
+        let chrome = self.theme.chrome();
+        let backdrop = self.backdrop();
         egui::Area::new("tool_controls".into())
             .order(egui::Order::Foreground)
             .fixed_pos(pos)
             .show(ctx, |ui| {
-                egui::Frame::popup(ui.style())
+                // Filled in once the frame's size is known, like its own fill.
+                let behind = ui.painter().add(egui::Shape::Noop);
+                let mut frame = egui::Frame::popup(ui.style())
                     .rounding(6.0)
-                    .inner_margin(egui::Margin::same(6.0))
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.color_edit_button_srgba(&mut self.color);
-                            ui.add(egui::Slider::new(&mut self.size, 1.0..=20.0).text("Size"));
+                    .inner_margin(egui::Margin::same(6.0));
+                if chrome.backdrop_fill != egui::Color32::TRANSPARENT {
+                    frame = frame.fill(chrome.backdrop_fill);
+                }
+                let framed = frame.show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let color = tr!("control-color");
+                        let picker = ui.color_edit_button_srgba(&mut self.color).on_hover_text(color);
+                        picker.widget_info(|| {
+                            egui::WidgetInfo::labeled(egui::WidgetType::ColorButton, color)
                         });
-                        if let Some(status) = &self.status {
-                            ui.label(status);
+                        if picker.changed() && self.tool == Tool::Arrange {
+                            self.recolor_picked(self.color);
                         }
+                        ui.add(egui::Slider::new(&mut self.size, 1.0..=20.0).text(tr!("control-size")));
                     });
+                    if !self.redact_proposals.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.label(tr!("redact-proposals", count = self.redact_proposals.len()));
+                            if ui.button(tr!("redact-accept-all")).clicked() {
+                                self.accept_all_proposals();
+                            }
+                            if ui.button(tr!("redact-dismiss")).clicked() {
+                                self.dismiss_proposals();
+                            }
+                        });
+                    }
+                    if matches!(self.tool, Tool::Pixelate | Tool::Blur) {
+                        ui.horizontal(|ui| {
+                            let area = &mut self.effect_area;
+                            let rect = tr!("control-effect-rect");
+                            ui.selectable_value(area, EffectArea::Rect, rect);
+                            let ellipse = tr!("control-effect-ellipse");
+                            ui.selectable_value(area, EffectArea::Ellipse, ellipse);
+                            let lasso = tr!("control-effect-lasso");
+                            ui.selectable_value(area, EffectArea::Lasso, lasso)
+                                .on_hover_text(tr!("control-effect-lasso-hint"));
+                            let brush = tr!("control-effect-brush");
+                            ui.selectable_value(area, EffectArea::Brush, brush)
+                                .on_hover_text(tr!("control-effect-brush-hint"));
+                        });
+                    }
+                    let busy = self.pending_export.is_some().then(|| tr!("status-exporting"));
+                    self.status.show(ui, busy);
+                });
+                if let Some(backdrop) = &backdrop {
+                    ui.painter().set(behind, backdrop.shape(framed.response.rect, 6.0));
+                }
             });
     }
 
-    fn show_text_editor(&mut self, ctx: &egui::Context) {
-        if self.file_dialog_open {
+    /// Editing without a pointer. Arrows move the selection, creating one in
+    /// the middle of the capture first, and Ctrl+arrows resize it; Shift
+    /// makes either step a single pixel. Ctrl+A selects the whole capture,
+    /// the number keys and `[`/`]` pick a tool and `+`/`-` change its size.
+    /// Shapes picked with the arrange tool take the arrows instead, Delete
+    /// removes them and Ctrl+G / Ctrl+Shift+G group and ungroup them.
+    fn handle_keyboard(&mut self, ctx: &egui::Context) {
+        // Keys belong to a focused toolbar button or text field.
+        if self.text_input.is_some()
+            || self.file_dialog_open
+            || self.selection_drag.is_some()
+            || ctx.memory(|m| m.focused().is_some())
+        {
             return;
         }
-        self.text_editor_rect = None;
-        let Some(input) = &mut self.text_input else {
+        let presses: Vec<(egui::Key, egui::Modifiers)> = ctx.input(|i| {
+            i.events
+                .iter()
+                .filter_map(|event| match event {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+                .collect()
+        });
+        let bounds = egui::Rect::from_min_size(egui::Pos2::ZERO, self.image_size());
+        for (key, modifiers) in presses {
+            let step = if modifiers.shift { 1.0 } else { KEYBOARD_STEP };
+            let delta = match key {
+                egui::Key::ArrowLeft => Some(egui::vec2(-step, 0.0)),
+                egui::Key::ArrowRight => Some(egui::vec2(step, 0.0)),
+                egui::Key::ArrowUp => Some(egui::vec2(0.0, -step)),
+                egui::Key::ArrowDown => Some(egui::vec2(0.0, step)),
+                _ => None,
+            };
+            if self.tool == Tool::Arrange && !self.arrange.picked.is_empty() {
+                let handled = match (key, delta) {
+                    (_, Some(delta)) => {
+                        self.move_picked(delta);
+                        true
+                    }
+                    (egui::Key::Delete | egui::Key::Backspace, _) => {
+                        self.delete_picked();
+                        true
+                    }
+                    (egui::Key::G, _) if modifiers.command => {
+                        if modifiers.shift {
+                            self.ungroup_picked();
+                        } else {
+                            self.group_picked();
+                        }
+                        true
+                    }
+                    _ => false,
+                };
+                if handled {
+                    continue;
+                }
+            }
+            if let Some(delta) = delta {
+                let Some(sel) = self.selection else {
+                    let rect = egui::Rect::from_center_size(bounds.center(), bounds.size() * 0.5);
+                    self.selection = Some(SelectionRect { rect });
+                    continue;
+                };
+                let rect = sel.rect;
+                let rect = if modifiers.command {
+                    let max = (rect.max + delta).clamp(rect.min + egui::Vec2::splat(1.0), bounds.max);
+                    egui::Rect::from_min_max(rect.min, max)
+                } else {
+                    let min = (rect.min + delta).clamp(bounds.min, (bounds.max - rect.size()).max(bounds.min));
+                    egui::Rect::from_min_size(min, rect.size())
+                };
+                self.selection = Some(SelectionRect { rect });
+                continue;
+            }
+
+            let tools = self.toolbar_tools();
+            let current = tools.iter().position(|tool| *tool == self.tool).unwrap_or(0);
+            match key {
+                egui::Key::A if modifiers.command => {
+                    self.selection = Some(SelectionRect { rect: bounds });
+                }
+                egui::Key::PageDown if modifiers.command => self.cycle_tab(1),
+                egui::Key::PageUp if modifiers.command => self.cycle_tab(-1),
+                _ if modifiers.command || modifiers.alt => {}
+                egui::Key::OpenBracket if !tools.is_empty() => {
+                    self.tool = tools[(current + tools.len() - 1) % tools.len()];
+                }
+                egui::Key::CloseBracket if !tools.is_empty() => {
+                    self.tool = tools[(current + 1) % tools.len()];
+                }
+                egui::Key::Plus | egui::Key::Equals => self.size = (self.size + 1.0).min(20.0),
+                egui::Key::Minus => self.size = (self.size - 1.0).max(1.0),
+                _ => {
+                    let digits = [
+                        egui::Key::Num1,
+                        egui::Key::Num2,
+                        egui::Key::Num3,
+                        egui::Key::Num4,
+                        egui::Key::Num5,
+                        egui::Key::Num6,
+                        egui::Key::Num7,
+                        egui::Key::Num8,
+                        egui::Key::Num9,
+                        egui::Key::Num0,
+                    ];
+                    if let Some(tool) = digits
+                        .iter()
+                        .position(|digit| *digit == key)
+                        .and_then(|index| tools.get(index))
+                    {
+                        self.tool = *tool;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tools on the toolbar in the order shown, for picking one from the
+    /// keyboard.
+    fn toolbar_tools(&self) -> Vec<Tool> {
+        self.toolbar
+            .buttons()
+            .into_iter()
+            .filter_map(|button| match button_action(button).0 {
+                ToolAction::Tool(tool) => Some(tool),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Drops the shape or selection being dragged out.
+    fn cancel_drag(&mut self) {
+        self.active_shape = None;
+        self.arrange.drag = None;
+        match self.selection_drag.take() {
+            Some(SelectionDrag::Creating { .. }) => self.selection = None,
+            Some(SelectionDrag::Adding { .. }) => {
+                self.selection = self.extra_selections.pop().map(|rect| SelectionRect { rect });
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies a two-finger pinch and pan to the view of `fit`, the rect the
+    /// capture fills unzoomed.
+    fn handle_gesture(&mut self, ctx: &egui::Context, fit: egui::Rect) {
+        ctx.input(|i| {
+            for event in &i.events {
+                if let egui::Event::Touch { id, phase, pos, .. } = event {
+                    self.touches.retain(|(known, _)| known != id);
+                    if matches!(phase, egui::TouchPhase::Start | egui::TouchPhase::Move) {
+                        self.touches.push((*id, *pos));
+                    }
+                }
+            }
+        });
+        let Some(touch) = ctx.multi_touch() else {
             return;
         };
-        let Some(image_rect) = self.last_image_rect else {
+        if self.touches.is_empty() {
             return;
-        };
-        let scale = self.last_pixels_per_point;
-        let screen_pos = image_rect.min + egui::vec2(input.pos.x / scale, input.pos.y / scale);
-        let editor_size = egui::vec2(220.0, 32.0);
-        let mut pos = screen_pos + egui::vec2(6.0, 6.0);
-        let mut rect = egui::Rect::from_min_size(pos, editor_size);
-        if rect.max.x > image_rect.max.x {
-            rect = rect.translate(egui::vec2(image_rect.max.x - rect.max.x, 0.0));
         }
-        if rect.max.y > image_rect.max.y {
-            rect = rect.translate(egui::vec2(0.0, image_rect.max.y - rect.max.y));
-        }
-        if rect.min.x < image_rect.min.x {
-            rect = rect.translate(egui::vec2(image_rect.min.x - rect.min.x, 0.0));
-        }
-        if rect.min.y < image_rect.min.y {
-            rect = rect.translate(egui::vec2(0.0, image_rect.min.y - rect.min.y));
-        }
-        pos = rect.min;
-        self.text_editor_rect = Some(rect);
+        let fingers = self
+            .touches
+            .iter()
+            .fold(egui::Vec2::ZERO, |sum, (_, pos)| sum + pos.to_vec2());
+        let between = (fingers / self.touches.len() as f32).to_pos2();
+        let zoom = (self.zoom * touch.zoom_delta).clamp(1.0, MAX_ZOOM);
+        // Zoom around the point between the fingers, then follow them.
+        let center = fit.center() + self.pan;
+        let center =
+            between + (center - between) * (zoom / self.zoom) + touch.translation_delta;
+        self.zoom = zoom;
+        self.pan = if zoom > 1.0 {
+            // The capture keeps covering the middle of where it fits.
+            let limit = fit.size() * zoom / 2.0;
+            (center - fit.center()).clamp(-limit, limit)
+        } else {
+            egui::Vec2::ZERO
+        };
+    }
 
-        egui::Area::new("text_editor".into())
-            .order(egui::Order::Foreground)
-            .fixed_pos(pos)
-            .show(ctx, |ui| {
-                egui::Frame::popup(ui.style())
-                    .rounding(4.0)
-                    .inner_margin(egui::Margin::same(4.0))
-                    .show(ui, |ui| {
-                        let response = ui.add(
-                            egui::TextEdit::singleline(&mut input.text)
-                                .desired_width(editor_size.x - 8.0)
-                                .font(egui::TextStyle::Body),
-                        );
-                        response.request_focus();
-                    });
-            });
+    fn zoomed_rect(&self, fit: egui::Rect) -> egui::Rect {
+        egui::Rect::from_center_size(fit.center() + self.pan, fit.size() * self.zoom)
     }
 
-    fn draw_shape_preview<F: Fn(egui::Pos2) -> egui::Pos2>(
-        &mut self,
-        shape: &Shape,
-        painter: &egui::Painter,
-        to_screen: &F,
-        scale: f32,
-        base_preview: Option<&RgbaImage>,
-        effect_index: &mut usize,
-        ctx: &egui::Context,
-    ) {
-        match shape {
-            Shape::Stroke(stroke) => {
-                let points: Vec<egui::Pos2> =
-                    stroke.points.iter().copied().map(to_screen).collect();
-                painter.add(egui::Shape::line(
-                    points,
-                    egui::Stroke::new(stroke.size, stroke.color),
+    /// Right click, or a long press on touch screens.
+    fn show_context_menu(&mut self, response: &egui::Response) {
+        let ctx = response.ctx.clone();
+        let menu = response.context_menu(|ui| {
+            if ui.button(tr!("tool-undo")).clicked() {
+                self.pop_shape();
+                ui.close_menu();
+            }
+            if ui.button(tr!("menu-redo")).clicked() {
+                self.redo_shape();
+                ui.close_menu();
+            }
+            if ui.button(tr!("tool-copy")).clicked() {
+                ui.close_menu();
+                self.copy_and_close(&ctx);
+            }
+            if ui.button(tr!("tool-save")).clicked() {
+                ui.close_menu();
+                self.save_image(&ctx);
+            }
+            if ui.button(tr!("tool-clear")).clicked() {
+                self.clear_shapes();
+                ui.close_menu();
+            }
+            let auto_redact = egui::Button::new(tr!("menu-auto-redact"));
+            if ui.add_enabled(self.selection.is_some(), auto_redact).clicked() {
+                self.auto_redact();
+                ui.close_menu();
+            }
+            if ui.button(tr!("menu-auto-crop")).clicked() {
+                self.auto_crop();
+                ui.close_menu();
+            }
+            if ui.button(tr!("menu-email")).clicked() {
+                ui.close_menu();
+                let subject = self.save.file_stem_for(self.window.as_ref());
+                self.start_export(ExportKind::Email { subject }, &ctx);
+            }
+            let share = egui::Button::new(tr!("menu-share-lan"));
+            if ui.add_enabled(self.selection.is_some(), share).clicked() {
+                ui.close_menu();
+                self.share_on_lan(&ctx);
+            }
+            let layers = egui::Button::new(tr!("menu-export-layers"));
+            if ui.add_enabled(self.selection.is_some(), layers).clicked() {
+                ui.close_menu();
+                self.export_layers(&ctx);
+            }
+            if !self.extra_selections.is_empty() {
+                let count = self.extra_selections.len() + usize::from(self.selection.is_some());
+                if ui.button(tr!("menu-export-all", count = count)).clicked() {
+                    ui.close_menu();
+                    self.export_all(&ctx);
+                }
+                if ui.button(tr!("menu-clear-regions")).clicked() {
+                    self.extra_selections.clear();
+                    ui.close_menu();
+                }
+            }
+            if self.tool == Tool::Arrange && !self.arrange.picked.is_empty() {
+                ui.separator();
+                let group = egui::Button::new(tr!("menu-group"));
+                if ui.add_enabled(self.arrange.picked.len() > 1, group).clicked() {
+                    self.group_picked();
+                    ui.close_menu();
+                }
+                if ui.button(tr!("menu-ungroup")).clicked() {
+                    self.ungroup_picked();
+                    ui.close_menu();
+                }
+                if ui.button(tr!("menu-delete-picked")).clicked() {
+                    self.delete_picked();
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            let mut chosen = None;
+            for preset in &self.presets {
+                if ui.button(&preset.name).clicked() {
+                    chosen = Some(preset.region);
+                    ui.close_menu();
+                }
+            }
+            if let Some(region) = chosen {
+                self.apply_preset(region);
+            }
+            let save_preset = egui::Button::new(tr!("menu-save-preset"));
+            if ui.add_enabled(self.selection.is_some(), save_preset).clicked() {
+                self.preset_name = Some(String::new());
+                ui.close_menu();
+            }
+            if self.recapture.is_some() {
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("menu-recapture")).clicked() {
+                        ui.close_menu();
+                        self.recapture_and_close(&ctx);
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut self.recapture_secs)
+                            .clamp_range(0..=60)
+                            .suffix(" s"),
+                    );
+                });
+            }
+            if self.zoom > 1.0 && ui.button(tr!("menu-reset-zoom")).clicked() {
+                self.zoom = 1.0;
+                self.pan = egui::Vec2::ZERO;
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button(tr!("menu-close")).clicked() {
+                ui.close_menu();
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        });
+        self.context_menu_rect = menu.map(|menu| menu.response.rect);
+    }
+
+    fn show_recovery_prompt(&mut self, ctx: &egui::Context) {
+        self.recovery_prompt_rect = None;
+        if self.orphan.is_none() {
+            return;
+        }
+        let mut choice = None;
+        let response = egui::Window::new(tr!("recovery-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(tr!("recovery-body"));
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("recovery-restore")).clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button(tr!("recovery-discard")).clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+        self.recovery_prompt_rect = response.map(|r| r.response.rect);
+        match choice {
+            Some(true) => self.restore_orphan(),
+            Some(false) => {
+                if let Some(orphan) = self.orphan.take() {
+                    orphan.discard();
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Asks for a name and saves the selection under it.
+    fn show_preset_prompt(&mut self, ctx: &egui::Context) {
+        self.preset_prompt_rect = None;
+        let Some(name) = self.preset_name.as_mut() else {
+            return;
+        };
+        let mut choice = None;
+        let response = egui::Window::new(tr!("preset-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                let field = ui.add(egui::TextEdit::singleline(name).hint_text(tr!("preset-hint")));
+                field.request_focus();
+                if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    choice = Some(true);
+                }
+                ui.horizontal(|ui| {
+                    let named = !name.trim().is_empty();
+                    if ui.add_enabled(named, egui::Button::new(tr!("preset-save"))).clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button(tr!("preset-cancel")).clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+        self.preset_prompt_rect = response.map(|r| r.response.rect);
+        match choice {
+            Some(true) => self.save_preset(),
+            Some(false) => self.preset_name = None,
+            None => {}
+        }
+    }
+
+    fn save_preset(&mut self) {
+        let Some(name) = self.preset_name.take() else {
+            return;
+        };
+        let (name, Some(region)) = (name.trim().to_string(), self.selection_region()) else {
+            return;
+        };
+        if name.is_empty() {
+            return;
+        }
+        let preset = RegionPreset { name, region };
+        self.status.push(match Config::save_region_preset(&preset) {
+            Ok(()) => tr!("status-preset-saved", name = preset.name),
+            Err(err) => err.user_message(),
+        });
+        self.presets.retain(|other| !other.name.eq_ignore_ascii_case(&preset.name));
+        self.presets.push(preset);
+    }
+
+    /// Selects `region`, clipped to the capture.
+    fn apply_preset(&mut self, region: RecordRegion) {
+        let rect = egui::Rect::from_min_size(
+            egui::pos2(region.x as f32, region.y as f32),
+            egui::vec2(region.width as f32, region.height as f32),
+        )
+        .intersect(egui::Rect::from_min_size(egui::Pos2::ZERO, self.image_size()));
+        if rect.width() >= 1.0 && rect.height() >= 1.0 {
+            self.selection = Some(SelectionRect { rect });
+        }
+    }
+
+    /// The selection in whole capture pixels.
+    fn selection_region(&self) -> Option<RecordRegion> {
+        let sel = self.selection?;
+        let (min_x, min_y, max_x, max_y) = rect_to_u32(&self.base_image, sel.rect)?;
+        Some(RecordRegion {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        })
+    }
+
+    fn restore_orphan(&mut self) {
+        let Some(restored) = self.orphan.take().and_then(Orphan::restore) else {
+            return;
+        };
+        self.replace_base(restored.base, restored.shapes, restored.selection);
+        self.arrange = Arrangement::restored(restored.groups, restored.hidden);
+        self.recovery_prompt_rect = None;
+    }
+
+    /// Edits `base` instead of the capture, with `shapes` drawn on it.
+    fn replace_base(&mut self, base: RgbaImage, shapes: Vec<Shape>, selection: Option<egui::Rect>) {
+        self.show_capture(Arc::new(base), shapes, selection);
+        // Restored and pasted images are already sRGB and their output is
+        // unknown.
+        self.icc_profile = None;
+        self.from_screen = false;
+        self.window = None;
+        self.captured_at = SystemTime::now();
+        self.recovery = Recovery::start(self.base_image.clone());
+    }
+
+    /// Shows `base` with `shapes`, dropping everything made for the capture
+    /// shown before.
+    fn show_capture(&mut self, base: Arc<RgbaImage>, shapes: Vec<Shape>, selection: Option<egui::Rect>) {
+        self.base_image = base;
+        self.texture = None;
+        self.backdrop_texture = None;
+        self.shapes = shapes;
+        self.redo_stack.clear();
+        self.undo_floor = 0;
+        self.active_shape = None;
+        self.text_input = None;
+        self.redact_proposals.clear();
+        self.selection = selection.map(|rect| SelectionRect { rect });
+        self.extra_selections.clear();
+        self.arrange = Arrangement::default();
+        self.selection_drag = None;
+        self.zoom = 1.0;
+        self.pan = egui::Vec2::ZERO;
+        self.shapes_version += 1;
+        self.effect_previews.clear();
+        self.annotation_cache = None;
+        self.prerender = None;
+        self.idle_since = None;
+        self.adjustments = Adjustments::default();
+        self.adjusted_base = None;
+        self.histogram = None;
+    }
+
+    /// Opens `image` in a new tab after the others and shows it.
+    fn open_tab(&mut self, title: String, image: RgbaImage) {
+        self.tabs.push(Tab::parked(title, image));
+        self.switch_tab(self.tabs.len() - 1);
+    }
+
+    /// Parks the shown capture in its tab and shows tab `index` instead.
+    /// Waits while an export or share of the shown one is running.
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab
+            || index >= self.tabs.len()
+            || self.pending_export.is_some()
+            || self.lan_share.is_some()
+        {
+            return;
+        }
+        let Some(state) = self.tabs[index].state.take() else {
+            return;
+        };
+        let parked = TabState {
+            base: self.base_image.clone(),
+            shapes: std::mem::take(&mut self.shapes),
+            redo_stack: std::mem::take(&mut self.redo_stack),
+            undo_floor: self.undo_floor,
+            selection: self.selection.map(|sel| sel.rect),
+            extra_selections: std::mem::take(&mut self.extra_selections),
+            arrange: std::mem::take(&mut self.arrange),
+            icc_profile: self.icc_profile.take(),
+            from_screen: self.from_screen,
+            window: self.window.take(),
+            captured_at: self.captured_at,
+            adjustments: self.adjustments,
+            recovery: self.recovery.take(),
+        };
+        self.tabs[self.active_tab].state = Some(parked);
+        self.show_capture(state.base, state.shapes, state.selection);
+        self.redo_stack = state.redo_stack;
+        self.undo_floor = state.undo_floor;
+        self.extra_selections = state.extra_selections;
+        self.arrange = state.arrange;
+        self.icc_profile = state.icc_profile;
+        self.from_screen = state.from_screen;
+        self.window = state.window;
+        self.captured_at = state.captured_at;
+        self.adjustments = state.adjustments;
+        self.recovery = state.recovery;
+        self.active_tab = index;
+    }
+
+    /// Moves `step` tabs along, wrapping round at the ends.
+    fn cycle_tab(&mut self, step: isize) {
+        let len = self.tabs.len() as isize;
+        self.switch_tab((self.active_tab as isize + step).rem_euclid(len) as usize);
+    }
+
+    /// Closes tab `index` and what was drawn on it; closing the last one
+    /// closes the editor.
+    fn close_tab(&mut self, index: usize, ctx: &egui::Context) {
+        if self.tabs.len() <= 1 {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+        if index == self.active_tab {
+            self.switch_tab(if index + 1 < self.tabs.len() { index + 1 } else { index - 1 });
+            if index == self.active_tab {
+                return;
+            }
+        }
+        self.tabs.remove(index);
+        if self.active_tab > index {
+            self.active_tab -= 1;
+        }
+    }
+
+    /// Opens image files dropped on the editor and images handed over
+    /// through its control in new tabs.
+    fn open_incoming_tabs(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        for path in dropped.into_iter().filter_map(|file| file.path) {
+            match image::open(&path) {
+                Ok(image) => {
+                    let title = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                    self.open_tab(title, image.to_rgba8());
+                }
+                Err(err) => self.status.push(tr!(
+                    "status-open-failed",
+                    path = path.display(),
+                    error = err
+                )),
+            }
+        }
+        for (title, image) in self.control.take_tabs() {
+            self.open_tab(title, image);
+        }
+    }
+
+    /// The strip of tab names along the top, once there is more than one.
+    fn show_tab_strip(&mut self, ctx: &egui::Context) {
+        self.tab_strip_rect = None;
+        if self.tabs.len() < 2 || self.file_dialog_open {
+            return;
+        }
+        let mut switch = None;
+        let mut close = None;
+        let area = egui::Area::new("tab_strip".into())
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 6.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .rounding(6.0)
+                    .inner_margin(egui::Margin::same(4.0))
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            for (i, tab) in self.tabs.iter().enumerate() {
+                                if ui.selectable_label(i == self.active_tab, &tab.title).clicked() {
+                                    switch = Some(i);
+                                }
+                                let close_tab = tr!("tab-close");
+                                if ui.small_button("×").on_hover_text(close_tab).clicked() {
+                                    close = Some(i);
+                                }
+                            }
+                        });
+                    });
+            });
+        self.tab_strip_rect = Some(area.response.rect);
+        if let Some(index) = switch {
+            self.switch_tab(index);
+        }
+        if let Some(index) = close {
+            self.close_tab(index, ctx);
+        }
+    }
+
+    /// Fades the capture flash out over `FLASH_SECS` from the first frame.
+    fn show_flash(&mut self, ctx: &egui::Context) {
+        if !self.flash {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let started = *self.flash_started.get_or_insert(now);
+        let left = 1.0 - (now - started) / FLASH_SECS;
+        if left <= 0.0 {
+            self.flash = false;
+            return;
+        }
+        paint_flash(ctx, left as f32);
+        ctx.request_repaint();
+    }
+
+    /// Whether the panels and selection size step aside this frame: while a
+    /// shape is drawn, with `hide_ui_while_drawing`, while H is held to
+    /// peek under them, and throughout when only picking an area to copy.
+    fn chrome_hidden(&self, ctx: &egui::Context) -> bool {
+        let drawing = self.active_shape.is_some() || self.arrange.drag.is_some();
+        let peeking = self.text_input.is_none()
+            && self.preset_name.is_none()
+            && ctx.memory(|m| m.focused().is_none())
+            && ctx.input(|i| i.key_down(egui::Key::H));
+        (self.hide_ui_while_drawing && drawing) || peeking || self.copy_region
+    }
+
+    /// Lists the shapes at the right edge, once there are any.
+    /// The layers panel, once something is drawn, over the adjustments.
+    fn show_layers_panel(&mut self, ctx: &egui::Context) {
+        self.layers_rect = None;
+        if self.file_dialog_open || self.chrome_hidden {
+            return;
+        }
+        if self.histogram.is_none() {
+            self.histogram = Some(Histogram::of(&self.adjusted_base()));
+        }
+        let mut action = None;
+        let mut adjusted = false;
+        let area = egui::Area::new("layers".into())
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-6.0, 6.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .rounding(6.0)
+                    .inner_margin(egui::Margin::same(6.0))
+                    .show(ui, |ui| {
+                        if !self.shapes.is_empty() {
+                            action = layers::show(ui, &self.shapes, &self.arrange);
+                        }
+                        if let Some(histogram) = &self.histogram {
+                            adjusted = adjust::show(ui, &mut self.adjustments, histogram);
+                        }
+                    });
+            });
+        self.layers_rect = Some(area.response.rect);
+        if adjusted {
+            self.adjustments_changed();
+        }
+        match action {
+            Some(LayerAction::Pick { index, add }) => {
+                self.tool = Tool::Arrange;
+                self.arrange.pick([index], add);
+            }
+            Some(LayerAction::ToggleHidden(index)) => {
+                self.arrange.toggle_hidden(index);
+                self.shapes_version = self.shapes_version.wrapping_add(1);
+                self.effect_previews.clear();
+            }
+            Some(LayerAction::Delete(index)) => self.remove_shape(index),
+            Some(LayerAction::Move { from, to }) => self.move_shape(from, to),
+            None => {}
+        }
+    }
+
+    /// Swaps the capture for the clipboard's image while nothing is drawn.
+    fn paste_base(&mut self) {
+        if !self.shapes.is_empty() {
+            self.status.push(tr!("status-paste-annotated"));
+            return;
+        }
+        match crate::clipboard::paste_image() {
+            Ok(image) => self.replace_base(image, Vec::new(), None),
+            Err(err) => self.status.push(err.user_message()),
+        }
+    }
+
+    /// Selects what is inside the uniform borders of the selection, or of
+    /// the whole capture without one.
+    fn auto_crop(&mut self) {
+        let area = match self.selection {
+            Some(sel) => sel.rect,
+            None => egui::Rect::from_min_size(egui::Pos2::ZERO, self.image_size()),
+        };
+        match crate::autocrop::content_box(&self.base_image, area) {
+            Some(rect) => {
+                self.selection = Some(SelectionRect { rect });
+                self.status.push(tr!(
+                    "status-auto-cropped",
+                    width = rect.width(),
+                    height = rect.height()
+                ));
+            }
+            None => self.status.push(tr!("status-auto-crop-none")),
+        }
+    }
+
+    /// Writes `caption` in the capture's top-left corner as a text shape,
+    /// which can be moved, restyled or undone like any other.
+    fn caption_window(&mut self, caption: &str) {
+        self.push_shape(Shape::Text(TextShape {
+            pos: egui::pos2(CAPTION_MARGIN, CAPTION_MARGIN),
+            text: caption.to_string(),
+            color: self.color,
+            size: CAPTION_SIZE,
+        }));
+    }
+
+    /// Writes when the capture was taken in the configured corner of the
+    /// selection, or of the whole capture without one.
+    fn insert_timestamp(&mut self) {
+        let text = self.timestamp.text(self.captured_at);
+        let area = match self.selection {
+            Some(sel) => sel.rect,
+            None => egui::Rect::from_min_size(egui::Pos2::ZERO, self.image_size()),
+        };
+        let scale = (CAPTION_SIZE / 6.0).round().max(1.0) as u32;
+        let (width, height) = text_bitmap_size(&text, scale);
+        let inset = egui::vec2(width as f32 + CAPTION_MARGIN, height as f32 + CAPTION_MARGIN);
+        let pos = match self.timestamp.corner {
+            Corner::TopLeft => area.min + egui::vec2(CAPTION_MARGIN, CAPTION_MARGIN),
+            Corner::TopRight => egui::pos2(area.max.x - inset.x, area.min.y + CAPTION_MARGIN),
+            Corner::BottomLeft => egui::pos2(area.min.x + CAPTION_MARGIN, area.max.y - inset.y),
+            Corner::BottomRight => area.max - inset,
+        };
+        self.push_shape(Shape::Text(TextShape {
+            pos,
+            text,
+            color: self.color,
+            size: CAPTION_SIZE,
+        }));
+    }
+
+    /// Looks for text and faces in the selection and offers each for
+    /// blurring.
+    fn auto_redact(&mut self) {
+        let Some(sel) = self.selection else {
+            return;
+        };
+        let mut proposals = crate::redact::detect(&self.base_image, sel.rect);
+        // Areas already covered by an effect need no second one.
+        proposals.retain(|proposal| {
+            !self.shapes.iter().any(|shape| match shape {
+                Shape::Effect(effect) if effect.covers_rect() => {
+                    egui::Rect::from_two_pos(effect.start, effect.end).contains_rect(proposal.rect)
+                }
+                _ => false,
+            })
+        });
+        self.status.push(match proposals.len() {
+            0 => tr!("status-redact-none").to_string(),
+            count => tr!("status-redact-found", count = count),
+        });
+        self.redact_proposals = proposals;
+    }
+
+    /// Blurs a proposal's area, strongly enough that text as tall as it can't
+    /// be read.
+    fn accept_proposal(&mut self, index: usize) {
+        let proposal = self.redact_proposals.remove(index);
+        let strength = (proposal.rect.size().min_elem() / REDACT_BLUR_DIVISOR).max(self.size);
+        let mut effect = EffectShape::new(proposal.rect.min, strength, EffectKind::Blur);
+        effect.end = proposal.rect.max;
+        self.push_shape(Shape::Effect(effect));
+    }
+
+    fn accept_all_proposals(&mut self) {
+        while !self.redact_proposals.is_empty() {
+            self.accept_proposal(0);
+        }
+    }
+
+    fn dismiss_proposals(&mut self) {
+        self.redact_proposals.clear();
+    }
+
+    fn save_recovery(&mut self, ctx: &egui::Context) {
+        let Some(recovery) = &mut self.recovery else {
+            return;
+        };
+        let selection = self.selection.map(|sel| sel.rect);
+        let now = ctx.input(|i| i.time);
+        if let Some(wait) = recovery.save(self.shapes_version, &self.shapes, &self.arrange, selection, now) {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(wait));
+        }
+    }
+
+    fn show_text_editor(&mut self, ctx: &egui::Context) {
+        if self.file_dialog_open {
+            return;
+        }
+        self.text_editor_rect = None;
+        let Some(input) = &mut self.text_input else {
+            return;
+        };
+        let Some(image_rect) = self.last_image_rect else {
+            return;
+        };
+        let screen_pos = self.last_view.to_screen(input.pos);
+        let editor_size = egui::vec2(220.0, 32.0);
+        let mut pos = screen_pos + egui::vec2(6.0, 6.0);
+        let mut rect = egui::Rect::from_min_size(pos, editor_size);
+        if rect.max.x > image_rect.max.x {
+            rect = rect.translate(egui::vec2(image_rect.max.x - rect.max.x, 0.0));
+        }
+        if rect.max.y > image_rect.max.y {
+            rect = rect.translate(egui::vec2(0.0, image_rect.max.y - rect.max.y));
+        }
+        if rect.min.x < image_rect.min.x {
+            rect = rect.translate(egui::vec2(image_rect.min.x - rect.min.x, 0.0));
+        }
+        if rect.min.y < image_rect.min.y {
+            rect = rect.translate(egui::vec2(0.0, image_rect.min.y - rect.min.y));
+        }
+        pos = rect.min;
+        self.text_editor_rect = Some(rect);
+
+        egui::Area::new("text_editor".into())
+            .order(egui::Order::Foreground)
+            .fixed_pos(pos)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .rounding(4.0)
+                    .inner_margin(egui::Margin::same(4.0))
+                    .show(ui, |ui| {
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut input.text)
+                                .desired_width(editor_size.x - 8.0)
+                                .font(egui::TextStyle::Body),
+                        );
+                        response.request_focus();
+                    });
+            });
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_shape_preview<F: Fn(egui::Pos2) -> egui::Pos2>(
+        &mut self,
+        shape: &Shape,
+        painter: &egui::Painter,
+        to_screen: &F,
+        scale: f32,
+        base_preview: Option<&RgbaImage>,
+        effect_index: &mut usize,
+        ctx: &egui::Context,
+        live: bool,
+    ) {
+        if let Some(bounds) = shape.bounds() {
+            let screen = egui::Rect::from_two_pos(to_screen(bounds.min), to_screen(bounds.max));
+            if !painter.clip_rect().intersects(screen) {
+                if matches!(shape, Shape::Effect(_)) {
+                    *effect_index += 1;
+                }
+                return;
+            }
+        }
+        match shape {
+            Shape::Stroke(stroke) if !stroke.widths.is_empty() => {
+                for (a, b, width) in stroke.segments() {
+                    painter.add(egui::Shape::line_segment(
+                        [to_screen(a), to_screen(b)],
+                        egui::Stroke::new(width, stroke.color),
+                    ));
+                }
+            }
+            Shape::Stroke(stroke) => {
+                let points: Vec<egui::Pos2> =
+                    stroke.points.iter().copied().map(to_screen).collect();
+                painter.add(egui::Shape::line(
+                    points,
+                    egui::Stroke::new(stroke.size, stroke.color),
                 ));
             }
             Shape::Line(line) => {
@@ -868,48 +2256,226 @@ impl EditorApp {
                     text.color,
                 );
             }
-            Shape::Effect(effect) => {
-                let rect_area =
-                    egui::Rect::from_two_pos(to_screen(effect.start), to_screen(effect.end));
-                let texture = base_preview
-                    .and_then(|base| self.ensure_effect_preview(ctx, base, effect, *effect_index));
-                if let Some(tex) = texture {
-                    painter.image(
-                        tex.id(),
-                        rect_area,
-                        egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)),
-                        egui::Color32::WHITE,
-                    );
-                } else {
-                    painter.add(egui::Shape::rect_stroke(
-                        rect_area,
-                        0.0,
-                        egui::Stroke::new(1.5, egui::Color32::WHITE),
-                    ));
+            Shape::Effect(effect) => {
+                let rect_area =
+                    egui::Rect::from_two_pos(to_screen(effect.start), to_screen(effect.end));
+                let texture = base_preview
+                    .and_then(|base| self.ensure_effect_preview(ctx, base, effect, *effect_index, live));
+                let outline = egui::Stroke::new(1.5, egui::Color32::WHITE);
+                if let Some((tex, uv)) = texture {
+                    painter.image(tex.id(), rect_area, uv, egui::Color32::WHITE);
+                } else if effect.outline == EffectOutline::Ellipse {
+                    painter.add(egui::Shape::closed_line(ellipse_points(rect_area, 40), outline));
+                } else if effect.outline == EffectOutline::Lasso {
+                    let points = effect.mask.iter().copied().map(to_screen).collect();
+                    painter.add(egui::Shape::closed_line(points, outline));
+                } else if !effect.mask.is_empty() {
+                    let points: Vec<egui::Pos2> = effect.mask.iter().copied().map(to_screen).collect();
+                    let stroke = egui::Stroke::new(
+                        effect.brush * scale,
+                        egui::Color32::from_white_alpha(96),
+                    );
+                    if let [point] = points.as_slice() {
+                        painter.circle_filled(*point, stroke.width / 2.0, stroke.color);
+                    } else {
+                        painter.add(egui::Shape::line(points, stroke));
+                    }
+                } else {
+                    painter.add(egui::Shape::rect_stroke(rect_area, 0.0, outline));
+                }
+                *effect_index += 1;
+            }
+        }
+    }
+
+    /// On a tool switch, remembers the colour and size the previous tool
+    /// was left with and picks up the new tool's.
+    fn sync_tool_style(&mut self) {
+        if self.tool == self.styled_tool {
+            return;
+        }
+        self.store_tool_style();
+        if let Some(style) = self.tool.style_key().and_then(|key| self.tool_styles.get(key)) {
+            let [r, g, b, a] = style.color;
+            self.color = egui::Color32::from_rgba_premultiplied(r, g, b, a);
+            self.size = style.size;
+        }
+        self.styled_tool = self.tool;
+    }
+
+    /// Saves the colour and size of the tool they belong to, writing the
+    /// state file when they changed.
+    fn store_tool_style(&mut self) {
+        let Some(key) = self.styled_tool.style_key() else {
+            return;
+        };
+        let style = ToolStyle {
+            color: self.color.to_array(),
+            size: self.size,
+        };
+        if self.tool_styles.set(key, style) {
+            if let Err(err) = self.tool_styles.save() {
+                log::warn!("tool styles: {}", err);
+            }
+        }
+    }
+
+    fn push_shape(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+        self.shapes_version = self.shapes_version.wrapping_add(1);
+        self.redo_stack.clear();
+        self.limit_undo();
+    }
+
+    /// Moves the undo floor up past the oldest undoable shapes until those
+    /// left are within `undo_limit`. Undoing and redoing only move shapes
+    /// between here and the redo stack, so adding one is all that can
+    /// exceed it.
+    fn limit_undo(&mut self) {
+        self.undo_floor = self.undo_floor.min(self.shapes.len());
+        let undoable = &self.shapes[self.undo_floor..];
+        let mut bytes: usize = undoable.iter().map(shape_bytes).sum();
+        let mut depth = undoable.len();
+        while depth > 0 && (depth > self.undo_limit.depth || bytes > self.undo_limit.bytes) {
+            bytes -= shape_bytes(&self.shapes[self.undo_floor]);
+            self.undo_floor += 1;
+            depth -= 1;
+        }
+    }
+
+    fn pop_shape(&mut self) {
+        if self.shapes.len() <= self.undo_floor {
+            if !self.shapes.is_empty() {
+                self.status.push(tr!("status-undo-limit"));
+            }
+            return;
+        }
+        if let Some(shape) = self.shapes.pop() {
+            self.arrange.removed(self.shapes.len());
+            self.redo_stack.push(shape);
+            self.shapes_version = self.shapes_version.wrapping_add(1);
+        }
+    }
+
+    /// The topmost shown shape within `tolerance` of `pos`.
+    fn topmost_at(&self, pos: egui::Pos2, tolerance: f32) -> Option<usize> {
+        self.shapes
+            .iter()
+            .enumerate()
+            .rposition(|(index, shape)| !self.arrange.is_hidden(index) && shape_hit(shape, pos, tolerance))
+    }
+
+    /// Removes the topmost shape under `pos`.
+    fn erase_at(&mut self, pos: egui::Pos2, tolerance: f32) {
+        let Some(index) = self.topmost_at(pos, tolerance) else {
+            return;
+        };
+        self.remove_shape(index);
+    }
+
+    fn remove_shape(&mut self, index: usize) {
+        if index < self.shapes.len() {
+            self.shapes.remove(index);
+            self.arrange.removed(index);
+            if index < self.undo_floor {
+                self.undo_floor -= 1;
+            }
+            self.shapes_edited();
+        }
+    }
+
+    /// Takes shape `from` out of the stacking order and puts it back in at
+    /// `to`.
+    fn move_shape(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.shapes.len() || to >= self.shapes.len() {
+            return;
+        }
+        let shape = self.shapes.remove(from);
+        self.shapes.insert(to, shape);
+        self.arrange.moved(from, to);
+        self.shapes_edited();
+    }
+
+    /// The shapes an export draws: all but those hidden in the layers panel.
+    fn visible_shapes(&self) -> Vec<Shape> {
+        self.shapes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.arrange.is_hidden(*index))
+            .map(|(_, shape)| shape.clone())
+            .collect()
+    }
+
+    /// Drops what was made for the shapes before an edit other than adding
+    /// or undoing one, which also ends redoing.
+    fn shapes_edited(&mut self) {
+        self.shapes_version = self.shapes_version.wrapping_add(1);
+        self.effect_previews.clear();
+        self.redo_stack.clear();
+    }
+
+    fn move_picked(&mut self, offset: egui::Vec2) {
+        if offset == egui::Vec2::ZERO || self.arrange.picked.is_empty() {
+            return;
+        }
+        for &index in &self.arrange.picked {
+            if let Some(shape) = self.shapes.get_mut(index) {
+                shape.translate(offset);
+            }
+        }
+        self.shapes_edited();
+    }
+
+    fn recolor_picked(&mut self, color: egui::Color32) {
+        if self.arrange.picked.is_empty() {
+            return;
+        }
+        for &index in &self.arrange.picked {
+            if let Some(shape) = self.shapes.get_mut(index) {
+                shape.recolor(color);
+            }
+        }
+        self.shapes_edited();
+    }
+
+    fn delete_picked(&mut self) {
+        let mut picked = std::mem::take(&mut self.arrange.picked);
+        picked.sort_unstable();
+        for index in picked.into_iter().rev() {
+            if index < self.shapes.len() {
+                self.shapes.remove(index);
+                self.arrange.removed(index);
+                if index < self.undo_floor {
+                    self.undo_floor -= 1;
                 }
-                *effect_index += 1;
             }
         }
+        self.shapes_edited();
     }
 
-    fn push_shape(&mut self, shape: Shape) {
-        self.shapes.push(shape);
-        self.shapes_version = self.shapes_version.wrapping_add(1);
-        self.effect_previews.clear();
-        self.redo_stack.clear();
+    fn group_picked(&mut self) {
+        if self.arrange.group() {
+            // Saved with the shapes for crash recovery.
+            self.shapes_version = self.shapes_version.wrapping_add(1);
+        }
     }
 
-    fn pop_shape(&mut self) {
-        if let Some(shape) = self.shapes.pop() {
-            self.redo_stack.push(shape);
+    fn ungroup_picked(&mut self) {
+        if self.arrange.ungroup() {
             self.shapes_version = self.shapes_version.wrapping_add(1);
-            self.effect_previews.clear();
         }
     }
 
+    /// Stylus state for the coming frame, from backends that know it.
+    pub(crate) fn set_pen(&mut self, pen: PenState) {
+        self.pen = pen;
+    }
+
     fn clear_shapes(&mut self) {
         if !self.shapes.is_empty() {
             self.shapes.clear();
+            self.undo_floor = 0;
+            self.arrange = Arrangement::default();
             self.shapes_version = self.shapes_version.wrapping_add(1);
             self.effect_previews.clear();
             self.redo_stack.clear();
@@ -920,7 +2486,6 @@ impl EditorApp {
         if let Some(shape) = self.redo_stack.pop() {
             self.shapes.push(shape);
             self.shapes_version = self.shapes_version.wrapping_add(1);
-            self.effect_previews.clear();
         }
     }
 
@@ -934,13 +2499,56 @@ impl EditorApp {
         max_count + 1
     }
 
-    fn render_full_image_without_effects(&self) -> RgbaImage {
-        let mut img = self.base_image.clone();
-        for shape in &self.shapes {
+    /// The capture as shown and exported: `base_image` with the adjustments
+    /// applied, made again only when they change.
+    fn adjusted_base(&mut self) -> Arc<RgbaImage> {
+        if self.adjustments.is_neutral() {
+            return self.base_image.clone();
+        }
+        self.adjusted_base
+            .get_or_insert_with(|| Arc::new(self.adjustments.apply(&self.base_image)))
+            .clone()
+    }
+
+    /// Drops everything rendered from the previous adjustments.
+    fn adjustments_changed(&mut self) {
+        self.adjusted_base = None;
+        self.histogram = None;
+        self.texture = None;
+        self.shapes_version = self.shapes_version.wrapping_add(1);
+        self.effect_previews.clear();
+    }
+
+    /// `render_full_image_without_effects`, recomposed only when the shapes
+    /// change rather than every frame.
+    fn annotated_base(&mut self) -> Rc<RgbaImage> {
+        if let Some(cache) = &self.annotation_cache {
+            if cache.shapes_version == self.shapes_version {
+                return cache.image.clone();
+            }
+        }
+        let started = Instant::now();
+        let image = Rc::new(self.render_full_image_without_effects());
+        self.stats.rendered(started);
+        self.annotation_cache = Some(AnnotationCache {
+            shapes_version: self.shapes_version,
+            image: image.clone(),
+        });
+        image
+    }
+
+    fn render_full_image_without_effects(&mut self) -> RgbaImage {
+        let mut img = (*self.adjusted_base()).clone();
+        let shown = self
+            .shapes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.arrange.is_hidden(*index));
+        for (_, shape) in shown {
             match shape {
                 Shape::Stroke(stroke) => {
-                    for win in stroke.points.windows(2) {
-                        draw_line(&mut img, win[0], win[1], stroke.color, stroke.size);
+                    for (a, b, width) in stroke.segments() {
+                        draw_line(&mut img, a, b, stroke.color, width);
                     }
                 }
                 Shape::Line(line) => {
@@ -985,127 +2593,136 @@ impl EditorApp {
         base: &RgbaImage,
         effect: &EffectShape,
         idx: usize,
-    ) -> Option<egui::TextureHandle> {
+        live: bool,
+    ) -> Option<(egui::TextureHandle, egui::Rect)> {
         let rect = normalize_rect(egui::Rect::from_two_pos(effect.start, effect.end));
         let (min_x, min_y, max_x, max_y) = rect_to_u32(base, rect)?;
-        let size_param = match effect.kind {
-            EffectKind::Pixelate => effect.size.round().max(4.0) as u32,
-            EffectKind::Blur => effect.size.round().max(2.0) as u32,
-        };
+        let size_param = effect_strength(effect);
         let rect_key = [min_x, min_y, max_x, max_y];
+        let mask_len = effect.mask.len();
         if let Some(preview) = self.effect_previews.get_mut(idx) {
             if preview.rect == rect_key
                 && preview.kind == effect.kind
                 && preview.size == size_param
+                && preview.mask_len == mask_len
                 && preview.shapes_version == self.shapes_version
             {
-                return Some(preview.texture.clone());
+                return Some((preview.texture.clone(), preview.uv));
             }
         }
 
-        let mut sub = crop_image_exact(base, rect)?;
-        match effect.kind {
-            EffectKind::Pixelate => apply_pixelate_full(&mut sub, size_param),
-            EffectKind::Blur => apply_blur_full(&mut sub, size_param.min(12)),
+        // While an effect is being dragged, wait until it has held still for
+        // a moment; the caller draws its outline until then.
+        if live {
+            let now = ctx.input(|i| i.time);
+            let key: PreviewKey = (rect_key, effect.kind, size_param, mask_len);
+            let since = match self.live_effect_change {
+                Some((last, since)) if last == key => since,
+                _ => {
+                    self.live_effect_change = Some((key, now));
+                    now
+                }
+            };
+            let remaining = EFFECT_PREVIEW_DEBOUNCE_SECS - (now - since);
+            if remaining > 0.0 {
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+                return None;
+            }
         }
-        let size = [sub.width() as usize, sub.height() as usize];
-        let pixels = sub.into_raw();
-        let image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
-        let texture = if let Some(preview) = self.effect_previews.get_mut(idx) {
-            preview.texture.set(image, egui::TextureOptions::default());
-            preview.rect = rect_key;
-            preview.kind = effect.kind;
-            preview.size = size_param;
-            preview.shapes_version = self.shapes_version;
-            preview.texture.clone()
-        } else {
-            let tex = ctx.load_texture(
-                format!("effect_preview_{}", idx),
-                image,
-                egui::TextureOptions::default(),
-            );
-            self.effect_previews.push(EffectPreview {
-                rect: rect_key,
-                kind: effect.kind,
-                size: size_param,
-                shapes_version: self.shapes_version,
-                texture: tex.clone(),
-            });
-            tex
-        };
-        Some(texture)
-    }
 
-    fn render_image(&self) -> RgbaImage {
-        let mut img = self.render_full_image();
-        if let Some(sel) = self.selection {
-            img = crop_image(&img, sel.rect);
+        let (mut sub, _) = render_effect_region(base, effect)?;
+        // Pixels the effect doesn't cover stay see-through.
+        if let Some(coverage) = effect.coverage((min_x, min_y), sub.width(), sub.height()) {
+            for (px, covered) in sub.pixels_mut().zip(coverage) {
+                if !covered {
+                    px[3] = 0;
+                }
+            }
         }
-        img
+        let max_side = ctx.input(|i| i.max_texture_side).min(DISPLAY_MAX_SIDE);
+        let image = display_image(&sub, max_side);
+        let size = image.size;
+
+        // Previews are uploaded into the top-left of a texture rounded up to
+        // whole tiles, so dragging an effect rewrites pixels in place instead
+        // of reallocating a texture every frame.
+        let fits = |preview: &EffectPreview| {
+            size[0] <= preview.capacity[0] && size[1] <= preview.capacity[1]
+        };
+        if let Some(preview) = self.effect_previews.get_mut(idx).filter(|p| fits(p)) {
+            preview
+                .texture
+                .set_partial([0, 0], image, egui::TextureOptions::default());
+        } else {
+            let capacity = [
+                (size[0].div_ceil(PREVIEW_TILE) * PREVIEW_TILE).min(max_side),
+                (size[1].div_ceil(PREVIEW_TILE) * PREVIEW_TILE).min(max_side),
+            ];
+            let mut padded = egui::ColorImage::new(capacity, egui::Color32::TRANSPARENT);
+            for (y, row) in image.pixels.chunks_exact(size[0]).enumerate() {
+                let start = y * capacity[0];
+                padded.pixels[start..start + size[0]].copy_from_slice(row);
+            }
+            if let Some(preview) = self.effect_previews.get_mut(idx) {
+                preview.texture.set(padded, egui::TextureOptions::default());
+                preview.capacity = capacity;
+            } else {
+                let texture = ctx.load_texture(
+                    format!("effect_preview_{}", idx),
+                    padded,
+                    egui::TextureOptions::default(),
+                );
+                self.effect_previews.push(EffectPreview {
+                    rect: rect_key,
+                    kind: effect.kind,
+                    size: size_param,
+                    mask_len,
+                    shapes_version: self.shapes_version,
+                    texture,
+                    capacity,
+                    uv: egui::Rect::NOTHING,
+                });
+            }
+        }
+        let preview = self.effect_previews.get_mut(idx)?;
+        preview.rect = rect_key;
+        preview.kind = effect.kind;
+        preview.size = size_param;
+        preview.mask_len = mask_len;
+        preview.shapes_version = self.shapes_version;
+        preview.uv = egui::Rect::from_min_max(
+            egui::Pos2::ZERO,
+            egui::pos2(
+                size[0] as f32 / preview.capacity[0] as f32,
+                size[1] as f32 / preview.capacity[1] as f32,
+            ),
+        );
+        Some((preview.texture.clone(), preview.uv))
     }
 
-    fn render_full_image(&self) -> RgbaImage {
-        let mut img = self.base_image.clone();
-        for shape in &self.shapes {
-            match shape {
-                Shape::Stroke(stroke) => {
-                    for win in stroke.points.windows(2) {
-                        draw_line(&mut img, win[0], win[1], stroke.color, stroke.size);
-                    }
-                }
-                Shape::Line(line) => {
-                    draw_line(&mut img, line.start, line.end, line.color, line.size);
-                }
-                Shape::Arrow(arrow) => {
-                    let (base, _, _) = arrow_head_points(arrow.start, arrow.end, arrow.size);
-                    draw_line(&mut img, arrow.start, base, arrow.color, arrow.size);
-                    draw_arrow_head_image(&mut img, arrow.start, arrow.end, arrow.color, arrow.size);
-                }
-                Shape::Rect(rect) => {
-                    let a = rect.start;
-                    let b = rect.end;
-                    let top_left = egui::pos2(a.x.min(b.x), a.y.min(b.y));
-                    let bottom_right = egui::pos2(a.x.max(b.x), a.y.max(b.y));
-                    let top_right = egui::pos2(bottom_right.x, top_left.y);
-                    let bottom_left = egui::pos2(top_left.x, bottom_right.y);
-                    draw_line(&mut img, top_left, top_right, rect.color, rect.size);
-                    draw_line(&mut img, top_right, bottom_right, rect.color, rect.size);
-                    draw_line(&mut img, bottom_right, bottom_left, rect.color, rect.size);
-                    draw_line(&mut img, bottom_left, top_left, rect.color, rect.size);
-                }
-                Shape::Circle(circle) => {
-                    draw_ellipse(&mut img, circle.start, circle.end, circle.color, circle.size);
-                }
-                Shape::CircleCount(counter) => {
-                    draw_circle_count_image(&mut img, counter);
-                }
-                Shape::Text(text) => {
-                    let scale = (text.size / 6.0).round().max(1.0) as u32;
-                    draw_text_bitmap(&mut img, text.pos, &text.text, text.color, scale);
-                }
-                Shape::Effect(effect) => {
-                    let rect = normalize_rect(egui::Rect::from_two_pos(effect.start, effect.end));
-                    match effect.kind {
-                        EffectKind::Pixelate => {
-                            let block = effect.size.round().max(4.0) as u32;
-                            apply_pixelate(&mut img, rect, block);
-                        }
-                        EffectKind::Blur => {
-                            let radius = effect.size.round().max(2.0) as u32;
-                            apply_blur(&mut img, rect, radius.min(12));
-                        }
-                    }
-                }
-            }
+    fn save_image(&mut self, ctx: &egui::Context) {
+        if self.pending_export.is_some() || self.portal_save.is_some() {
+            return;
         }
-        img
+        let file_name = format!("{}.png", self.save.file_stem_for(self.window.as_ref()));
+        if let Some(dialog) = self.save_dialog.clone() {
+            let (tx, rx) = mpsc::channel();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send(dialog(&file_name));
+                ctx.request_repaint();
+            });
+            self.portal_save = Some(rx);
+            return;
+        }
+        self.open_file_dialog(&file_name);
     }
 
-    fn save_image(&mut self) {
+    fn open_file_dialog(&mut self, file_name: &str) {
         if let Some(rect) = self.last_image_rect {
             let pos = rect.center() - FILE_DIALOG_SIZE * 0.5;
             self.file_dialog = FileDialog::new()
-                .default_file_name("screenshot.png")
+                .default_file_name(file_name)
                 .default_size(FILE_DIALOG_SIZE)
                 .default_pos(pos);
         }
@@ -1113,98 +2730,434 @@ impl EditorApp {
         self.file_dialog_open = true;
     }
 
-    fn copy_and_close(&mut self, ctx: &egui::Context) {
-        let rendered = self.render_image();
-        let mut copied = false;
-        let mut method = "none";
-
-        if is_wayland() {
-            if let Ok(png) = encode_png(&rendered) {
-                let wl_ok = try_wl_copy_png(&png).is_ok();
-                let mut x11_ok = false;
-
-                if try_xclip("image/png", &png).is_ok() {
-                    x11_ok = true;
-                } else if let Ok(bmp) = encode_bmp(&rendered) {
-                    if try_xclip("image/bmp", &bmp).is_ok() {
-                        x11_ok = true;
-                    }
+    /// Saves where the desktop's file chooser said once it closes. When it
+    /// can't be shown, the editor's own opens instead.
+    fn poll_portal_save(&mut self, ctx: &egui::Context) {
+        let outcome = match self.portal_save.as_ref().map(Receiver::try_recv) {
+            None | Some(Err(TryRecvError::Empty)) => return,
+            Some(Ok(outcome)) => outcome,
+            Some(Err(TryRecvError::Disconnected)) => Ok(None),
+        };
+        self.portal_save = None;
+        match outcome {
+            Ok(Some(path)) => self.start_export(ExportKind::Save(path), ctx),
+            Ok(None) => {
+                // Cancelling returns to the editor when it outlives exports.
+                if self.after_export == AfterExport::Close {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
+            }
+            Err(err) => {
+                log::warn!("portal save dialog: {}", err);
+                let file_name = format!("{}.png", self.save.file_stem_for(self.window.as_ref()));
+                self.open_file_dialog(&file_name);
+            }
+        }
+    }
 
-                if wl_ok || x11_ok {
-                    copied = true;
-                    method = match (wl_ok, x11_ok) {
-                        (true, true) => "wl-copy image/png + xclip image/png/bmp",
-                        (true, false) => "wl-copy image/png",
-                        (false, true) => "xclip image/png/bmp",
-                        (false, false) => "none",
-                    };
-                }
+    /// Starts rendering the result on a worker thread; `poll_export` closes
+    /// the editor once it lands. On failure the editor stays open with the
+    /// reason in the status line so the user can try again or save instead.
+    fn start_export(&mut self, kind: ExportKind, ctx: &egui::Context) {
+        if self.pending_export.is_some() {
+            return;
+        }
+        let key = self.render_key();
+        let source = ExportSource {
+            base: self.adjusted_base(),
+            shapes: self.visible_shapes(),
+            selection: key.selection,
+            prerendered: self.prerender.take().and_then(|p| p.take(key)),
+            icc_profile: self.icc_profile.clone(),
+            post_render: self.post_render.clone(),
+            matte: self.save.matte,
+            scale: self.export_scale(ctx),
+            frame: Frame::from_config(&self.save),
+            window: self.window.clone(),
+        };
+        self.pending_export = Some(ExportJob::spawn(source, kind, ctx.clone()));
+    }
+
+    /// Saves the kept selections and the current one as numbered files in
+    /// the save folder.
+    fn export_all(&mut self, ctx: &egui::Context) {
+        if self.pending_export.is_some() {
+            return;
+        }
+        let mut regions = self.extra_selections.clone();
+        regions.extend(self.selection.map(|sel| sel.rect));
+        let source = ExportSource {
+            base: self.adjusted_base(),
+            shapes: self.visible_shapes(),
+            selection: None,
+            prerendered: None,
+            icc_profile: self.icc_profile.clone(),
+            post_render: self.post_render.clone(),
+            matte: self.save.matte,
+            scale: self.export_scale(ctx),
+            frame: Frame::from_config(&self.save),
+            window: self.window.clone(),
+        };
+        self.pending_export = Some(ExportJob::spawn_all(
+            source,
+            regions,
+            self.save.directory(),
+            self.save.file_stem_for(self.window.as_ref()),
+            ctx.clone(),
+        ));
+    }
+
+    /// Saves the selection to the save folder as a clean crop and an
+    /// annotations-only layer of the same size.
+    fn export_layers(&mut self, ctx: &egui::Context) {
+        if self.pending_export.is_some() {
+            return;
+        }
+        let source = ExportSource {
+            base: self.adjusted_base(),
+            shapes: self.visible_shapes(),
+            selection: self.selection.map(|sel| sel.rect),
+            prerendered: None,
+            icc_profile: self.icc_profile.clone(),
+            post_render: self.post_render.clone(),
+            matte: self.save.matte,
+            scale: self.export_scale(ctx),
+            frame: Frame::from_config(&self.save),
+            window: self.window.clone(),
+        };
+        self.pending_export = Some(ExportJob::spawn_layers(
+            source,
+            self.save.directory(),
+            self.save.file_stem_for(self.window.as_ref()),
+            ctx.clone(),
+        ));
+    }
+
+    fn render_key(&self) -> RenderKey {
+        RenderKey {
+            shapes_version: self.shapes_version,
+            selection: self.selection.map(|sel| sel.rect),
+        }
+    }
+
+    /// Starts a `Prerender` once the shapes and selection have been left
+    /// alone for `PRERENDER_IDLE_SECS`.
+    fn prerender_when_idle(&mut self, ctx: &egui::Context) {
+        let busy = self.pending_export.is_some()
+            || self.active_shape.is_some()
+            || self.selection_drag.is_some()
+            || ctx.input(|i| i.pointer.any_down());
+        if busy || self.selection.is_none() {
+            return;
+        }
+        let key = self.render_key();
+        if self.prerender.as_ref().is_some_and(|p| p.key == key) {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let since = match self.idle_since {
+            Some((idle_key, since)) if idle_key == key => since,
+            _ => {
+                self.idle_since = Some((key, now));
+                now
             }
+        };
+        let remaining = PRERENDER_IDLE_SECS - (now - since);
+        if remaining > 0.0 {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+            return;
         }
+        let base = self.adjusted_base();
+        self.prerender = Some(Prerender::spawn(
+            key,
+            base,
+            self.visible_shapes(),
+            self.post_render.clone(),
+            Frame::from_config(&self.save),
+            self.export_scale(ctx),
+        ));
+    }
 
-        if copied {
-            self.status = Some(format!("Copied to clipboard ({})", method));
+    /// The scale saved PNGs are tagged with: the output's, for captures of
+    /// it.
+    fn export_scale(&self, ctx: &egui::Context) -> Option<f32> {
+        if self.from_screen {
+            ctx.native_pixels_per_point()
         } else {
-            self.status = Some("Clipboard copy failed".to_string());
+            None
+        }
+    }
+
+    /// Starts serving the result on the local network and shows its QR code;
+    /// `poll_share` closes the editor once a device fetched it.
+    fn share_on_lan(&mut self, ctx: &egui::Context) {
+        if self.pending_export.is_some() || self.lan_share.is_some() {
+            return;
+        }
+        match LanShare::start(&self.share, ctx.clone()) {
+            Ok((share, png)) => {
+                let url = share.url.clone();
+                self.lan_share = Some(share);
+                self.start_export(ExportKind::Share { png, url }, ctx);
+            }
+            Err(err) => self.status.push(err.user_message()),
+        }
+    }
+
+    fn poll_share(&mut self, ctx: &egui::Context) {
+        let Some(end) = self.lan_share.as_ref().and_then(LanShare::poll) else {
+            return;
+        };
+        let Some(share) = self.lan_share.take() else {
+            return;
+        };
+        match end {
+            ShareEnd::Downloaded(peer) => {
+                log::info!("shared capture fetched by {}", peer);
+                self.exports.borrow_mut().push(ExportResult::Shared { url: share.url.clone() });
+                self.after_export(ctx);
+            }
+            ShareEnd::TimedOut => self.status.push(tr!("status-share-timeout")),
+            ShareEnd::Failed => self.status.push(tr!("status-share-failed")),
+        }
+    }
+
+    fn show_share_overlay(&mut self, ctx: &egui::Context) {
+        self.share_rect = None;
+        let Some(share) = &self.lan_share else {
+            return;
+        };
+        let mut stop = false;
+        let response = egui::Window::new(tr!("share-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    let (rect, _) = ui.allocate_exact_size(
+                        egui::vec2(SHARE_QR_SIZE, SHARE_QR_SIZE),
+                        egui::Sense::hover(),
+                    );
+                    share.paint_qr(ui.painter(), rect);
+                    ui.label(egui::RichText::new(&share.url).monospace());
+                    ui.label(tr!("share-hint"));
+                    if ui.button(tr!("share-stop")).clicked() {
+                        stop = true;
+                    }
+                });
+            });
+        self.share_rect = response.map(|r| r.response.rect);
+        if stop {
+            self.stop_sharing();
         }
+    }
+
+    fn stop_sharing(&mut self) {
+        self.lan_share = None;
+        self.share_rect = None;
+        self.status.push(tr!("status-share-stopped"));
+    }
+
+    fn copy_and_close(&mut self, ctx: &egui::Context) {
+        self.start_export(ExportKind::Copy, ctx);
+    }
+
+    /// Closes the editor for the caller to capture again after the picked
+    /// delay, e.g. once a menu that closed when the editor appeared is open
+    /// again.
+    fn recapture_and_close(&mut self, ctx: &egui::Context) {
+        let Some(recapture) = self.recapture.as_ref() else {
+            return;
+        };
+        recapture.set(u64::from(self.recapture_secs) * 1000);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    /// Hands the selection to the caller to record and closes the editor.
+    fn record_and_close(&mut self, ctx: &egui::Context) {
+        let (Some(record), Some(region)) = (self.record.as_ref(), self.selection_region()) else {
+            return;
+        };
+        record.set(region);
         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
     }
+
+    /// Closes the editor after an export, or keeps it for another one as
+    /// configured.
+    fn after_export(&mut self, ctx: &egui::Context) {
+        match self.after_export {
+            // Other tabs stay open; only the exported one is done.
+            AfterExport::Close if self.tabs.len() > 1 => self.close_tab(self.active_tab, ctx),
+            AfterExport::Close => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            AfterExport::Stay => {}
+            AfterExport::Minimize => ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true)),
+        }
+    }
+
+    fn poll_export(&mut self, ctx: &egui::Context) {
+        let Some(outcome) = self.pending_export.as_ref().and_then(ExportJob::poll) else {
+            return;
+        };
+        self.pending_export = None;
+        match outcome {
+            Ok((exports, status)) => {
+                let link = match exports.as_slice() {
+                    // The editor stays open with the QR code until a device
+                    // fetches the capture.
+                    [ExportResult::Shared { url }] => {
+                        self.status.push_link(status, StatusLink::Url(url.clone()));
+                        return;
+                    }
+                    [ExportResult::Saved { path }
+                    | ExportResult::Recorded { path }
+                    | ExportResult::Emailed { path }
+                    | ExportResult::Copied {
+                        history_path: Some(path),
+                    }] => Some(path.clone()),
+                    // Export all links to the folder the regions went to.
+                    [ExportResult::Saved { path }, ..] => path.parent().map(Path::to_path_buf),
+                    _ => None,
+                };
+                match link {
+                    Some(path) => self.status.push_link(status, StatusLink::File(path)),
+                    None => self.status.push(status),
+                }
+                self.exports.borrow_mut().extend(exports);
+                self.after_export(ctx);
+            }
+            Err(status) => {
+                self.lan_share = None;
+                self.status.push(status);
+            }
+        }
+    }
+}
+
+/// The tool in use when the editor closes keeps its colour and size too.
+impl Drop for EditorApp {
+    fn drop(&mut self) {
+        self.store_tool_style();
+    }
 }
 
 impl eframe::App for EditorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.ui(ctx);
+    }
+}
+
+impl EditorApp {
+    /// One editor frame; shared by the eframe window and the layer-shell
+    /// surface.
+    pub(crate) fn ui(&mut self, ctx: &egui::Context) {
+        // Nothing drawn leaves the overlay clear for another capture.
+        if self.control.is_hidden() {
+            return;
+        }
+        let started = Instant::now();
+        self.theme.apply(ctx);
+        if ctx.input(|i| i.key_pressed(egui::Key::F12)) {
+            self.stats.shown = !self.stats.shown;
+        }
+        self.open_incoming_tabs(ctx);
+        if self.copy_region {
+            self.tool = Tool::Select;
+        }
+        self.chrome_hidden = self.chrome_hidden(ctx);
+        if let Some(accept) = self.accept.take() {
+            let kind = match accept {
+                Accept::Copy => ExportKind::Copy,
+                Accept::Save(path) => ExportKind::Save(path),
+            };
+            self.start_export(kind, ctx);
+        }
         if self.texture.is_none() {
+            // Only the on-screen copy is scaled; shapes stay in capture
+            // pixels and the image widget stretches the texture to fit.
+            let max_side = ctx.input(|i| i.max_texture_side).min(DISPLAY_MAX_SIDE);
+            let base = self.adjusted_base();
             self.texture = Some(ctx.load_texture(
                 "capture",
-                self.texture_image.clone(),
+                display_image(&base, max_side),
                 egui::TextureOptions::default(),
             ));
+            self.transparent = has_transparency(&self.base_image);
+        }
+        if self.transparent && self.checker_texture.is_none() {
+            self.checker_texture = Some(ctx.load_texture(
+                "checker",
+                checker_image(),
+                egui::TextureOptions {
+                    wrap_mode: egui::TextureWrapMode::Repeat,
+                    ..egui::TextureOptions::NEAREST
+                },
+            ));
+        }
+        if self.backdrop_texture.is_none() && self.theme.chrome().frosted {
+            self.backdrop_texture = Some(ctx.load_texture(
+                "capture_backdrop",
+                backdrop_image(&self.base_image),
+                egui::TextureOptions::LINEAR,
+            ));
         }
 
         egui::CentralPanel::default()
             .frame(egui::Frame::none())
             .show(ctx, |ui| {
-                if let Some(texture) = &self.texture {
-                    let scale = ctx.pixels_per_point();
-                    let image_size = self.image_size() / scale;
-                    let response = ui.add(
-                        egui::Image::new(texture)
-                            .fit_to_exact_size(image_size)
+                if let Some(texture) = self.texture.clone() {
+                    let natural = self.image_size() / ctx.pixels_per_point();
+                    let fit = display_rect(natural, ui.max_rect());
+                    self.handle_gesture(ctx, fit);
+                    let rect = self.zoomed_rect(fit);
+                    if let Some(checker) = self.checker_texture.as_ref().filter(|_| self.transparent) {
+                        paint_checkerboard(ui.painter(), checker.id(), rect);
+                    }
+                    let response = ui.put(
+                        rect,
+                        egui::Image::new(&texture)
+                            .fit_to_exact_size(rect.size())
                             .sense(egui::Sense::click_and_drag()),
                     );
                     let painter = ui.painter();
-                    self.last_image_rect = Some(response.rect);
-                    self.last_pixels_per_point = scale;
+                    // Zoomed in, only part of the capture is on screen.
+                    self.last_image_rect = Some(response.rect.intersect(ui.max_rect()));
+                    self.last_view = ViewTransform::new(response.rect, self.image_size());
                     self.handle_input(&response);
                     self.draw_overlay(&response, painter);
+                    self.show_context_menu(&response);
                 }
             });
 
+        self.poll_export(ctx);
+        self.poll_portal_save(ctx);
+        self.poll_share(ctx);
+        self.prerender_when_idle(ctx);
         self.file_dialog.update(ctx);
         self.file_dialog_open = matches!(self.file_dialog.state(), DialogState::Open);
 
         if let Some(path) = self.file_dialog.take_selected() {
-            let rendered = self.render_image();
-            match rendered.save(&path) {
-                Ok(()) => {
-                    self.status = Some(format!("Saved {}", path.display()));
-                }
-                Err(err) => {
-                    self.status = Some(format!("Save failed: {}", err));
-                }
-            }
+            self.start_export(ExportKind::Save(path), ctx);
             self.file_dialog_open = false;
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
         }
         if self.file_dialog_open && matches!(self.file_dialog.state(), DialogState::Closed) {
             self.file_dialog_open = false;
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            // Cancelling returns to the editor when it outlives exports.
+            if self.after_export == AfterExport::Close {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
         }
 
+        self.show_tab_strip(ctx);
+        self.show_layers_panel(ctx);
         self.show_tool_buttons(ctx);
         self.show_tool_controls(ctx);
         self.show_text_editor(ctx);
+        self.show_recovery_prompt(ctx);
+        self.show_preset_prompt(ctx);
+        self.show_share_overlay(ctx);
+        self.handle_keyboard(ctx);
+        self.save_recovery(ctx);
+        self.show_flash(ctx);
 
         let copy_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::C);
         let copy_shortcut_shift =
@@ -1232,7 +3185,20 @@ impl eframe::App for EditorApp {
             i.consume_shortcut(&save_shortcut) || i.consume_shortcut(&save_shortcut_cmd)
         });
         if save_requested {
-            self.save_image();
+            self.save_image(ctx);
+        }
+
+        // winit turns Ctrl+V into a text-only Paste event, so a pasted image
+        // only shows up as the key's release.
+        let paste_requested = self.text_input.is_none()
+            && ctx.input(|i| {
+                i.events.iter().any(|e| {
+                    matches!(e, egui::Event::Key { key: egui::Key::V, pressed: false, modifiers, .. }
+                        if modifiers.command)
+                })
+            });
+        if paste_requested {
+            self.paste_base();
         }
 
         let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::Z);
@@ -1251,13 +3217,18 @@ impl eframe::App for EditorApp {
             self.pop_shape();
         }
 
-        if self.tool != Tool::Select {
+        if !matches!(self.tool, Tool::Select | Tool::Arrange) {
             self.last_draw_tool = self.tool;
         }
+        self.sync_tool_style();
 
-        let enter_pressed = ctx.input(|i| i.key_pressed(egui::Key::Enter));
+        // The preset prompt handles its own Enter.
+        let enter_pressed =
+            self.preset_name.is_none() && ctx.input(|i| i.key_pressed(egui::Key::Enter));
         if enter_pressed {
-            if let Some(input) = self.text_input.take() {
+            if self.text_input.is_none() && !self.redact_proposals.is_empty() {
+                self.accept_all_proposals();
+            } else if let Some(input) = self.text_input.take() {
                 if !input.text.trim().is_empty() {
                     self.push_shape(Shape::Text(TextShape {
                         pos: input.pos,
@@ -1266,31 +3237,73 @@ impl eframe::App for EditorApp {
                         size: self.size.max(8.0),
                     }));
                 }
-            } else if self.tool == Tool::Select && self.selection.is_some() {
+            } else if self.tool == Tool::Select
+                && self.selection.is_some()
+                && ctx.memory(|m| m.focused().is_none())
+            {
                 self.tool = self.last_draw_tool;
             }
         }
 
+        if let Some(opened_at) = self.opened_at.take() {
+            profile::record("first frame", opened_at.elapsed());
+            self.stats.first_frame(opened_at.elapsed());
+        }
+
         let esc_pressed = ctx.input(|i| i.key_pressed(egui::Key::Escape));
         if esc_pressed {
             if self.text_input.is_some() {
                 self.text_input = None;
-            } else {
+            } else if self.preset_name.is_some() {
+                self.preset_name = None;
+            } else if !self.redact_proposals.is_empty() {
+                self.dismiss_proposals();
+            } else if self.tool == Tool::Arrange && !self.arrange.picked.is_empty() {
+                self.arrange.picked.clear();
+            } else if self.lan_share.is_some() {
+                self.stop_sharing();
+            } else if self.pending_export.is_none() {
                 ctx.send_viewport_cmd(egui::ViewportCommand::Close);
             }
         }
+
+        self.stats.frame_built(started);
+        if self.stats.shown {
+            self.stats.show(ctx, self.shapes.len());
+        }
+    }
+}
+
+fn button_tooltip(button: ToolbarButton) -> &'static str {
+    match button {
+        ToolbarButton::Select => tr!("tool-select"),
+        ToolbarButton::Pencil => tr!("tool-pencil"),
+        ToolbarButton::Line => tr!("tool-line"),
+        ToolbarButton::Arrow => tr!("tool-arrow"),
+        ToolbarButton::Rect => tr!("tool-rect"),
+        ToolbarButton::Circle => tr!("tool-circle"),
+        ToolbarButton::Marker => tr!("tool-marker"),
+        ToolbarButton::MarkerLine => tr!("tool-marker-line"),
+        ToolbarButton::CircleCount => tr!("tool-circle-count"),
+        ToolbarButton::Text => tr!("tool-text"),
+        ToolbarButton::Timestamp => tr!("tool-timestamp"),
+        ToolbarButton::Pixelate => tr!("tool-pixelate"),
+        ToolbarButton::Blur => tr!("tool-blur"),
+        ToolbarButton::Arrange => tr!("tool-arrange"),
+        ToolbarButton::Record => tr!("tool-record"),
+        ToolbarButton::Undo => tr!("tool-undo"),
+        ToolbarButton::Copy => tr!("tool-copy"),
+        ToolbarButton::Save => tr!("tool-save"),
+        ToolbarButton::Clear => tr!("tool-clear"),
     }
 }
 
-pub fn run_viewer(image: DynamicImage) -> Result<(), CaptureError> {
-    let mut options = eframe::NativeOptions::default();
-    options.viewport = egui::ViewportBuilder::default()
-        .with_title("Fireshot (Wayland)")
-        .with_app_id("org.fireshot.Fireshot")
-        .with_fullscreen(true)
-        .with_decorations(false)
-        .with_resizable(false)
-        .with_always_on_top();
+pub(crate) fn native_options(viewport: egui::ViewportBuilder) -> eframe::NativeOptions {
+    #[allow(unused_mut)]
+    let mut options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
     #[cfg(target_os = "linux")]
     {
         options.event_loop_builder = Some(Box::new(|builder| {
@@ -1298,10 +3311,96 @@ pub fn run_viewer(image: DynamicImage) -> Result<(), CaptureError> {
             winit::platform::x11::EventLoopBuilderExtX11::with_any_thread(builder, true);
         }));
     }
-    eframe::run_native(
+    options
+}
+
+/// Runs the editor until it closes and returns everything it exported.
+pub fn run_viewer(
+    image: DynamicImage,
+    mut options: ViewerOptions,
+) -> Result<Vec<ExportResult>, CaptureError> {
+    let opened_at = Instant::now();
+    let exports = Rc::new(RefCell::new(Vec::new()));
+    let mut app = EditorApp::new(image, exports.clone(), options.theme, options.tool_defaults);
+    app.opened_at = Some(opened_at);
+    app.icc_profile = options.icc_profile.clone();
+    app.from_screen = options.from_screen;
+    app.window = options.window.take();
+    if let Some(window) = app.window.as_ref().filter(|_| options.window_caption) {
+        app.caption_window(&window.caption());
+    }
+    app.toolbar = options.toolbar.clone();
+    app.record = options.record.clone();
+    app.recapture = options.recapture.clone();
+    app.recapture_secs = options.recapture.as_ref().map_or(0, RecaptureRequest::default_secs);
+    app.post_render = options.post_render.clone();
+    app.save_dialog = options.save_dialog.clone();
+    app.share = options.share.clone();
+    app.timestamp = options.timestamp.clone();
+    app.save = options.save.clone();
+    app.presets = options.presets.clone();
+    app.tool_styles = ToolStyles::load();
+    app.undo_limit = options.undo_limit;
+    app.control = options.control.clone();
+    if let Some(title) = options.title.take() {
+        app.tabs[0].title = title;
+    }
+    for (title, image) in std::mem::take(&mut options.tabs) {
+        app.tabs.push(Tab::parked(title, image));
+    }
+    app.after_export = options.after_export;
+    app.hide_ui_while_drawing = options.hide_ui_while_drawing;
+    app.flash = options.flash && options.from_screen;
+    if let Some(accept) = options.accept.take() {
+        // Scripts wait for the editor to exit.
+        app.after_export = AfterExport::Close;
+        app.accept = Some(accept);
+    }
+    if options.copy_region {
+        app.after_export = AfterExport::Close;
+        app.copy_region = true;
+    }
+    if let Some(region) = options.selection {
+        app.apply_preset(region);
+    }
+    // A quick pick isn't the place to offer restoring a lost session.
+    if !app.copy_region {
+        app.orphan = Orphan::find();
+    }
+    app.recovery = Recovery::start(app.base_image.clone());
+    let control = options.control.clone();
+    if options.layer_shell {
+        match layer_shell::Session::connect() {
+            Ok(session) => {
+                if app.after_export == AfterExport::Minimize {
+                    app.after_export = AfterExport::Close;
+                }
+                session.run(app, &control)?;
+                return Ok(exports.take());
+            }
+            Err(err) => log::debug!("layer shell unavailable, opening a window: {}", err),
+        }
+    }
+
+    let native = native_options(
+        egui::ViewportBuilder::default()
+            .with_title("Fireshot (Wayland)")
+            .with_app_id("org.fireshot.Fireshot")
+            .with_fullscreen(true)
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_always_on_top(),
+    );
+    let result = eframe::run_native(
         "Fireshot (Wayland)",
-        options,
-        Box::new(|_cc| Box::new(EditorApp::new(image))),
-    )
-    .map_err(|e| CaptureError::Io(e.to_string()))
+        native,
+        Box::new(move |cc| {
+            options.control.attach(&cc.egui_ctx);
+            Box::new(app)
+        }),
+    );
+    control.detach();
+    result.map_err(|e| CaptureError::Io(e.to_string()))?;
+    let exports = exports.take();
+    Ok(exports)
 }
@@ -0,0 +1,137 @@
+//! Arranging shapes already drawn: picking several, by clicking them or
+//! dragging a band round them, to move, recolour or delete together,
+//! groups whose shapes are always picked as one, and shapes hidden from the
+//! editor and exports from the layers panel.
+
+use eframe::egui;
+
+/// What a drag with the arrange tool is doing.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ArrangeDrag {
+    /// A band from `start`; what it touches is picked on release.
+    Band { start: egui::Pos2, end: egui::Pos2 },
+    /// Moving the picked shapes along with the pointer, last seen at `last`.
+    Moving { last: egui::Pos2 },
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Arrangement {
+    /// Indices of the picked shapes.
+    pub(crate) picked: Vec<usize>,
+    /// Shape indices picked together; a shape is in at most one group.
+    pub(crate) groups: Vec<Vec<usize>>,
+    /// Indices of shapes left out of the editor and exports.
+    pub(crate) hidden: Vec<usize>,
+    pub(crate) drag: Option<ArrangeDrag>,
+}
+
+impl Arrangement {
+    pub(crate) fn restored(groups: Vec<Vec<usize>>, hidden: Vec<usize>) -> Self {
+        Self {
+            groups,
+            hidden,
+            ..Self::default()
+        }
+    }
+
+    /// `index` and the rest of its group.
+    fn members(&self, index: usize) -> Vec<usize> {
+        self.groups
+            .iter()
+            .find(|group| group.contains(&index))
+            .cloned()
+            .unwrap_or_else(|| vec![index])
+    }
+
+    /// Picks `indices` and everything grouped with them, in place of what
+    /// was picked unless `add` is set.
+    pub(crate) fn pick(&mut self, indices: impl IntoIterator<Item = usize>, add: bool) {
+        if !add {
+            self.picked.clear();
+        }
+        for index in indices {
+            for member in self.members(index) {
+                if !self.picked.contains(&member) {
+                    self.picked.push(member);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn is_picked(&self, index: usize) -> bool {
+        self.picked.contains(&index)
+    }
+
+    pub(crate) fn is_hidden(&self, index: usize) -> bool {
+        self.hidden.contains(&index)
+    }
+
+    /// Hides shape `index`, which can't stay picked, or shows it again.
+    pub(crate) fn toggle_hidden(&mut self, index: usize) {
+        if self.is_hidden(index) {
+            self.hidden.retain(|&i| i != index);
+        } else {
+            self.hidden.push(index);
+            self.picked.retain(|&i| i != index);
+        }
+    }
+
+    /// Makes the picked shapes one group, taking them out of any other.
+    /// Returns whether the groups changed.
+    pub(crate) fn group(&mut self) -> bool {
+        if self.picked.len() < 2 {
+            return false;
+        }
+        let picked = &self.picked;
+        for group in &mut self.groups {
+            group.retain(|index| !picked.contains(index));
+        }
+        self.groups.retain(|group| group.len() > 1);
+        let mut group = self.picked.clone();
+        group.sort_unstable();
+        self.groups.push(group);
+        true
+    }
+
+    /// Breaks up the groups of the picked shapes, which stay picked.
+    /// Returns whether the groups changed.
+    pub(crate) fn ungroup(&mut self) -> bool {
+        let before = self.groups.len();
+        let picked = &self.picked;
+        self.groups
+            .retain(|group| !group.iter().any(|index| picked.contains(index)));
+        self.groups.len() != before
+    }
+
+    /// Follows shape `index` being removed: it is dropped from the picks
+    /// and groups, and the indices after it move down one.
+    pub(crate) fn removed(&mut self, index: usize) {
+        let follow = |indices: &mut Vec<usize>| {
+            indices.retain(|&i| i != index);
+            for i in indices.iter_mut().filter(|i| **i > index) {
+                *i -= 1;
+            }
+        };
+        follow(&mut self.picked);
+        follow(&mut self.hidden);
+        self.groups.iter_mut().for_each(follow);
+        self.groups.retain(|group| group.len() > 1);
+    }
+
+    /// Follows shape `from` being taken out and put back in at `to`.
+    pub(crate) fn moved(&mut self, from: usize, to: usize) {
+        let follow = |indices: &mut Vec<usize>| {
+            for i in indices.iter_mut() {
+                *i = match *i {
+                    i if i == from => to,
+                    i if from < i && i <= to => i - 1,
+                    i if to <= i && i < from => i + 1,
+                    i => i,
+                };
+            }
+        };
+        follow(&mut self.picked);
+        follow(&mut self.hidden);
+        self.groups.iter_mut().for_each(follow);
+    }
+}
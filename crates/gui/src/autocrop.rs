@@ -0,0 +1,56 @@
+//! Auto-crop: the content left once uniform borders are trimmed away, such
+//! as the letterboxing round a centred dialog or a solid wallpaper margin.
+
+use eframe::egui;
+use image::{Rgba, RgbaImage};
+
+use crate::image_ops::rect_to_u32;
+
+/// Largest per-channel difference from a border's colour still counted as
+/// border, so compression noise and dithered wallpaper trim too.
+const BORDER_TOLERANCE: u8 = 12;
+
+/// The box inside `area` after trimming, from each edge inwards, every row
+/// or column that is all the colour that edge starts with. `None` when
+/// there is no border to trim or nothing but border.
+pub(crate) fn content_box(image: &RgbaImage, area: egui::Rect) -> Option<egui::Rect> {
+    let (min_x, min_y, max_x, max_y) = rect_to_u32(image, area)?;
+    let (mut left, mut top, mut right, mut bottom) = (min_x, min_y, max_x, max_y);
+    let row = |y: u32, left: u32, right: u32, border: Rgba<u8>| {
+        (left..right).all(|x| matches(image.get_pixel(x, y), border))
+    };
+    let column = |x: u32, top: u32, bottom: u32, border: Rgba<u8>| {
+        (top..bottom).all(|y| matches(image.get_pixel(x, y), border))
+    };
+
+    let border = *image.get_pixel(left, top);
+    while top < bottom && row(top, left, right, border) {
+        top += 1;
+    }
+    let border = *image.get_pixel(left, bottom - 1);
+    while bottom > top && row(bottom - 1, left, right, border) {
+        bottom -= 1;
+    }
+    if top >= bottom {
+        return None;
+    }
+    let border = *image.get_pixel(left, top);
+    while left < right && column(left, top, bottom, border) {
+        left += 1;
+    }
+    let border = *image.get_pixel(right - 1, top);
+    while right > left && column(right - 1, top, bottom, border) {
+        right -= 1;
+    }
+    let trimmed = (left, top, right, bottom) != (min_x, min_y, max_x, max_y);
+    (trimmed && left < right).then(|| {
+        egui::Rect::from_min_max(
+            egui::pos2(left as f32, top as f32),
+            egui::pos2(right as f32, bottom as f32),
+        )
+    })
+}
+
+fn matches(pixel: &Rgba<u8>, border: Rgba<u8>) -> bool {
+    pixel.0.iter().zip(border.0).all(|(a, b)| a.abs_diff(b) <= BORDER_TOLERANCE)
+}
@@ -0,0 +1,112 @@
+//! The raster and effect kernels, public for `benches/kernels.rs` behind the
+//! `bench` feature. Thin wrappers only; the kernels stay crate-private.
+
+use eframe::egui;
+use fireshot_core::shapes::{
+    ArrowShape, CircleCountShape, CircleShape, EffectKind, EffectShape, LineShape, RectShape,
+    Shape, StrokeShape, TextShape,
+};
+use image::{Rgba, RgbaImage};
+
+pub fn draw_line(
+    img: &mut RgbaImage,
+    start: egui::Pos2,
+    end: egui::Pos2,
+    color: egui::Color32,
+    size: f32,
+) {
+    crate::draw::draw_line(img, start, end, color, size);
+}
+
+pub fn fill_triangle(
+    img: &mut RgbaImage,
+    a: egui::Pos2,
+    b: egui::Pos2,
+    c: egui::Pos2,
+    color: Rgba<u8>,
+) {
+    crate::draw::fill_triangle(img, a, b, c, color);
+}
+
+pub fn apply_blur(img: &mut RgbaImage, rect: egui::Rect, radius: u32) {
+    crate::effects::apply_blur(img, rect, radius);
+}
+
+pub fn apply_pixelate(img: &mut RgbaImage, rect: egui::Rect, block: u32) {
+    crate::effects::apply_pixelate(img, rect, block);
+}
+
+pub fn crop_image(img: &RgbaImage, rect: egui::Rect) -> RgbaImage {
+    crate::image_ops::crop_image(img, rect)
+}
+
+/// Shapes to render, opaque outside the crate.
+pub struct Scene(Vec<Shape>);
+
+impl Scene {
+    /// One of every shape, an effect of each kind, and a pen stroke
+    /// crossing the capture, laid out in proportion to `width` x `height`.
+    pub fn synthetic(width: u32, height: u32) -> Self {
+        let (w, h) = (width as f32, height as f32);
+        let at = |x: f32, y: f32| egui::pos2(w * x, h * y);
+        let color = egui::Color32::from_rgb(220, 50, 47);
+        let mut stroke = StrokeShape::new(at(0.05, 0.5), color, 6.0, None);
+        for i in 1..=200 {
+            let t = i as f32 / 200.0;
+            let y = 0.5 + 0.2 * (t * std::f32::consts::TAU * 3.0).sin();
+            stroke.push_point(at(0.05 + 0.9 * t, y), None, true);
+        }
+        let mut blur = EffectShape::new(at(0.55, 0.1), 12.0, EffectKind::Blur);
+        blur.end = at(0.9, 0.4);
+        let mut pixelate = EffectShape::new(at(0.1, 0.6), 16.0, EffectKind::Pixelate);
+        pixelate.end = at(0.45, 0.9);
+        Self(vec![
+            Shape::Effect(blur),
+            Shape::Effect(pixelate),
+            Shape::Stroke(stroke),
+            Shape::Line(LineShape {
+                start: at(0.1, 0.1),
+                end: at(0.4, 0.3),
+                color,
+                size: 4.0,
+            }),
+            Shape::Arrow(ArrowShape {
+                start: at(0.6, 0.6),
+                end: at(0.9, 0.9),
+                color,
+                size: 8.0,
+            }),
+            Shape::Rect(RectShape {
+                start: at(0.2, 0.2),
+                end: at(0.8, 0.8),
+                color,
+                size: 3.0,
+            }),
+            Shape::Circle(CircleShape {
+                start: at(0.3, 0.3),
+                end: at(0.5, 0.6),
+                color,
+                size: 5.0,
+            }),
+            Shape::CircleCount(CircleCountShape {
+                center: at(0.7, 0.2),
+                pointer: at(0.75, 0.3),
+                color,
+                size: 24.0,
+                count: 1,
+            }),
+            Shape::Text(TextShape {
+                pos: at(0.1, 0.05),
+                text: "Fireshot benchmark".to_string(),
+                color,
+                size: 24.0,
+            }),
+        ])
+    }
+}
+
+/// The whole capture with every shape of `scene` drawn on it, as exported
+/// without a selection.
+pub fn render_full_image(base: &RgbaImage, scene: &Scene) -> RgbaImage {
+    crate::export::render_selection(base, &scene.0, None)
+}
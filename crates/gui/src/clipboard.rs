@@ -1,37 +1,57 @@
-use std::io::Cursor;
-
+use fireshot_core::sandbox::{host_command, Sandbox};
+use fireshot_core::{profile, CaptureError};
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::png::PngEncoder;
 use image::RgbaImage;
 
-pub(crate) fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, image::ImageError> {
+use crate::color::set_png_scale;
+
+/// `image` as PNG; with a `scale`, tagged with the DPI of an output at that
+/// scale.
+pub(crate) fn encode_png(image: &RgbaImage, scale: Option<f32>) -> Result<Vec<u8>, image::ImageError> {
     let mut bytes = Vec::new();
-    let dyn_img = image::DynamicImage::ImageRgba8(image.clone());
-    dyn_img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)?;
+    image.write_with_encoder(PngEncoder::new(&mut bytes))?;
+    if let Some(scale) = scale {
+        set_png_scale(&mut bytes, scale);
+    }
     Ok(bytes)
 }
 
 pub(crate) fn encode_bmp(image: &RgbaImage) -> Result<Vec<u8>, image::ImageError> {
     let mut bytes = Vec::new();
-    let dyn_img = image::DynamicImage::ImageRgba8(image.clone());
-    dyn_img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Bmp)?;
+    image.write_with_encoder(BmpEncoder::new(&mut bytes))?;
     Ok(bytes)
 }
 
-pub(crate) fn try_wl_copy_png(bytes: &[u8]) -> Result<(), String> {
-    let mut child = std::process::Command::new("wl-copy")
-        .arg("--type")
-        .arg("image/png")
-        .arg("--foreground")
+pub(crate) fn try_wl_copy(mime: &str, bytes: &[u8]) -> Result<(), String> {
+    // flatpak-spawn starts even when the host has no wl-copy, so there it
+    // forks to serve the clipboard and its exit says whether it could.
+    let sandboxed = Sandbox::detect() == Some(Sandbox::Flatpak);
+    let mut command = host_command("wl-copy");
+    command.arg("--type").arg(mime);
+    if !sandboxed {
+        command.arg("--foreground");
+    }
+    let mut child = command
         .stdin(std::process::Stdio::piped())
         .spawn()
         .map_err(|e| e.to_string())?;
     if let Some(mut stdin) = child.stdin.take() {
         std::io::Write::write_all(&mut stdin, bytes).map_err(|e| e.to_string())?;
     }
-    Ok(())
+    if !sandboxed {
+        return Ok(());
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("wl-copy exited with {}", status))
+    }
 }
 
 pub(crate) fn try_xclip(mime: &str, bytes: &[u8]) -> Result<(), String> {
-    let mut child = std::process::Command::new("xclip")
+    let mut child = host_command("xclip")
         .arg("-selection")
         .arg("clipboard")
         .arg("-t")
@@ -54,3 +74,126 @@ pub(crate) fn try_xclip(mime: &str, bytes: &[u8]) -> Result<(), String> {
 pub(crate) fn is_wayland() -> bool {
     std::env::var("WAYLAND_DISPLAY").is_ok()
 }
+
+/// Copies an already encoded PNG, returning the method(s) that succeeded.
+/// `image` is used for the BMP fallback when xclip refuses PNG.
+pub(crate) fn copy_png(png: &[u8], image: &RgbaImage) -> Option<&'static str> {
+    let _timer = profile::Timer::start("clipboard handoff");
+    if !is_wayland() {
+        return None;
+    }
+    let wl_ok = try_wl_copy("image/png", png).is_ok();
+    let mut x11_ok = false;
+
+    if try_xclip("image/png", png).is_ok() {
+        x11_ok = true;
+    } else if let Ok(bmp) = encode_bmp(image) {
+        if try_xclip("image/bmp", &bmp).is_ok() {
+            x11_ok = true;
+        }
+    }
+
+    match (wl_ok, x11_ok) {
+        (true, true) => Some("wl-copy image/png + xclip image/png/bmp"),
+        (true, false) => Some("wl-copy image/png"),
+        (false, true) => Some("xclip image/png/bmp"),
+        (false, false) => None,
+    }
+}
+
+/// Copies an image to the clipboard outside of the editor.
+pub fn copy_image(image: &image::DynamicImage) -> Result<&'static str, CaptureError> {
+    let rgba = image.to_rgba8();
+    let png = encode_png(&rgba, None).map_err(|e| CaptureError::EncodeError {
+        format: "PNG".to_string(),
+        reason: e.to_string(),
+    })?;
+    copy_png(&png, &rgba).ok_or_else(no_clipboard)
+}
+
+/// Copies plain text (a path or URL) to the clipboard.
+pub fn copy_text(text: &str) -> Result<(), CaptureError> {
+    let wl = if is_wayland() {
+        try_wl_copy("text/plain;charset=utf-8", text.as_bytes())
+    } else {
+        Err("not a wayland session".to_string())
+    };
+    wl.or_else(|_| try_xclip("UTF8_STRING", text.as_bytes()))
+        .map_err(CaptureError::ClipboardUnavailable)
+}
+
+/// Image types the clipboard may offer, in order of preference.
+const PASTE_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/bmp"];
+
+fn clipboard_output(program: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = host_command(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(format!("{} exited with {}", program, output.status))
+    }
+}
+
+/// The first of `PASTE_TYPES` among the offered `targets`, one per line.
+fn paste_type(targets: &[u8]) -> Option<&'static str> {
+    let targets = String::from_utf8_lossy(targets);
+    PASTE_TYPES
+        .into_iter()
+        .find(|mime| targets.lines().any(|target| target.trim() == *mime))
+}
+
+fn try_wl_paste() -> Result<Vec<u8>, String> {
+    let targets = clipboard_output("wl-paste", &["--list-types"])?;
+    let mime = paste_type(&targets).ok_or("the clipboard holds no image")?;
+    clipboard_output("wl-paste", &["--no-newline", "--type", mime])
+}
+
+fn try_xclip_paste() -> Result<Vec<u8>, String> {
+    let targets = clipboard_output("xclip", &["-selection", "clipboard", "-t", "TARGETS", "-o"])?;
+    let mime = paste_type(&targets).ok_or("the clipboard holds no image")?;
+    clipboard_output("xclip", &["-selection", "clipboard", "-t", mime, "-o"])
+}
+
+/// Reads the image on the clipboard, such as a screenshot taken by another
+/// tool.
+pub fn paste_image() -> Result<RgbaImage, CaptureError> {
+    let bytes = if is_wayland() {
+        try_wl_paste().or_else(|_| try_xclip_paste())
+    } else {
+        try_xclip_paste()
+    }
+    .map_err(CaptureError::ClipboardUnavailable)?;
+    image::load_from_memory(&bytes)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| CaptureError::ClipboardUnavailable(e.to_string()))
+}
+
+/// The text on the clipboard, for pasting into text fields.
+pub(crate) fn paste_text() -> Option<String> {
+    let wl = || clipboard_output("wl-paste", &["--no-newline", "--type", "text"]);
+    let xclip = || {
+        clipboard_output("xclip", &["-selection", "clipboard", "-t", "UTF8_STRING", "-o"])
+    };
+    let bytes = if is_wayland() {
+        wl().or_else(|_| xclip())
+    } else {
+        xclip()
+    }
+    .ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+pub(crate) fn no_clipboard() -> CaptureError {
+    let reason = if !is_wayland() {
+        "not a wayland session"
+    } else if Sandbox::detect() == Some(Sandbox::Flatpak) {
+        "neither wl-copy nor xclip could be run on the host through flatpak-spawn"
+    } else {
+        "neither wl-copy nor xclip accepted the image"
+    };
+    CaptureError::ClipboardUnavailable(reason.to_string())
+}
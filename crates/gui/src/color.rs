@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{
+    ExtendedColorType, ImageEncoder, ImageError, ImageFormat, ImageResult, Rgb, RgbImage, RgbaImage,
+};
+use fireshot_core::config::Matte;
+use fireshot_core::window::WindowInfo;
+use moxcms::{ColorProfile, Layout, TransformOptions};
+
+/// Saves `image` (sRGB) to `path`. With a `profile`, PNG and JPEG files are
+/// converted into that profile's colour space and tagged with it, so viewers
+/// on the capturing output show the colours it showed; other formats, and
+/// profiles that can't be used, are saved as sRGB. Transparency is kept
+/// except in JPEG and BMP files, where it is laid over `matte`. With a
+/// `scale`, PNG files carry the DPI that shows them at their size on screen,
+/// and with a `window`, its title and app id.
+pub fn save_image(
+    image: &RgbaImage,
+    path: &Path,
+    profile: Option<&[u8]>,
+    matte: Matte,
+    scale: Option<f32>,
+    window: Option<&WindowInfo>,
+) -> ImageResult<()> {
+    let format = ImageFormat::from_path(path)?;
+    let converted = profile
+        .filter(|_| matches!(format, ImageFormat::Png | ImageFormat::Jpeg))
+        .and_then(|icc| Some((icc, from_srgb(image, icc)?)));
+    if format == ImageFormat::Png {
+        let (image, icc) = match &converted {
+            Some((icc, converted)) => (converted, Some(*icc)),
+            None => (image, None),
+        };
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        if let Some(icc) = icc {
+            encoder.set_icc_profile(icc.to_vec()).ok();
+        }
+        image.write_with_encoder(encoder)?;
+        if let Some(scale) = scale {
+            set_png_scale(&mut png, scale);
+        }
+        if let Some(window) = window {
+            set_png_window(&mut png, window);
+        }
+        return std::fs::write(path, png).map_err(ImageError::IoError);
+    }
+    let Some((icc, converted)) = converted else {
+        return match format {
+            ImageFormat::Jpeg | ImageFormat::Bmp => flatten(image, matte).save(path),
+            _ => image.save(path),
+        };
+    };
+
+    let (width, height) = converted.dimensions();
+    let rgb = flatten(&converted, matte);
+    let mut encoder = JpegEncoder::new(BufWriter::new(File::create(path)?));
+    encoder.set_icc_profile(icc.to_vec()).ok();
+    encoder.write_image(rgb.as_raw(), width, height, ExtendedColorType::Rgb8)
+}
+
+/// Pixels per inch of an output at scale 1, which documents assume for
+/// images without a DPI of their own.
+const BASE_DPI: f32 = 96.0;
+/// pHYs counts pixels per metre.
+const METRES_PER_INCH: f32 = 0.0254;
+/// Bytes before the first chunk after IHDR: the signature and IHDR itself.
+const PNG_HEADER_LEN: usize = 8 + 4 + 4 + 13 + 4;
+
+/// Adds a pHYs chunk to the encoded `png`, giving it `scale` times the base
+/// DPI so a capture of a HiDPI output is shown at the physical size it had
+/// there instead of `scale` times as large.
+pub(crate) fn set_png_scale(png: &mut Vec<u8>, scale: f32) {
+    if png.len() < PNG_HEADER_LEN || &png[12..16] != b"IHDR" {
+        return;
+    }
+    let per_metre = (BASE_DPI * scale / METRES_PER_INCH).round() as u32;
+    let mut data = Vec::with_capacity(4 + 9);
+    data.extend_from_slice(b"pHYs");
+    data.extend_from_slice(&per_metre.to_be_bytes());
+    data.extend_from_slice(&per_metre.to_be_bytes());
+    // The unit is the metre.
+    data.push(1);
+    insert_chunk(png, data);
+}
+
+/// Adds iTXt chunks to the encoded `png` with the title and app id of the
+/// window it was captured from, as `Title` and `Application`.
+pub(crate) fn set_png_window(png: &mut Vec<u8>, window: &WindowInfo) {
+    if png.len() < PNG_HEADER_LEN || &png[12..16] != b"IHDR" {
+        return;
+    }
+    for (keyword, text) in [("Application", &window.app_id), ("Title", &window.title)] {
+        if text.is_empty() {
+            continue;
+        }
+        let mut data = Vec::with_capacity(4 + keyword.len() + 5 + text.len());
+        data.extend_from_slice(b"iTXt");
+        data.extend_from_slice(keyword.as_bytes());
+        // Uncompressed, with empty language tag and translated keyword.
+        data.extend_from_slice(&[0, 0, 0, 0, 0]);
+        data.extend_from_slice(text.as_bytes());
+        insert_chunk(png, data);
+    }
+}
+
+/// Inserts a chunk right after IHDR; `data` is its type and contents.
+fn insert_chunk(png: &mut Vec<u8>, data: Vec<u8>) {
+    let mut chunk = Vec::with_capacity(4 + data.len() + 4);
+    chunk.extend_from_slice(&(data.len() as u32 - 4).to_be_bytes());
+    chunk.extend_from_slice(&data);
+    chunk.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+    png.splice(PNG_HEADER_LEN..PNG_HEADER_LEN, chunk);
+}
+
+/// Side of a checkerboard matte square, in pixels.
+const CHECKER_SIZE: u32 = 8;
+
+/// `image` laid over `matte`, for formats without an alpha channel.
+fn flatten(image: &RgbaImage, matte: Matte) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let back = match matte {
+            Matte::Color(color) => color,
+            Matte::Checkerboard if (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2) => [204; 3],
+            Matte::Checkerboard => [153; 3],
+        };
+        let a = u16::from(a);
+        let over = |c: u8, back: u8| ((u16::from(c) * a + u16::from(back) * (255 - a) + 127) / 255) as u8;
+        Rgb([over(r, back[0]), over(g, back[1]), over(b, back[2])])
+    })
+}
+
+fn from_srgb(image: &RgbaImage, icc: &[u8]) -> Option<RgbaImage> {
+    let target = ColorProfile::new_from_slice(icc).ok()?;
+    let transform = ColorProfile::new_srgb()
+        .create_transform_8bit(Layout::Rgba, &target, Layout::Rgba, TransformOptions::default())
+        .ok()?;
+    let mut converted = RgbaImage::new(image.width(), image.height());
+    transform.transform(image.as_raw(), &mut converted).ok()?;
+    Some(converted)
+}
@@ -0,0 +1,223 @@
+use eframe::egui;
+use fireshot_core::{tr, CaptureError};
+use image::{Rgba, RgbaImage};
+
+use crate::app::native_options;
+use crate::image_ops::{display_image, DISPLAY_MAX_SIDE};
+use crate::parallel::for_each_row_band;
+use crate::theme::Theme;
+
+const COMPARE_SIZE: egui::Vec2 = egui::Vec2 { x: 1200.0, y: 760.0 };
+/// Space between the two captures in a side-by-side image.
+const SIDE_BY_SIDE_GAP: u32 = 8;
+const GAP_COLOR: Rgba<u8> = Rgba([128, 128, 128, 255]);
+/// How bright unchanged pixels stay in the heatmap, out of 255.
+const HEATMAP_DIM: u16 = 90;
+
+/// How two captures are laid out in a comparison.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareMode {
+    #[default]
+    SideBySide,
+    /// Changed pixels in yellow to red by how much they changed, over a dimmed
+    /// grey copy of the first capture.
+    Diff,
+}
+
+/// Which pixels differ between two captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffStats {
+    pub changed: u64,
+    pub total: u64,
+}
+
+impl DiffStats {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.changed as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// `before` and `after` next to each other, top-aligned.
+pub fn side_by_side(before: &RgbaImage, after: &RgbaImage) -> RgbaImage {
+    let width = before.width() + SIDE_BY_SIDE_GAP + after.width();
+    let height = before.height().max(after.height());
+    let mut out = RgbaImage::new(width, height);
+    for y in 0..height {
+        for x in before.width()..before.width() + SIDE_BY_SIDE_GAP {
+            out.put_pixel(x, y, GAP_COLOR);
+        }
+    }
+    image::imageops::replace(&mut out, before, 0, 0);
+    image::imageops::replace(&mut out, after, i64::from(before.width() + SIDE_BY_SIDE_GAP), 0);
+    out
+}
+
+/// A heatmap of where `after` differs from `before` by more than `threshold`
+/// on any channel. Captures of different sizes are compared from their
+/// top-left; pixels only one of them covers count as changed.
+pub fn diff_heatmap(before: &RgbaImage, after: &RgbaImage, threshold: u8) -> (RgbaImage, DiffStats) {
+    let width = before.width().max(after.width());
+    let height = before.height().max(after.height());
+    let mut out = RgbaImage::new(width, height);
+    let row_bytes = width as usize * 4;
+    let changed = std::sync::atomic::AtomicU64::new(0);
+    for_each_row_band(out.as_mut(), row_bytes, 1, |first, band| {
+        let mut band_changed = 0;
+        for (i, row) in band.chunks_exact_mut(row_bytes).enumerate() {
+            let y = (first + i) as u32;
+            for (x, px) in row.chunks_exact_mut(4).enumerate() {
+                let x = x as u32;
+                let a = before.get_pixel_checked(x, y);
+                let b = after.get_pixel_checked(x, y);
+                let delta = match (a, b) {
+                    (Some(a), Some(b)) => a.0.iter().zip(b.0).map(|(a, b)| a.abs_diff(b)).max().unwrap_or(0),
+                    _ => u8::MAX,
+                };
+                if delta > threshold {
+                    band_changed += 1;
+                    // Yellow for slight changes, red for complete ones.
+                    px.copy_from_slice(&[255, 220 - (u16::from(delta) * 220 / 255) as u8, 0, 255]);
+                } else {
+                    let grey = a.map(|a| luma(a) * HEATMAP_DIM / 255).unwrap_or(0) as u8;
+                    px.copy_from_slice(&[grey, grey, grey, 255]);
+                }
+            }
+        }
+        changed.fetch_add(band_changed, std::sync::atomic::Ordering::Relaxed);
+    });
+    let stats = DiffStats {
+        changed: changed.into_inner(),
+        total: u64::from(width) * u64::from(height),
+    };
+    (out, stats)
+}
+
+fn luma(px: &Rgba<u8>) -> u16 {
+    (u16::from(px[0]) * 3 + u16::from(px[1]) * 6 + u16::from(px[2])) / 10
+}
+
+/// What the comparison window was closed with.
+pub enum CompareExport {
+    Save(RgbaImage),
+    Copy(RgbaImage),
+}
+
+struct CompareApp {
+    theme: Theme,
+    mode: CompareMode,
+    side_by_side: RgbaImage,
+    diff: RgbaImage,
+    stats: DiffStats,
+    textures: [Option<egui::TextureHandle>; 2],
+    export: std::rc::Rc<std::cell::RefCell<Option<CompareExport>>>,
+}
+
+impl CompareApp {
+    fn current(&self) -> &RgbaImage {
+        match self.mode {
+            CompareMode::SideBySide => &self.side_by_side,
+            CompareMode::Diff => &self.diff,
+        }
+    }
+
+    fn texture(&mut self, ctx: &egui::Context) -> egui::TextureHandle {
+        let slot = self.mode as usize;
+        if self.textures[slot].is_none() {
+            let image = display_image(self.current(), DISPLAY_MAX_SIDE);
+            self.textures[slot] =
+                Some(ctx.load_texture("fireshot-compare", image, egui::TextureOptions::LINEAR));
+        }
+        self.textures[slot].clone().expect("texture was just loaded")
+    }
+
+    fn finish(&mut self, ctx: &egui::Context, export: fn(RgbaImage) -> CompareExport) {
+        *self.export.borrow_mut() = Some(export(self.current().clone()));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+}
+
+impl eframe::App for CompareApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.theme.apply(ctx);
+        egui::TopBottomPanel::top("compare-bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.mode, CompareMode::SideBySide, tr!("compare-side-by-side"));
+                ui.selectable_value(&mut self.mode, CompareMode::Diff, tr!("compare-diff"));
+                ui.separator();
+                ui.label(tr!(
+                    "compare-stats",
+                    changed = self.stats.changed,
+                    total = self.stats.total,
+                    percent = format!("{:.2}", self.stats.percent())
+                ));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button(tr!("compare-copy")).clicked() {
+                        self.finish(ctx, CompareExport::Copy);
+                    }
+                    if ui.button(tr!("compare-save")).clicked() {
+                        self.finish(ctx, CompareExport::Save);
+                    }
+                });
+            });
+        });
+        let texture = self.texture(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.centered_and_justified(|ui| {
+                ui.add(egui::Image::new(&texture).shrink_to_fit());
+            });
+        });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Tab)) {
+            self.mode = match self.mode {
+                CompareMode::SideBySide => CompareMode::Diff,
+                CompareMode::Diff => CompareMode::SideBySide,
+            };
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
+
+/// Shows `before` and `after` side by side or as a diff heatmap (Tab
+/// switches) and returns the comparison the user saved or copied, if any.
+pub fn run_compare(
+    before: RgbaImage,
+    after: RgbaImage,
+    mode: CompareMode,
+    threshold: u8,
+    theme: Theme,
+) -> Result<Option<CompareExport>, CaptureError> {
+    let (diff, stats) = diff_heatmap(&before, &after, threshold);
+    let side_by_side = side_by_side(&before, &after);
+    let options = native_options(
+        egui::ViewportBuilder::default()
+            .with_title("Fireshot")
+            .with_app_id("org.fireshot.Fireshot")
+            .with_inner_size(COMPARE_SIZE),
+    );
+    let export = std::rc::Rc::new(std::cell::RefCell::new(None));
+    let app_export = export.clone();
+    eframe::run_native(
+        "Fireshot",
+        options,
+        Box::new(move |_cc| {
+            Box::new(CompareApp {
+                theme,
+                mode,
+                side_by_side,
+                diff,
+                stats,
+                textures: [None, None],
+                export: app_export,
+            })
+        }),
+    )
+    .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let export = export.borrow_mut().take();
+    Ok(export)
+}
@@ -0,0 +1,66 @@
+use eframe::egui;
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+
+use crate::text::{draw_text_bitmap, text_bitmap_size};
+
+/// Steps per row of a contact sheet.
+const COLUMNS: usize = 2;
+/// Width each step is scaled down to.
+const TILE_WIDTH: u32 = 640;
+/// Space round and between the steps.
+const GAP: u32 = 24;
+const BACKGROUND: Rgba<u8> = Rgba([245, 245, 245, 255]);
+const BADGE: Rgba<u8> = Rgba([32, 32, 32, 255]);
+/// Scale of the bitmap font the step numbers are drawn in.
+const NUMBER_SCALE: u32 = 4;
+const NUMBER_PADDING: u32 = 8;
+
+/// `steps` in a grid, each scaled to the same width and numbered from 1 in
+/// its top-left corner.
+pub fn contact_sheet(steps: &[RgbaImage]) -> RgbaImage {
+    let tiles: Vec<RgbaImage> = steps
+        .iter()
+        .map(|step| {
+            let width = TILE_WIDTH.min(step.width()).max(1);
+            let height = (step.height() as u64 * width as u64 / step.width().max(1) as u64).max(1) as u32;
+            image::imageops::resize(step, width, height, FilterType::Triangle)
+        })
+        .collect();
+    let rows: Vec<&[RgbaImage]> = tiles.chunks(COLUMNS).collect();
+    let row_heights: Vec<u32> = rows
+        .iter()
+        .map(|row| row.iter().map(RgbaImage::height).max().unwrap_or(0))
+        .collect();
+    let columns = tiles.len().clamp(1, COLUMNS) as u32;
+    let width = GAP + columns * (TILE_WIDTH + GAP);
+    let height = GAP + row_heights.iter().map(|h| h + GAP).sum::<u32>();
+    let mut sheet = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+    let mut y = GAP;
+    let mut number = 1;
+    for (row, row_height) in rows.iter().zip(&row_heights) {
+        for (column, tile) in row.iter().enumerate() {
+            let x = GAP + column as u32 * (TILE_WIDTH + GAP);
+            image::imageops::replace(&mut sheet, tile, i64::from(x), i64::from(y));
+            draw_number(&mut sheet, x, y, number);
+            number += 1;
+        }
+        y += row_height + GAP;
+    }
+    sheet
+}
+
+fn draw_number(sheet: &mut RgbaImage, x: u32, y: u32, number: usize) {
+    let text = number.to_string();
+    let (text_width, text_height) = text_bitmap_size(&text, NUMBER_SCALE);
+    let badge_width = text_width.max(0) as u32 + NUMBER_PADDING * 2;
+    let badge_height = text_height.max(0) as u32 + NUMBER_PADDING * 2;
+    for py in y..(y + badge_height).min(sheet.height()) {
+        for px in x..(x + badge_width).min(sheet.width()) {
+            sheet.put_pixel(px, py, BADGE);
+        }
+    }
+    let pos = egui::pos2((x + NUMBER_PADDING) as f32, (y + NUMBER_PADDING) as f32);
+    draw_text_bitmap(sheet, pos, &text, egui::Color32::WHITE, NUMBER_SCALE);
+}
@@ -0,0 +1,297 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+use fireshot_core::config::{
+    AfterExport, EditorConfig, RegionPreset, SaveConfig, ShareConfig, TimestampConfig,
+    ToolbarConfig,
+};
+use fireshot_core::window::WindowInfo;
+use fireshot_core::{CaptureError, RecordRegion};
+use image::RgbaImage;
+
+use crate::shapes::{button_action, Tool, ToolAction};
+use crate::theme::{parse_hex_color, Theme};
+
+/// Handle for poking a running editor from other threads (e.g. a DBus
+/// service in the same process).
+#[derive(Clone, Default)]
+pub struct EditorControl {
+    ctx: Arc<Mutex<Option<egui::Context>>>,
+    /// Images handed over to open as new tabs, with their titles.
+    tabs: Arc<Mutex<Vec<(String, RgbaImage)>>>,
+    /// Out of the way while another capture is taken.
+    hidden: Arc<AtomicBool>,
+}
+
+impl EditorControl {
+    pub(crate) fn attach(&self, ctx: &egui::Context) {
+        if let Ok(mut slot) = self.ctx.lock() {
+            *slot = Some(ctx.clone());
+        }
+    }
+
+    pub(crate) fn detach(&self) {
+        if let Ok(mut slot) = self.ctx.lock() {
+            *slot = None;
+        }
+    }
+
+    /// Raises the editor window, if one is open.
+    pub fn focus(&self) {
+        let Ok(slot) = self.ctx.lock() else {
+            return;
+        };
+        if let Some(ctx) = slot.as_ref() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            ctx.request_repaint();
+        }
+    }
+
+    /// Gets the editor out of the way of a capture: the window is minimized
+    /// and the layer-shell overlay draws nothing, so it turns see-through.
+    /// Returns `false` when no editor is open.
+    pub fn hide(&self) -> bool {
+        let Ok(slot) = self.ctx.lock() else {
+            return false;
+        };
+        let Some(ctx) = slot.as_ref() else {
+            return false;
+        };
+        self.hidden.store(true, Ordering::SeqCst);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        ctx.request_repaint();
+        true
+    }
+
+    /// Brings the editor back after `hide`, as far as the desktop lets a
+    /// minimized window be restored.
+    pub fn show(&self) {
+        if !self.hidden.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let Ok(slot) = self.ctx.lock() else {
+            return;
+        };
+        if let Some(ctx) = slot.as_ref() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.request_repaint();
+        }
+    }
+
+    pub(crate) fn is_hidden(&self) -> bool {
+        self.hidden.load(Ordering::SeqCst)
+    }
+
+    /// Opens `image` in a new tab of the editor and raises it. Returns
+    /// `false` when no editor is open.
+    pub fn open_tab(&self, title: String, image: RgbaImage) -> bool {
+        let Ok(slot) = self.ctx.lock() else {
+            return false;
+        };
+        let Some(ctx) = slot.as_ref() else {
+            return false;
+        };
+        if let Ok(mut tabs) = self.tabs.lock() {
+            tabs.push((title, image));
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        ctx.request_repaint();
+        true
+    }
+
+    pub(crate) fn take_tabs(&self) -> Vec<(String, RgbaImage)> {
+        self.tabs
+            .lock()
+            .map(|mut tabs| std::mem::take(&mut *tabs))
+            .unwrap_or_default()
+    }
+}
+
+/// Where the editor leaves the region picked with its Record button, for
+/// the caller to record once the editor has closed.
+#[derive(Clone, Default)]
+pub struct RecordRequest {
+    region: Arc<Mutex<Option<RecordRegion>>>,
+}
+
+impl RecordRequest {
+    pub(crate) fn set(&self, region: RecordRegion) {
+        if let Ok(mut slot) = self.region.lock() {
+            *slot = Some(region);
+        }
+    }
+
+    /// The region to record, if the editor was closed with Record.
+    pub fn take(&self) -> Option<RecordRegion> {
+        self.region.lock().ok().and_then(|mut slot| slot.take())
+    }
+}
+
+/// The tool, colour and size the editor opens with.
+#[derive(Debug, Clone, Copy)]
+pub struct ToolDefaults {
+    pub(crate) tool: Tool,
+    pub(crate) color: egui::Color32,
+    pub(crate) size: f32,
+}
+
+impl Default for ToolDefaults {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Select,
+            color: egui::Color32::from_rgb(255, 0, 0),
+            size: 3.0,
+        }
+    }
+}
+
+impl ToolDefaults {
+    /// From `[editor] default_*`; an unreadable colour keeps the default.
+    pub fn from_config(config: &EditorConfig) -> Self {
+        let defaults = Self::default();
+        let tool = match config.default_tool.map(|button| button_action(button).0) {
+            Some(ToolAction::Tool(tool)) => tool,
+            _ => defaults.tool,
+        };
+        Self {
+            tool,
+            color: parse_hex_color(&config.default_color).unwrap_or(defaults.color),
+            size: config.default_size.clamp(1.0, 20.0),
+        }
+    }
+}
+
+/// How far back Undo reaches.
+#[derive(Debug, Clone, Copy)]
+pub struct UndoLimit {
+    pub(crate) depth: usize,
+    /// Rough bytes, as `shape_bytes` counts them.
+    pub(crate) bytes: usize,
+}
+
+impl Default for UndoLimit {
+    fn default() -> Self {
+        Self::from_config(&EditorConfig::default())
+    }
+}
+
+impl UndoLimit {
+    /// From `[editor] undo_depth` and `undo_memory_mb`.
+    pub fn from_config(config: &EditorConfig) -> Self {
+        Self {
+            depth: config.undo_depth,
+            bytes: (config.undo_memory_mb as usize).saturating_mul(1 << 20),
+        }
+    }
+}
+
+/// Where the editor leaves the delay picked with Recapture, for the caller
+/// to capture the screen again after it and open a new editor on that.
+#[derive(Clone, Default)]
+pub struct RecaptureRequest {
+    default_secs: u32,
+    delay_ms: Arc<Mutex<Option<u64>>>,
+}
+
+impl RecaptureRequest {
+    /// A request whose delay starts out at `default_secs`.
+    pub fn new(default_secs: u32) -> Self {
+        Self {
+            default_secs,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn default_secs(&self) -> u32 {
+        self.default_secs
+    }
+
+    pub(crate) fn set(&self, delay_ms: u64) {
+        if let Ok(mut slot) = self.delay_ms.lock() {
+            *slot = Some(delay_ms);
+        }
+    }
+
+    /// The delay to recapture after, if the editor was closed with
+    /// Recapture.
+    pub fn take(&self) -> Option<u64> {
+        self.delay_ms.lock().ok().and_then(|mut slot| slot.take())
+    }
+}
+
+/// An export the editor performs as soon as it opens, then closes, for
+/// scripts that want the editor's rendering without clicking through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Accept {
+    Copy,
+    Save(PathBuf),
+}
+
+/// Runs on every rendered export before it is copied or saved, such as a
+/// user script redacting parts of it.
+pub type PostRender = Arc<dyn Fn(&mut RgbaImage) + Send + Sync>;
+
+/// Asks the desktop where to save, suggesting a file name, and blocks until
+/// it answers; `None` when the user cancels. Called off the UI thread.
+pub type SaveDialog = Arc<dyn Fn(&str) -> Result<Option<PathBuf>, CaptureError> + Send + Sync>;
+
+/// Everything the caller can configure about an editor session.
+#[derive(Clone, Default)]
+pub struct ViewerOptions {
+    pub control: EditorControl,
+    pub theme: Theme,
+    /// Draw on the wlr-layer-shell overlay layer when the compositor has it,
+    /// instead of a fullscreen window.
+    pub layer_shell: bool,
+    /// ICC profile of the captured output. Saved PNG/JPEG files are
+    /// converted into it and tagged with it; `None` saves sRGB.
+    pub icc_profile: Option<Arc<[u8]>>,
+    /// The image was just captured from the screen the editor opens on, so
+    /// its PNGs carry the DPI of that output's scale.
+    pub from_screen: bool,
+    /// The focused window when the capture was taken; fills `{title}` and
+    /// `{app_id}` in file names and is named in saved PNGs.
+    pub window: Option<WindowInfo>,
+    /// Write `window`'s title and app id in the capture's top-left corner.
+    pub window_caption: bool,
+    /// Where the tool buttons start out; the grip moves them from there.
+    pub toolbar: ToolbarConfig,
+    /// Shows the Record button, which closes the editor and leaves the
+    /// selection here.
+    pub record: Option<RecordRequest>,
+    /// Shows Recapture in the context menu, which closes the editor and
+    /// leaves the picked delay here.
+    pub recapture: Option<RecaptureRequest>,
+    pub post_render: Option<PostRender>,
+    /// Saves through this instead of the editor's own file chooser.
+    pub save_dialog: Option<SaveDialog>,
+    /// How the Share on LAN export serves the capture.
+    pub share: ShareConfig,
+    /// What the Insert timestamp button writes and where.
+    pub timestamp: TimestampConfig,
+    pub tool_defaults: ToolDefaults,
+    pub undo_limit: UndoLimit,
+    /// Names the save dialog suggests and emailed captures.
+    pub save: SaveConfig,
+    /// Named selections offered in the context menu.
+    pub presets: Vec<RegionPreset>,
+    /// Whether the editor closes once an export finishes.
+    pub after_export: AfterExport,
+    /// Hide the editor's panels while a shape is being drawn.
+    pub hide_ui_while_drawing: bool,
+    /// Flash white as the editor opens, to show the capture was taken.
+    pub flash: bool,
+    /// Start with this area selected.
+    pub selection: Option<RecordRegion>,
+    /// Export right away and close.
+    pub accept: Option<Accept>,
+    /// Show only the selection overlay, and copy the area and close as soon
+    /// as one is picked.
+    pub copy_region: bool,
+    /// Name of the first tab; "Capture" when unset.
+    pub title: Option<String>,
+    /// More images, opened beside the first in tabs of their own.
+    pub tabs: Vec<(String, RgbaImage)>,
+}
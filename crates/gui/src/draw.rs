@@ -1,8 +1,11 @@
 use eframe::egui;
+use fireshot_core::shapes::CircleCountShape;
 use image::{Rgba, RgbaImage};
 
+use crate::parallel::for_each_row_band;
 use crate::text::{circlecount_text_scale, draw_text_bitmap, text_bitmap_size};
-use crate::shapes::{CircleCountShape, ToolIcon};
+use crate::shapes::ToolIcon;
+use crate::theme::Chrome;
 
 pub(crate) const CIRCLECOUNT_PADDING: f32 = 2.0;
 const CIRCLECOUNT_THICKNESS_OFFSET: f32 = 15.0;
@@ -240,7 +243,13 @@ fn draw_filled_circle(
     }
 }
 
-fn fill_triangle(img: &mut RgbaImage, a: egui::Pos2, b: egui::Pos2, c: egui::Pos2, color: Rgba<u8>) {
+pub(crate) fn fill_triangle(
+    img: &mut RgbaImage,
+    a: egui::Pos2,
+    b: egui::Pos2,
+    c: egui::Pos2,
+    color: Rgba<u8>,
+) {
     let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as i32;
     let max_x = a.x.max(b.x).max(c.x).ceil().min(img.width() as f32) as i32;
     let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as i32;
@@ -251,19 +260,29 @@ fn fill_triangle(img: &mut RgbaImage, a: egui::Pos2, b: egui::Pos2, c: egui::Pos
         return;
     }
 
-    for y in min_y..max_y {
-        for x in min_x..max_x {
-            let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
-            let w0 = edge_function(b, c, p);
-            let w1 = edge_function(c, a, p);
-            let w2 = edge_function(a, b, p);
-            let has_pos = w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0;
-            let has_neg = w0 <= 0.0 && w1 <= 0.0 && w2 <= 0.0;
-            if has_pos || has_neg {
-                img.put_pixel(x as u32, y as u32, color);
+    if max_x <= min_x || max_y <= min_y {
+        return;
+    }
+
+    let row_bytes = img.width() as usize * 4;
+    let rows = &mut img.as_mut()[min_y as usize * row_bytes..max_y as usize * row_bytes];
+    for_each_row_band(rows, row_bytes, 1, |first, band| {
+        for (i, row) in band.chunks_exact_mut(row_bytes).enumerate() {
+            let y = min_y + (first + i) as i32;
+            for x in min_x..max_x {
+                let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = edge_function(b, c, p);
+                let w1 = edge_function(c, a, p);
+                let w2 = edge_function(a, b, p);
+                let has_pos = w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0;
+                let has_neg = w0 <= 0.0 && w1 <= 0.0 && w2 <= 0.0;
+                if has_pos || has_neg {
+                    let px = x as usize * 4;
+                    row[px..px + 4].copy_from_slice(&color.0);
+                }
             }
         }
-    }
+    });
 }
 
 fn fill_quad(
@@ -294,11 +313,59 @@ pub(crate) fn draw_handles(painter: &egui::Painter, rect: egui::Rect, radius: f3
     }
 }
 
+/// The blurred capture, laid over the capture's on-screen rect, for
+/// painting behind the editor chrome.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Backdrop {
+    pub(crate) texture: egui::TextureId,
+    pub(crate) image_rect: egui::Rect,
+}
+
+impl Backdrop {
+    /// The part of the blur under `rect`.
+    pub(crate) fn shape(&self, rect: egui::Rect, rounding: f32) -> egui::Shape {
+        let size = self.image_rect.size().max(egui::Vec2::splat(1.0));
+        let uv = egui::Rect::from_min_max(
+            ((rect.min - self.image_rect.min) / size).to_pos2(),
+            ((rect.max - self.image_rect.min) / size).to_pos2(),
+        );
+        let mut shape = egui::epaint::RectShape::filled(rect, rounding, egui::Color32::WHITE);
+        shape.fill_texture_id = self.texture;
+        shape.uv = uv;
+        shape.into()
+    }
+}
+
+/// Side of a checkerboard square, in points.
+const CHECKER_SIZE: f32 = 8.0;
+
+/// The checkerboard texture tiled over `rect`, starting at its corner so the
+/// squares move with the capture.
+pub(crate) fn paint_checkerboard(painter: &egui::Painter, texture: egui::TextureId, rect: egui::Rect) {
+    let mut shape = egui::epaint::RectShape::filled(rect, 0.0, egui::Color32::WHITE);
+    shape.fill_texture_id = texture;
+    shape.uv = egui::Rect::from_min_max(egui::Pos2::ZERO, (rect.size() / (CHECKER_SIZE * 2.0)).to_pos2());
+    painter.add(shape);
+}
+
+/// How long the capture flash takes to fade out, in seconds.
+pub(crate) const FLASH_SECS: f64 = 0.3;
+
+/// White over the whole editor, from clear at `strength` 0 to opaque at 1.
+pub(crate) fn paint_flash(ctx: &egui::Context, strength: f32) {
+    let layer = egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("capture_flash"));
+    let alpha = (strength.clamp(0.0, 1.0) * 255.0).round() as u8;
+    ctx.layer_painter(layer)
+        .rect_filled(ctx.screen_rect(), 0.0, egui::Color32::from_white_alpha(alpha));
+}
+
 pub(crate) fn draw_selection_hud(
     painter: &egui::Painter,
     sel_rect_screen: egui::Rect,
     sel_rect_image: egui::Rect,
     image_rect: egui::Rect,
+    chrome: &Chrome,
+    backdrop: Option<&Backdrop>,
 ) {
     let width = sel_rect_image.width().round().max(0.0) as i32;
     let height = sel_rect_image.height().round().max(0.0) as i32;
@@ -307,7 +374,7 @@ pub(crate) fn draw_selection_hud(
     let label = format!("{}x{}  {},{}", width, height, x, y);
 
     let font_id = egui::FontId::proportional(12.0);
-    let text_color = egui::Color32::WHITE;
+    let text_color = chrome.hud_text;
     let padding = egui::vec2(6.0, 3.0);
     let text_size = painter
         .layout_no_wrap(label.clone(), font_id.clone(), text_color)
@@ -330,11 +397,10 @@ pub(crate) fn draw_selection_hud(
         hud_rect = hud_rect.translate(egui::vec2(0.0, image_rect.min.y - hud_rect.min.y));
     }
 
-    painter.rect_filled(
-        hud_rect,
-        3.0,
-        egui::Color32::from_rgba_premultiplied(0, 0, 0, 190),
-    );
+    if let Some(backdrop) = backdrop.filter(|_| chrome.frosted) {
+        painter.add(backdrop.shape(hud_rect, 3.0));
+    }
+    painter.rect_filled(hud_rect, 3.0, chrome.hud_fill);
     painter.text(
         hud_rect.min + padding,
         egui::Align2::LEFT_TOP,
@@ -421,6 +487,13 @@ pub(crate) fn paint_tool_icon(painter: &egui::Painter, rect: egui::Rect, icon: T
                 color,
             );
         }
+        ToolIcon::Timestamp => {
+            let radius = inner.width().min(inner.height()) * 0.5;
+            let center = inner.center();
+            painter.circle_stroke(center, radius, stroke);
+            painter.line_segment([center, center - egui::vec2(0.0, radius * 0.65)], stroke);
+            painter.line_segment([center, center + egui::vec2(radius * 0.5, 0.0)], stroke);
+        }
         ToolIcon::Pixelate => {
             let size = (inner.width().min(inner.height()) * 0.3).max(2.0);
             let step = size + 2.0;
@@ -443,6 +516,22 @@ pub(crate) fn paint_tool_icon(painter: &egui::Painter, rect: egui::Rect, icon: T
                 color,
             );
         }
+        ToolIcon::Arrange => {
+            let band = [
+                inner.min,
+                egui::pos2(inner.max.x, inner.min.y),
+                inner.max,
+                egui::pos2(inner.min.x, inner.max.y),
+                inner.min,
+            ];
+            painter.extend(egui::Shape::dashed_line(&band, stroke, 2.0, 2.0));
+            let tip = inner.center();
+            painter.add(egui::Shape::convex_polygon(
+                vec![tip, tip + egui::vec2(2.5, 7.0), tip + egui::vec2(7.0, 2.5)],
+                color,
+                egui::Stroke::NONE,
+            ));
+        }
         ToolIcon::Undo => {
             let mid = rect.center();
             let left = egui::pos2(inner.min.x, mid.y);
@@ -476,6 +565,19 @@ pub(crate) fn paint_tool_icon(painter: &egui::Painter, rect: egui::Rect, icon: T
                 stroke,
             );
         }
+        ToolIcon::Record => {
+            let radius = inner.width().min(inner.height()) * 0.5;
+            painter.circle_stroke(inner.center(), radius, stroke);
+            painter.circle_filled(inner.center(), radius * 0.55, egui::Color32::from_rgb(220, 50, 47));
+        }
+        ToolIcon::Grip => {
+            let radius = (rect.width().min(rect.height()) * 0.05).max(1.0);
+            for x in [0.3, 0.7] {
+                for y in [0.0, 0.5, 1.0] {
+                    painter.circle_filled(inner.lerp_inside(egui::vec2(x, y)), radius, color);
+                }
+            }
+        }
     }
 }
 
@@ -1,97 +1,116 @@
 use eframe::egui;
+use fireshot_core::shapes::{EffectKind, EffectShape};
 use image::RgbaImage;
 
 use crate::geometry::normalize_rect;
+use crate::image_ops::rect_to_u32;
+use crate::parallel::for_each_row_band;
 
 pub(crate) fn apply_pixelate(img: &mut RgbaImage, rect: egui::Rect, block: u32) {
     let rect = normalize_rect(rect);
-    let min_x = rect.min.x.floor().max(0.0) as u32;
-    let min_y = rect.min.y.floor().max(0.0) as u32;
-    let max_x = rect.max.x.ceil().min(img.width() as f32) as u32;
-    let max_y = rect.max.y.ceil().min(img.height() as f32) as u32;
-    let block = block.max(2);
+    let min_x = rect.min.x.floor().max(0.0) as usize;
+    let min_y = rect.min.y.floor().max(0.0) as usize;
+    let max_x = rect.max.x.ceil().min(img.width() as f32) as usize;
+    let max_y = rect.max.y.ceil().min(img.height() as f32) as usize;
+    if max_x <= min_x || max_y <= min_y {
+        return;
+    }
+    let block = block.max(2) as usize;
+    let row_bytes = img.width() as usize * 4;
 
-    let mut y = min_y;
-    while y < max_y {
-        let mut x = min_x;
-        while x < max_x {
-            let bx = (x + block).min(max_x);
-            let by = (y + block).min(max_y);
-            let mut r = 0u64;
-            let mut g = 0u64;
-            let mut b = 0u64;
-            let mut a = 0u64;
-            let mut count = 0u64;
-            for yy in y..by {
-                for xx in x..bx {
-                    let p = img.get_pixel(xx, yy);
-                    r += p[0] as u64;
-                    g += p[1] as u64;
-                    b += p[2] as u64;
-                    a += p[3] as u64;
-                    count += 1;
+    let rows = &mut img.as_mut()[min_y * row_bytes..max_y * row_bytes];
+    for_each_row_band(rows, row_bytes, block, |_, band| {
+        let band_rows = band.len() / row_bytes;
+        let mut y = 0;
+        while y < band_rows {
+            let by = (y + block).min(band_rows);
+            let mut x = min_x;
+            while x < max_x {
+                let bx = (x + block).min(max_x);
+                let mut sum = [0u64; 4];
+                for yy in y..by {
+                    let row = &band[yy * row_bytes + x * 4..yy * row_bytes + bx * 4];
+                    for px in row.chunks_exact(4) {
+                        for (total, value) in sum.iter_mut().zip(px) {
+                            *total += *value as u64;
+                        }
+                    }
                 }
-            }
-            if count > 0 {
-                let avg = image::Rgba([
-                    (r / count) as u8,
-                    (g / count) as u8,
-                    (b / count) as u8,
-                    (a / count) as u8,
-                ]);
+                let count = ((by - y) * (bx - x)) as u64;
+                let avg = sum.map(|total| (total / count) as u8);
                 for yy in y..by {
-                    for xx in x..bx {
-                        img.put_pixel(xx, yy, avg);
+                    let row = &mut band[yy * row_bytes + x * 4..yy * row_bytes + bx * 4];
+                    for px in row.chunks_exact_mut(4) {
+                        px.copy_from_slice(&avg);
                     }
                 }
+                x += block;
             }
-            x += block;
+            y += block;
         }
-        y += block;
-    }
+    });
 }
 
+/// Box blur over `rect` using a summed-area table, so the cost per pixel
+/// doesn't depend on the radius. The window is clamped to `rect`. The table
+/// wraps instead of widening: any one window's total fits in `u32`, so the
+/// wrapped differences still come out exact.
 pub(crate) fn apply_blur(img: &mut RgbaImage, rect: egui::Rect, radius: u32) {
     let rect = normalize_rect(rect);
-    let min_x = rect.min.x.floor().max(0.0) as i32;
-    let min_y = rect.min.y.floor().max(0.0) as i32;
-    let max_x = rect.max.x.ceil().min(img.width() as f32) as i32;
-    let max_y = rect.max.y.ceil().min(img.height() as f32) as i32;
-    let radius = radius.max(1) as i32;
+    let min_x = rect.min.x.floor().max(0.0) as usize;
+    let min_y = rect.min.y.floor().max(0.0) as usize;
+    let max_x = rect.max.x.ceil().min(img.width() as f32) as usize;
+    let max_y = rect.max.y.ceil().min(img.height() as f32) as usize;
+    if max_x <= min_x || max_y <= min_y {
+        return;
+    }
+    let radius = radius.max(1) as usize;
+    let row_bytes = img.width() as usize * 4;
+    let width = max_x - min_x;
+    let height = max_y - min_y;
 
-    let original = img.clone();
-    for y in min_y..max_y {
-        for x in min_x..max_x {
-            let mut r = 0u64;
-            let mut g = 0u64;
-            let mut b = 0u64;
-            let mut a = 0u64;
-            let mut count = 0u64;
-            let y0 = (y - radius).max(0);
-            let y1 = (y + radius).min(max_y - 1);
-            let x0 = (x - radius).max(0);
-            let x1 = (x + radius).min(max_x - 1);
-            for yy in y0..=y1 {
-                for xx in x0..=x1 {
-                    let p = original.get_pixel(xx as u32, yy as u32);
-                    r += p[0] as u64;
-                    g += p[1] as u64;
-                    b += p[2] as u64;
-                    a += p[3] as u64;
-                    count += 1;
-                }
-            }
-            if count > 0 {
-                let avg = image::Rgba([
-                    (r / count) as u8,
-                    (g / count) as u8,
-                    (b / count) as u8,
-                    (a / count) as u8,
-                ]);
-                img.put_pixel(x as u32, y as u32, avg);
+    let stride = width + 1;
+    let mut table = vec![[0u32; 4]; stride * (height + 1)];
+    for y in 0..height {
+        let start = (min_y + y) * row_bytes + min_x * 4;
+        let src = &img.as_raw()[start..start + width * 4];
+        let mut row_sum = [0u32; 4];
+        for (x, px) in src.chunks_exact(4).enumerate() {
+            for (total, value) in row_sum.iter_mut().zip(px) {
+                *total = total.wrapping_add(*value as u32);
             }
+            let above = table[y * stride + x + 1];
+            table[(y + 1) * stride + x + 1] =
+                std::array::from_fn(|c| above[c].wrapping_add(row_sum[c]));
         }
     }
+
+    let rows = &mut img.as_mut()[min_y * row_bytes..max_y * row_bytes];
+    for_each_row_band(rows, row_bytes, 1, |first, band| {
+        for (i, row) in band.chunks_exact_mut(row_bytes).enumerate() {
+            let y = first + i;
+            let y0 = y.saturating_sub(radius);
+            let y1 = (y + radius + 1).min(height);
+            for x in 0..width {
+                let x0 = x.saturating_sub(radius);
+                let x1 = (x + radius + 1).min(width);
+                let count = ((y1 - y0) * (x1 - x0)) as u32;
+                let top_left = table[y0 * stride + x0];
+                let top_right = table[y0 * stride + x1];
+                let bottom_left = table[y1 * stride + x0];
+                let bottom_right = table[y1 * stride + x1];
+                let avg: [u8; 4] = std::array::from_fn(|c| {
+                    let sum = bottom_right[c]
+                        .wrapping_sub(top_right[c])
+                        .wrapping_sub(bottom_left[c])
+                        .wrapping_add(top_left[c]);
+                    (sum / count) as u8
+                });
+                let px = (min_x + x) * 4;
+                row[px..px + 4].copy_from_slice(&avg);
+            }
+        }
+    });
 }
 
 pub(crate) fn apply_pixelate_full(img: &mut RgbaImage, block: u32) {
@@ -109,3 +128,89 @@ pub(crate) fn apply_blur_full(img: &mut RgbaImage, radius: u32) {
     );
     apply_blur(img, rect, radius);
 }
+
+/// Regions at least this large go to the GPU when `gpu-effects` is enabled.
+#[cfg(feature = "gpu-effects")]
+const GPU_MIN_PIXELS: u64 = 512 * 512;
+
+/// Applies an effect to the whole image, on the GPU for large images when the
+/// `gpu-effects` feature is enabled and a device is available.
+pub(crate) fn apply_effect_full(img: &mut RgbaImage, kind: EffectKind, size: u32) {
+    #[cfg(feature = "gpu-effects")]
+    if img.width() as u64 * img.height() as u64 >= GPU_MIN_PIXELS
+        && crate::gpu::shared().is_some_and(|gpu| gpu.apply(img, kind, size))
+    {
+        return;
+    }
+    match kind {
+        EffectKind::Pixelate => apply_pixelate_full(img, size),
+        EffectKind::Blur => apply_blur_full(img, size),
+    }
+}
+
+/// The pixelate block or blur radius `effect` is rendered with.
+pub(crate) fn effect_strength(effect: &EffectShape) -> u32 {
+    match effect.kind {
+        EffectKind::Pixelate => effect.size.round().max(4.0) as u32,
+        EffectKind::Blur => effect.size.round().max(2.0) as u32,
+    }
+}
+
+/// The image-space area `effect` reads: its rect, plus a blur's radius all
+/// round since the blur window reaches past the edge.
+pub(crate) fn effect_reach(effect: &EffectShape) -> egui::Rect {
+    let rect = normalize_rect(egui::Rect::from_two_pos(effect.start, effect.end));
+    match effect.kind {
+        EffectKind::Pixelate => rect,
+        EffectKind::Blur => rect.expand(effect_strength(effect) as f32),
+    }
+}
+
+/// `effect` rendered over its whole rect, sampled from the composited `img`
+/// around it, and the rect's top-left corner. The editor's preview and the
+/// export both render effects this way, so their edges match: a blur near
+/// the edge averages in the pixels just outside the rect, and is only cut
+/// short by the image's own edges. `None` when the rect is off the image.
+pub(crate) fn render_effect_region(
+    img: &RgbaImage,
+    effect: &EffectShape,
+) -> Option<(RgbaImage, (u32, u32))> {
+    let rect = normalize_rect(egui::Rect::from_two_pos(effect.start, effect.end));
+    let (min_x, min_y, max_x, max_y) = rect_to_u32(img, rect)?;
+    let (reach_x, reach_y, reach_max_x, reach_max_y) = rect_to_u32(img, effect_reach(effect))?;
+    let mut reach = image::imageops::crop_imm(
+        img,
+        reach_x,
+        reach_y,
+        reach_max_x - reach_x,
+        reach_max_y - reach_y,
+    )
+    .to_image();
+    apply_effect_full(&mut reach, effect.kind, effect_strength(effect));
+    let region = image::imageops::crop_imm(
+        &reach,
+        min_x - reach_x,
+        min_y - reach_y,
+        max_x - min_x,
+        max_y - min_y,
+    )
+    .to_image();
+    Some((region, (min_x, min_y)))
+}
+
+/// Renders `effect` into `img`, leaving every pixel a painted effect's
+/// brush didn't cover as it was.
+pub(crate) fn apply_effect_shape(img: &mut RgbaImage, effect: &EffectShape) {
+    let Some((region, (min_x, min_y))) = render_effect_region(img, effect) else {
+        return;
+    };
+    let Some(coverage) = effect.coverage((min_x, min_y), region.width(), region.height()) else {
+        image::imageops::replace(img, &region, i64::from(min_x), i64::from(min_y));
+        return;
+    };
+    for ((x, y, px), covered) in region.enumerate_pixels().zip(coverage) {
+        if covered {
+            img.put_pixel(min_x + x, min_y + y, *px);
+        }
+    }
+}
@@ -0,0 +1,623 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+
+use eframe::egui;
+use fireshot_core::config::{Matte, OutsideSelection, SaveConfig};
+use fireshot_core::shapes::{EffectKind, Shape};
+use fireshot_core::window::WindowInfo;
+use fireshot_core::{paths, profile, tr, CaptureError, ExportResult};
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::clipboard::{copy_png, encode_png, no_clipboard};
+use crate::color::{save_image, set_png_window};
+use crate::control::PostRender;
+use crate::draw::{
+    arrow_head_points, draw_arrow_head_image, draw_circle_count_image, draw_ellipse, draw_line,
+};
+use crate::effects::{apply_effect_full, apply_effect_shape, effect_reach};
+use crate::geometry::normalize_rect;
+use crate::image_ops::{crop_image, rect_to_u32};
+use crate::text::draw_text_bitmap;
+use crate::theme::parse_hex_color;
+
+/// Blur radius for the capture round the selection with `outside_selection =
+/// "blur"`.
+const OUTSIDE_BLUR_RADIUS: u32 = 12;
+/// What the capture round the selection is darkened to with
+/// `outside_selection = "dim"`.
+const OUTSIDE_DIM: f32 = 0.4;
+
+pub(crate) enum ExportKind {
+    Copy,
+    Save(PathBuf),
+    /// Hands the PNG to a running `LanShare`, which serves it at `url`.
+    Share { png: Sender<Vec<u8>>, url: String },
+    /// Attaches the result to a new message in the mail client.
+    Email { subject: String },
+}
+
+/// Everything a render depends on; a pre-render is only used for the key it
+/// was made from.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct RenderKey {
+    pub(crate) shapes_version: u64,
+    pub(crate) selection: Option<egui::Rect>,
+}
+
+/// What to export: the capture and shapes, plus the channel of a matching
+/// `Prerender` whose result is used instead of rendering again.
+pub(crate) struct ExportSource {
+    pub(crate) base: Arc<RgbaImage>,
+    pub(crate) shapes: Vec<Shape>,
+    pub(crate) selection: Option<egui::Rect>,
+    pub(crate) prerendered: Option<Receiver<(RgbaImage, Vec<u8>)>>,
+    /// Output profile saved files are converted into and tagged with.
+    pub(crate) icc_profile: Option<Arc<[u8]>>,
+    pub(crate) post_render: Option<PostRender>,
+    /// Background for formats without transparency.
+    pub(crate) matte: Matte,
+    /// Scale of the output the capture came from, for the DPI saved PNGs
+    /// carry.
+    pub(crate) scale: Option<f32>,
+    pub(crate) frame: Frame,
+    /// The window the capture was taken from, named in saved PNGs.
+    pub(crate) window: Option<WindowInfo>,
+}
+
+/// A finished export: what to report back and the status line to show.
+pub(crate) type ExportOutcome = Result<(ExportResult, String), String>;
+
+/// A finished job, which may have exported several files.
+pub(crate) type JobOutcome = Result<(Vec<ExportResult>, String), String>;
+
+/// Renders, encodes and delivers an export on a worker thread so large
+/// captures don't stall the editor.
+pub(crate) struct ExportJob {
+    rx: Receiver<JobOutcome>,
+}
+
+impl ExportJob {
+    pub(crate) fn spawn(mut source: ExportSource, kind: ExportKind, ctx: egui::Context) -> Self {
+        Self::run(ctx, move || {
+            let timer = profile::Timer::start("export");
+            let (rendered, png) = match source.prerendered.take().and_then(|rx| rx.recv().ok()) {
+                Some((image, png)) => (image, Some(png)),
+                None => (
+                    render_export(
+                        &source.base,
+                        &source.shapes,
+                        source.selection,
+                        source.post_render.as_ref(),
+                        source.frame,
+                    ),
+                    None,
+                ),
+            };
+            let outcome = match kind {
+                ExportKind::Copy => {
+                    copy(&rendered, png, source.scale).map_err(|err| err.user_message())
+                }
+                ExportKind::Save(path) => save(&rendered, png, path, &source),
+                ExportKind::Share { png: share, url } => {
+                    share_png(&rendered, png, share, url, source.scale)
+                }
+                ExportKind::Email { subject } => email(&rendered, png, subject, source.scale),
+            };
+            drop(timer);
+            outcome.map(|(export, status)| (vec![export], status))
+        })
+    }
+
+    /// Saves each of `regions` of the capture as `<stem>-<n>.png` in `dir`,
+    /// numbered from 1 in the order given.
+    pub(crate) fn spawn_all(
+        source: ExportSource,
+        regions: Vec<egui::Rect>,
+        dir: PathBuf,
+        stem: String,
+        ctx: egui::Context,
+    ) -> Self {
+        Self::run(ctx, move || {
+            let _timer = profile::Timer::start("export");
+            std::fs::create_dir_all(&dir)
+                .map_err(|err| tr!("status-save-failed", error = err))?;
+            let mut exports = Vec::new();
+            for (i, region) in regions.into_iter().enumerate() {
+                let rendered = render_export(
+                    &source.base,
+                    &source.shapes,
+                    Some(region),
+                    source.post_render.as_ref(),
+                    source.frame,
+                );
+                let path = dir.join(format!("{}-{}.png", stem, i + 1));
+                let (export, _) = save(&rendered, None, path, &source)?;
+                exports.push(export);
+            }
+            let status = tr!("status-saved-all", count = exports.len(), path = dir.display());
+            Ok((exports, status))
+        })
+    }
+
+    /// Saves the selection twice, as `<stem>-base.png` without the shapes
+    /// and as `<stem>-annotations.png` with only them on transparency, for
+    /// restyling the callouts in a design tool.
+    pub(crate) fn spawn_layers(
+        source: ExportSource,
+        dir: PathBuf,
+        stem: String,
+        ctx: egui::Context,
+    ) -> Self {
+        Self::run(ctx, move || {
+            let _timer = profile::Timer::start("export");
+            std::fs::create_dir_all(&dir)
+                .map_err(|err| tr!("status-save-failed", error = err))?;
+            let post_render = source.post_render.as_ref();
+            let (selection, frame) = (source.selection, source.frame);
+            let base = render_export(&source.base, &[], selection, post_render, frame);
+            let flat = render_export(&source.base, &source.shapes, selection, post_render, frame);
+            let annotations = annotation_layer(&base, &flat);
+            let base_path = dir.join(format!("{}-base.png", stem));
+            let (base, _) = save(&base, None, base_path, &source)?;
+            let annotations_path = dir.join(format!("{}-annotations.png", stem));
+            let (annotations, _) = save(&annotations, None, annotations_path, &source)?;
+            let status = tr!("status-saved-layers", path = dir.display());
+            Ok((vec![base, annotations], status))
+        })
+    }
+
+    fn run(ctx: egui::Context, job: impl FnOnce() -> JobOutcome + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(job());
+            ctx.request_repaint();
+        });
+        Self { rx }
+    }
+
+    /// The outcome once the worker is done; `None` while it is still running.
+    pub(crate) fn poll(&self) -> Option<JobOutcome> {
+        match self.rx.try_recv() {
+            Ok(outcome) => Some(outcome),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(tr!("status-export-failed").to_string())),
+        }
+    }
+}
+
+/// How long the shapes and selection must stay unchanged before a
+/// `Prerender` starts.
+pub(crate) const PRERENDER_IDLE_SECS: f64 = 0.4;
+
+/// Renders and encodes the current result in the background while the user
+/// is idle, so a copy right after only has to hand the PNG over.
+pub(crate) struct Prerender {
+    pub(crate) key: RenderKey,
+    rx: Receiver<(RgbaImage, Vec<u8>)>,
+}
+
+impl Prerender {
+    pub(crate) fn spawn(
+        key: RenderKey,
+        base: Arc<RgbaImage>,
+        shapes: Vec<Shape>,
+        post_render: Option<PostRender>,
+        frame: Frame,
+        scale: Option<f32>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let post_render = post_render.as_ref();
+            let rendered = render_export(&base, &shapes, key.selection, post_render, frame);
+            if let Ok(png) = encode_png(&rendered, scale) {
+                let _ = tx.send((rendered, png));
+            }
+        });
+        Self { key, rx }
+    }
+
+    /// The pre-render's result channel, if it was made for `key`. The
+    /// export waits on it when it is still running.
+    pub(crate) fn take(self, key: RenderKey) -> Option<Receiver<(RgbaImage, Vec<u8>)>> {
+        (self.key == key).then_some(self.rx)
+    }
+}
+
+/// The exported image: the selection rendered, `post_render` run on it, then
+/// finished with `frame`.
+fn render_export(
+    base: &RgbaImage,
+    shapes: &[Shape],
+    selection: Option<egui::Rect>,
+    post_render: Option<&PostRender>,
+    frame: Frame,
+) -> RgbaImage {
+    let mut rendered = match selection {
+        Some(selection) if frame.outside != OutsideSelection::Crop => {
+            let mut rendered = render_selection(base, shapes, None);
+            mute_outside(&mut rendered, selection, frame.outside);
+            rendered
+        }
+        _ => render_selection(base, shapes, selection),
+    };
+    if let Some(post_render) = post_render {
+        post_render(&mut rendered);
+    }
+    frame.apply(rendered)
+}
+
+/// What exported images are finished with, from `[save]`: the rest of the
+/// capture round the selection unless it is cropped away, a border, then
+/// rounded corners on the result.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Frame {
+    outside: OutsideSelection,
+    border: Option<Border>,
+    corners: Option<Corners>,
+}
+
+impl Frame {
+    pub(crate) fn from_config(save: &SaveConfig) -> Self {
+        Self {
+            outside: save.outside_selection,
+            border: Border::from_config(save),
+            corners: Corners::from_config(save),
+        }
+    }
+
+    fn apply(&self, image: RgbaImage) -> RgbaImage {
+        let mut image = match self.border {
+            Some(border) => border.frame(&image),
+            None => image,
+        };
+        if let Some(corners) = self.corners {
+            corners.round(&mut image);
+        }
+        image
+    }
+}
+
+/// Blurs or darkens everything in `img` but `selection`, so the selection
+/// stands out from its surroundings.
+fn mute_outside(img: &mut RgbaImage, selection: egui::Rect, outside: OutsideSelection) {
+    let Some((min_x, min_y, max_x, max_y)) = rect_to_u32(img, selection) else {
+        return;
+    };
+    let kept = imageops::crop_imm(img, min_x, min_y, max_x - min_x, max_y - min_y).to_image();
+    match outside {
+        OutsideSelection::Crop => return,
+        OutsideSelection::Blur => apply_effect_full(img, EffectKind::Blur, OUTSIDE_BLUR_RADIUS),
+        OutsideSelection::Dim => {
+            for pixel in img.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = (*channel as f32 * OUTSIDE_DIM).round() as u8;
+                }
+            }
+        }
+    }
+    imageops::replace(img, &kept, i64::from(min_x), i64::from(min_y));
+}
+
+/// A frame round exported images, from `[save] border_*`, so captures of
+/// white windows don't vanish into a white page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Border {
+    width: u32,
+    color: Rgba<u8>,
+    /// A 1px line in a contrasting colour between the frame and the image.
+    contrast_line: bool,
+}
+
+impl Border {
+    /// `None` when `border_width` is 0. An unreadable colour is black.
+    fn from_config(save: &SaveConfig) -> Option<Self> {
+        if save.border_width == 0 {
+            return None;
+        }
+        let color = parse_hex_color(&save.border_color).unwrap_or(egui::Color32::BLACK);
+        Some(Self {
+            width: save.border_width,
+            color: Rgba(color.to_array()),
+            contrast_line: save.border_contrast_line,
+        })
+    }
+
+    /// `image` in the middle of the frame, which adds `width` pixels on
+    /// every side, and one more for the contrast line.
+    fn frame(&self, image: &RgbaImage) -> RgbaImage {
+        let inner = u32::from(self.contrast_line);
+        let margin = self.width + inner;
+        let (width, height) = (image.width() + margin * 2, image.height() + margin * 2);
+        let mut framed = RgbaImage::from_pixel(width, height, self.color);
+        if self.contrast_line {
+            let [r, g, b, _] = self.color.0;
+            let luma = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+            let contrast = if luma < 128.0 {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            };
+            let line = RgbaImage::from_pixel(image.width() + 2, image.height() + 2, contrast);
+            imageops::replace(&mut framed, &line, i64::from(self.width), i64::from(self.width));
+        }
+        imageops::replace(&mut framed, image, i64::from(margin), i64::from(margin));
+        framed
+    }
+}
+
+/// Rounded corners for exported images, from `[save] corner_*`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Corners {
+    radius: u32,
+    /// What shows outside the corners; transparent when unset.
+    background: Option<Rgba<u8>>,
+}
+
+impl Corners {
+    /// `None` when `corner_radius` is 0. An unreadable background is
+    /// transparent.
+    fn from_config(save: &SaveConfig) -> Option<Self> {
+        if save.corner_radius == 0 {
+            return None;
+        }
+        let background = save
+            .corner_background
+            .as_deref()
+            .and_then(parse_hex_color)
+            .map(|color| Rgba(color.to_array()));
+        Some(Self {
+            radius: save.corner_radius,
+            background,
+        })
+    }
+
+    /// Cuts the corners of `image` to quarter circles, antialiased, with the
+    /// background showing outside them. The radius is at most half the
+    /// shorter side.
+    fn round(&self, image: &mut RgbaImage) {
+        let (width, height) = image.dimensions();
+        let radius = self.radius.min(width / 2).min(height / 2);
+        let r = radius as f32;
+        for y in 0..radius {
+            for x in 0..radius {
+                // Pixel centres against the circle's, so the edge is
+                // antialiased over about a pixel.
+                let (dx, dy) = (r - (x as f32 + 0.5), r - (y as f32 + 0.5));
+                let coverage = (r - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+                if coverage >= 1.0 {
+                    continue;
+                }
+                let (right, bottom) = (width - 1 - x, height - 1 - y);
+                for (px, py) in [(x, y), (right, y), (x, bottom), (right, bottom)] {
+                    let pixel = image.get_pixel_mut(px, py);
+                    *pixel = self.cut(*pixel, coverage);
+                }
+            }
+        }
+    }
+
+    /// `pixel` with `coverage` of it kept, over the background if there is
+    /// one.
+    fn cut(&self, pixel: Rgba<u8>, coverage: f32) -> Rgba<u8> {
+        match self.background {
+            Some(background) => {
+                let mix = |a: u8, b: u8| a as f32 * coverage + b as f32 * (1.0 - coverage);
+                Rgba(std::array::from_fn(|i| mix(pixel.0[i], background.0[i]).round() as u8))
+            }
+            None => {
+                let [r, g, b, a] = pixel.0;
+                Rgba([r, g, b, (a as f32 * coverage).round() as u8])
+            }
+        }
+    }
+}
+
+/// Every shape drawn on the whole of `base`, as an export without a
+/// selection, with no editor needed; for `fireshot annotate`.
+pub fn render_annotations(base: &RgbaImage, shapes: &[Shape]) -> RgbaImage {
+    render_selection(base, shapes, None)
+}
+
+/// Renders only the part of the capture the export needs. The region is the
+/// selection grown by the reach of every effect whose rect overlaps it,
+/// since effects read pixels across their rect and a blur past its edges;
+/// that keeps effect edges identical to a render of the full capture.
+pub(crate) fn render_selection(
+    base: &RgbaImage,
+    shapes: &[Shape],
+    selection: Option<egui::Rect>,
+) -> RgbaImage {
+    let Some(selection) = selection else {
+        let mut img = base.clone();
+        draw_shapes(&mut img, shapes);
+        return img;
+    };
+    let effects: Vec<(egui::Rect, egui::Rect)> = shapes
+        .iter()
+        .filter_map(|shape| match shape {
+            Shape::Effect(effect) => Some((
+                normalize_rect(egui::Rect::from_two_pos(effect.start, effect.end)),
+                effect_reach(effect),
+            )),
+            _ => None,
+        })
+        .collect();
+    let mut region = selection;
+    loop {
+        let grown = effects
+            .iter()
+            .filter(|(rect, _)| rect.intersects(region))
+            .fold(region, |acc, (_, reach)| acc.union(*reach));
+        if grown == region {
+            break;
+        }
+        region = grown;
+    }
+    let Some((min_x, min_y, max_x, max_y)) = rect_to_u32(base, region) else {
+        return crop_image(base, selection);
+    };
+
+    let origin = egui::vec2(min_x as f32, min_y as f32);
+    let mut img = imageops::crop_imm(base, min_x, min_y, max_x - min_x, max_y - min_y).to_image();
+    let shifted: Vec<Shape> = shapes.iter().map(|shape| shape.translated(-origin)).collect();
+    draw_shapes(&mut img, &shifted);
+    if region == selection {
+        img
+    } else {
+        crop_image(&img, selection.translate(-origin))
+    }
+}
+
+/// What the shapes changed: `flat`'s pixels where they differ from `base`,
+/// transparent elsewhere. Shapes overwrite the pixels they cover and effects
+/// rewrite theirs, so laid over `base` this gives `flat` back.
+fn annotation_layer(base: &RgbaImage, flat: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(flat.width(), flat.height(), |x, y| {
+        let px = *flat.get_pixel(x, y);
+        if base.get_pixel_checked(x, y) == Some(&px) {
+            image::Rgba([0, 0, 0, 0])
+        } else {
+            px
+        }
+    })
+}
+
+fn encode_error(err: image::ImageError) -> CaptureError {
+    CaptureError::EncodeError {
+        format: "PNG".to_string(),
+        reason: err.to_string(),
+    }
+}
+
+fn copy(
+    rendered: &RgbaImage,
+    png: Option<Vec<u8>>,
+    scale: Option<f32>,
+) -> Result<(ExportResult, String), CaptureError> {
+    let png = match png {
+        Some(png) => png,
+        None => encode_png(rendered, scale).map_err(encode_error)?,
+    };
+    let method = copy_png(&png, rendered).ok_or_else(no_clipboard)?;
+    let history_path = fireshot_core::history::record_png(&png).ok().flatten();
+    Ok((
+        ExportResult::Copied { history_path },
+        tr!("status-copied", method = method),
+    ))
+}
+
+fn share_png(
+    rendered: &RgbaImage,
+    png: Option<Vec<u8>>,
+    share: Sender<Vec<u8>>,
+    url: String,
+    scale: Option<f32>,
+) -> ExportOutcome {
+    let png = match png {
+        Some(png) => png,
+        None => encode_png(rendered, scale).map_err(|err| encode_error(err).user_message())?,
+    };
+    // The server is gone when sharing was stopped or timed out meanwhile.
+    share
+        .send(png)
+        .map_err(|_| tr!("status-share-stopped").to_string())?;
+    Ok((ExportResult::Shared { url }, tr!("status-share-waiting").to_string()))
+}
+
+fn email(
+    rendered: &RgbaImage,
+    png: Option<Vec<u8>>,
+    subject: String,
+    scale: Option<f32>,
+) -> ExportOutcome {
+    let png = match png {
+        Some(png) => png,
+        None => encode_png(rendered, scale).map_err(|err| encode_error(err).user_message())?,
+    };
+    let dir = paths::email_dir();
+    // Mail clients have read earlier attachments by now.
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join(format!("{}.png", subject));
+    std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&path, &png))
+        .map_err(|err| tr!("status-email-failed", error = err))?;
+    fireshot_core::history::record_png(&png).ok();
+    paths::compose_email(&subject, &path)
+        .map_err(|err| tr!("status-email-failed", error = err))?;
+    Ok((ExportResult::Emailed { path }, tr!("status-emailed").to_string()))
+}
+
+/// Saves `rendered` to `path` with `source`'s profile, matte, scale and
+/// window; `png` is its encoding when there already is one.
+fn save(
+    rendered: &RgbaImage,
+    png: Option<Vec<u8>>,
+    path: PathBuf,
+    source: &ExportSource,
+) -> ExportOutcome {
+    let (icc_profile, scale) = (source.icc_profile.as_deref(), source.scale);
+    let is_png = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    let written = match &png {
+        Some(png) if is_png && icc_profile.is_none() => {
+            let mut tagged = png.clone();
+            if let Some(window) = &source.window {
+                set_png_window(&mut tagged, window);
+            }
+            std::fs::write(&path, tagged).map_err(|err| err.to_string())
+        }
+        _ => save_image(rendered, &path, icc_profile, source.matte, scale, source.window.as_ref())
+            .map_err(|err| err.to_string()),
+    };
+    written.map_err(|err| tr!("status-save-failed", error = err))?;
+    if let Some(png) = png.or_else(|| encode_png(rendered, scale).ok()) {
+        fireshot_core::history::record_png(&png).ok();
+    }
+    let status = tr!("status-saved", path = path.display());
+    Ok((ExportResult::Saved { path }, status))
+}
+
+fn draw_shapes(img: &mut RgbaImage, shapes: &[Shape]) {
+    for shape in shapes {
+        match shape {
+            Shape::Stroke(stroke) => {
+                for (a, b, width) in stroke.segments() {
+                    draw_line(img, a, b, stroke.color, width);
+                }
+            }
+            Shape::Line(line) => {
+                draw_line(img, line.start, line.end, line.color, line.size);
+            }
+            Shape::Arrow(arrow) => {
+                let (base, _, _) = arrow_head_points(arrow.start, arrow.end, arrow.size);
+                draw_line(img, arrow.start, base, arrow.color, arrow.size);
+                draw_arrow_head_image(img, arrow.start, arrow.end, arrow.color, arrow.size);
+            }
+            Shape::Rect(rect) => {
+                let a = rect.start;
+                let b = rect.end;
+                let top_left = egui::pos2(a.x.min(b.x), a.y.min(b.y));
+                let bottom_right = egui::pos2(a.x.max(b.x), a.y.max(b.y));
+                let top_right = egui::pos2(bottom_right.x, top_left.y);
+                let bottom_left = egui::pos2(top_left.x, bottom_right.y);
+                draw_line(img, top_left, top_right, rect.color, rect.size);
+                draw_line(img, top_right, bottom_right, rect.color, rect.size);
+                draw_line(img, bottom_right, bottom_left, rect.color, rect.size);
+                draw_line(img, bottom_left, top_left, rect.color, rect.size);
+            }
+            Shape::Circle(circle) => {
+                draw_ellipse(img, circle.start, circle.end, circle.color, circle.size);
+            }
+            Shape::CircleCount(counter) => {
+                draw_circle_count_image(img, counter);
+            }
+            Shape::Text(text) => {
+                let scale = (text.size / 6.0).round().max(1.0) as u32;
+                draw_text_bitmap(img, text.pos, &text.text, text.color, scale);
+            }
+            Shape::Effect(effect) => apply_effect_shape(img, effect),
+        }
+    }
+}
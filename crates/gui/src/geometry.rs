@@ -1,6 +1,7 @@
 use eframe::egui;
+use fireshot_core::config::{ToolbarAnchor, ToolbarConfig, ToolbarSide};
 
-use crate::shapes::SelectionCorner;
+use crate::shapes::{SelectionCorner, SCREEN_DOCK_MARGIN};
 
 pub(crate) fn normalize_rect(rect: egui::Rect) -> egui::Rect {
     let min = egui::pos2(rect.min.x.min(rect.max.x), rect.min.y.min(rect.max.y));
@@ -26,14 +27,65 @@ pub(crate) fn hit_corner(rect: egui::Rect, pos: egui::Pos2, radius: f32) -> Opti
     None
 }
 
-pub(crate) fn selection_screen_rect(
-    sel_rect_image: egui::Rect,
-    image_rect: egui::Rect,
-    scale: f32,
-) -> egui::Rect {
-    let min = image_rect.min + egui::vec2(sel_rect_image.min.x / scale, sel_rect_image.min.y / scale);
-    let max = image_rect.min + egui::vec2(sel_rect_image.max.x / scale, sel_rect_image.max.y / scale);
-    egui::Rect::from_min_max(min, max)
+/// Where the capture is drawn: at its natural size from the top-left when it
+/// fits, otherwise scaled down and centered. A capture stitched across
+/// several outputs is larger than the output the editor opens on, and
+/// showing all of it lets a selection cross monitor boundaries.
+pub(crate) fn display_rect(natural: egui::Vec2, available: egui::Rect) -> egui::Rect {
+    // Allow a point of slack so rounding in the compositor's scale doesn't
+    // shrink a capture of exactly this output.
+    let fits = natural.x <= available.width() + 1.0 && natural.y <= available.height() + 1.0;
+    if fits || natural.x <= 0.0 || natural.y <= 0.0 {
+        return egui::Rect::from_min_size(available.min, natural);
+    }
+    let factor = (available.width() / natural.x).min(available.height() / natural.y);
+    egui::Rect::from_center_size(available.center(), natural * factor)
+}
+
+/// Maps capture pixels to egui points for the image as it is laid out. The
+/// factors come from the displayed rect rather than `pixels_per_point`, so
+/// fractional scales and captures whose size isn't an exact multiple of the
+/// logical screen map without drift.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ViewTransform {
+    pub(crate) origin: egui::Pos2,
+    /// Capture pixels per point on each axis.
+    pub(crate) scale: egui::Vec2,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            origin: egui::Pos2::ZERO,
+            scale: egui::Vec2::splat(1.0),
+        }
+    }
+}
+
+impl ViewTransform {
+    pub(crate) fn new(rect: egui::Rect, image_size: egui::Vec2) -> Self {
+        let axis = |pixels: f32, points: f32| if points > 0.0 { pixels / points } else { 1.0 };
+        Self {
+            origin: rect.min,
+            scale: egui::vec2(
+                axis(image_size.x, rect.width()),
+                axis(image_size.y, rect.height()),
+            ),
+        }
+    }
+
+    pub(crate) fn to_screen(self, pos: egui::Pos2) -> egui::Pos2 {
+        self.origin + egui::vec2(pos.x / self.scale.x, pos.y / self.scale.y)
+    }
+
+    pub(crate) fn to_image(self, pos: egui::Pos2) -> egui::Pos2 {
+        let offset = pos - self.origin;
+        egui::pos2(offset.x * self.scale.x, offset.y * self.scale.y)
+    }
+
+    pub(crate) fn rect_to_screen(self, rect: egui::Rect) -> egui::Rect {
+        egui::Rect::from_min_max(self.to_screen(rect.min), self.to_screen(rect.max))
+    }
 }
 
 pub(crate) fn layout_tool_buttons(
@@ -139,6 +191,162 @@ pub(crate) fn layout_tool_buttons(
     positions
 }
 
+/// Positions for `count` buttons in rows (or columns) on `side` of
+/// `selection`, or of `bounds` when anchored to the screen. Lines that don't
+/// fit stack outwards from the first. A pinned toolbar ignores both and starts
+/// at its pin.
+pub(crate) fn dock_tool_buttons(
+    toolbar: &ToolbarConfig,
+    selection: egui::Rect,
+    bounds: egui::Rect,
+    button_size: egui::Vec2,
+    spacing: f32,
+    count: usize,
+) -> Vec<egui::Pos2> {
+    if let Some([x, y]) = toolbar.pin {
+        let vertical = matches!(toolbar.side, ToolbarSide::Left | ToolbarSide::Right);
+        return pinned_tool_buttons(
+            bounds.min + egui::vec2(x, y),
+            vertical,
+            bounds,
+            button_size,
+            spacing,
+            count,
+        );
+    }
+    if toolbar.side == ToolbarSide::Auto {
+        return layout_tool_buttons(selection, bounds, button_size, spacing, count);
+    }
+    let vertical = matches!(toolbar.side, ToolbarSide::Left | ToolbarSide::Right);
+    let (room, step) = if vertical {
+        (bounds.height(), button_size.y + spacing)
+    } else {
+        (bounds.width(), button_size.x + spacing)
+    };
+    let per_line = (((room + spacing) / step).floor() as usize).max(1);
+    let screen = toolbar.anchor == ToolbarAnchor::Screen;
+    let target = if screen { bounds } else { selection };
+    let max = bounds.max - button_size;
+
+    let mut positions = Vec::with_capacity(count);
+    for (line, start) in (0..count).step_by(per_line).enumerate() {
+        let n = per_line.min(count - start);
+        let offset = line as f32 * (if vertical { button_size.x } else { button_size.y } + spacing);
+        // Screen docks sit inside the edge, selection docks outside it.
+        let inset = if screen { spacing + offset } else { -(spacing + offset) };
+        match toolbar.side {
+            ToolbarSide::Top => {
+                let y = if screen {
+                    target.min.y + inset
+                } else {
+                    target.min.y + inset - button_size.y
+                };
+                let y = y.clamp(bounds.min.y, max.y.max(bounds.min.y));
+                positions.extend(row_positions(target.center().x, y, n, button_size, spacing, bounds));
+            }
+            ToolbarSide::Bottom => {
+                let y = if screen {
+                    target.max.y - inset - button_size.y
+                } else {
+                    target.max.y - inset
+                };
+                let y = y.clamp(bounds.min.y, max.y.max(bounds.min.y));
+                positions.extend(row_positions(target.center().x, y, n, button_size, spacing, bounds));
+            }
+            ToolbarSide::Left => {
+                let x = if screen {
+                    target.min.x + inset
+                } else {
+                    target.min.x + inset - button_size.x
+                };
+                let x = x.clamp(bounds.min.x, max.x.max(bounds.min.x));
+                positions.extend(col_positions(target.center().y, x, n, button_size, spacing, bounds));
+            }
+            ToolbarSide::Right | ToolbarSide::Auto => {
+                let x = if screen {
+                    target.max.x - inset - button_size.x
+                } else {
+                    target.max.x - inset
+                };
+                let x = x.clamp(bounds.min.x, max.x.max(bounds.min.x));
+                positions.extend(col_positions(target.center().y, x, n, button_size, spacing, bounds));
+            }
+        }
+    }
+    positions
+}
+
+/// Positions for `count` buttons in rows (or columns when `vertical`) from
+/// `origin`, wrapping at the edge of `bounds`.
+pub(crate) fn pinned_tool_buttons(
+    origin: egui::Pos2,
+    vertical: bool,
+    bounds: egui::Rect,
+    button_size: egui::Vec2,
+    spacing: f32,
+    count: usize,
+) -> Vec<egui::Pos2> {
+    let step = button_size + egui::Vec2::splat(spacing);
+    let max = (bounds.max - button_size).max(bounds.min);
+    let origin = origin.clamp(bounds.min, max);
+    let room = if vertical {
+        max.y - origin.y
+    } else {
+        max.x - origin.x
+    };
+    let per_line = ((room / if vertical { step.y } else { step.x }).floor() as usize + 1).max(1);
+    (0..count)
+        .map(|i| {
+            let (along, across) = ((i % per_line) as f32, (i / per_line) as f32);
+            let offset = if vertical {
+                egui::vec2(across * step.x, along * step.y)
+            } else {
+                egui::vec2(along * step.x, across * step.y)
+            };
+            (origin + offset).min(max)
+        })
+        .collect()
+}
+
+/// Where a toolbar grip dropped at `pos` docks: the nearest screen edge
+/// within `SCREEN_DOCK_MARGIN`, otherwise the side of `selection` it was
+/// dropped on.
+pub(crate) fn dock_for_drop(
+    pos: egui::Pos2,
+    selection: egui::Rect,
+    bounds: egui::Rect,
+) -> (ToolbarSide, ToolbarAnchor) {
+    let edges = [
+        (pos.y - bounds.min.y, ToolbarSide::Top),
+        (bounds.max.y - pos.y, ToolbarSide::Bottom),
+        (pos.x - bounds.min.x, ToolbarSide::Left),
+        (bounds.max.x - pos.x, ToolbarSide::Right),
+    ];
+    let (distance, side) = edges
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap_or((f32::INFINITY, ToolbarSide::Auto));
+    if distance <= SCREEN_DOCK_MARGIN {
+        return (side, ToolbarAnchor::Screen);
+    }
+    // Compare against the selection's proportions so a drop off a corner
+    // picks the side it is further past.
+    let offset = pos - selection.center();
+    let half = (selection.size() * 0.5).max(egui::Vec2::splat(1.0));
+    let side = if (offset.x / half.x).abs() > (offset.y / half.y).abs() {
+        if offset.x < 0.0 {
+            ToolbarSide::Left
+        } else {
+            ToolbarSide::Right
+        }
+    } else if offset.y < 0.0 {
+        ToolbarSide::Top
+    } else {
+        ToolbarSide::Bottom
+    };
+    (side, ToolbarAnchor::Selection)
+}
+
 pub(crate) fn row_positions(
     center_x: f32,
     y: f32,
@@ -176,3 +384,59 @@ pub(crate) fn col_positions(
         .map(|i| egui::pos2(x, start_y + i as f32 * (button_size.y + spacing)))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: egui::Pos2, b: egui::Pos2) {
+        assert!((a - b).length() < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    /// A 1920x1080 logical screen at `scale`, captured at its physical size.
+    fn screen_at(scale: f32) -> (ViewTransform, egui::Vec2) {
+        let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(1920.0, 1080.0));
+        let image = (rect.size() * scale).round();
+        (ViewTransform::new(rect, image), image)
+    }
+
+    #[test]
+    fn round_trips_at_fractional_scales() {
+        for scale in [1.0, 1.25, 1.5, 1.75, 2.0, 2.25] {
+            let (view, image) = screen_at(scale);
+            for pos in [
+                egui::pos2(0.0, 0.0),
+                egui::pos2(1.0, 1.0),
+                egui::pos2(333.0, 777.0),
+                egui::pos2(image.x, image.y),
+            ] {
+                assert_close(view.to_image(view.to_screen(pos)), pos);
+            }
+            let point = egui::pos2(123.4, 567.8);
+            assert_close(view.to_screen(view.to_image(point)), point);
+        }
+    }
+
+    #[test]
+    fn corners_map_to_image_corners() {
+        // 1.25 of 1366 isn't a whole pixel; the capture's rounded width is
+        // still reached exactly at the right edge.
+        let rect = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(1366.0, 768.0));
+        let image = egui::vec2(1708.0, 960.0);
+        let view = ViewTransform::new(rect, image);
+        assert_close(view.to_image(rect.min), egui::Pos2::ZERO);
+        assert_close(view.to_image(rect.max), image.to_pos2());
+        let full = egui::Rect::from_min_size(egui::Pos2::ZERO, image);
+        let on_screen = view.rect_to_screen(full);
+        assert_close(on_screen.min, rect.min);
+        assert_close(on_screen.max, rect.max);
+    }
+
+    #[test]
+    fn empty_rect_keeps_unit_scale() {
+        let rect = egui::Rect::from_min_size(egui::pos2(5.0, 5.0), egui::Vec2::ZERO);
+        let view = ViewTransform::new(rect, egui::vec2(100.0, 100.0));
+        assert_eq!(view.scale, egui::Vec2::splat(1.0));
+        assert_close(view.to_image(egui::pos2(15.0, 25.0)), egui::pos2(10.0, 20.0));
+    }
+}
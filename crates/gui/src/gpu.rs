@@ -0,0 +1,294 @@
+//! Blur and pixelate as wgpu compute shaders, for effect previews on regions
+//! too large for the CPU kernels to keep up with. eframe draws through glow,
+//! so this opens its own device on the same adapter; when none is usable the
+//! callers stay on the CPU path in `effects`.
+
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll, Wake, Waker};
+
+use fireshot_core::shapes::EffectKind;
+use image::RgbaImage;
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = r#"
+struct Params {
+    width: u32,
+    height: u32,
+    size: u32,
+    _pad: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<u32>;
+@group(0) @binding(2) var<storage, read_write> dst: array<u32>;
+
+fn texel(x: u32, y: u32) -> vec4<f32> {
+    return unpack4x8unorm(src[y * params.width + x]);
+}
+
+@compute @workgroup_size(16, 16)
+fn blur_h(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let x0 = u32(max(i32(id.x) - i32(params.size), 0));
+    let x1 = min(id.x + params.size, params.width - 1u);
+    var sum = vec4<f32>(0.0);
+    for (var x = x0; x <= x1; x++) {
+        sum += texel(x, id.y);
+    }
+    dst[id.y * params.width + id.x] = pack4x8unorm(sum / f32(x1 - x0 + 1u));
+}
+
+@compute @workgroup_size(16, 16)
+fn blur_v(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let y0 = u32(max(i32(id.y) - i32(params.size), 0));
+    let y1 = min(id.y + params.size, params.height - 1u);
+    var sum = vec4<f32>(0.0);
+    for (var y = y0; y <= y1; y++) {
+        sum += texel(id.x, y);
+    }
+    dst[id.y * params.width + id.x] = pack4x8unorm(sum / f32(y1 - y0 + 1u));
+}
+
+@compute @workgroup_size(16, 16)
+fn pixelate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let bx = id.x / params.size * params.size;
+    let by = id.y / params.size * params.size;
+    let ex = min(bx + params.size, params.width);
+    let ey = min(by + params.size, params.height);
+    var sum = vec4<f32>(0.0);
+    for (var y = by; y < ey; y++) {
+        for (var x = bx; x < ex; x++) {
+            sum += texel(x, y);
+        }
+    }
+    dst[id.y * params.width + id.x] = pack4x8unorm(sum / f32((ex - bx) * (ey - by)));
+}
+"#;
+
+pub(crate) struct GpuEffects {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    blur_h: wgpu::ComputePipeline,
+    blur_v: wgpu::ComputePipeline,
+    pixelate: wgpu::ComputePipeline,
+}
+
+/// The process-wide device, created on first use; `None` when there is no
+/// adapter.
+pub(crate) fn shared() -> Option<&'static GpuEffects> {
+    static GPU: OnceLock<Option<GpuEffects>> = OnceLock::new();
+    GPU.get_or_init(GpuEffects::new).as_ref()
+}
+
+impl GpuEffects {
+    fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))?;
+        let (device, queue) = block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("fireshot effects"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+            },
+            None,
+        ))
+        .ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("effects"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("effects"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("effects"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+            })
+        };
+        let blur_h = pipeline("blur_h");
+        let blur_v = pipeline("blur_v");
+        let pixelate = pipeline("pixelate");
+        Some(Self {
+            device,
+            queue,
+            layout,
+            blur_h,
+            blur_v,
+            pixelate,
+        })
+    }
+
+    /// Applies `kind` to the whole of `img` in place, the way
+    /// `apply_blur_full`/`apply_pixelate_full` do. Returns `false` without
+    /// touching `img` when the image doesn't fit the device's limits.
+    pub(crate) fn apply(&self, img: &mut RgbaImage, kind: EffectKind, size: u32) -> bool {
+        let (width, height) = img.dimensions();
+        let bytes = img.as_raw().len() as u64;
+        let limits = self.device.limits();
+        let groups = |n: u32| n.div_ceil(16);
+        if bytes == 0
+            || bytes > limits.max_storage_buffer_binding_size as u64
+            || groups(width).max(groups(height)) > limits.max_compute_workgroups_per_dimension
+        {
+            return false;
+        }
+
+        let params: Vec<u8> = [width, height, size.max(1), 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let params = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("effect params"),
+                contents: &params,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let input = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("effect input"),
+                contents: img.as_raw(),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+        let storage = |label| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: bytes,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let scratch = storage("effect scratch");
+        let output = storage("effect output");
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("effect readback"),
+            size: bytes,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind = |src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: src.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: dst.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let passes = match kind {
+            EffectKind::Blur => vec![
+                (&self.blur_h, bind(&input, &scratch)),
+                (&self.blur_v, bind(&scratch, &output)),
+            ],
+            EffectKind::Pixelate => vec![(&self.pixelate, bind(&input, &output))],
+        };
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for (pipeline, bind_group) in &passes {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(groups(width), groups(height), 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, bytes);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        if !matches!(rx.recv(), Ok(Ok(()))) {
+            return false;
+        }
+        img.copy_from_slice(&slice.get_mapped_range());
+        readback.unmap();
+        true
+    }
+}
+
+/// Drives a wgpu future to completion on the calling thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
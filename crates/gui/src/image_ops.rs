@@ -1,6 +1,10 @@
 use eframe::egui;
+use image::imageops::FilterType;
 use image::RgbaImage;
 
+use crate::effects::apply_blur_full;
+use crate::parallel::for_each_row_band;
+
 pub(crate) fn rect_to_u32(img: &RgbaImage, rect: egui::Rect) -> Option<(u32, u32, u32, u32)> {
     let width = img.width() as f32;
     let height = img.height() as f32;
@@ -14,23 +18,6 @@ pub(crate) fn rect_to_u32(img: &RgbaImage, rect: egui::Rect) -> Option<(u32, u32
     Some((min_x, min_y, max_x, max_y))
 }
 
-pub(crate) fn crop_image_exact(img: &RgbaImage, rect: egui::Rect) -> Option<RgbaImage> {
-    let (min_x, min_y, max_x, max_y) = rect_to_u32(img, rect)?;
-    let out_w = max_x - min_x;
-    let out_h = max_y - min_y;
-    if out_w == 0 || out_h == 0 {
-        return None;
-    }
-    let mut out = RgbaImage::new(out_w, out_h);
-    for y in 0..out_h {
-        for x in 0..out_w {
-            let px = img.get_pixel(min_x + x, min_y + y);
-            out.put_pixel(x, y, *px);
-        }
-    }
-    Some(out)
-}
-
 pub(crate) fn crop_image(img: &RgbaImage, rect: egui::Rect) -> RgbaImage {
     let width = img.width() as f32;
     let height = img.height() as f32;
@@ -44,12 +31,69 @@ pub(crate) fn crop_image(img: &RgbaImage, rect: egui::Rect) -> RgbaImage {
         return img.clone();
     }
 
+    copy_region(img, min_x, min_y, out_w, out_h)
+}
+
+fn copy_region(img: &RgbaImage, min_x: u32, min_y: u32, out_w: u32, out_h: u32) -> RgbaImage {
+    let src_row_bytes = img.width() as usize * 4;
+    let out_row_bytes = out_w as usize * 4;
+    let x_offset = min_x as usize * 4;
     let mut out = RgbaImage::new(out_w, out_h);
-    for y in 0..out_h {
-        for x in 0..out_w {
-            let px = img.get_pixel(min_x + x, min_y + y);
-            out.put_pixel(x, y, *px);
+    for_each_row_band(out.as_mut(), out_row_bytes, 1, |first, band| {
+        for (i, row) in band.chunks_exact_mut(out_row_bytes).enumerate() {
+            let start = (min_y as usize + first + i) * src_row_bytes + x_offset;
+            row.copy_from_slice(&img.as_raw()[start..start + out_row_bytes]);
         }
-    }
+    });
     out
 }
+
+/// Longest side of the on-screen capture texture. Larger captures, such as
+/// multi-monitor stitches, are shown downscaled and exported at full size.
+pub(crate) const DISPLAY_MAX_SIDE: usize = 8192;
+
+/// Longest side of the blurred capture behind the editor chrome; the texture
+/// filter smooths it further where it is stretched.
+const BACKDROP_SIDE: u32 = 160;
+
+/// The capture shrunk and blurred, for the frosted backdrop.
+pub(crate) fn backdrop_image(img: &RgbaImage) -> egui::ColorImage {
+    let factor = BACKDROP_SIDE as f64 / img.width().max(img.height()).max(1) as f64;
+    let width = ((img.width() as f64 * factor).round() as u32).max(1);
+    let height = ((img.height() as f64 * factor).round() as u32).max(1);
+    let mut small = image::imageops::resize(img, width, height, FilterType::Triangle);
+    apply_blur_full(&mut small, 3);
+    egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], small.as_raw())
+}
+
+/// The capture as a texture image no larger than `max_side` on either axis.
+pub(crate) fn display_image(img: &RgbaImage, max_side: usize) -> egui::ColorImage {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let longest = width.max(height);
+    if longest <= max_side.max(1) {
+        return egui::ColorImage::from_rgba_unmultiplied([width, height], img.as_raw());
+    }
+    let factor = max_side as f64 / longest as f64;
+    let scaled_w = ((width as f64 * factor).round() as u32).max(1);
+    let scaled_h = ((height as f64 * factor).round() as u32).max(1);
+    let scaled = image::imageops::resize(img, scaled_w, scaled_h, FilterType::Triangle);
+    egui::ColorImage::from_rgba_unmultiplied(
+        [scaled_w as usize, scaled_h as usize],
+        scaled.as_raw(),
+    )
+}
+
+/// Whether any pixel of `img` lets what's behind it show through.
+pub(crate) fn has_transparency(img: &RgbaImage) -> bool {
+    img.pixels().any(|px| px[3] < u8::MAX)
+}
+
+/// One light and one dark square each way; repeated behind a capture with
+/// transparency so the see-through parts are visible.
+pub(crate) fn checker_image() -> egui::ColorImage {
+    let (light, dark) = (egui::Color32::from_gray(204), egui::Color32::from_gray(153));
+    egui::ColorImage {
+        size: [2, 2],
+        pixels: vec![light, dark, dark, light],
+    }
+}
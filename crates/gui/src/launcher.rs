@@ -0,0 +1,297 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use eframe::egui;
+use fireshot_core::history::{self, HistoryEntry};
+use fireshot_core::{paths, tr, CaptureError, CaptureMode, CaptureRequest, RecordRegion};
+use image::RgbaImage;
+
+use crate::app::native_options;
+use crate::image_ops::display_image;
+use crate::theme::Theme;
+
+const LAUNCHER_SIZE: egui::Vec2 = egui::Vec2 { x: 380.0, y: 170.0 };
+/// The box each screen's thumbnail is fitted into.
+const THUMBNAIL_SIZE: egui::Vec2 = egui::Vec2 { x: 110.0, y: 62.0 };
+/// Thumbnails in a row, as many as there are capture buttons.
+const THUMBNAILS_PER_ROW: usize = 3;
+/// A row of thumbnails with the screen names under them.
+const THUMBNAIL_ROW_HEIGHT: f32 = 96.0;
+/// The history search field.
+const SEARCH_ROW_HEIGHT: f32 = 30.0;
+/// A capture the search found, or the line saying it found none.
+const RESULT_ROW_HEIGHT: f32 = 24.0;
+/// Captures listed at most; `fireshot history search` lists them all.
+const MAX_RESULTS: usize = 5;
+
+/// One screen the launcher offers to capture on its own.
+pub struct LauncherScreen {
+    /// The connector, such as `DP-2`.
+    pub name: String,
+    /// Where the screen is in a full-screen capture.
+    pub region: RecordRegion,
+    /// What was on it when the launcher opened.
+    pub image: RgbaImage,
+}
+
+struct ScreenButton {
+    name: String,
+    region: RecordRegion,
+    texture: egui::TextureHandle,
+}
+
+/// Searching the text OCR read from the history.
+#[derive(Default)]
+struct HistorySearch {
+    query: String,
+    results: Vec<HistoryEntry>,
+}
+
+impl HistorySearch {
+    /// Lines under the field: a capture each, or the one saying none match.
+    fn lines(&self) -> usize {
+        if self.query.trim().is_empty() {
+            0
+        } else {
+            self.results.len().max(1)
+        }
+    }
+}
+
+struct LauncherApp {
+    theme: Theme,
+    delay_secs: u32,
+    choice: Rc<RefCell<Option<CaptureRequest>>>,
+    status: Option<String>,
+    screens: Vec<ScreenButton>,
+    /// `None` unless `[history] ocr` makes the history searchable.
+    search: Option<HistorySearch>,
+}
+
+/// The window, taller for screen thumbnails and the search and its results.
+fn launcher_size(screens: usize, search: Option<&HistorySearch>) -> egui::Vec2 {
+    let rows = screens.div_ceil(THUMBNAILS_PER_ROW);
+    let mut height = LAUNCHER_SIZE.y + rows as f32 * THUMBNAIL_ROW_HEIGHT;
+    if let Some(search) = search {
+        height += SEARCH_ROW_HEIGHT + search.lines() as f32 * RESULT_ROW_HEIGHT;
+    }
+    egui::vec2(LAUNCHER_SIZE.x, height)
+}
+
+impl LauncherApp {
+    fn choose(&mut self, ctx: &egui::Context, mode: CaptureMode) {
+        self.choose_region(ctx, mode, None);
+    }
+
+    fn choose_region(
+        &mut self,
+        ctx: &egui::Context,
+        mode: CaptureMode,
+        region: Option<RecordRegion>,
+    ) {
+        *self.choice.borrow_mut() = Some(CaptureRequest {
+            mode,
+            delay_ms: u64::from(self.delay_secs) * 1000,
+            region,
+            ..Default::default()
+        });
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    /// A thumbnail button per screen, which captures just that screen.
+    fn screens(&mut self, ui: &mut egui::Ui) {
+        let mut chosen = None;
+        for row in self.screens.chunks(THUMBNAILS_PER_ROW) {
+            ui.horizontal(|ui| {
+                for screen in row {
+                    ui.vertical(|ui| {
+                        ui.set_width(THUMBNAIL_SIZE.x);
+                        let thumbnail = egui::Image::new(&screen.texture)
+                            .max_size(THUMBNAIL_SIZE)
+                            .maintain_aspect_ratio(true);
+                        if ui
+                            .add_sized(THUMBNAIL_SIZE, egui::ImageButton::new(thumbnail))
+                            .on_hover_text(tr!("launcher-screen-tip", name = screen.name.as_str()))
+                            .clicked()
+                        {
+                            chosen = Some(screen.region);
+                        }
+                        ui.vertical_centered(|ui| ui.small(&screen.name));
+                    });
+                }
+            });
+        }
+        if let Some(region) = chosen {
+            self.choose_region(ui.ctx(), CaptureMode::Screen, Some(region));
+        }
+    }
+
+    fn open_dir(&mut self, dir: std::path::PathBuf) {
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            self.status = Some(tr!("launcher-create-failed", path = dir.display(), error = err));
+            return;
+        }
+        self.open(dir);
+    }
+
+    fn open(&mut self, path: PathBuf) {
+        if let Err(err) = paths::open_with_default_app(&path) {
+            self.status = Some(tr!("launcher-open-failed", path = path.display(), error = err));
+        }
+    }
+
+    /// The search field over the history's text, with a button per capture
+    /// that contains it, which opens the capture.
+    fn history_search(&mut self, ui: &mut egui::Ui) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        let field = egui::TextEdit::singleline(&mut search.query)
+            .hint_text(tr!("launcher-search-hint"))
+            .desired_width(f32::INFINITY);
+        if ui.add(field).changed() {
+            let query = search.query.trim();
+            search.results = if query.is_empty() {
+                Vec::new()
+            } else {
+                history::search(query).into_iter().take(MAX_RESULTS).collect()
+            };
+            let size = launcher_size(self.screens.len(), Some(search));
+            ui.ctx().send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        }
+        let mut chosen = None;
+        for entry in &search.results {
+            if ui
+                .button(entry.label())
+                .on_hover_text(entry.path.display().to_string())
+                .clicked()
+            {
+                chosen = Some(entry.path.clone());
+            }
+        }
+        if search.results.is_empty() && search.lines() > 0 {
+            ui.label(tr!("launcher-search-none"));
+        }
+        if let Some(path) = chosen {
+            self.open(path);
+        }
+    }
+}
+
+impl eframe::App for LauncherApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.theme.apply(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Fireshot");
+            ui.add_space(6.0);
+            let button_size = egui::vec2(110.0, 36.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_sized(button_size, egui::Button::new(tr!("launcher-region")))
+                    .on_hover_text(tr!("launcher-region-tip"))
+                    .clicked()
+                {
+                    self.choose(ctx, CaptureMode::Graphical);
+                }
+                if ui
+                    .add_sized(button_size, egui::Button::new(tr!("launcher-full")))
+                    .on_hover_text(tr!("launcher-full-tip"))
+                    .clicked()
+                {
+                    self.choose(ctx, CaptureMode::Fullscreen);
+                }
+                if ui
+                    .add_sized(button_size, egui::Button::new(tr!("launcher-window")))
+                    .on_hover_text(tr!("launcher-window-tip"))
+                    .clicked()
+                {
+                    self.choose(ctx, CaptureMode::Window);
+                }
+            });
+            if !self.screens.is_empty() {
+                ui.add_space(6.0);
+                self.screens(ui);
+            }
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                ui.label(tr!("launcher-delay"));
+                ui.add(
+                    egui::DragValue::new(&mut self.delay_secs)
+                        .clamp_range(0..=60)
+                        .suffix(" s"),
+                );
+                ui.separator();
+                if ui.button(tr!("launcher-settings")).clicked() {
+                    self.open_dir(paths::config_dir());
+                }
+                if ui.button(tr!("launcher-history")).clicked() {
+                    self.open_dir(paths::history_dir());
+                }
+            });
+            if self.search.is_some() {
+                ui.add_space(6.0);
+                self.history_search(ui);
+            }
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+        });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
+
+/// Shows the launcher window and returns the capture the user picked, if any.
+/// `screens` adds a thumbnail button for each screen, and `history_search` a
+/// field that searches the text OCR read from the history.
+pub fn run_launcher(
+    theme: Theme,
+    screens: Vec<LauncherScreen>,
+    history_search: bool,
+) -> Result<Option<CaptureRequest>, CaptureError> {
+    let search = history_search.then(HistorySearch::default);
+    let size = launcher_size(screens.len(), search.as_ref());
+    let options = native_options(
+        egui::ViewportBuilder::default()
+            .with_title("Fireshot")
+            .with_app_id("org.fireshot.Fireshot")
+            .with_inner_size(size)
+            .with_resizable(false),
+    );
+    let choice = Rc::new(RefCell::new(None));
+    let app_choice = choice.clone();
+    eframe::run_native(
+        "Fireshot",
+        options,
+        Box::new(move |cc| {
+            // Loaded at twice the box so they stay sharp on HiDPI screens.
+            let max_side = (THUMBNAIL_SIZE.x * 2.0) as usize;
+            let screens = screens
+                .into_iter()
+                .map(|screen| ScreenButton {
+                    texture: cc.egui_ctx.load_texture(
+                        format!("screen-{}", screen.name),
+                        display_image(&screen.image, max_side),
+                        egui::TextureOptions::LINEAR,
+                    ),
+                    name: screen.name,
+                    region: screen.region,
+                })
+                .collect();
+            Box::new(LauncherApp {
+                theme,
+                delay_secs: 0,
+                choice: app_choice,
+                status: None,
+                screens,
+                search,
+            })
+        }),
+    )
+    .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let choice = choice.borrow_mut().take();
+    Ok(choice)
+}
@@ -0,0 +1,1254 @@
+//! Editor surface on the wlr-layer-shell overlay layer. Unlike a fullscreen
+//! toplevel it can't be reordered or animated by the compositor, doesn't show
+//! up in task switchers and holds the keyboard exclusively while it's open.
+//! eframe only knows toplevels, so this drives egui itself: sctk for the
+//! surface and input, EGL through glutin and egui_glow for painting.
+
+use std::ffi::{c_char, CString};
+use std::fs::File;
+use std::num::NonZeroU32;
+use std::os::fd::OwnedFd;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use egui_glow::glow;
+use fireshot_core::{paths, CaptureError};
+use glutin::api::egl::{
+    context::PossiblyCurrentContext, display::Display as EglDisplay, surface::Surface as EglSurface,
+};
+use glutin::config::ConfigTemplateBuilder;
+use glutin::context::{ContextApi, ContextAttributesBuilder};
+use glutin::prelude::*;
+use glutin::surface::{SurfaceAttributesBuilder, WindowSurface};
+use log::warn;
+use raw_window_handle::{
+    RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
+};
+use smithay_client_toolkit::compositor::{CompositorHandler, CompositorState};
+use smithay_client_toolkit::output::{OutputHandler, OutputState};
+use smithay_client_toolkit::reexports::calloop::{self, EventLoop};
+use smithay_client_toolkit::reexports::calloop_wayland_source::WaylandSource;
+use smithay_client_toolkit::reexports::client::globals::registry_queue_init;
+use smithay_client_toolkit::reexports::client::protocol::{
+    wl_keyboard, wl_output, wl_pointer, wl_seat, wl_surface, wl_touch,
+};
+use smithay_client_toolkit::reexports::client::{
+    event_created_child, Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2::ZwpTabletManagerV2,
+    zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+    zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+    zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+    zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+    zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
+    zwp_tablet_tool_v2::{self, ZwpTabletToolV2},
+    zwp_tablet_v2::{self, ZwpTabletV2},
+};
+use smithay_client_toolkit::registry::{ProvidesRegistryState, RegistryState};
+use smithay_client_toolkit::seat::pointer::{
+    CursorIcon, PointerEvent, PointerEventKind, PointerHandler, ThemeSpec, ThemedPointer, BTN_LEFT,
+    BTN_MIDDLE, BTN_RIGHT,
+};
+use smithay_client_toolkit::seat::touch::TouchHandler;
+use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
+use smithay_client_toolkit::shell::wlr_layer::{
+    Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
+    LayerSurfaceConfigure,
+};
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shm::{Shm, ShmHandler};
+use smithay_client_toolkit::{
+    delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
+    delegate_seat, delegate_shm, delegate_touch, registry_handlers,
+};
+use xkbcommon_dl::{
+    keysyms, xkb_context, xkb_context_flags, xkb_keymap, xkb_keymap_compile_flags,
+    xkb_keymap_format, xkb_state, xkb_state_component, XkbCommon, XKB_MOD_NAME_ALT,
+    XKB_MOD_NAME_CTRL, XKB_MOD_NAME_SHIFT,
+};
+
+use crate::app::EditorApp;
+use crate::clipboard;
+use crate::control::EditorControl;
+use crate::shapes::PenState;
+
+/// A connected overlay surface with its GL context, ready to run an editor.
+pub(crate) struct Session {
+    event_loop: EventLoop<'static, State>,
+    state: State,
+    gl: Gl,
+}
+
+struct Gl {
+    display: EglDisplay,
+    context: PossiblyCurrentContext,
+    surface: EglSurface<WindowSurface>,
+    painter: egui_glow::Painter,
+}
+
+impl Session {
+    /// Maps the overlay surface. Fails when this isn't a Wayland session, the
+    /// compositor has no layer shell or EGL can't be set up; the caller then
+    /// opens a regular window instead.
+    pub(crate) fn connect() -> Result<Self, String> {
+        let conn = Connection::connect_to_env().map_err(|e| e.to_string())?;
+        let (globals, event_queue) =
+            registry_queue_init::<State>(&conn).map_err(|e| e.to_string())?;
+        let qh = event_queue.handle();
+        let compositor = CompositorState::bind(&globals, &qh).map_err(|e| e.to_string())?;
+        let layer_shell = LayerShell::bind(&globals, &qh).map_err(|e| e.to_string())?;
+        let shm = Shm::bind(&globals, &qh).map_err(|e| e.to_string())?;
+        // Optional: without it, styluses arrive as an emulated pointer.
+        let tablet_manager = globals.bind::<ZwpTabletManagerV2, _, _>(&qh, 1..=1, ()).ok();
+
+        let surface = compositor.create_surface(&qh);
+        let gl = Gl::new(&conn, &surface)?;
+        let layer =
+            layer_shell.create_layer_surface(&qh, surface, Layer::Overlay, Some("fireshot"), None);
+        layer.set_anchor(Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT);
+        layer.set_exclusive_zone(-1);
+        layer.set_keyboard_interactivity(KeyboardInteractivity::Exclusive);
+        // The first commit carries no buffer; the compositor answers with the
+        // configure that sizes the surface.
+        layer.commit();
+
+        let event_loop = EventLoop::try_new().map_err(|e| e.to_string())?;
+        WaylandSource::new(conn.clone(), event_queue)
+            .insert(event_loop.handle())
+            .map_err(|e| e.to_string())?;
+        let state = State {
+            registry_state: RegistryState::new(&globals),
+            seat_state: SeatState::new(&globals, &qh),
+            output_state: OutputState::new(&globals, &qh),
+            compositor,
+            shm,
+            conn,
+            layer,
+            pointer: None,
+            keyboard: None,
+            touch: None,
+            touches: Vec::new(),
+            touch_pointer: None,
+            tablet_manager,
+            tablet_seat: None,
+            tools: Vec::new(),
+            pen: PenState::default(),
+            keymap: None,
+            repeat_info: None,
+            repeating: None,
+            events: Vec::new(),
+            modifiers: egui::Modifiers::NONE,
+            cursor: None,
+            size: None,
+            scale: 1,
+            resized: false,
+            closed: false,
+        };
+        Ok(Self {
+            event_loop,
+            state,
+            gl,
+        })
+    }
+
+    /// Runs `app` until it asks to close or the compositor drops the surface.
+    pub(crate) fn run(
+        mut self,
+        mut app: EditorApp,
+        control: &EditorControl,
+    ) -> Result<(), CaptureError> {
+        let ctx = egui::Context::default();
+        control.attach(&ctx);
+
+        // Repaints requested from egui, including from export threads, land
+        // here and wake the loop.
+        let repaint_at = Arc::new(Mutex::new(Some(Instant::now())));
+        let (ping, ping_source) =
+            calloop::ping::make_ping().map_err(|e| CaptureError::Io(e.to_string()))?;
+        self.event_loop
+            .handle()
+            .insert_source(ping_source, |_, _, _| {})
+            .map_err(|e| CaptureError::Io(e.to_string()))?;
+        let wake_at = repaint_at.clone();
+        ctx.set_request_repaint_callback(move |info| {
+            let at = Instant::now() + info.delay;
+            if let Ok(mut slot) = wake_at.lock() {
+                if slot.is_none_or(|current| at < current) {
+                    *slot = Some(at);
+                }
+            }
+            ping.ping();
+        });
+
+        let started = Instant::now();
+        let result = loop {
+            let deadline = [
+                repaint_at.lock().ok().and_then(|slot| *slot),
+                self.state.repeating.map(|(_, at)| at),
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            // Nothing can be drawn before the first configure.
+            let timeout = deadline
+                .filter(|_| self.state.size.is_some())
+                .map(|at| at.saturating_duration_since(Instant::now()));
+            if let Err(err) = self.event_loop.dispatch(timeout, &mut self.state) {
+                break Err(CaptureError::Io(err.to_string()));
+            }
+            if self.state.closed {
+                break Ok(());
+            }
+            self.state.repeat_key(Instant::now());
+            let Some((width, height)) = self.state.size else {
+                continue;
+            };
+
+            let due = repaint_at
+                .lock()
+                .ok()
+                .and_then(|slot| *slot)
+                .is_some_and(|at| at <= Instant::now());
+            if !due && self.state.events.is_empty() && !self.state.resized {
+                continue;
+            }
+            if let Ok(mut slot) = repaint_at.lock() {
+                *slot = None;
+            }
+
+            let scale = self.state.scale.max(1);
+            let physical = [width * scale as u32, height * scale as u32];
+            if std::mem::take(&mut self.state.resized) {
+                self.state.layer.wl_surface().set_buffer_scale(scale);
+                self.gl.resize(physical);
+            }
+
+            let raw_input = self.state.take_input(
+                egui::vec2(width as f32, height as f32),
+                scale as f32,
+                started.elapsed(),
+                self.gl.painter.max_texture_side(),
+            );
+            app.set_pen(self.state.pen);
+            let output = ctx.run(raw_input, |ctx| app.ui(ctx));
+            self.state.set_cursor(output.platform_output.cursor_icon);
+            if !output.platform_output.copied_text.is_empty() {
+                if let Err(err) = clipboard::copy_text(&output.platform_output.copied_text) {
+                    warn!("copy: {}", err);
+                }
+            }
+            if let Some(open) = &output.platform_output.open_url {
+                // xdg-open takes URLs as well as paths.
+                if let Err(err) = paths::open_with_default_app(Path::new(&open.url)) {
+                    warn!("open {}: {}", open.url, err);
+                }
+            }
+            let close = output
+                .viewport_output
+                .get(&egui::ViewportId::ROOT)
+                .is_some_and(|viewport| {
+                    viewport
+                        .commands
+                        .iter()
+                        .any(|cmd| matches!(cmd, egui::ViewportCommand::Close))
+                });
+
+            let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+            self.gl.painter.clear(physical, [0.0; 4]);
+            self.gl.painter.paint_and_update_textures(
+                physical,
+                output.pixels_per_point,
+                &primitives,
+                &output.textures_delta,
+            );
+            if let Err(err) = self.gl.surface.swap_buffers(&self.gl.context) {
+                break Err(CaptureError::Io(err.to_string()));
+            }
+            if close {
+                break Ok(());
+            }
+        };
+
+        control.detach();
+        self.gl.painter.destroy();
+        drop(self.gl.surface);
+        drop(self.gl.context);
+        drop(self.gl.display);
+        result
+    }
+}
+
+impl Gl {
+    fn new(conn: &Connection, surface: &wl_surface::WlSurface) -> Result<Self, String> {
+        let mut display_handle = WaylandDisplayHandle::empty();
+        display_handle.display = conn.backend().display_ptr().cast();
+        let mut window_handle = WaylandWindowHandle::empty();
+        window_handle.surface = surface.id().as_ptr().cast();
+        let window_handle = RawWindowHandle::Wayland(window_handle);
+
+        // SAFETY: both handles point at live objects owned by `conn` and
+        // `surface`, which outlive the EGL objects (`Session::run` drops
+        // these first).
+        unsafe {
+            let display = EglDisplay::new(RawDisplayHandle::Wayland(display_handle))
+                .map_err(|e| e.to_string())?;
+            let config = display
+                .find_configs(ConfigTemplateBuilder::new().with_alpha_size(8).build())
+                .map_err(|e| e.to_string())?
+                .next()
+                .ok_or("no EGL config")?;
+            let context = display
+                .create_context(
+                    &config,
+                    &ContextAttributesBuilder::new().build(Some(window_handle)),
+                )
+                .or_else(|_| {
+                    let gles = ContextAttributesBuilder::new()
+                        .with_context_api(ContextApi::Gles(None))
+                        .build(Some(window_handle));
+                    display.create_context(&config, &gles)
+                })
+                .map_err(|e| e.to_string())?;
+            let one = NonZeroU32::MIN;
+            let surface = display
+                .create_window_surface(
+                    &config,
+                    &SurfaceAttributesBuilder::<WindowSurface>::new().build(
+                        window_handle,
+                        one,
+                        one,
+                    ),
+                )
+                .map_err(|e| e.to_string())?;
+            let context = context.make_current(&surface).map_err(|e| e.to_string())?;
+            let gl =
+                glow::Context::from_loader_function_cstr(|name| display.get_proc_address(name));
+            // egui_glow wants an Arc even though the context stays on this
+            // thread.
+            #[allow(clippy::arc_with_non_send_sync)]
+            let gl = Arc::new(gl);
+            let painter = egui_glow::Painter::new(gl, "", None).map_err(|e| e.to_string())?;
+            Ok(Self {
+                display,
+                context,
+                surface,
+                painter,
+            })
+        }
+    }
+
+    fn resize(&self, [width, height]: [u32; 2]) {
+        if let (Some(width), Some(height)) = (NonZeroU32::new(width), NonZeroU32::new(height)) {
+            self.surface.resize(&self.context, width, height);
+        }
+    }
+}
+
+/// A stylus end in proximity or known to the seat, with the state its
+/// events build up until the next frame event.
+struct PenTool {
+    tool: ZwpTabletToolV2,
+    eraser: bool,
+    pos: egui::Pos2,
+    pressure: f32,
+    moved: bool,
+    /// Tip pressed or lifted since the last frame.
+    tip: Option<bool>,
+}
+
+struct State {
+    registry_state: RegistryState,
+    seat_state: SeatState,
+    output_state: OutputState,
+    compositor: CompositorState,
+    shm: Shm,
+    conn: Connection,
+    layer: LayerSurface,
+    pointer: Option<ThemedPointer>,
+    keyboard: Option<wl_keyboard::WlKeyboard>,
+    touch: Option<wl_touch::WlTouch>,
+    /// Fingers down and where they last were.
+    touches: Vec<(i32, egui::Pos2)>,
+    /// The finger that also drives the pointer, as egui-winit does it.
+    touch_pointer: Option<i32>,
+    tablet_manager: Option<ZwpTabletManagerV2>,
+    tablet_seat: Option<ZwpTabletSeatV2>,
+    tools: Vec<PenTool>,
+    /// The stylus currently in use, if any.
+    pen: PenState,
+    keymap: Option<Keymap>,
+    /// Repeats per second and the delay before the first one.
+    repeat_info: Option<(u32, Duration)>,
+    /// Held key being repeated and when it next fires.
+    repeating: Option<(u32, Instant)>,
+    events: Vec<egui::Event>,
+    modifiers: egui::Modifiers,
+    cursor: Option<egui::CursorIcon>,
+    /// Surface size in logical pixels, once configured.
+    size: Option<(u32, u32)>,
+    scale: i32,
+    resized: bool,
+    closed: bool,
+}
+
+impl State {
+    fn take_input(
+        &mut self,
+        size: egui::Vec2,
+        pixels_per_point: f32,
+        time: Duration,
+        max_texture_side: usize,
+    ) -> egui::RawInput {
+        let mut raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, size)),
+            max_texture_side: Some(max_texture_side),
+            time: Some(time.as_secs_f64()),
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            focused: true,
+            ..Default::default()
+        };
+        let viewport = raw_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default();
+        viewport.native_pixels_per_point = Some(pixels_per_point);
+        viewport.inner_rect = raw_input.screen_rect;
+        viewport.fullscreen = Some(true);
+        viewport.focused = Some(true);
+        raw_input
+    }
+
+    fn set_cursor(&mut self, icon: egui::CursorIcon) {
+        if self.cursor == Some(icon) {
+            return;
+        }
+        let Some(pointer) = &self.pointer else {
+            return;
+        };
+        let applied = match cursor_icon(icon) {
+            Some(icon) => pointer.set_cursor(&self.conn, icon),
+            None => pointer.hide_cursor(),
+        };
+        // Without an enter serial the compositor ignores us; retry after the
+        // next enter.
+        self.cursor = applied.ok().map(|_| icon);
+    }
+
+    fn key(&mut self, code: u32, pressed: bool, repeat: bool) {
+        let Some(keymap) = &self.keymap else {
+            return;
+        };
+        if let Some(key) = keymap.key(code) {
+            // As egui-winit does, the clipboard keys become their own events
+            // instead of key presses.
+            if pressed && self.modifiers.command {
+                match key {
+                    egui::Key::X => return self.events.push(egui::Event::Cut),
+                    egui::Key::C => return self.events.push(egui::Event::Copy),
+                    egui::Key::V => {
+                        let text = clipboard::paste_text().map(|text| text.replace("\r\n", "\n"));
+                        if let Some(text) = text.filter(|text| !text.is_empty()) {
+                            self.events.push(egui::Event::Paste(text));
+                        }
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+            self.events.push(egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat,
+                modifiers: self.modifiers,
+            });
+        }
+        if pressed && !self.modifiers.ctrl && !self.modifiers.alt {
+            if let Some(text) = keymap.text(code) {
+                self.events.push(egui::Event::Text(text));
+            }
+        }
+    }
+
+    fn touch_event(&mut self, id: i32, phase: egui::TouchPhase, pos: egui::Pos2) {
+        self.events.push(egui::Event::Touch {
+            device_id: egui::TouchDeviceId(0),
+            id: id.into(),
+            phase,
+            pos,
+            force: None,
+        });
+    }
+
+    /// Lifts every finger, releasing the pointer one drives.
+    fn cancel_touches(&mut self) {
+        for (id, pos) in std::mem::take(&mut self.touches) {
+            self.touch_event(id, egui::TouchPhase::Cancel, pos);
+            if self.touch_pointer == Some(id) {
+                self.touch_pointer = None;
+                self.events.push(egui::Event::PointerButton {
+                    pos,
+                    button: egui::PointerButton::Primary,
+                    pressed: false,
+                    modifiers: self.modifiers,
+                });
+                self.events.push(egui::Event::PointerGone);
+            }
+        }
+    }
+
+    fn repeat_key(&mut self, now: Instant) {
+        let (Some((code, at)), Some((rate, _))) = (self.repeating, self.repeat_info) else {
+            return;
+        };
+        if at <= now {
+            self.repeating = Some((code, now + Duration::from_secs(1) / rate));
+            self.key(code, true, true);
+        }
+    }
+}
+
+impl CompositorHandler for State {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        new_factor: i32,
+    ) {
+        self.scale = new_factor;
+        self.resized = true;
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+}
+
+impl OutputHandler for State {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {}
+
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {}
+
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {}
+}
+
+impl LayerShellHandler for State {
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
+        self.closed = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let (width, height) = configure.new_size;
+        if width > 0 && height > 0 && self.size != Some((width, height)) {
+            self.size = Some((width, height));
+            self.resized = true;
+        }
+    }
+}
+
+impl SeatHandler for State {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
+
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if let (Some(manager), None) = (&self.tablet_manager, &self.tablet_seat) {
+            self.tablet_seat = Some(manager.get_tablet_seat(&seat, qh, ()));
+        }
+        if capability == Capability::Keyboard && self.keyboard.is_none() {
+            self.keyboard = Some(seat.get_keyboard(qh, ()));
+        }
+        if capability == Capability::Touch && self.touch.is_none() {
+            self.touch = self.seat_state.get_touch(qh, &seat).ok();
+        }
+        if capability == Capability::Pointer && self.pointer.is_none() {
+            let surface = self.compositor.create_surface(qh);
+            self.pointer = self
+                .seat_state
+                .get_pointer_with_theme(qh, &seat, self.shm.wl_shm(), surface, ThemeSpec::System)
+                .ok();
+        }
+    }
+
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Keyboard {
+            if let Some(keyboard) = self.keyboard.take() {
+                keyboard.release();
+            }
+            self.repeating = None;
+        }
+        if capability == Capability::Pointer {
+            if let Some(pointer) = self.pointer.take() {
+                pointer.pointer().release();
+            }
+        }
+        if capability == Capability::Touch {
+            if let Some(touch) = self.touch.take() {
+                touch.release();
+            }
+            self.cancel_touches();
+        }
+    }
+
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
+}
+
+impl PointerHandler for State {
+    fn pointer_frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _pointer: &wl_pointer::WlPointer,
+        events: &[PointerEvent],
+    ) {
+        for event in events {
+            let pos = egui::pos2(event.position.0 as f32, event.position.1 as f32);
+            match event.kind {
+                PointerEventKind::Enter { .. } => {
+                    self.cursor = None;
+                    self.events.push(egui::Event::PointerMoved(pos));
+                }
+                PointerEventKind::Leave { .. } => {
+                    self.events.push(egui::Event::PointerGone);
+                }
+                PointerEventKind::Motion { .. } => {
+                    self.pen = PenState::default();
+                    self.events.push(egui::Event::PointerMoved(pos));
+                }
+                PointerEventKind::Press { button, .. }
+                | PointerEventKind::Release { button, .. } => {
+                    self.pen = PenState::default();
+                    let button = match button {
+                        BTN_LEFT => egui::PointerButton::Primary,
+                        BTN_RIGHT => egui::PointerButton::Secondary,
+                        BTN_MIDDLE => egui::PointerButton::Middle,
+                        _ => continue,
+                    };
+                    self.events.push(egui::Event::PointerButton {
+                        pos,
+                        button,
+                        pressed: matches!(event.kind, PointerEventKind::Press { .. }),
+                        modifiers: self.modifiers,
+                    });
+                }
+                PointerEventKind::Axis {
+                    horizontal,
+                    vertical,
+                    ..
+                } => {
+                    let (unit, delta) = if horizontal.discrete != 0 || vertical.discrete != 0 {
+                        (
+                            egui::MouseWheelUnit::Line,
+                            egui::vec2(horizontal.discrete as f32, vertical.discrete as f32),
+                        )
+                    } else {
+                        (
+                            egui::MouseWheelUnit::Point,
+                            egui::vec2(horizontal.absolute as f32, vertical.absolute as f32),
+                        )
+                    };
+                    // Wayland scrolls towards positive, egui moves the content.
+                    self.events.push(egui::Event::MouseWheel {
+                        unit,
+                        delta: -delta,
+                        modifiers: self.modifiers,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl TouchHandler for State {
+    fn down(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _serial: u32,
+        _time: u32,
+        _surface: wl_surface::WlSurface,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        let pos = egui::pos2(position.0 as f32, position.1 as f32);
+        self.touches.push((id, pos));
+        self.touch_event(id, egui::TouchPhase::Start, pos);
+        if self.touch_pointer.is_none() {
+            self.touch_pointer = Some(id);
+            self.pen = PenState::default();
+            self.events.push(egui::Event::PointerMoved(pos));
+            self.events.push(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed: true,
+                modifiers: self.modifiers,
+            });
+        }
+    }
+
+    fn up(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _serial: u32,
+        _time: u32,
+        id: i32,
+    ) {
+        let Some(index) = self.touches.iter().position(|(known, _)| *known == id) else {
+            return;
+        };
+        let (_, pos) = self.touches.remove(index);
+        self.touch_event(id, egui::TouchPhase::End, pos);
+        if self.touch_pointer == Some(id) {
+            self.touch_pointer = None;
+            self.events.push(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed: false,
+                modifiers: self.modifiers,
+            });
+            self.events.push(egui::Event::PointerGone);
+        }
+    }
+
+    fn motion(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _time: u32,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        let pos = egui::pos2(position.0 as f32, position.1 as f32);
+        let Some(known) = self.touches.iter_mut().find(|(known, _)| *known == id) else {
+            return;
+        };
+        known.1 = pos;
+        self.touch_event(id, egui::TouchPhase::Move, pos);
+        if self.touch_pointer == Some(id) {
+            self.events.push(egui::Event::PointerMoved(pos));
+        }
+    }
+
+    fn shape(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_touch::WlTouch,
+        _: i32,
+        _: f64,
+        _: f64,
+    ) {
+    }
+
+    fn orientation(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_touch::WlTouch,
+        _: i32,
+        _: f64,
+    ) {
+    }
+
+    fn cancel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _touch: &wl_touch::WlTouch) {
+        self.cancel_touches();
+    }
+}
+
+impl ShmHandler for State {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl ProvidesRegistryState for State {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    registry_handlers![OutputState, SeatState];
+}
+
+impl Dispatch<wl_keyboard::WlKeyboard, ()> for State {
+    fn event(
+        state: &mut Self,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
+        _: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_keyboard::Event::Keymap {
+                format: WEnum::Value(wl_keyboard::KeymapFormat::XkbV1),
+                fd,
+                size,
+            } => {
+                state.keymap = Keymap::from_fd(fd, size as usize);
+            }
+            wl_keyboard::Event::Leave { .. } => {
+                state.repeating = None;
+                state.modifiers = egui::Modifiers::NONE;
+            }
+            wl_keyboard::Event::Key {
+                key,
+                state: key_state,
+                ..
+            } => {
+                let code = key + 8;
+                let pressed = key_state == WEnum::Value(wl_keyboard::KeyState::Pressed);
+                state.key(code, pressed, false);
+                if pressed {
+                    let repeats = state
+                        .keymap
+                        .as_ref()
+                        .is_some_and(|keymap| keymap.repeats(code));
+                    if let (true, Some((_, delay))) = (repeats, state.repeat_info) {
+                        state.repeating = Some((code, Instant::now() + delay));
+                    }
+                } else if state.repeating.is_some_and(|(held, _)| held == code) {
+                    state.repeating = None;
+                }
+            }
+            wl_keyboard::Event::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+                ..
+            } => {
+                if let Some(keymap) = &state.keymap {
+                    state.modifiers =
+                        keymap.update_modifiers(mods_depressed, mods_latched, mods_locked, group);
+                }
+            }
+            wl_keyboard::Event::RepeatInfo { rate, delay } => {
+                state.repeat_info = u32::try_from(rate)
+                    .ok()
+                    .filter(|rate| *rate > 0)
+                    .map(|rate| (rate, Duration::from_millis(delay.max(0) as u64)));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletManagerV2, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletManagerV2,
+        _: <ZwpTabletManagerV2 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletSeatV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        _seat: &ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        _: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_seat_v2::Event::ToolAdded { id } = event {
+            state.tools.push(PenTool {
+                tool: id,
+                eraser: false,
+                pos: egui::Pos2::ZERO,
+                pressure: 0.0,
+                moved: false,
+                tip: None,
+            });
+        }
+    }
+
+    event_created_child!(State, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletToolV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        tool: &ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_tablet_tool_v2::Event;
+        if let Event::Removed = event {
+            state.tools.retain(|known| known.tool != *tool);
+            tool.destroy();
+            return;
+        }
+        let Some(index) = state.tools.iter().position(|known| known.tool == *tool) else {
+            return;
+        };
+        let pen = &mut state.tools[index];
+        match event {
+            Event::Type { tool_type } => {
+                pen.eraser = tool_type == WEnum::Value(zwp_tablet_tool_v2::Type::Eraser);
+            }
+            Event::Motion { x, y } => {
+                pen.pos = egui::pos2(x as f32, y as f32);
+                pen.moved = true;
+            }
+            // The protocol's pressure runs from 0 to 65535.
+            Event::Pressure { pressure } => pen.pressure = pressure as f32 / 65535.0,
+            Event::Down { .. } => pen.tip = Some(true),
+            Event::Up => pen.tip = Some(false),
+            Event::ProximityOut => {
+                pen.tip = None;
+                pen.moved = false;
+                state.pen = PenState::default();
+                state.events.push(egui::Event::PointerGone);
+            }
+            Event::Frame { .. } => {
+                let (pos, eraser, pressure) = (pen.pos, pen.eraser, pen.pressure);
+                let tip = pen.tip.take();
+                if std::mem::take(&mut pen.moved) {
+                    state.events.push(egui::Event::PointerMoved(pos));
+                }
+                state.pen = PenState {
+                    pressure: Some(pressure),
+                    eraser,
+                };
+                if let Some(pressed) = tip {
+                    state.events.push(egui::Event::PointerButton {
+                        pos,
+                        button: egui::PointerButton::Primary,
+                        pressed,
+                        modifiers: state.modifiers,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletV2, ()> for State {
+    fn event(
+        _: &mut Self,
+        tablet: &ZwpTabletV2,
+        event: zwp_tablet_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_v2::Event::Removed = event {
+            tablet.destroy();
+        }
+    }
+}
+
+// Pads (buttons, rings and strips on the tablet) aren't used, but their
+// objects still have to be accepted.
+impl Dispatch<ZwpTabletPadV2, ()> for State {
+    fn event(
+        _: &mut Self,
+        pad: &ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_pad_v2::Event::Removed = event {
+            pad.destroy();
+        }
+    }
+
+    event_created_child!(State, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadGroupV2,
+        _: zwp_tablet_pad_group_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+
+    event_created_child!(State, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadRingV2, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadRingV2,
+        _: <ZwpTabletPadRingV2 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadStripV2,
+        _: <ZwpTabletPadStripV2 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_compositor!(State);
+delegate_output!(State);
+delegate_shm!(State);
+delegate_seat!(State);
+delegate_pointer!(State);
+delegate_touch!(State);
+delegate_layer!(State);
+delegate_registry!(State);
+
+/// The compositor's keymap, loaded through a dlopened libxkbcommon.
+struct Keymap {
+    xkb: &'static XkbCommon,
+    context: *mut xkb_context,
+    keymap: *mut xkb_keymap,
+    state: *mut xkb_state,
+}
+
+impl Keymap {
+    fn from_fd(fd: OwnedFd, size: usize) -> Option<Self> {
+        let xkb = xkbcommon_dl::xkbcommon_option()?;
+        // SAFETY: the compositor shares the keymap read-only; a private
+        // mapping can't be changed under us.
+        let map = unsafe {
+            memmap2::MmapOptions::new()
+                .len(size)
+                .map_copy_read_only(&File::from(fd))
+                .ok()?
+        };
+        // The map is NUL-terminated; strip it so CString doesn't reject it.
+        let text = CString::new(map.split(|b| *b == 0).next()?).ok()?;
+        // SAFETY: plain libxkbcommon calls; every pointer is checked before
+        // use and owned by the returned value.
+        unsafe {
+            let context = (xkb.xkb_context_new)(xkb_context_flags::XKB_CONTEXT_NO_FLAGS);
+            if context.is_null() {
+                return None;
+            }
+            let keymap = (xkb.xkb_keymap_new_from_string)(
+                context,
+                text.as_ptr(),
+                xkb_keymap_format::XKB_KEYMAP_FORMAT_TEXT_V1,
+                xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+            );
+            if keymap.is_null() {
+                (xkb.xkb_context_unref)(context);
+                return None;
+            }
+            let state = (xkb.xkb_state_new)(keymap);
+            if state.is_null() {
+                (xkb.xkb_keymap_unref)(keymap);
+                (xkb.xkb_context_unref)(context);
+                return None;
+            }
+            Some(Self {
+                xkb,
+                context,
+                keymap,
+                state,
+            })
+        }
+    }
+
+    fn update_modifiers(
+        &self,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    ) -> egui::Modifiers {
+        let active = |name: &[u8]| {
+            // SAFETY: `name` is one of xkbcommon-dl's NUL-terminated names.
+            unsafe {
+                (self.xkb.xkb_state_mod_name_is_active)(
+                    self.state,
+                    name.as_ptr().cast::<c_char>(),
+                    xkb_state_component::XKB_STATE_MODS_EFFECTIVE,
+                ) > 0
+            }
+        };
+        // SAFETY: `self.state` is valid for the lifetime of `self`.
+        unsafe {
+            (self.xkb.xkb_state_update_mask)(self.state, depressed, latched, locked, 0, 0, group);
+        }
+        let ctrl = active(XKB_MOD_NAME_CTRL);
+        egui::Modifiers {
+            alt: active(XKB_MOD_NAME_ALT),
+            ctrl,
+            shift: active(XKB_MOD_NAME_SHIFT),
+            mac_cmd: false,
+            command: ctrl,
+        }
+    }
+
+    fn key(&self, code: u32) -> Option<egui::Key> {
+        // SAFETY: `self.state` is valid for the lifetime of `self`.
+        let sym = unsafe { (self.xkb.xkb_state_key_get_one_sym)(self.state, code) };
+        let key = match sym {
+            keysyms::Escape => egui::Key::Escape,
+            keysyms::Tab | keysyms::ISO_Left_Tab => egui::Key::Tab,
+            keysyms::BackSpace => egui::Key::Backspace,
+            keysyms::Return | keysyms::KP_Enter => egui::Key::Enter,
+            keysyms::space => egui::Key::Space,
+            keysyms::Insert => egui::Key::Insert,
+            keysyms::Delete | keysyms::KP_Delete => egui::Key::Delete,
+            keysyms::Home => egui::Key::Home,
+            keysyms::End => egui::Key::End,
+            keysyms::Page_Up => egui::Key::PageUp,
+            keysyms::Page_Down => egui::Key::PageDown,
+            keysyms::Left => egui::Key::ArrowLeft,
+            keysyms::Right => egui::Key::ArrowRight,
+            keysyms::Up => egui::Key::ArrowUp,
+            keysyms::Down => egui::Key::ArrowDown,
+            keysyms::F1..=keysyms::F20 => {
+                return egui::Key::from_name(&format!("F{}", sym - keysyms::F1 + 1));
+            }
+            _ => {
+                // SAFETY: pure lookup on a keysym value.
+                let ch = char::from_u32(unsafe { (self.xkb.xkb_keysym_to_utf32)(sym) })?;
+                return egui::Key::from_name(ch.encode_utf8(&mut [0; 4]));
+            }
+        };
+        Some(key)
+    }
+
+    fn text(&self, code: u32) -> Option<String> {
+        let mut buf = [0u8; 64];
+        // SAFETY: xkbcommon writes at most `buf.len()` bytes including the NUL.
+        let len = unsafe {
+            (self.xkb.xkb_state_key_get_utf8)(self.state, code, buf.as_mut_ptr().cast(), buf.len())
+        };
+        let len = usize::try_from(len).ok().filter(|len| *len > 0)?;
+        let text = std::str::from_utf8(buf.get(..len)?).ok()?;
+        (!text.chars().any(char::is_control)).then(|| text.to_string())
+    }
+
+    fn repeats(&self, code: u32) -> bool {
+        // SAFETY: `self.keymap` is valid for the lifetime of `self`.
+        unsafe { (self.xkb.xkb_keymap_key_repeats)(self.keymap, code) != 0 }
+    }
+}
+
+impl Drop for Keymap {
+    fn drop(&mut self) {
+        // SAFETY: each pointer was created in `from_fd` and is released once.
+        unsafe {
+            (self.xkb.xkb_state_unref)(self.state);
+            (self.xkb.xkb_keymap_unref)(self.keymap);
+            (self.xkb.xkb_context_unref)(self.context);
+        }
+    }
+}
+
+/// The compositor-side cursor for an egui icon; `None` hides it.
+fn cursor_icon(icon: egui::CursorIcon) -> Option<CursorIcon> {
+    use egui::CursorIcon as Egui;
+    Some(match icon {
+        Egui::None => return None,
+        Egui::ContextMenu => CursorIcon::ContextMenu,
+        Egui::Help => CursorIcon::Help,
+        Egui::PointingHand => CursorIcon::Pointer,
+        Egui::Progress => CursorIcon::Progress,
+        Egui::Wait => CursorIcon::Wait,
+        Egui::Cell => CursorIcon::Cell,
+        Egui::Crosshair => CursorIcon::Crosshair,
+        Egui::Text => CursorIcon::Text,
+        Egui::VerticalText => CursorIcon::VerticalText,
+        Egui::Alias => CursorIcon::Alias,
+        Egui::Copy => CursorIcon::Copy,
+        Egui::Move => CursorIcon::Move,
+        Egui::NoDrop => CursorIcon::NoDrop,
+        Egui::NotAllowed => CursorIcon::NotAllowed,
+        Egui::Grab => CursorIcon::Grab,
+        Egui::Grabbing => CursorIcon::Grabbing,
+        Egui::AllScroll => CursorIcon::AllScroll,
+        Egui::ResizeHorizontal => CursorIcon::EwResize,
+        Egui::ResizeNeSw => CursorIcon::NeswResize,
+        Egui::ResizeNwSe => CursorIcon::NwseResize,
+        Egui::ResizeVertical => CursorIcon::NsResize,
+        Egui::ResizeEast => CursorIcon::EResize,
+        Egui::ResizeSouthEast => CursorIcon::SeResize,
+        Egui::ResizeSouth => CursorIcon::SResize,
+        Egui::ResizeSouthWest => CursorIcon::SwResize,
+        Egui::ResizeWest => CursorIcon::WResize,
+        Egui::ResizeNorthWest => CursorIcon::NwResize,
+        Egui::ResizeNorth => CursorIcon::NResize,
+        Egui::ResizeNorthEast => CursorIcon::NeResize,
+        Egui::ResizeColumn => CursorIcon::ColResize,
+        Egui::ResizeRow => CursorIcon::RowResize,
+        Egui::ZoomIn => CursorIcon::ZoomIn,
+        Egui::ZoomOut => CursorIcon::ZoomOut,
+        _ => CursorIcon::Default,
+    })
+}
@@ -0,0 +1,106 @@
+//! The layers panel: every shape, frontmost first, to hide or show, drag
+//! into another place in the stacking order, or delete one at a time.
+
+use eframe::egui;
+use fireshot_core::shapes::{EffectKind, Shape};
+use fireshot_core::tr;
+
+use crate::arrange::Arrangement;
+
+/// Tallest the list grows before it scrolls, in points.
+const LAYERS_MAX_HEIGHT: f32 = 360.0;
+/// Characters of a text shape shown as its name.
+const TEXT_NAME_LEN: usize = 24;
+
+/// What was done in the panel this frame.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LayerAction {
+    /// Picks the shape for the arrange tool; `add` keeps what was picked.
+    Pick { index: usize, add: bool },
+    ToggleHidden(usize),
+    Delete(usize),
+    /// Takes shape `from` out and puts it back in at `to`.
+    Move { from: usize, to: usize },
+}
+
+/// The panel's rows inside a header that collapses them.
+pub(crate) fn show(ui: &mut egui::Ui, shapes: &[Shape], arrange: &Arrangement) -> Option<LayerAction> {
+    let mut action = None;
+    egui::CollapsingHeader::new(tr!("layers-title", count = shapes.len()))
+        .id_source("layers")
+        .default_open(false)
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(LAYERS_MAX_HEIGHT)
+                .show(ui, |ui| {
+                    for index in (0..shapes.len()).rev() {
+                        if let Some(row) = show_row(ui, index, &shapes[index], arrange) {
+                            action = Some(row);
+                        }
+                    }
+                });
+        });
+    action
+}
+
+fn show_row(ui: &mut egui::Ui, index: usize, shape: &Shape, arrange: &Arrangement) -> Option<LayerAction> {
+    let mut action = None;
+    let row = ui.horizontal(|ui| {
+        let mut shown = !arrange.is_hidden(index);
+        if ui
+            .checkbox(&mut shown, "")
+            .on_hover_text(tr!("layers-visible"))
+            .changed()
+        {
+            action = Some(LayerAction::ToggleHidden(index));
+        }
+        ui.dnd_drag_source(egui::Id::new(("layer", index)), index, |ui| {
+            ui.label("☰");
+        })
+        .response
+        .on_hover_text(tr!("layers-reorder"));
+        let (swatch, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+        if let Some(color) = shape.color() {
+            ui.painter().rect_filled(swatch, 2.0, color);
+        }
+        let name = ui.selectable_label(arrange.is_picked(index), layer_name(shape));
+        if name.clicked() {
+            let add = ui.input(|i| i.modifiers.shift);
+            action = Some(LayerAction::Pick { index, add });
+        }
+        if ui.small_button("×").on_hover_text(tr!("layers-delete")).clicked() {
+            action = Some(LayerAction::Delete(index));
+        }
+    });
+    let response = row.response;
+    if response.dnd_hover_payload::<usize>().is_some() {
+        let line = egui::Stroke::new(2.0, ui.visuals().selection.stroke.color);
+        ui.painter().hline(response.rect.x_range(), response.rect.top(), line);
+    }
+    if let Some(from) = response.dnd_release_payload::<usize>() {
+        action = Some(LayerAction::Move { from: *from, to: index });
+    }
+    action
+}
+
+fn layer_name(shape: &Shape) -> String {
+    match shape {
+        Shape::Stroke(_) => tr!("layer-stroke").to_string(),
+        Shape::Line(_) => tr!("layer-line").to_string(),
+        Shape::Arrow(_) => tr!("layer-arrow").to_string(),
+        Shape::Rect(_) => tr!("layer-rect").to_string(),
+        Shape::Circle(_) => tr!("layer-circle").to_string(),
+        Shape::CircleCount(counter) => tr!("layer-counter", count = counter.count),
+        Shape::Text(text) => {
+            let mut name: String = text.text.chars().take(TEXT_NAME_LEN).collect();
+            if text.text.chars().count() > TEXT_NAME_LEN {
+                name.push('…');
+            }
+            name
+        }
+        Shape::Effect(effect) => match effect.kind {
+            EffectKind::Pixelate => tr!("layer-pixelate").to_string(),
+            EffectKind::Blur => tr!("layer-blur").to_string(),
+        },
+    }
+}
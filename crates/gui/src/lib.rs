@@ -1,10 +1,49 @@
+mod adjust;
 mod app;
+mod arrange;
+mod autocrop;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 mod clipboard;
+mod color;
+mod compare;
+mod contact_sheet;
+mod control;
 mod draw;
 mod effects;
+mod export;
 mod geometry;
+#[cfg(feature = "gpu-effects")]
+mod gpu;
 mod image_ops;
+mod launcher;
+mod layers;
+mod layer_shell;
+mod parallel;
+mod recovery;
+mod redact;
 mod shapes;
+mod share;
+mod snap;
+mod stats;
+mod status;
+mod tabs;
 mod text;
+mod theme;
 
 pub use app::run_viewer;
+pub use control::{
+    Accept, EditorControl, PostRender, RecaptureRequest, RecordRequest, SaveDialog, ToolDefaults,
+    UndoLimit, ViewerOptions,
+};
+pub use clipboard::{
+    copy_image as copy_image_to_clipboard, copy_text as copy_text_to_clipboard,
+    paste_image as paste_image_from_clipboard,
+};
+pub use color::save_image;
+pub use compare::{diff_heatmap, run_compare, side_by_side, CompareExport, CompareMode, DiffStats};
+pub use contact_sheet::contact_sheet;
+pub use export::render_annotations;
+pub use launcher::{run_launcher, LauncherScreen};
+pub use theme::Theme;
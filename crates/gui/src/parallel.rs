@@ -0,0 +1,41 @@
+use std::sync::OnceLock;
+
+/// Below this many pixels a pass runs on the calling thread; spawning costs
+/// more than it saves.
+const MIN_PARALLEL_PIXELS: usize = 1 << 16;
+
+fn thread_count() -> usize {
+    static COUNT: OnceLock<usize> = OnceLock::new();
+    *COUNT.get_or_init(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    })
+}
+
+/// Splits `rows` (whole RGBA rows of `row_bytes` each) into bands and runs
+/// `f(first_row, band)` on every band from its own thread. Band heights are
+/// multiples of `align`, so passes that work in fixed-height blocks never
+/// see a block cut in two.
+pub(crate) fn for_each_row_band<F>(rows: &mut [u8], row_bytes: usize, align: usize, f: F)
+where
+    F: Fn(usize, &mut [u8]) + Sync,
+{
+    if row_bytes == 0 || rows.is_empty() {
+        return;
+    }
+    let row_count = rows.len() / row_bytes;
+    let threads = thread_count().min(row_count);
+    if threads <= 1 || rows.len() / 4 < MIN_PARALLEL_PIXELS {
+        f(0, rows);
+        return;
+    }
+    let align = align.max(1);
+    let band = row_count.div_ceil(threads).div_ceil(align) * align;
+    std::thread::scope(|scope| {
+        for (i, chunk) in rows.chunks_mut(band * row_bytes).enumerate() {
+            let f = &f;
+            scope.spawn(move || f(i * band, chunk));
+        }
+    });
+}
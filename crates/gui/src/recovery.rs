@@ -0,0 +1,198 @@
+//! Crash recovery. While an editor is open, its capture and shapes are kept
+//! in a directory of their own under `$XDG_STATE_HOME/fireshot/recovery`.
+//! Closing the editor removes that directory, so any directory whose process
+//! is gone belongs to a session that died and can be restored.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eframe::egui;
+use fireshot_core::paths;
+use fireshot_core::shapes::Shape;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::arrange::Arrangement;
+use crate::clipboard::encode_png;
+
+/// Minimum time between two writes of the shapes.
+pub(crate) const RECOVERY_SAVE_SECS: f64 = 2.0;
+
+const BASE_FILE: &str = "base.png";
+const SHAPES_FILE: &str = "shapes.toml";
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    shapes: Vec<Shape>,
+    selection: Option<egui::Rect>,
+    /// Indices of shapes grouped together.
+    #[serde(default)]
+    groups: Vec<Vec<usize>>,
+    /// Indices of shapes hidden from the layers panel.
+    #[serde(default)]
+    hidden: Vec<usize>,
+}
+
+/// The running session's recovery state.
+pub(crate) struct Recovery {
+    dir: PathBuf,
+    saved: Option<(u64, Option<egui::Rect>)>,
+    saved_at: f64,
+}
+
+impl Recovery {
+    /// Starts keeping state for a session on `base`. The capture is encoded
+    /// and written on a background thread. Incognito sessions keep none.
+    pub(crate) fn start(base: Arc<RgbaImage>) -> Option<Self> {
+        if fireshot_core::history::is_incognito() {
+            return None;
+        }
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let dir = paths::recovery_dir().join(format!("{}-{}", std::process::id(), millis));
+        let base_dir = dir.clone();
+        std::thread::spawn(move || {
+            if std::fs::create_dir_all(&base_dir).is_err() {
+                return;
+            }
+            if let Ok(png) = encode_png(&base, None) {
+                write_atomic(&base_dir.join(BASE_FILE), &png);
+            }
+        });
+        Some(Self {
+            dir,
+            saved: None,
+            saved_at: f64::NEG_INFINITY,
+        })
+    }
+
+    /// Writes the shapes and selection if they changed since the last write.
+    /// Returns the seconds until a pending change may be written, when the
+    /// last write was too recent.
+    pub(crate) fn save(
+        &mut self,
+        version: u64,
+        shapes: &[Shape],
+        arrange: &Arrangement,
+        selection: Option<egui::Rect>,
+        now: f64,
+    ) -> Option<f64> {
+        if self.saved == Some((version, selection)) {
+            return None;
+        }
+        let wait = RECOVERY_SAVE_SECS - (now - self.saved_at);
+        if wait > 0.0 {
+            return Some(wait);
+        }
+        let snapshot = Snapshot {
+            shapes: shapes.to_vec(),
+            selection,
+            groups: arrange.groups.clone(),
+            hidden: arrange.hidden.clone(),
+        };
+        if let Ok(text) = toml::to_string(&snapshot) {
+            if std::fs::create_dir_all(&self.dir).is_ok() {
+                write_atomic(&self.dir.join(SHAPES_FILE), text.as_bytes());
+            }
+        }
+        self.saved = Some((version, selection));
+        self.saved_at = now;
+        None
+    }
+}
+
+impl Drop for Recovery {
+    /// A normal close discards the state; a panic leaves it for the next
+    /// launch.
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// State left behind by a session that didn't close.
+pub(crate) struct Orphan {
+    dir: PathBuf,
+}
+
+/// What an orphaned session had open.
+pub(crate) struct RestoredSession {
+    pub(crate) base: RgbaImage,
+    pub(crate) shapes: Vec<Shape>,
+    pub(crate) selection: Option<egui::Rect>,
+    pub(crate) groups: Vec<Vec<usize>>,
+    pub(crate) hidden: Vec<usize>,
+}
+
+impl Orphan {
+    /// The newest session whose process is no longer running.
+    pub(crate) fn find() -> Option<Self> {
+        let read_dir = std::fs::read_dir(paths::recovery_dir()).ok()?;
+        let mut newest: Option<PathBuf> = None;
+        for dir in read_dir.flatten().map(|entry| entry.path()) {
+            if owner_running(&dir) {
+                continue;
+            }
+            // Died before the capture was written; nothing to restore.
+            if !dir.join(BASE_FILE).is_file() {
+                let _ = std::fs::remove_dir_all(&dir);
+                continue;
+            }
+            if newest.as_ref().is_none_or(|n| session_started(&dir) > session_started(n)) {
+                newest = Some(dir);
+            }
+        }
+        newest.map(|dir| Self { dir })
+    }
+
+    /// Loads the session and removes it from disk; `None` when its files
+    /// can't be read.
+    pub(crate) fn restore(self) -> Option<RestoredSession> {
+        let base = image::open(self.dir.join(BASE_FILE)).ok()?.to_rgba8();
+        let snapshot = std::fs::read_to_string(self.dir.join(SHAPES_FILE))
+            .ok()
+            .and_then(|text| toml::from_str::<Snapshot>(&text).ok());
+        let (shapes, selection, groups, hidden) = snapshot
+            .map(|snapshot| (snapshot.shapes, snapshot.selection, snapshot.groups, snapshot.hidden))
+            .unwrap_or_default();
+        self.discard();
+        Some(RestoredSession {
+            base,
+            shapes,
+            selection,
+            groups,
+            hidden,
+        })
+    }
+
+    pub(crate) fn discard(self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Session directories are named `<pid>-<start millis>`.
+fn session_name(dir: &Path) -> Option<(u32, u128)> {
+    let (pid, millis) = dir.file_name()?.to_str()?.split_once('-')?;
+    Some((pid.parse().ok()?, millis.parse().ok()?))
+}
+
+fn session_started(dir: &Path) -> u128 {
+    session_name(dir).map_or(0, |(_, millis)| millis)
+}
+
+fn owner_running(dir: &Path) -> bool {
+    session_name(dir).is_some_and(|(pid, _)| Path::new("/proc").join(pid.to_string()).exists())
+}
+
+/// Writes through a temporary file so a crash mid-write can't leave a
+/// truncated file behind.
+fn write_atomic(path: &Path, bytes: &[u8]) {
+    let tmp = path.with_extension("tmp");
+    if std::fs::write(&tmp, bytes).is_ok() {
+        let _ = std::fs::rename(&tmp, path);
+    }
+}
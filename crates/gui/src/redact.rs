@@ -0,0 +1,317 @@
+//! Auto-redact: finds text lines and faces in the capture so they can be
+//! blurred with a click each. Both detectors are heuristics tuned for
+//! screenshots rather than trained models, so their results are proposals for
+//! the user to accept or dismiss.
+
+use eframe::egui;
+use image::RgbaImage;
+
+/// Brightness step between neighbouring pixels that counts as a glyph edge.
+const TEXT_EDGE_CONTRAST: u8 = 48;
+
+/// Edge pixels this close on a row are joined, merging glyphs into words and
+/// words into lines.
+const TEXT_JOIN_GAP: usize = 8;
+
+/// Text line heights, in capture pixels, that are looked for.
+const TEXT_MIN_HEIGHT: u32 = 6;
+const TEXT_MAX_HEIGHT: u32 = 96;
+
+/// Share of a line's box taken by edge pixels; flat boxes and photos fall
+/// outside it.
+const TEXT_MIN_DENSITY: f32 = 0.08;
+const TEXT_MAX_DENSITY: f32 = 0.65;
+
+/// Words closer than this fraction of their height are merged into one line,
+/// and lines closer than `TEXT_LINE_SPACING` of it into one paragraph.
+const TEXT_WORD_SPACING: f32 = 1.0;
+const TEXT_LINE_SPACING: f32 = 0.6;
+
+/// Smallest face side, in capture pixels.
+const FACE_MIN_SIDE: u32 = 24;
+
+/// Width over height of a face's skin area.
+const FACE_MIN_ASPECT: f32 = 0.5;
+const FACE_MAX_ASPECT: f32 = 1.25;
+
+/// Share of a face's box that is skin. Eyes, brows and mouth keep it below
+/// the top; a flat skin-coloured panel would fill it.
+const FACE_MIN_FILL: f32 = 0.4;
+const FACE_MAX_FILL: f32 = 0.9;
+
+/// Margin added round a proposal, in capture pixels.
+const PADDING: f32 = 3.0;
+
+/// An accepted proposal is blurred with a radius of its shorter side over
+/// this, which leaves no letter of a line that tall readable.
+pub(crate) const REDACT_BLUR_DIVISOR: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RedactKind {
+    Text,
+    Face,
+}
+
+/// An area auto-redact suggests blurring, in capture pixels.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RedactProposal {
+    pub(crate) rect: egui::Rect,
+    pub(crate) kind: RedactKind,
+}
+
+/// Text lines and faces within `area` of `image`.
+pub(crate) fn detect(image: &RgbaImage, area: egui::Rect) -> Vec<RedactProposal> {
+    let bounds = egui::Rect::from_min_size(
+        egui::Pos2::ZERO,
+        egui::vec2(image.width() as f32, image.height() as f32),
+    );
+    let area = area.intersect(bounds);
+    let (x0, y0) = (area.min.x.floor().max(0.0) as u32, area.min.y.floor().max(0.0) as u32);
+    let (x1, y1) = (area.max.x.ceil() as u32, area.max.y.ceil() as u32);
+    if x1 <= x0 || y1 <= y0 {
+        return Vec::new();
+    }
+    let sub = image::imageops::crop_imm(image, x0, y0, x1 - x0, y1 - y0).to_image();
+    let origin = egui::vec2(x0 as f32, y0 as f32);
+    let text = detect_text(&sub).into_iter().map(|rect| (rect, RedactKind::Text));
+    let faces = detect_faces(&sub).into_iter().map(|rect| (rect, RedactKind::Face));
+    let proposals: Vec<RedactProposal> = text
+        .chain(faces)
+        .map(|(rect, kind)| RedactProposal {
+            rect: rect.expand(PADDING).translate(origin).intersect(area),
+            kind,
+        })
+        .collect();
+    // Eyes and mouths pass for short words; the face covers them already.
+    proposals
+        .iter()
+        .enumerate()
+        .filter(|(i, proposal)| {
+            !proposals.iter().enumerate().any(|(j, other)| {
+                *i != j && other.rect != proposal.rect && other.rect.contains_rect(proposal.rect)
+            })
+        })
+        .map(|(_, proposal)| *proposal)
+        .collect()
+}
+
+fn luma(px: &image::Rgba<u8>) -> u8 {
+    ((u16::from(px[0]) * 3 + u16::from(px[1]) * 6 + u16::from(px[2])) / 10) as u8
+}
+
+/// Lines of text: strong brightness edges joined along rows into boxes that
+/// are wider than tall and neither empty nor solid.
+fn detect_text(image: &RgbaImage) -> Vec<egui::Rect> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let grey: Vec<u8> = image.pixels().map(luma).collect();
+    let mut edges = vec![false; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let here = grey[y * width + x];
+            let right = (x + 1 < width).then(|| grey[y * width + x + 1]);
+            let below = (y + 1 < height).then(|| grey[(y + 1) * width + x]);
+            edges[y * width + x] = [right, below]
+                .into_iter()
+                .flatten()
+                .any(|next| here.abs_diff(next) >= TEXT_EDGE_CONTRAST);
+        }
+    }
+
+    drop_rules(&mut edges, width, height);
+
+    let mut joined = edges.clone();
+    for row in joined.chunks_exact_mut(width) {
+        let mut last = None;
+        for x in 0..width {
+            if !row[x] {
+                continue;
+            }
+            if let Some(last) = last.filter(|last| x - last <= TEXT_JOIN_GAP) {
+                row[last..x].fill(true);
+            }
+            last = Some(x);
+        }
+    }
+
+    let lines: Vec<egui::Rect> = components(&joined, width, height)
+        .into_iter()
+        .filter(|c| {
+            let (w, h) = (c.width(), c.height());
+            let edge_count = (c.y0..c.y1)
+                .map(|y| edges[y as usize * width + c.x0 as usize..y as usize * width + c.x1 as usize]
+                    .iter()
+                    .filter(|edge| **edge)
+                    .count())
+                .sum::<usize>();
+            let density = edge_count as f32 / (w * h) as f32;
+            (TEXT_MIN_HEIGHT..=TEXT_MAX_HEIGHT).contains(&h)
+                && w >= h
+                && (TEXT_MIN_DENSITY..=TEXT_MAX_DENSITY).contains(&density)
+        })
+        .map(|c| c.rect())
+        .collect();
+    merge_lines(lines)
+}
+
+/// Clears straight edge runs longer than any glyph stroke, such as window
+/// borders and separators, which would otherwise tie the text they frame into
+/// one box.
+fn drop_rules(edges: &mut [bool], width: usize, height: usize) {
+    let max = TEXT_MAX_HEIGHT as usize;
+    let mut rules = Vec::new();
+    for y in 0..height {
+        let mut run = 0;
+        for x in 0..=width {
+            if x < width && edges[y * width + x] {
+                run += 1;
+                continue;
+            }
+            if run > max {
+                rules.extend((x - run..x).map(|x| y * width + x));
+            }
+            run = 0;
+        }
+    }
+    for x in 0..width {
+        let mut run = 0;
+        for y in 0..=height {
+            if y < height && edges[y * width + x] {
+                run += 1;
+                continue;
+            }
+            if run > max {
+                rules.extend((y - run..y).map(|y| y * width + x));
+            }
+            run = 0;
+        }
+    }
+    for i in rules {
+        edges[i] = false;
+    }
+}
+
+/// Joins words on a line into the line and lines stacked closely over each
+/// other into paragraphs.
+fn merge_lines(mut boxes: Vec<egui::Rect>) -> Vec<egui::Rect> {
+    let reach = |rect: &egui::Rect| {
+        rect.expand2(egui::vec2(
+            rect.height() * TEXT_WORD_SPACING,
+            rect.height() * TEXT_LINE_SPACING,
+        ))
+    };
+    loop {
+        let mut merged: Vec<egui::Rect> = Vec::new();
+        for rect in &boxes {
+            let near = merged
+                .iter_mut()
+                .find(|other| reach(other).intersects(*rect) || reach(rect).intersects(**other));
+            match near {
+                Some(other) => *other = other.union(*rect),
+                None => merged.push(*rect),
+            }
+        }
+        if merged.len() == boxes.len() {
+            return merged;
+        }
+        boxes = merged;
+    }
+}
+
+/// Faces: blobs of skin tone about as wide as tall, with darker features
+/// inside. Skin is told apart by its chroma (YCbCr), which holds across most
+/// skin tones and lighting.
+fn detect_faces(image: &RgbaImage) -> Vec<egui::Rect> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let skin: Vec<bool> = image
+        .pixels()
+        .map(|px| {
+            let [r, g, b] = [px[0], px[1], px[2]].map(f32::from);
+            let y = 0.299 * r + 0.587 * g + 0.114 * b;
+            let cb = 128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b;
+            let cr = 128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+            y > 40.0 && (77.0..=127.0).contains(&cb) && (133.0..=173.0).contains(&cr)
+        })
+        .collect();
+    components(&skin, width, height)
+        .into_iter()
+        .filter(|c| {
+            let (w, h) = (c.width(), c.height());
+            let aspect = w as f32 / h as f32;
+            let fill = c.count as f32 / (w * h) as f32;
+            w.min(h) >= FACE_MIN_SIDE
+                && (FACE_MIN_ASPECT..=FACE_MAX_ASPECT).contains(&aspect)
+                && (FACE_MIN_FILL..=FACE_MAX_FILL).contains(&fill)
+        })
+        .map(|c| c.rect())
+        .collect()
+}
+
+/// A 4-connected group of set pixels: its bounding box (max exclusive) and
+/// how many pixels it has.
+struct Component {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+    count: usize,
+}
+
+impl Component {
+    fn width(&self) -> u32 {
+        self.x1 - self.x0
+    }
+
+    fn height(&self) -> u32 {
+        self.y1 - self.y0
+    }
+
+    fn rect(&self) -> egui::Rect {
+        egui::Rect::from_min_max(
+            egui::pos2(self.x0 as f32, self.y0 as f32),
+            egui::pos2(self.x1 as f32, self.y1 as f32),
+        )
+    }
+}
+
+fn components(mask: &[bool], width: usize, height: usize) -> Vec<Component> {
+    let mut seen = vec![false; mask.len()];
+    let mut stack = Vec::new();
+    let mut found = Vec::new();
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        stack.push(start);
+        let (sx, sy) = ((start % width) as u32, (start / width) as u32);
+        let mut component = Component {
+            x0: sx,
+            y0: sy,
+            x1: sx + 1,
+            y1: sy + 1,
+            count: 0,
+        };
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            component.count += 1;
+            component.x0 = component.x0.min(x as u32);
+            component.y0 = component.y0.min(y as u32);
+            component.x1 = component.x1.max(x as u32 + 1);
+            component.y1 = component.y1.max(y as u32 + 1);
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then(|| i + 1),
+                (y > 0).then(|| i - width),
+                (y + 1 < height).then(|| i + width),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if mask[next] && !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        found.push(component);
+    }
+    found
+}
@@ -1,4 +1,14 @@
+use std::rc::Rc;
+
 use eframe::egui;
+use fireshot_core::config::ToolbarButton;
+use fireshot_core::shapes::{
+    segment_distance, CircleShape, EffectKind, EffectOutline, RectShape, Shape, TextShape,
+};
+use image::RgbaImage;
+
+use crate::draw::{circlecount_bubble_size, CIRCLECOUNT_PADDING};
+use crate::text::text_bitmap_size;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Tool {
@@ -14,6 +24,41 @@ pub(crate) enum Tool {
     Text,
     Pixelate,
     Blur,
+    /// Picks shapes already drawn to move, recolour, delete or group them.
+    Arrange,
+}
+
+impl Tool {
+    /// The name the tool's colour and size are remembered under; `None`
+    /// for tools that don't draw.
+    pub(crate) fn style_key(self) -> Option<&'static str> {
+        Some(match self {
+            Tool::Select | Tool::Arrange => return None,
+            Tool::Pencil => "pencil",
+            Tool::Line => "line",
+            Tool::Arrow => "arrow",
+            Tool::Rect => "rect",
+            Tool::Circle => "circle",
+            Tool::Marker => "marker",
+            Tool::MarkerLine => "marker-line",
+            Tool::CircleCount => "circle-count",
+            Tool::Text => "text",
+            Tool::Pixelate => "pixelate",
+            Tool::Blur => "blur",
+        })
+    }
+}
+
+/// What Pixelate and Blur cover as they are dragged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum EffectArea {
+    #[default]
+    Rect,
+    Ellipse,
+    /// The polygon the pointer traces.
+    Lasso,
+    /// The pixels the pointer paints over.
+    Brush,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -23,6 +68,12 @@ pub(crate) enum ToolAction {
     Copy,
     Save,
     Clear,
+    /// Writes the capture time in a corner of the selection.
+    Timestamp,
+    /// Closes the editor and records the selection.
+    Record,
+    /// Dragged to dock the toolbar elsewhere.
+    Grip,
 }
 
 #[derive(Clone, Copy)]
@@ -37,94 +88,16 @@ pub(crate) enum ToolIcon {
     MarkerLine,
     CircleCount,
     Text,
+    Timestamp,
     Pixelate,
     Blur,
+    Arrange,
     Undo,
     Copy,
     Save,
     Clear,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct StrokeShape {
-    pub(crate) points: Vec<egui::Pos2>,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct LineShape {
-    pub(crate) start: egui::Pos2,
-    pub(crate) end: egui::Pos2,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct RectShape {
-    pub(crate) start: egui::Pos2,
-    pub(crate) end: egui::Pos2,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct CircleShape {
-    pub(crate) start: egui::Pos2,
-    pub(crate) end: egui::Pos2,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct ArrowShape {
-    pub(crate) start: egui::Pos2,
-    pub(crate) end: egui::Pos2,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct CircleCountShape {
-    pub(crate) center: egui::Pos2,
-    pub(crate) pointer: egui::Pos2,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-    pub(crate) count: u32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct TextShape {
-    pub(crate) pos: egui::Pos2,
-    pub(crate) text: String,
-    pub(crate) color: egui::Color32,
-    pub(crate) size: f32,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) struct EffectShape {
-    pub(crate) start: egui::Pos2,
-    pub(crate) end: egui::Pos2,
-    pub(crate) size: f32,
-    pub(crate) kind: EffectKind,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum EffectKind {
-    Pixelate,
-    Blur,
-}
-
-#[derive(Debug, Clone)]
-pub(crate) enum Shape {
-    Stroke(StrokeShape),
-    Line(LineShape),
-    Arrow(ArrowShape),
-    Rect(RectShape),
-    Circle(CircleShape),
-    CircleCount(CircleCountShape),
-    Text(TextShape),
-    Effect(EffectShape),
+    Record,
+    Grip,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -135,6 +108,8 @@ pub(crate) struct SelectionRect {
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum SelectionDrag {
     Creating { start: egui::Pos2 },
+    /// Creating another selection while the previous one is kept.
+    Adding { start: egui::Pos2 },
     Moving { offset: egui::Vec2 },
     Resizing { corner: SelectionCorner },
 }
@@ -149,15 +124,209 @@ pub(crate) enum SelectionCorner {
 
 pub(crate) const FILE_DIALOG_SIZE: egui::Vec2 = egui::Vec2 { x: 720.0, y: 480.0 };
 
+/// Effect preview textures are allocated in multiples of this many pixels.
+pub(crate) const PREVIEW_TILE: usize = 128;
+
+/// A dragged effect's preview is regenerated once it has been still this long.
+pub(crate) const EFFECT_PREVIEW_DEBOUNCE_SECS: f64 = 0.05;
+
+/// Width of the effect brush per step of the tool size, in image pixels.
+pub(crate) const EFFECT_BRUSH_PER_SIZE: f32 = 6.0;
+
+/// Furthest a pinch zooms into the capture.
+pub(crate) const MAX_ZOOM: f32 = 8.0;
+
+/// Text size and inset from the corner, in image pixels, of the window
+/// caption `[editor] window_caption` adds and of inserted timestamps.
+pub(crate) const CAPTION_SIZE: f32 = 20.0;
+pub(crate) const CAPTION_MARGIN: f32 = 12.0;
+
+/// How far (in screen points) a line, outline or effect has to be dragged
+/// to be kept; shorter drags are stray clicks.
+pub(crate) const MIN_DRAG_DISTANCE: f32 = 4.0;
+
+/// How close (in screen points) the eraser has to come to a shape.
+pub(crate) const ERASER_REACH: f32 = 4.0;
+
+/// Dropping the toolbar grip this close (in screen points) to a screen edge
+/// docks the toolbar to that edge rather than to the selection.
+pub(crate) const SCREEN_DOCK_MARGIN: f32 = 48.0;
+
+/// Capture pixels an arrow key moves or resizes the selection by; one with
+/// Shift held.
+pub(crate) const KEYBOARD_STEP: f32 = 10.0;
+
+/// What a configured toolbar button does and how it is drawn.
+pub(crate) fn button_action(button: ToolbarButton) -> (ToolAction, ToolIcon) {
+    match button {
+        ToolbarButton::Select => (ToolAction::Tool(Tool::Select), ToolIcon::Select),
+        ToolbarButton::Pencil => (ToolAction::Tool(Tool::Pencil), ToolIcon::Pencil),
+        ToolbarButton::Line => (ToolAction::Tool(Tool::Line), ToolIcon::Line),
+        ToolbarButton::Arrow => (ToolAction::Tool(Tool::Arrow), ToolIcon::Arrow),
+        ToolbarButton::Rect => (ToolAction::Tool(Tool::Rect), ToolIcon::Rect),
+        ToolbarButton::Circle => (ToolAction::Tool(Tool::Circle), ToolIcon::Circle),
+        ToolbarButton::Marker => (ToolAction::Tool(Tool::Marker), ToolIcon::Marker),
+        ToolbarButton::MarkerLine => (ToolAction::Tool(Tool::MarkerLine), ToolIcon::MarkerLine),
+        ToolbarButton::CircleCount => (ToolAction::Tool(Tool::CircleCount), ToolIcon::CircleCount),
+        ToolbarButton::Text => (ToolAction::Tool(Tool::Text), ToolIcon::Text),
+        ToolbarButton::Timestamp => (ToolAction::Timestamp, ToolIcon::Timestamp),
+        ToolbarButton::Pixelate => (ToolAction::Tool(Tool::Pixelate), ToolIcon::Pixelate),
+        ToolbarButton::Blur => (ToolAction::Tool(Tool::Blur), ToolIcon::Blur),
+        ToolbarButton::Arrange => (ToolAction::Tool(Tool::Arrange), ToolIcon::Arrange),
+        ToolbarButton::Record => (ToolAction::Record, ToolIcon::Record),
+        ToolbarButton::Undo => (ToolAction::Undo, ToolIcon::Undo),
+        ToolbarButton::Copy => (ToolAction::Copy, ToolIcon::Copy),
+        ToolbarButton::Save => (ToolAction::Save, ToolIcon::Save),
+        ToolbarButton::Clear => (ToolAction::Clear, ToolIcon::Clear),
+    }
+}
+
+/// Stylus state from the tablet protocol, for the frame being drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct PenState {
+    /// Tip pressure from 0 to 1; `None` for a mouse.
+    pub(crate) pressure: Option<f32>,
+    /// The stylus is used with its eraser end.
+    pub(crate) eraser: bool,
+}
+
 pub(crate) struct TextInput {
     pub(crate) pos: egui::Pos2,
     pub(crate) text: String,
 }
 
+/// The capture with every non-effect shape drawn on it, reused by effect
+/// previews until the shapes change.
+pub(crate) struct AnnotationCache {
+    pub(crate) shapes_version: u64,
+    pub(crate) image: Rc<RgbaImage>,
+}
+
+/// Pixel rect, kind, strength and brush path length an effect preview was
+/// rendered for.
+pub(crate) type PreviewKey = ([u32; 4], EffectKind, u32, usize);
+
 pub(crate) struct EffectPreview {
     pub(crate) rect: [u32; 4],
     pub(crate) kind: EffectKind,
     pub(crate) size: u32,
+    pub(crate) mask_len: usize,
     pub(crate) shapes_version: u64,
     pub(crate) texture: egui::TextureHandle,
+    /// Allocated texture size; the preview occupies `uv` of it.
+    pub(crate) capacity: [usize; 2],
+    pub(crate) uv: egui::Rect,
+}
+
+/// Image-space area the shape covers, for picking it with a band and
+/// outlining it once picked.
+pub(crate) fn shape_area(shape: &Shape) -> egui::Rect {
+    match shape {
+        Shape::CircleCount(counter) => {
+            let radius = circlecount_bubble_size(counter.size) + CIRCLECOUNT_PADDING;
+            egui::Rect::from_center_size(counter.center, egui::Vec2::splat(radius * 2.0))
+                .union(egui::Rect::from_min_max(counter.pointer, counter.pointer))
+        }
+        Shape::Text(text) => text_area(text),
+        _ => shape.bounds().unwrap_or(egui::Rect::NOTHING),
+    }
+}
+
+/// Whether `pos` (image space) is within `tolerance` of what the shape
+/// paints, for erasing it.
+pub(crate) fn shape_hit(shape: &Shape, pos: egui::Pos2, tolerance: f32) -> bool {
+    match shape {
+        Shape::Stroke(stroke) => {
+            if stroke.points.len() == 1 {
+                return stroke.points[0].distance(pos) <= stroke.size / 2.0 + tolerance;
+            }
+            stroke.segments().any(|(a, b, width)| {
+                segment_distance(pos, a, b) <= width / 2.0 + tolerance
+            })
+        }
+        Shape::Line(line) => {
+            segment_distance(pos, line.start, line.end) <= line.size / 2.0 + tolerance
+        }
+        Shape::Arrow(arrow) => {
+            segment_distance(pos, arrow.start, arrow.end) <= arrow.size * 2.0 + tolerance
+        }
+        Shape::Rect(rect) => {
+            let area = egui::Rect::from_two_pos(rect.start, rect.end);
+            let reach = rect.size / 2.0 + tolerance;
+            area.expand(reach).contains(pos) && !area.shrink(reach).contains(pos)
+        }
+        Shape::Circle(circle) => {
+            let area = egui::Rect::from_two_pos(circle.start, circle.end);
+            let radius = area.size() / 2.0;
+            if radius.x <= 0.0 || radius.y <= 0.0 {
+                return area.expand(tolerance).contains(pos);
+            }
+            // Distance from the outline, approximated along the smaller
+            // radius.
+            let offset = (pos - area.center()) / radius;
+            let off_outline = (offset.length() - 1.0).abs() * radius.min_elem();
+            off_outline <= circle.size / 2.0 + tolerance
+        }
+        Shape::CircleCount(counter) => {
+            let radius = circlecount_bubble_size(counter.size) + CIRCLECOUNT_PADDING;
+            counter.center.distance(pos) <= radius + tolerance
+        }
+        Shape::Text(text) => text_area(text).expand(tolerance).contains(pos),
+        Shape::Effect(effect) if effect.outline != EffectOutline::Rect => {
+            effect.outline_contains(pos)
+        }
+        Shape::Effect(effect) if !effect.mask.is_empty() => {
+            let reach = effect.brush / 2.0 + tolerance;
+            match effect.mask.as_slice() {
+                [point] => point.distance(pos) <= reach,
+                mask => mask
+                    .windows(2)
+                    .any(|pair| segment_distance(pos, pair[0], pair[1]) <= reach),
+            }
+        }
+        Shape::Effect(effect) => egui::Rect::from_two_pos(effect.start, effect.end)
+            .expand(tolerance)
+            .contains(pos),
+    }
+}
+
+/// Whether a just-drawn shape was dragged less than `min_extent` (image
+/// pixels) and would be an invisible sliver. Strokes, brush dabs and
+/// counters are kept, since a click draws a visible dot with them.
+pub(crate) fn shape_is_degenerate(shape: &Shape, min_extent: f32) -> bool {
+    match shape {
+        Shape::Line(line) => line.start.distance(line.end) < min_extent,
+        Shape::Arrow(arrow) => arrow.start.distance(arrow.end) < min_extent,
+        Shape::Rect(RectShape { start, end, .. })
+        | Shape::Circle(CircleShape { start, end, .. }) => {
+            (*end - *start).abs().max_elem() < min_extent
+        }
+        Shape::Effect(effect) if effect.outline == EffectOutline::Rect => {
+            effect.mask.is_empty() && (effect.end - effect.start).abs().min_elem() < min_extent
+        }
+        Shape::Effect(effect) => (effect.end - effect.start).abs().min_elem() < min_extent,
+        Shape::Stroke(_) | Shape::CircleCount(_) | Shape::Text(_) => false,
+    }
+}
+
+/// Roughly the memory `shape` holds, counting its point lists and text.
+pub(crate) fn shape_bytes(shape: &Shape) -> usize {
+    use std::mem::size_of;
+    let heap = match shape {
+        Shape::Stroke(stroke) => {
+            stroke.points.capacity() * size_of::<egui::Pos2>()
+                + stroke.widths.capacity() * size_of::<f32>()
+        }
+        Shape::Effect(effect) => effect.mask.capacity() * size_of::<egui::Pos2>(),
+        Shape::Text(text) => text.text.capacity(),
+        _ => 0,
+    };
+    size_of::<Shape>() + heap
+}
+
+/// Where the exported text lands, in the bitmap font it is rendered with.
+fn text_area(text: &TextShape) -> egui::Rect {
+    let scale = (text.size / 6.0).round().max(1.0) as u32;
+    let (width, height) = text_bitmap_size(&text.text, scale);
+    egui::Rect::from_min_size(text.pos, egui::vec2(width as f32, height as f32))
 }
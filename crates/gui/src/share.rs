@@ -0,0 +1,210 @@
+//! Share on LAN: serves one export over HTTP on the local network for a
+//! phone to fetch by scanning a QR code, until the first download or a
+//! timeout.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use fireshot_core::config::ShareConfig;
+use fireshot_core::CaptureError;
+use log::{debug, warn};
+use qrcode::{Color, QrCode};
+
+/// How often the server checks for a stop while no one connects.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// A client gets this long to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Side of the QR code in the editor, in points; big enough for phones to
+/// read from arm's length.
+pub(crate) const SHARE_QR_SIZE: f32 = 240.0;
+
+/// How a share ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShareEnd {
+    /// The image was fetched from this address.
+    Downloaded(IpAddr),
+    TimedOut,
+    Failed,
+}
+
+/// A running share. Dropping it stops the server.
+pub(crate) struct LanShare {
+    pub(crate) url: String,
+    /// QR code modules, row by row, `true` for dark.
+    qr: Vec<bool>,
+    qr_width: usize,
+    ended: Receiver<ShareEnd>,
+    stop: Arc<AtomicBool>,
+}
+
+impl LanShare {
+    /// Starts listening right away; the PNG sent on the returned channel is
+    /// served once it is ready, so rendering can overlap the phone scanning
+    /// the code.
+    pub(crate) fn start(
+        config: &ShareConfig,
+        ctx: egui::Context,
+    ) -> Result<(Self, Sender<Vec<u8>>), CaptureError> {
+        let ip = local_ip().ok_or_else(|| {
+            CaptureError::Unsupported("no local network address to share on".to_string())
+        })?;
+        let listener = TcpListener::bind(SocketAddr::new(IpAddr::from([0, 0, 0, 0]), config.port))
+            .map_err(|e| CaptureError::Io(format!("share on port {}: {}", config.port, e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| CaptureError::Io(e.to_string()))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| CaptureError::Io(e.to_string()))?
+            .port();
+        let file = format!("{:016x}.png", RandomState::new().build_hasher().finish());
+        let url = format!("http://{}/{}", SocketAddr::new(ip, port), file);
+        let code = QrCode::new(url.as_bytes()).map_err(|e| CaptureError::Io(e.to_string()))?;
+        let qr = code.to_colors().into_iter().map(|color| color == Color::Dark).collect();
+
+        let (png_tx, png_rx) = mpsc::channel();
+        let (ended_tx, ended) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let deadline = Instant::now() + Duration::from_secs(config.timeout_secs);
+        let server_stop = stop.clone();
+        std::thread::Builder::new()
+            .name("fireshot-share".to_string())
+            .spawn(move || {
+                let end = serve(listener, &file, png_rx, deadline, &server_stop);
+                debug!("share ended: {:?}", end);
+                let _ = ended_tx.send(end);
+                ctx.request_repaint();
+            })
+            .map_err(|e| CaptureError::Io(e.to_string()))?;
+        let share = Self {
+            url,
+            qr,
+            qr_width: code.width(),
+            ended,
+            stop,
+        };
+        Ok((share, png_tx))
+    }
+
+    /// How the share ended, once it has.
+    pub(crate) fn poll(&self) -> Option<ShareEnd> {
+        match self.ended.try_recv() {
+            Ok(end) => Some(end),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(ShareEnd::Failed),
+        }
+    }
+
+    /// Paints the QR code into `rect` on a white square with a quiet zone.
+    pub(crate) fn paint_qr(&self, painter: &egui::Painter, rect: egui::Rect) {
+        painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+        // The spec asks for four modules of margin.
+        let modules = self.qr_width as f32 + 8.0;
+        let module = rect.width().min(rect.height()) / modules;
+        let origin = rect.min + egui::vec2(module * 4.0, module * 4.0);
+        for (i, dark) in self.qr.iter().enumerate() {
+            if *dark {
+                let (x, y) = ((i % self.qr_width) as f32, (i / self.qr_width) as f32);
+                let min = origin + egui::vec2(x * module, y * module);
+                painter.rect_filled(
+                    egui::Rect::from_min_size(min, egui::vec2(module, module)),
+                    0.0,
+                    egui::Color32::BLACK,
+                );
+            }
+        }
+    }
+}
+
+impl Drop for LanShare {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The address other devices reach this one on: the one the route to the
+/// internet leaves from. Connecting a UDP socket sends nothing.
+fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+fn serve(
+    listener: TcpListener,
+    file: &str,
+    png_rx: Receiver<Vec<u8>>,
+    deadline: Instant,
+    stop: &AtomicBool,
+) -> ShareEnd {
+    let mut png: Option<Vec<u8>> = None;
+    loop {
+        if stop.load(Ordering::Relaxed) || Instant::now() >= deadline {
+            return ShareEnd::TimedOut;
+        }
+        let (stream, peer) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            Err(err) => {
+                warn!("share: {}", err);
+                return ShareEnd::Failed;
+            }
+        };
+        if png.is_none() {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match png_rx.recv_timeout(wait) {
+                Ok(bytes) => png = Some(bytes),
+                Err(RecvTimeoutError::Timeout) => return ShareEnd::TimedOut,
+                Err(RecvTimeoutError::Disconnected) => return ShareEnd::Failed,
+            }
+        }
+        let bytes = png.as_deref().unwrap_or_default();
+        match respond(stream, file, bytes) {
+            Ok(true) => return ShareEnd::Downloaded(peer.ip()),
+            Ok(false) => {}
+            Err(err) => debug!("share: {}: {}", peer, err),
+        }
+    }
+}
+
+/// Answers one request; `true` when it was for the image and all of it was
+/// sent.
+fn respond(stream: TcpStream, file: &str, png: &[u8]) -> std::io::Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let mut stream = reader.into_inner();
+    let mut parts = request.split_whitespace();
+    let wanted = matches!(
+        (parts.next(), parts.next()),
+        (Some("GET"), Some(path)) if path.trim_start_matches('/') == file
+    );
+    if !wanted {
+        stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        return Ok(false);
+    }
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\n\
+         Content-Disposition: inline; filename=\"screenshot.png\"\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        png.len()
+    )?;
+    stream.write_all(png)?;
+    stream.flush()?;
+    Ok(true)
+}
@@ -0,0 +1,218 @@
+//! Magnetic selection: pulls a dragged selection edge onto a nearby straight
+//! edge in the capture, such as a window border or panel, and finds the
+//! window under a click from those edges.
+
+use eframe::egui;
+use image::{Rgba, RgbaImage};
+
+/// How far (in screen points) a dragged edge reaches for an edge to snap to.
+pub(crate) const SNAP_DISTANCE: f32 = 8.0;
+
+/// Largest per-channel difference across an edge for that pixel pair to count.
+const EDGE_CONTRAST: u8 = 40;
+
+/// Fraction of the selection's side that has to lie along the edge.
+const EDGE_COVERAGE: f32 = 0.6;
+
+/// Shorter selection sides don't snap; too few pixels to tell an edge apart
+/// from texture.
+const MIN_SPAN: f32 = 16.0;
+
+/// Pixel pairs sampled along a side, at most.
+const MAX_SAMPLES: u32 = 256;
+
+/// Shortest straight edge, in capture pixels, taken for a window side.
+const MIN_WINDOW_SIDE: u32 = 48;
+
+/// Breaks this long in a window side are bridged, e.g. where a shadow or a
+/// same-coloured control meets the border.
+const MAX_GAP: u32 = 2;
+
+/// Times a found side is given up for the next one out because it doesn't
+/// run along the rectangle the other sides make.
+const REFINE_PASSES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    /// A vertical edge, found between two columns.
+    X,
+    /// A horizontal edge, found between two rows.
+    Y,
+}
+
+/// Moves `pos` (a coordinate on `axis`, in capture pixels) onto the
+/// strongest edge within `radius`, looking only along `span` on the other
+/// axis. Returns `pos` unchanged when there's no clear edge.
+pub(crate) fn snap_edge(
+    image: &RgbaImage,
+    axis: Axis,
+    pos: f32,
+    span: (f32, f32),
+    radius: f32,
+) -> f32 {
+    let (along, across) = match axis {
+        Axis::X => (image.width(), image.height()),
+        Axis::Y => (image.height(), image.width()),
+    };
+    let start = span.0.min(span.1).max(0.0) as u32;
+    let end = (span.0.max(span.1) as u32).min(across);
+    if along < 2 || (end.saturating_sub(start) as f32) < MIN_SPAN {
+        return pos;
+    }
+
+    let first = (pos - radius).round().max(1.0) as u32;
+    let last = ((pos + radius).round() as u32).min(along - 1);
+    let mut best: Option<(f32, f32)> = None;
+    for edge in first..=last {
+        let strength = edge_strength(image, axis, edge, start, end);
+        if strength < EDGE_COVERAGE {
+            continue;
+        }
+        let edge = edge as f32;
+        let better = best.is_none_or(|(best_edge, best_strength)| {
+            strength > best_strength
+                || (strength == best_strength && (edge - pos).abs() < (best_edge - pos).abs())
+        });
+        if better {
+            best = Some((edge, strength));
+        }
+    }
+    best.map_or(pos, |(edge, _)| edge)
+}
+
+/// Fraction of sampled pixel pairs across the boundary before `edge` that
+/// differ by at least `EDGE_CONTRAST`.
+fn edge_strength(image: &RgbaImage, axis: Axis, edge: u32, start: u32, end: u32) -> f32 {
+    let step = ((end - start) / MAX_SAMPLES).max(1);
+    let mut samples = 0u32;
+    let mut hits = 0u32;
+    for i in (start..end).step_by(step as usize) {
+        let (a, b) = match axis {
+            Axis::X => (image.get_pixel(edge - 1, i), image.get_pixel(edge, i)),
+            Axis::Y => (image.get_pixel(i, edge - 1), image.get_pixel(i, edge)),
+        };
+        if contrast(a, b) >= EDGE_CONTRAST {
+            hits += 1;
+        }
+        samples += 1;
+    }
+    if samples == 0 {
+        0.0
+    } else {
+        hits as f32 / samples as f32
+    }
+}
+
+/// The window-like rectangle around `pos`: the nearest straight edges on
+/// each side of it that run along each other. Sides without such an edge
+/// extend to the capture's border. `None` when the result is too small to
+/// be a window.
+pub(crate) fn detect_window(image: &RgbaImage, pos: egui::Pos2) -> Option<egui::Rect> {
+    let (width, height) = image.dimensions();
+    if width < 2 || height < 2 {
+        return None;
+    }
+    let x = (pos.x.max(0.0) as u32).min(width - 1);
+    let y = (pos.y.max(0.0) as u32).min(height - 1);
+    let mut left = find_side_before(image, Axis::X, x, y);
+    let mut right = find_side_after(image, Axis::X, x, y);
+    let mut top = find_side_before(image, Axis::Y, y, x);
+    let mut bottom = find_side_after(image, Axis::Y, y, x);
+
+    for _ in 0..REFINE_PASSES {
+        let mut settled = true;
+        if left > 0 && edge_strength(image, Axis::X, left, top, bottom) < EDGE_COVERAGE {
+            left = find_side_before(image, Axis::X, left - 1, y);
+            settled = false;
+        }
+        if right < width && edge_strength(image, Axis::X, right, top, bottom) < EDGE_COVERAGE {
+            right = find_side_after(image, Axis::X, right, y);
+            settled = false;
+        }
+        if top > 0 && edge_strength(image, Axis::Y, top, left, right) < EDGE_COVERAGE {
+            top = find_side_before(image, Axis::Y, top - 1, x);
+            settled = false;
+        }
+        if bottom < height && edge_strength(image, Axis::Y, bottom, left, right) < EDGE_COVERAGE {
+            bottom = find_side_after(image, Axis::Y, bottom, x);
+            settled = false;
+        }
+        if settled {
+            break;
+        }
+    }
+
+    if right - left < MIN_WINDOW_SIDE || bottom - top < MIN_WINDOW_SIDE {
+        return None;
+    }
+    Some(egui::Rect::from_min_max(
+        egui::pos2(left as f32, top as f32),
+        egui::pos2(right as f32, bottom as f32),
+    ))
+}
+
+/// The nearest window side at or before `from` on `axis`, crossing `at`;
+/// 0 when there is none.
+fn find_side_before(image: &RgbaImage, axis: Axis, from: u32, at: u32) -> u32 {
+    (1..=from)
+        .rev()
+        .find(|&edge| is_window_side(image, axis, edge, at))
+        .unwrap_or(0)
+}
+
+/// The nearest window side after `from` on `axis`, crossing `at`; the
+/// capture's size on `axis` when there is none.
+fn find_side_after(image: &RgbaImage, axis: Axis, from: u32, at: u32) -> u32 {
+    let end = match axis {
+        Axis::X => image.width(),
+        Axis::Y => image.height(),
+    };
+    (from + 1..end)
+        .find(|&edge| is_window_side(image, axis, edge, at))
+        .unwrap_or(end)
+}
+
+/// Whether the boundary before `edge` is a straight edge at least
+/// `MIN_WINDOW_SIDE` long through `at`.
+fn is_window_side(image: &RgbaImage, axis: Axis, edge: u32, at: u32) -> bool {
+    let across = match axis {
+        Axis::X => image.height(),
+        Axis::Y => image.width(),
+    };
+    let is_edge = |i: u32| {
+        let (a, b) = match axis {
+            Axis::X => (image.get_pixel(edge - 1, i), image.get_pixel(edge, i)),
+            Axis::Y => (image.get_pixel(i, edge - 1), image.get_pixel(i, edge)),
+        };
+        contrast(a, b) >= EDGE_CONTRAST
+    };
+    if !is_edge(at) {
+        return false;
+    }
+    // Walk both ways from `at`, bridging short gaps, until the run is long
+    // enough or ends.
+    let mut run = 1;
+    for forward in [false, true] {
+        let mut i = at;
+        let mut gap = 0;
+        while run < MIN_WINDOW_SIDE && gap <= MAX_GAP {
+            i = match (forward, i) {
+                (false, 0) => break,
+                (false, _) => i - 1,
+                (true, _) if i + 1 >= across => break,
+                (true, _) => i + 1,
+            };
+            if is_edge(i) {
+                run += 1 + gap;
+                gap = 0;
+            } else {
+                gap += 1;
+            }
+        }
+    }
+    run >= MIN_WINDOW_SIDE
+}
+
+fn contrast(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    (0..3).map(|c| a[c].abs_diff(b[c])).max().unwrap_or(0)
+}
@@ -0,0 +1,66 @@
+//! The F12 overlay: how long the editor takes to build frames and render
+//! annotations, how much it draws and how much texture memory it holds, so
+//! a slow editor can be reported with numbers.
+
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use fireshot_core::tr;
+
+#[derive(Default)]
+pub(crate) struct FrameStats {
+    pub(crate) shown: bool,
+    /// Building the last frame, before egui painted it.
+    frame: Duration,
+    /// Recomposing the annotations over the capture, when they last
+    /// changed.
+    render: Option<Duration>,
+    /// From the editor being handed the capture to its first frame.
+    first_frame: Option<Duration>,
+}
+
+impl FrameStats {
+    pub(crate) fn frame_built(&mut self, started: Instant) {
+        self.frame = started.elapsed();
+    }
+
+    pub(crate) fn rendered(&mut self, started: Instant) {
+        self.render = Some(started.elapsed());
+    }
+
+    pub(crate) fn first_frame(&mut self, elapsed: Duration) {
+        self.first_frame = Some(elapsed);
+    }
+
+    /// The numbers in the top-right corner, above everything else.
+    pub(crate) fn show(&self, ctx: &egui::Context, shapes: usize) {
+        let texture_bytes: usize = ctx
+            .tex_manager()
+            .read()
+            .allocated()
+            .map(|(_, meta)| meta.bytes_used())
+            .sum();
+        let lines = [
+            tr!("stats-frame", ms = millis(Some(self.frame))),
+            tr!("stats-render", ms = millis(self.render)),
+            tr!("stats-first-frame", ms = millis(self.first_frame)),
+            tr!("stats-shapes", count = shapes),
+            tr!("stats-textures", mib = format!("{:.1}", texture_bytes as f64 / 1048576.0)),
+        ];
+        egui::Area::new("frame_stats".into())
+            .order(egui::Order::Tooltip)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-6.0, 6.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for line in lines {
+                        ui.monospace(line);
+                    }
+                });
+            });
+    }
+}
+
+fn millis(elapsed: Option<Duration>) -> String {
+    elapsed.map_or_else(|| "–".to_string(), |d| format!("{:.1}", d.as_secs_f64() * 1000.0))
+}
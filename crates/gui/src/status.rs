@@ -0,0 +1,141 @@
+//! The editor's status bar: the last few messages, newest first. The log is
+//! process-wide so an editor reopened by the daemon still shows where the
+//! previous capture went.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use eframe::egui;
+use fireshot_core::{paths, tr};
+use log::warn;
+
+use crate::clipboard;
+
+/// Messages kept; older ones drop off the end.
+const STATUS_LOG_LEN: usize = 8;
+
+static LOG: Mutex<VecDeque<StatusEntry>> = Mutex::new(VecDeque::new());
+
+/// What clicking a message opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StatusLink {
+    File(PathBuf),
+    Url(String),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StatusEntry {
+    pub(crate) text: String,
+    pub(crate) link: Option<StatusLink>,
+}
+
+/// The editor's view of the log.
+#[derive(Debug, Default)]
+pub(crate) struct StatusLog {
+    /// Older messages are listed under the newest one.
+    expanded: bool,
+    /// The link last copied, whose button says so.
+    copied: Option<StatusLink>,
+}
+
+impl StatusLog {
+    pub(crate) fn push(&self, text: impl Into<String>) {
+        self.push_entry(StatusEntry {
+            text: text.into(),
+            link: None,
+        });
+    }
+
+    pub(crate) fn push_link(&self, text: impl Into<String>, link: StatusLink) {
+        self.push_entry(StatusEntry {
+            text: text.into(),
+            link: Some(link),
+        });
+    }
+
+    fn push_entry(&self, entry: StatusEntry) {
+        if let Ok(mut log) = LOG.lock() {
+            log.push_front(entry);
+            log.truncate(STATUS_LOG_LEN);
+        }
+    }
+
+    fn entries(&self) -> Vec<StatusEntry> {
+        LOG.lock().map(|log| log.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// The newest message with a toggle for the rest, or `busy` with a
+    /// spinner while an export runs.
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, busy: Option<&str>) {
+        let entries = self.entries();
+        if busy.is_none() && entries.is_empty() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            if let Some(busy) = busy {
+                ui.spinner();
+                ui.label(busy);
+            } else if let Some(newest) = entries.first() {
+                self.show_entry(ui, newest);
+            }
+            if entries.len() > 1 {
+                let toggle = if self.expanded {
+                    tr!("status-log-hide")
+                } else {
+                    tr!("status-log-show")
+                };
+                if ui.small_button(toggle).clicked() {
+                    self.expanded = !self.expanded;
+                }
+            }
+        });
+        if self.expanded {
+            let older = if busy.is_some() { &entries[..] } else { &entries[1..] };
+            for entry in older {
+                ui.horizontal(|ui| self.show_entry(ui, entry));
+            }
+        }
+    }
+
+    /// A message; one with a link opens it when clicked and has a button
+    /// copying the path or URL.
+    fn show_entry(&mut self, ui: &mut egui::Ui, entry: &StatusEntry) {
+        let Some(link) = &entry.link else {
+            ui.label(&entry.text);
+            return;
+        };
+        let (hint, copy) = match link {
+            StatusLink::File(_) => (tr!("status-open-file"), tr!("status-copy-path")),
+            StatusLink::Url(_) => (tr!("status-open-url"), tr!("status-copy-url")),
+        };
+        if ui.link(&entry.text).on_hover_text(hint).clicked() {
+            // xdg-open takes URLs as well as paths.
+            let target = PathBuf::from(link.text());
+            if let Err(err) = paths::open_with_default_app(&target) {
+                warn!("open {}: {}", target.display(), err);
+            }
+        }
+        let copy = if self.copied.as_ref() == Some(link) {
+            tr!("status-copied-link")
+        } else {
+            copy
+        };
+        if ui.small_button(copy).clicked() {
+            match clipboard::copy_text(&link.text()) {
+                Ok(()) => self.copied = Some(link.clone()),
+                Err(err) => warn!("copy {}: {}", link.text(), err),
+            }
+        }
+    }
+}
+
+impl StatusLink {
+    /// The path or URL as text.
+    pub(crate) fn text(&self) -> String {
+        match self {
+            Self::File(path) => path.display().to_string(),
+            Self::Url(url) => url.clone(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Several captures in one editor. Only the shown tab's capture and shapes
+//! live in the editor's fields; switching parks them in its `Tab` and
+//! brings the other tab's back, so tool settings are shared while each
+//! capture keeps its own shapes, undo history and crash recovery.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use eframe::egui;
+use fireshot_core::shapes::Shape;
+use fireshot_core::window::WindowInfo;
+use image::RgbaImage;
+
+use crate::adjust::Adjustments;
+use crate::arrange::Arrangement;
+use crate::recovery::Recovery;
+
+/// Everything kept per capture.
+pub(crate) struct TabState {
+    pub(crate) base: Arc<RgbaImage>,
+    pub(crate) shapes: Vec<Shape>,
+    pub(crate) redo_stack: Vec<Shape>,
+    pub(crate) undo_floor: usize,
+    pub(crate) selection: Option<egui::Rect>,
+    pub(crate) extra_selections: Vec<egui::Rect>,
+    pub(crate) arrange: Arrangement,
+    pub(crate) icc_profile: Option<Arc<[u8]>>,
+    pub(crate) from_screen: bool,
+    pub(crate) window: Option<WindowInfo>,
+    pub(crate) captured_at: SystemTime,
+    pub(crate) adjustments: Adjustments,
+    pub(crate) recovery: Option<Recovery>,
+}
+
+pub(crate) struct Tab {
+    pub(crate) title: String,
+    /// `None` for the shown tab, whose state is the editor's.
+    pub(crate) state: Option<TabState>,
+}
+
+impl Tab {
+    pub(crate) fn shown(title: String) -> Self {
+        Self { title, state: None }
+    }
+
+    /// A tab for `base`, not shown yet and with nothing drawn.
+    pub(crate) fn parked(title: String, base: RgbaImage) -> Self {
+        let base = Arc::new(base);
+        let recovery = Recovery::start(base.clone());
+        Self {
+            title,
+            state: Some(TabState {
+                base,
+                shapes: Vec::new(),
+                redo_stack: Vec::new(),
+                undo_floor: 0,
+                selection: None,
+                extra_selections: Vec::new(),
+                arrange: Arrangement::default(),
+                icc_profile: None,
+                from_screen: false,
+                window: None,
+                captured_at: SystemTime::now(),
+                adjustments: Adjustments::default(),
+                recovery,
+            }),
+        }
+    }
+}
@@ -56,7 +56,7 @@ fn draw_char_5x7(
                 for sx in 0..scale {
                     for sy in 0..scale {
                         let px = x + (col as i32 * scale as i32) + sx as i32;
-                        let py = y + (row as i32 * scale as i32) + sy as i32;
+                        let py = y + (row * scale as i32) + sy as i32;
                         if px >= 0
                             && py >= 0
                             && (px as u32) < img.width()
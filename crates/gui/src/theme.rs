@@ -0,0 +1,211 @@
+use eframe::egui;
+use fireshot_core::config::{Backdrop, ThemeConfig, ThemeMode};
+
+/// Colors for the editor chrome (toolbar, controls panel, help overlay and
+/// HUD). Annotation colors are the user's and are not themed.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub dark: bool,
+    pub accent: Option<egui::Color32>,
+    pub high_contrast: bool,
+    pub backdrop: Backdrop,
+    pub reduce_transparency: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            dark: true,
+            accent: None,
+            high_contrast: false,
+            backdrop: Backdrop::default(),
+            reduce_transparency: false,
+        }
+    }
+}
+
+/// Colors painted by hand rather than through egui widgets.
+pub(crate) struct Chrome {
+    pub(crate) panel_fill: egui::Color32,
+    pub(crate) panel_stroke: egui::Color32,
+    pub(crate) title: egui::Color32,
+    pub(crate) text: egui::Color32,
+    pub(crate) hud_fill: egui::Color32,
+    pub(crate) hud_text: egui::Color32,
+    /// Behind the tool buttons and controls panel; transparent for none.
+    pub(crate) backdrop_fill: egui::Color32,
+    /// Paint the blurred capture under `backdrop_fill` and `hud_fill`.
+    pub(crate) frosted: bool,
+    /// Selection outline and handles; white reads on any screenshot.
+    pub(crate) outline: egui::Color32,
+    /// Drawn under the outline and handles so they read on light captures
+    /// too.
+    pub(crate) outline_halo: Option<egui::Color32>,
+    pub(crate) outline_width: f32,
+    pub(crate) handle_radius: f32,
+    /// Over the capture outside the selection, and over all of it before
+    /// there is one.
+    pub(crate) selection_dim: egui::Color32,
+    pub(crate) idle_dim: egui::Color32,
+    pub(crate) button_size: f32,
+}
+
+/// Parses `#rrggbb` (the `#` is optional).
+pub(crate) fn parse_hex_color(text: &str) -> Option<egui::Color32> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(egui::Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+impl Theme {
+    /// Resolves the configured theme; `system_prefers_dark` is the desktop's
+    /// preference when known. An unparsable accent is ignored.
+    pub fn from_config(config: &ThemeConfig, system_prefers_dark: Option<bool>) -> Self {
+        let dark = match config.mode {
+            ThemeMode::System => system_prefers_dark.unwrap_or(true),
+            ThemeMode::Light => false,
+            ThemeMode::Dark => true,
+        };
+        Self {
+            dark,
+            accent: config.accent.as_deref().and_then(parse_hex_color),
+            high_contrast: config.high_contrast,
+            backdrop: config.backdrop,
+            reduce_transparency: config.reduce_transparency,
+        }
+    }
+
+    fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        if let Some(accent) = self.accent {
+            visuals.selection.bg_fill = accent;
+            visuals.selection.stroke.color = accent;
+            visuals.hyperlink_color = accent;
+            visuals.widgets.hovered.bg_stroke.color = accent;
+        }
+        if self.high_contrast {
+            let (fg, bg) = if self.dark {
+                (egui::Color32::WHITE, egui::Color32::BLACK)
+            } else {
+                (egui::Color32::BLACK, egui::Color32::WHITE)
+            };
+            visuals.override_text_color = Some(fg);
+            visuals.panel_fill = bg;
+            visuals.window_fill = bg;
+            visuals.window_stroke = egui::Stroke::new(2.0, fg);
+            visuals.window_shadow = egui::epaint::Shadow::NONE;
+            visuals.popup_shadow = egui::epaint::Shadow::NONE;
+            for widget in [
+                &mut visuals.widgets.inactive,
+                &mut visuals.widgets.hovered,
+                &mut visuals.widgets.active,
+            ] {
+                widget.bg_stroke = egui::Stroke::new(2.0, fg);
+                widget.fg_stroke.color = fg;
+            }
+            visuals.widgets.noninteractive.bg_stroke = egui::Stroke::new(1.5, fg);
+        }
+        visuals
+    }
+
+    /// Installs the theme's visuals unless they are already current.
+    pub(crate) fn apply(&self, ctx: &egui::Context) {
+        let visuals = self.visuals();
+        if ctx.style().visuals != visuals {
+            ctx.set_visuals(visuals);
+        }
+    }
+
+    pub(crate) fn chrome(&self) -> Chrome {
+        let outline = self.accent.unwrap_or(egui::Color32::WHITE);
+        if self.high_contrast {
+            let (fg, bg) = if self.dark {
+                (egui::Color32::WHITE, egui::Color32::BLACK)
+            } else {
+                (egui::Color32::BLACK, egui::Color32::WHITE)
+            };
+            return Chrome {
+                panel_fill: bg,
+                panel_stroke: fg,
+                title: fg,
+                text: fg,
+                hud_fill: bg,
+                hud_text: fg,
+                backdrop_fill: bg,
+                frosted: false,
+                outline,
+                outline_halo: Some(egui::Color32::BLACK),
+                outline_width: 3.0,
+                handle_radius: 6.0,
+                selection_dim: egui::Color32::TRANSPARENT,
+                idle_dim: egui::Color32::TRANSPARENT,
+                button_size: 40.0,
+            };
+        }
+        let dim = |alpha| egui::Color32::from_rgba_premultiplied(0, 0, 0, alpha);
+        let (panel, hud_alpha) = if self.dark {
+            (egui::Color32::from_rgb(12, 12, 12), 190)
+        } else {
+            (egui::Color32::from_rgb(248, 248, 248), 220)
+        };
+        let [r, g, b, _] = panel.to_array();
+        let translucent = |alpha| egui::Color32::from_rgba_unmultiplied(r, g, b, alpha);
+        let backdrop_fill = match (self.backdrop, self.reduce_transparency) {
+            (Backdrop::None, _) => egui::Color32::TRANSPARENT,
+            (_, true) => panel,
+            // Over a blur the panel can be lighter than over the sharp capture.
+            (Backdrop::Frosted, false) => translucent(170),
+            (Backdrop::Dim, false) => translucent(215),
+        };
+        let hud_fill = if self.reduce_transparency {
+            panel
+        } else {
+            translucent(hud_alpha)
+        };
+        let frosted = self.backdrop == Backdrop::Frosted && !self.reduce_transparency;
+        if self.dark {
+            Chrome {
+                panel_fill: panel,
+                panel_stroke: egui::Color32::from_rgba_unmultiplied(255, 255, 255, 30),
+                title: egui::Color32::from_rgb(245, 245, 245),
+                text: egui::Color32::from_rgb(220, 220, 220),
+                hud_fill,
+                hud_text: egui::Color32::WHITE,
+                backdrop_fill,
+                frosted,
+                outline,
+                outline_halo: None,
+                outline_width: 1.5,
+                handle_radius: 4.0,
+                selection_dim: dim(110),
+                idle_dim: dim(70),
+                button_size: 28.0,
+            }
+        } else {
+            Chrome {
+                panel_fill: panel,
+                panel_stroke: egui::Color32::from_rgba_unmultiplied(0, 0, 0, 40),
+                title: egui::Color32::from_rgb(20, 20, 20),
+                text: egui::Color32::from_rgb(60, 60, 60),
+                hud_fill,
+                hud_text: egui::Color32::from_rgb(20, 20, 20),
+                backdrop_fill,
+                frosted,
+                outline,
+                outline_halo: None,
+                outline_width: 1.5,
+                handle_radius: 4.0,
+                selection_dim: dim(110),
+                idle_dim: dim(70),
+                button_size: 28.0,
+            }
+        }
+    }
+}
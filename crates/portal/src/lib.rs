@@ -1,22 +1,87 @@
-use fireshot_core::CaptureError;
-use image::DynamicImage;
+use fireshot_core::{profile, CaptureError};
+use image::{DynamicImage, ImageDecoder, RgbaImage};
+use moxcms::{ColorProfile, Layout, TransformOptions};
 use std::path::PathBuf;
 use ashpd::desktop::file_chooser::{FileFilter, SelectedFiles};
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use ashpd::WindowIdentifier;
+use futures_util::StreamExt;
+
+mod record;
+
+pub use record::{record_screen, RecordedFrame};
+
+/// How long a non-interactive screenshot may take before we give up on the
+/// portal; interactive requests wait on the user and have no limit.
+const PORTAL_TIMEOUT_SECS: u64 = 60;
+
+fn portal_error(err: ashpd::Error) -> CaptureError {
+    use ashpd::desktop::ResponseError;
+    use ashpd::PortalError;
+    match err {
+        ashpd::Error::Response(ResponseError::Cancelled) => CaptureError::PortalCancelled,
+        ashpd::Error::Portal(PortalError::Cancelled(_)) => CaptureError::PortalCancelled,
+        ashpd::Error::Portal(PortalError::NotAllowed(msg)) => CaptureError::PermissionDenied(msg),
+        err => CaptureError::Portal(err.to_string()),
+    }
+}
 
 pub struct CapturedImage {
+    /// The capture, converted to sRGB.
     pub image: DynamicImage,
     pub uri: String,
+    /// The output's ICC profile, when the backend embedded one.
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+/// Decodes a portal capture. Backends on wide-gamut outputs tag the file with
+/// the output's profile; those pixels are converted to sRGB for editing and
+/// the profile is handed back for exports that want to embed it.
+fn decode(bytes: &[u8]) -> Result<(DynamicImage, Option<Vec<u8>>), CaptureError> {
+    let io_error = |e: image::ImageError| CaptureError::Io(e.to_string());
+    let mut decoder = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| CaptureError::Io(e.to_string()))?
+        .into_decoder()
+        .map_err(io_error)?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder).map_err(io_error)?;
+    match icc_profile.as_deref().and_then(|icc| to_srgb(&image, icc)) {
+        Some(converted) => Ok((converted, icc_profile)),
+        None => Ok((image, None)),
+    }
+}
+
+/// `image` converted from `icc` to sRGB; `None` when the profile can't be
+/// parsed or isn't an RGB profile.
+fn to_srgb(image: &DynamicImage, icc: &[u8]) -> Option<DynamicImage> {
+    let source = ColorProfile::new_from_slice(icc).ok()?;
+    let transform = source
+        .create_transform_8bit(
+            Layout::Rgba,
+            &ColorProfile::new_srgb(),
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .ok()?;
+    let rgba = image.to_rgba8();
+    let mut converted = RgbaImage::new(rgba.width(), rgba.height());
+    transform.transform(rgba.as_raw(), &mut converted).ok()?;
+    Some(DynamicImage::ImageRgba8(converted))
 }
 
 pub async fn capture_interactive() -> Result<CapturedImage, CaptureError> {
     // Wayland compositor-independent capture via xdg-desktop-portal screenshot.
+    let request_timer = profile::Timer::start("capture request");
     let response = ashpd::desktop::screenshot::Screenshot::request()
         .interactive(true)
         .send()
         .await
-        .map_err(|e| CaptureError::Portal(e.to_string()))?
+        .map_err(portal_error)?
         .response()
-        .map_err(|e| CaptureError::Portal(e.to_string()))?;
+        .map_err(portal_error)?;
+
+    drop(request_timer);
 
     let uri = response.uri().to_string();
     let url = url::Url::parse(&uri).map_err(|e| CaptureError::Portal(e.to_string()))?;
@@ -24,23 +89,38 @@ pub async fn capture_interactive() -> Result<CapturedImage, CaptureError> {
         .to_file_path()
         .map_err(|_| CaptureError::Portal("invalid portal file uri".to_string()))?;
 
+    let _decode_timer = profile::Timer::start("image decode");
     let bytes = tokio::fs::read(&path)
         .await
         .map_err(|e| CaptureError::Io(e.to_string()))?;
-    let image = image::load_from_memory(&bytes)
-        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let (image, icc_profile) = decode(&bytes)?;
 
-    Ok(CapturedImage { image, uri })
+    Ok(CapturedImage {
+        image,
+        uri,
+        icc_profile,
+    })
 }
 
 pub async fn capture_fullscreen() -> Result<CapturedImage, CaptureError> {
-    let response = ashpd::desktop::screenshot::Screenshot::request()
+    let request_timer = profile::Timer::start("capture request");
+    let request = ashpd::desktop::screenshot::Screenshot::request()
         .interactive(false)
-        .send()
-        .await
-        .map_err(|e| CaptureError::Portal(e.to_string()))?
-        .response()
-        .map_err(|e| CaptureError::Portal(e.to_string()))?;
+        .send();
+    let response = tokio::time::timeout(
+        std::time::Duration::from_secs(PORTAL_TIMEOUT_SECS),
+        request,
+    )
+    .await
+    .map_err(|_| CaptureError::PortalTimeout {
+        portal: "Screenshot",
+        secs: PORTAL_TIMEOUT_SECS,
+    })?
+    .map_err(portal_error)?
+    .response()
+    .map_err(portal_error)?;
+
+    drop(request_timer);
 
     let uri = response.uri().to_string();
     let url = url::Url::parse(&uri).map_err(|e| CaptureError::Portal(e.to_string()))?;
@@ -48,13 +128,17 @@ pub async fn capture_fullscreen() -> Result<CapturedImage, CaptureError> {
         .to_file_path()
         .map_err(|_| CaptureError::Portal("invalid portal file uri".to_string()))?;
 
+    let _decode_timer = profile::Timer::start("image decode");
     let bytes = tokio::fs::read(&path)
         .await
         .map_err(|e| CaptureError::Io(e.to_string()))?;
-    let image = image::load_from_memory(&bytes)
-        .map_err(|e| CaptureError::Io(e.to_string()))?;
+    let (image, icc_profile) = decode(&bytes)?;
 
-    Ok(CapturedImage { image, uri })
+    Ok(CapturedImage {
+        image,
+        uri,
+        icc_profile,
+    })
 }
 
 pub async fn probe_screenshot() -> Result<String, CaptureError> {
@@ -62,9 +146,9 @@ pub async fn probe_screenshot() -> Result<String, CaptureError> {
         .interactive(true)
         .send()
         .await
-        .map_err(|e| CaptureError::Portal(e.to_string()))?
+        .map_err(portal_error)?
         .response()
-        .map_err(|e| CaptureError::Portal(e.to_string()))?;
+        .map_err(portal_error)?;
 
     Ok(response.uri().to_string())
 }
@@ -87,9 +171,14 @@ pub async fn save_file_dialog(default_name: &str) -> Result<Option<PathBuf>, Cap
         )
         .send()
         .await
-        .map_err(|e| CaptureError::Portal(e.to_string()))?
+        .map_err(portal_error)?
         .response()
-        .map_err(|e| CaptureError::Portal(e.to_string()))?;
+        .map_err(portal_error);
+    let response = match response {
+        Ok(response) => response,
+        Err(CaptureError::PortalCancelled) => return Ok(None),
+        Err(err) => return Err(err),
+    };
 
     let Some(uri) = response.uris().first() else {
         return Ok(None);
@@ -100,3 +189,66 @@ pub async fn save_file_dialog(default_name: &str) -> Result<Option<PathBuf>, Cap
         .map_err(|_| CaptureError::Portal("invalid portal file uri".to_string()))?;
     Ok(Some(path))
 }
+
+/// The desktop's light/dark preference from the Settings portal, or `None`
+/// when it states none or the portal doesn't answer promptly.
+pub async fn prefers_dark() -> Option<bool> {
+    use ashpd::desktop::settings::{ColorScheme, Settings};
+    let read = async {
+        let settings = Settings::new().await.ok()?;
+        settings.color_scheme().await.ok()
+    };
+    let scheme = tokio::time::timeout(std::time::Duration::from_millis(500), read)
+        .await
+        .ok()
+        .flatten()?;
+    match scheme {
+        ColorScheme::PreferDark => Some(true),
+        ColorScheme::PreferLight => Some(false),
+        ColorScheme::NoPreference => None,
+    }
+}
+
+pub struct GlobalShortcut {
+    pub id: String,
+    pub description: String,
+    pub trigger: String,
+}
+
+/// Binds the shortcuts through the GlobalShortcuts portal and calls
+/// `on_activated` with the shortcut id every time one fires. Only returns
+/// when the portal session ends or binding fails.
+pub async fn listen_global_shortcuts<F: FnMut(&str)>(
+    shortcuts: &[GlobalShortcut],
+    mut on_activated: F,
+) -> Result<(), CaptureError> {
+    let portal = GlobalShortcuts::new()
+        .await
+        .map_err(portal_error)?;
+    let session = portal
+        .create_session()
+        .await
+        .map_err(portal_error)?;
+    let new_shortcuts: Vec<NewShortcut> = shortcuts
+        .iter()
+        .map(|s| {
+            NewShortcut::new(s.id.as_str(), s.description.as_str())
+                .preferred_trigger(Some(s.trigger.as_str()))
+        })
+        .collect();
+    portal
+        .bind_shortcuts(&session, &new_shortcuts, &WindowIdentifier::default())
+        .await
+        .map_err(portal_error)?
+        .response()
+        .map_err(portal_error)?;
+
+    let mut activated = portal
+        .receive_activated()
+        .await
+        .map_err(portal_error)?;
+    while let Some(event) = activated.next().await {
+        on_activated(event.shortcut_id());
+    }
+    Ok(())
+}
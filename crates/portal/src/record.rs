@@ -0,0 +1,368 @@
+use std::future::Future;
+use std::sync::mpsc;
+use std::time::Instant;
+
+use fireshot_core::{CaptureError, RecordRegion, RecordTarget};
+use image::RgbaImage;
+
+/// One frame of a recording, cropped to the recorded region if any.
+pub struct RecordedFrame {
+    pub image: RgbaImage,
+    pub captured_at: Instant,
+}
+
+/// Streams `target`, picked in the ScreenCast portal dialog, into `frames`
+/// at most `fps` a second until `stop` resolves or the stream ends.
+/// Compositors only send frames when something changes, so consumers should
+/// time frames by `captured_at` rather than assume a steady rate.
+#[cfg(feature = "recording")]
+pub async fn record_screen(
+    target: RecordTarget,
+    fps: u32,
+    stop: impl Future<Output = ()>,
+    frames: mpsc::Sender<RecordedFrame>,
+) -> Result<(), CaptureError> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    use ashpd::desktop::screencast::{CursorMode, PersistMode, Screencast, SourceType};
+    use ashpd::WindowIdentifier;
+
+    use crate::portal_error;
+
+    let proxy = Screencast::new().await.map_err(portal_error)?;
+    let session = proxy.create_session().await.map_err(portal_error)?;
+    proxy
+        .select_sources(
+            &session,
+            CursorMode::Embedded,
+            match target {
+                RecordTarget::Window => SourceType::Window.into(),
+                RecordTarget::Region(_) | RecordTarget::Output => SourceType::Monitor.into(),
+            },
+            false,
+            None,
+            PersistMode::DoNot,
+        )
+        .await
+        .map_err(portal_error)?
+        .response()
+        .map_err(portal_error)?;
+    let streams = proxy
+        .start(&session, &WindowIdentifier::default())
+        .await
+        .map_err(portal_error)?
+        .response()
+        .map_err(portal_error)?;
+    let Some(source) = streams.streams().first() else {
+        return Err(CaptureError::Portal("ScreenCast started no stream".to_string()));
+    };
+    let crop = Crop {
+        region: match target {
+            RecordTarget::Region(region) => Some(region),
+            RecordTarget::Window | RecordTarget::Output => None,
+        },
+        origin: source.position().unwrap_or((0, 0)),
+        logical_width: source.size().map(|(width, _)| width),
+    };
+    let node_id = source.pipe_wire_node_id();
+    let fd = proxy
+        .open_pipe_wire_remote(&session)
+        .await
+        .map_err(portal_error)?;
+    // SAFETY: the portal hands over a fresh descriptor that nothing else owns.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let (quit_tx, quit_rx) = pipewire::channel::channel::<()>();
+    let (done_tx, mut done_rx) = tokio::sync::oneshot::channel();
+    std::thread::Builder::new()
+        .name("fireshot-record".to_string())
+        .spawn(move || {
+            let _ = done_tx.send(stream::run(fd, node_id, crop, fps, frames, quit_rx));
+        })
+        .map_err(|e| CaptureError::Io(e.to_string()))?;
+
+    tokio::pin!(stop);
+    let result = tokio::select! {
+        _ = &mut stop => {
+            let _ = quit_tx.send(());
+            (&mut done_rx).await
+        }
+        result = &mut done_rx => result,
+    };
+    let _ = session.close().await;
+    result.unwrap_or_else(|_| Err(CaptureError::Io("recording thread panicked".to_string())))
+}
+
+#[cfg(not(feature = "recording"))]
+pub async fn record_screen(
+    _target: RecordTarget,
+    _fps: u32,
+    _stop: impl Future<Output = ()>,
+    _frames: mpsc::Sender<RecordedFrame>,
+) -> Result<(), CaptureError> {
+    Err(CaptureError::Unsupported(
+        "this build has no screen recording (enable the `recording` feature)".to_string(),
+    ))
+}
+
+/// Where the recorded region sits in the monitor's stream.
+#[cfg_attr(not(feature = "recording"), allow(dead_code))]
+struct Crop {
+    /// `None` keeps the whole stream.
+    region: Option<RecordRegion>,
+    /// The monitor's top-left in the desktop, in logical points.
+    origin: (i32, i32),
+    /// The monitor's width in logical points, to find its scale.
+    logical_width: Option<i32>,
+}
+
+#[cfg_attr(not(feature = "recording"), allow(dead_code))]
+impl Crop {
+    /// Copies the region out of a 4-byte-per-pixel frame. Captures cover the
+    /// desktop from its top-left, so the monitor's origin is scaled into
+    /// frame pixels and taken off the region. `None` when the region misses
+    /// the frame or the buffer is short.
+    fn apply(&self, bytes: &[u8], stride: usize, width: u32, height: u32, bgr: bool) -> Option<RgbaImage> {
+        let (x0, y0, x1, y1) = match self.region {
+            Some(region) => {
+                let scale = match self.logical_width {
+                    Some(logical) if logical > 0 => width as f32 / logical as f32,
+                    _ => 1.0,
+                };
+                let left = region.x as f32 - self.origin.0 as f32 * scale;
+                let top = region.y as f32 - self.origin.1 as f32 * scale;
+                (
+                    left.max(0.0) as u32,
+                    top.max(0.0) as u32,
+                    ((left + region.width as f32).max(0.0) as u32).min(width),
+                    ((top + region.height as f32).max(0.0) as u32).min(height),
+                )
+            }
+            None => (0, 0, width, height),
+        };
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        let end = (y1 as usize - 1) * stride + x1 as usize * 4;
+        if bytes.len() < end {
+            return None;
+        }
+        let mut image = RgbaImage::new(x1 - x0, y1 - y0);
+        for (row, out) in (y0..y1).zip(image.chunks_exact_mut((x1 - x0) as usize * 4)) {
+            let start = row as usize * stride + x0 as usize * 4;
+            let src = &bytes[start..start + out.len()];
+            for (dst, px) in out.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+                let (r, b) = if bgr { (px[2], px[0]) } else { (px[0], px[2]) };
+                dst.copy_from_slice(&[r, px[1], b, 255]);
+            }
+        }
+        Some(image)
+    }
+}
+
+#[cfg(feature = "recording")]
+mod stream {
+    use std::cell::RefCell;
+    use std::os::fd::OwnedFd;
+    use std::rc::Rc;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use fireshot_core::CaptureError;
+    use pipewire as pw;
+    use pw::properties::properties;
+    use pw::spa;
+    use spa::param::format::{FormatProperties, MediaSubtype, MediaType};
+    use spa::param::video::{VideoFormat, VideoInfoRaw};
+    use spa::param::{format_utils, ParamType};
+    use spa::pod::{serialize::PodSerializer, Pod, Value};
+    use spa::utils::{Direction, Fraction, Rectangle, SpaTypes};
+
+    use super::{Crop, RecordedFrame};
+
+    struct StreamState {
+        format: VideoInfoRaw,
+        crop: Crop,
+        interval: Duration,
+        last: Option<Instant>,
+        frames: mpsc::Sender<RecordedFrame>,
+    }
+
+    fn pw_error(err: pw::Error) -> CaptureError {
+        CaptureError::Portal(format!("pipewire: {}", err))
+    }
+
+    /// Runs the PipeWire loop for the screencast node until `quit` fires or
+    /// the stream fails.
+    pub(super) fn run(
+        fd: OwnedFd,
+        node_id: u32,
+        crop: Crop,
+        fps: u32,
+        frames: mpsc::Sender<RecordedFrame>,
+        quit: pw::channel::Receiver<()>,
+    ) -> Result<(), CaptureError> {
+        pw::init();
+        let mainloop = pw::main_loop::MainLoop::new(None).map_err(pw_error)?;
+        let context = pw::context::Context::new(&mainloop).map_err(pw_error)?;
+        let core = context.connect_fd(fd, None).map_err(pw_error)?;
+        let _quit = quit.attach(mainloop.loop_(), {
+            let mainloop = mainloop.clone();
+            move |()| mainloop.quit()
+        });
+
+        let stream = pw::stream::Stream::new(
+            &core,
+            "fireshot-record",
+            properties! {
+                *pw::keys::MEDIA_TYPE => "Video",
+                *pw::keys::MEDIA_CATEGORY => "Capture",
+                *pw::keys::MEDIA_ROLE => "Screen",
+            },
+        )
+        .map_err(pw_error)?;
+        let failure = Rc::new(RefCell::new(None));
+        let state = StreamState {
+            format: VideoInfoRaw::default(),
+            crop,
+            interval: Duration::from_secs_f64(1.0 / fps.max(1) as f64),
+            last: None,
+            frames,
+        };
+        let _listener = stream
+            .add_local_listener_with_user_data(state)
+            .state_changed({
+                let mainloop = mainloop.clone();
+                let failure = failure.clone();
+                move |_, _, _, new| match new {
+                    pw::stream::StreamState::Error(err) => {
+                        *failure.borrow_mut() = Some(err);
+                        mainloop.quit();
+                    }
+                    pw::stream::StreamState::Unconnected => mainloop.quit(),
+                    _ => {}
+                }
+            })
+            .param_changed(|_, state, id, param| {
+                let Some(param) = param else {
+                    return;
+                };
+                if id != ParamType::Format.as_raw() {
+                    return;
+                }
+                let Ok((media_type, media_subtype)) = format_utils::parse_format(param) else {
+                    return;
+                };
+                if media_type == MediaType::Video && media_subtype == MediaSubtype::Raw {
+                    let _ = state.format.parse(param);
+                }
+            })
+            .process(|stream, state| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let now = Instant::now();
+                if state.last.is_some_and(|last| now - last < state.interval) {
+                    return;
+                }
+                let Some(data) = buffer.datas_mut().first_mut() else {
+                    return;
+                };
+                let (offset, stride) = {
+                    let chunk = data.chunk();
+                    (chunk.offset() as usize, chunk.stride().max(0) as usize)
+                };
+                let size = state.format.size();
+                let format = state.format.format();
+                let bgr = format == VideoFormat::BGRx || format == VideoFormat::BGRA;
+                let Some(bytes) = data.data() else {
+                    return;
+                };
+                let Some(bytes) = bytes.get(offset..) else {
+                    return;
+                };
+                if let Some(image) = state.crop.apply(bytes, stride, size.width, size.height, bgr) {
+                    state.last = Some(now);
+                    let _ = state.frames.send(RecordedFrame {
+                        image,
+                        captured_at: now,
+                    });
+                }
+            })
+            .register()
+            .map_err(pw_error)?;
+
+        let format = spa::pod::object!(
+            SpaTypes::ObjectParamFormat,
+            ParamType::EnumFormat,
+            spa::pod::property!(FormatProperties::MediaType, Id, MediaType::Video),
+            spa::pod::property!(FormatProperties::MediaSubtype, Id, MediaSubtype::Raw),
+            spa::pod::property!(
+                FormatProperties::VideoFormat,
+                Choice,
+                Enum,
+                Id,
+                VideoFormat::BGRx,
+                VideoFormat::BGRx,
+                VideoFormat::BGRA,
+                VideoFormat::RGBx,
+                VideoFormat::RGBA
+            ),
+            spa::pod::property!(
+                FormatProperties::VideoSize,
+                Choice,
+                Range,
+                Rectangle,
+                Rectangle {
+                    width: 1920,
+                    height: 1080
+                },
+                Rectangle {
+                    width: 1,
+                    height: 1
+                },
+                Rectangle {
+                    width: 8192,
+                    height: 8192
+                }
+            ),
+            spa::pod::property!(
+                FormatProperties::VideoFramerate,
+                Choice,
+                Range,
+                Fraction,
+                Fraction {
+                    num: fps.max(1),
+                    denom: 1
+                },
+                Fraction { num: 0, denom: 1 },
+                Fraction {
+                    num: 1000,
+                    denom: 1
+                }
+            ),
+        );
+        let values: Vec<u8> =
+            PodSerializer::serialize(std::io::Cursor::new(Vec::new()), &Value::Object(format))
+                .map_err(|e| CaptureError::Portal(format!("pipewire: {:?}", e)))?
+                .0
+                .into_inner();
+        let pod = Pod::from_bytes(&values)
+            .ok_or_else(|| CaptureError::Portal("pipewire: invalid format pod".to_string()))?;
+        stream
+            .connect(
+                Direction::Input,
+                Some(node_id),
+                pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+                &mut [pod],
+            )
+            .map_err(pw_error)?;
+
+        mainloop.run();
+        let _ = stream.disconnect();
+        match failure.take() {
+            Some(err) => Err(CaptureError::Portal(format!("pipewire: {}", err))),
+            None => Ok(()),
+        }
+    }
+}